@@ -0,0 +1,5 @@
+ALTER TABLE class_sessions ADD COLUMN qr_secret TEXT;
+ALTER TABLE class_sessions ADD COLUMN qr_rotation_seconds INTEGER NOT NULL DEFAULT 30;
+
+-- Sessions started before rotating tokens still need a signing key
+UPDATE class_sessions SET qr_secret = lower(hex(randomblob(32))) WHERE qr_secret IS NULL;
@@ -0,0 +1,16 @@
+-- One-time email codes for signup and password reset. Only a salted hash of the
+-- code is stored; a new request for the same email and purpose replaces the row.
+CREATE TABLE email_otps (
+    otpID INTEGER PRIMARY KEY AUTOINCREMENT,
+    emailAddress TEXT NOT NULL,
+    purpose TEXT NOT NULL CHECK (purpose IN ('signup', 'password_reset')),
+    code_salt TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    verified_at TEXT,
+    UNIQUE (emailAddress, purpose)
+);
+
+CREATE INDEX idx_email_otps_expires ON email_otps(expires_at);
@@ -0,0 +1,22 @@
+ALTER TABLE modules ADD COLUMN allow_walk_ins INTEGER NOT NULL DEFAULT 0;
+
+-- QR check-ins from students who aren't enrolled, held for the lecturer to decide
+CREATE TABLE walk_in_requests (
+    requestID INTEGER PRIMARY KEY AUTOINCREMENT,
+    classID INTEGER NOT NULL,
+    sessionID INTEGER NOT NULL,
+    studentID INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'accepted', 'rejected')),
+    requested_at TEXT NOT NULL,
+    check_latitude REAL,
+    check_longitude REAL,
+    location_accuracy REAL,
+    decided_by TEXT,
+    decided_at TEXT,
+    FOREIGN KEY (classID) REFERENCES classes (classID),
+    FOREIGN KEY (sessionID) REFERENCES class_sessions (sessionID),
+    FOREIGN KEY (studentID) REFERENCES users (userID),
+    UNIQUE(classID, studentID)
+);
+
+CREATE INDEX idx_walk_in_requests_class ON walk_in_requests(classID, status);
@@ -0,0 +1,7 @@
+-- Minutes after a class starts during which a check-in is on time, and after which it is no longer accepted
+ALTER TABLE modules ADD COLUMN on_time_minutes INTEGER NOT NULL DEFAULT 10;
+ALTER TABLE modules ADD COLUMN late_minutes INTEGER NOT NULL DEFAULT 30;
+
+-- Per-class overrides; NULL falls back to the module's window
+ALTER TABLE classes ADD COLUMN on_time_minutes INTEGER;
+ALTER TABLE classes ADD COLUMN late_minutes INTEGER;
@@ -0,0 +1,19 @@
+-- Students asking for an absence to be excused, reviewed by the module's staff
+CREATE TABLE excuse_requests (
+    requestID INTEGER PRIMARY KEY AUTOINCREMENT,
+    classID INTEGER NOT NULL,
+    studentID INTEGER NOT NULL,
+    reason TEXT NOT NULL,
+    attachment_path TEXT,
+    attachment_name TEXT,
+    attachment_type TEXT,
+    status TEXT NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'approved', 'rejected')),
+    submitted_at TEXT NOT NULL,
+    decided_by TEXT,
+    decided_at TEXT,
+    FOREIGN KEY (classID) REFERENCES classes (classID),
+    FOREIGN KEY (studentID) REFERENCES users (userID),
+    UNIQUE(classID, studentID)
+);
+
+CREATE INDEX idx_excuse_requests_status ON excuse_requests(status, classID);
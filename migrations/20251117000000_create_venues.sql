@@ -0,0 +1,32 @@
+-- Lecture venues with a saved geofence, so a session can be fenced without a live GPS fix.
+-- A venue is fenced by a polygon (JSON array of [latitude, longitude] pairs) when it has
+-- one, otherwise by a circle of radius_meters around its centre.
+CREATE TABLE venues (
+    venueID INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    building TEXT,
+    campus TEXT,
+    latitude REAL,
+    longitude REAL,
+    radius_meters REAL,
+    polygon TEXT,
+    capacity INTEGER,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+ALTER TABLE classes ADD COLUMN venueID INTEGER REFERENCES venues (venueID);
+
+-- The polygon a session was fenced with, copied from the venue when it started
+ALTER TABLE class_sessions ADD COLUMN location_polygon TEXT;
+
+-- Register every venue already in use (without a geofence yet) and link its classes
+INSERT OR IGNORE INTO venues (name)
+SELECT DISTINCT TRIM(venue) FROM classes
+WHERE venue IS NOT NULL AND TRIM(venue) != '';
+
+UPDATE classes
+SET venueID = (SELECT v.venueID FROM venues v WHERE v.name = TRIM(classes.venue))
+WHERE venue IS NOT NULL;
+
+CREATE INDEX idx_classes_venue ON classes(venueID);
@@ -0,0 +1,49 @@
+-- Recurring classes. A series holds the RFC 5545 rule its classes were generated
+-- from; each generated class links back through classes.series_id, so renaming or
+-- moving one class no longer detaches it from the rest.
+CREATE TABLE class_series (
+    seriesID INTEGER PRIMARY KEY AUTOINCREMENT,
+    moduleCode TEXT NOT NULL,
+    -- e.g. FREQ=WEEKLY;BYDAY=MO,WE;COUNT=24
+    rrule TEXT NOT NULL,
+    -- Date of the first class, YYYY-MM-DD
+    dtstart TEXT NOT NULL,
+    -- Comma-separated YYYY-MM-DD dates the rule skips
+    exdates TEXT,
+    created_by TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (moduleCode) REFERENCES modules (moduleCode)
+);
+
+ALTER TABLE classes ADD COLUMN series_id INTEGER REFERENCES class_series (seriesID);
+
+-- Series used to be recognised by module, title and repeat pattern; turn each such
+-- group into a series ending on its last class
+CREATE TEMP TABLE series_backfill AS
+SELECT ROW_NUMBER() OVER (ORDER BY moduleCode, title, recurring) AS seriesID,
+       moduleCode, title, recurring,
+       MIN(date) AS dtstart, MAX(date) AS until, MIN(created_by) AS created_by
+FROM classes
+WHERE recurring IN ('Daily', 'Weekly', 'Monthly')
+GROUP BY moduleCode, title, recurring
+HAVING COUNT(*) > 1;
+
+INSERT INTO class_series (seriesID, moduleCode, rrule, dtstart, created_by)
+SELECT seriesID, moduleCode,
+       'FREQ=' || UPPER(recurring) || ';UNTIL=' || REPLACE(until, '-', ''),
+       dtstart, created_by
+FROM series_backfill;
+
+UPDATE classes
+SET series_id = (
+    SELECT b.seriesID FROM series_backfill b
+    WHERE b.moduleCode = classes.moduleCode
+      AND b.title = classes.title
+      AND b.recurring = classes.recurring
+)
+WHERE recurring IN ('Daily', 'Weekly', 'Monthly');
+
+DROP TABLE series_backfill;
+
+CREATE INDEX idx_classes_series ON classes(series_id);
@@ -0,0 +1,39 @@
+-- Teaching terms, and the public holidays and recess weeks no classes are held on.
+-- Dates are inclusive YYYY-MM-DD. A blackout imported from an .ics file keeps the
+-- event's UID so importing the same file again updates it instead of duplicating it.
+CREATE TABLE academic_terms (
+    termID INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (end_date >= start_date)
+);
+
+CREATE TABLE blackout_dates (
+    blackoutID INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL DEFAULT 'holiday' CHECK (kind IN ('holiday', 'recess', 'other')),
+    start_date TEXT NOT NULL,
+    end_date TEXT NOT NULL,
+    uid TEXT UNIQUE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CHECK (end_date >= start_date),
+    UNIQUE (name, start_date)
+);
+
+CREATE INDEX idx_blackout_dates_range ON blackout_dates(start_date, end_date);
+
+-- Whether a series drops ('skip') or moves ('shift') a class that lands on a blackout
+ALTER TABLE class_series ADD COLUMN blackout_policy TEXT NOT NULL DEFAULT 'skip';
+
+-- Classes that count towards statistics: any held on a blackout date are left out
+CREATE VIEW teaching_classes AS
+SELECT c.*
+FROM classes c
+WHERE NOT EXISTS (
+    SELECT 1 FROM blackout_dates b
+    WHERE c.date BETWEEN b.start_date AND b.end_date
+);
@@ -0,0 +1,8 @@
+-- One secret token per user for their read-only .ics calendar feed. Calendar apps
+-- fetch the feed without signing in, so the token is the only credential;
+-- resetting it replaces the row and existing subscriptions stop updating.
+CREATE TABLE calendar_feeds (
+    userID INTEGER PRIMARY KEY REFERENCES users(userID) ON DELETE CASCADE,
+    token TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL
+);
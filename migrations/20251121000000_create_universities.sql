@@ -0,0 +1,31 @@
+-- Each university's IANA timezone. Class dates and times are wall-clock times at the
+-- module's university, and the timezone is what turns them into instants, so a server
+-- running in UTC still starts and ends classes on time.
+CREATE TABLE universities (
+    name TEXT PRIMARY KEY COLLATE NOCASE,
+    timezone TEXT NOT NULL DEFAULT 'Africa/Johannesburg',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT OR IGNORE INTO universities (name) VALUES ('Stellenbosch University');
+
+INSERT OR IGNORE INTO universities (name)
+SELECT DISTINCT TRIM(university) FROM users
+WHERE TRIM(university) != '';
+
+-- The university a module is taught at, taken from the lecturer who created it
+ALTER TABLE modules ADD COLUMN university TEXT NOT NULL DEFAULT 'Stellenbosch University';
+
+UPDATE modules
+SET university = COALESCE(
+    (
+        SELECT TRIM(u.university)
+        FROM lecturer_module lm
+        INNER JOIN users u ON u.emailAddress = lm.lecturerEmailAddress
+        WHERE lm.moduleCode = modules.moduleCode AND TRIM(u.university) != ''
+        ORDER BY lm.created_at
+        LIMIT 1
+    ),
+    university
+);
@@ -0,0 +1,46 @@
+-- Tutorial groups within a module, each with its own members and tutor. A class can
+-- be aimed at one or more groups; a class without any is for the whole module.
+CREATE TABLE module_groups (
+    groupID INTEGER PRIMARY KEY AUTOINCREMENT,
+    moduleCode TEXT NOT NULL,
+    name TEXT NOT NULL COLLATE NOCASE,
+    tutorEmailAddress TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (moduleCode, name),
+    FOREIGN KEY (moduleCode) REFERENCES modules (moduleCode) ON DELETE CASCADE
+);
+
+CREATE TABLE group_students (
+    groupID INTEGER NOT NULL,
+    studentEmailAddress TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (groupID, studentEmailAddress),
+    FOREIGN KEY (groupID) REFERENCES module_groups (groupID) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_group_students_student ON group_students(studentEmailAddress);
+
+CREATE TABLE class_groups (
+    classID INTEGER NOT NULL,
+    groupID INTEGER NOT NULL,
+    PRIMARY KEY (classID, groupID),
+    FOREIGN KEY (classID) REFERENCES classes (classID) ON DELETE CASCADE,
+    FOREIGN KEY (groupID) REFERENCES module_groups (groupID) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_class_groups_group ON class_groups(groupID);
+
+-- The students expected at each class: everyone enrolled in its module, or only the
+-- members of its groups when it has any
+CREATE VIEW class_students AS
+SELECT c.classID, ms.studentEmailAddress
+FROM classes c
+INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+WHERE NOT EXISTS (SELECT 1 FROM class_groups cg WHERE cg.classID = c.classID)
+   OR EXISTS (
+       SELECT 1
+       FROM class_groups cg
+       INNER JOIN group_students gs ON gs.groupID = cg.groupID
+       WHERE cg.classID = c.classID AND gs.studentEmailAddress = ms.studentEmailAddress
+   );
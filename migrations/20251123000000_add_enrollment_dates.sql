@@ -0,0 +1,25 @@
+-- When each enrollment starts and ends, so students are only expected at classes held
+-- while they were enrolled. `enrolled_on` is the first day; NULL for enrollments made
+-- before dates were kept, which count from the module's first class. `unenrolled_on`
+-- is the first day no longer enrolled, NULL while the student is still enrolled.
+ALTER TABLE module_students ADD COLUMN enrolled_on TEXT;
+ALTER TABLE module_students ADD COLUMN unenrolled_on TEXT;
+
+-- The students expected at each class now also have to be enrolled on its date
+DROP VIEW IF EXISTS class_students;
+
+CREATE VIEW class_students AS
+SELECT c.classID, ms.studentEmailAddress
+FROM classes c
+INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+WHERE (ms.enrolled_on IS NULL OR c.date >= ms.enrolled_on)
+  AND (ms.unenrolled_on IS NULL OR c.date < ms.unenrolled_on)
+  AND (
+      NOT EXISTS (SELECT 1 FROM class_groups cg WHERE cg.classID = c.classID)
+      OR EXISTS (
+          SELECT 1
+          FROM class_groups cg
+          INNER JOIN group_students gs ON gs.groupID = cg.groupID
+          WHERE cg.classID = c.classID AND gs.studentEmailAddress = ms.studentEmailAddress
+      )
+  );
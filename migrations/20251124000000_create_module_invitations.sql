@@ -0,0 +1,17 @@
+-- A code students can enter (or follow as a link) to enroll themselves in a module.
+-- Created the first time a lecturer asks for it, and replaceable if it leaks.
+ALTER TABLE modules ADD COLUMN join_code TEXT;
+
+CREATE UNIQUE INDEX idx_modules_join_code ON modules(join_code);
+
+-- Emails a lecturer enrolled before they had an account. Each is turned into an
+-- enrollment when a student registers with that email.
+CREATE TABLE module_invitations (
+    moduleCode TEXT NOT NULL,
+    emailAddress TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (moduleCode, emailAddress),
+    FOREIGN KEY (moduleCode) REFERENCES modules (moduleCode) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_module_invitations_email ON module_invitations(emailAddress);
@@ -0,0 +1,6 @@
+-- The session sweep looks for classes still upcoming or in progress up to a date, and
+-- for the sessions it has just ended by their `ended_at`. Without these, each pass
+-- scans every class and session ever held.
+CREATE INDEX idx_classes_status_date ON classes(status, date);
+
+CREATE INDEX idx_class_sessions_ended ON class_sessions(ended_at);
@@ -0,0 +1,5 @@
+-- A class of a series that was edited on its own keeps the date the series put it
+-- on, as RECURRENCE-ID does in iCalendar. Later edits to the series leave such a
+-- class alone, and regenerating the series doesn't add another class on that date.
+-- NULL for classes that still follow their series.
+ALTER TABLE classes ADD COLUMN occurrence_date TEXT;
@@ -0,0 +1,43 @@
+-- Earlier enrollments of students who left a module and later enrolled again. The
+-- current (or last) enrollment stays in module_students; each one it replaced is kept
+-- here, so classes held during it still count towards the student's attendance.
+CREATE TABLE module_enrollment_history (
+    moduleCode TEXT NOT NULL,
+    studentEmailAddress TEXT NOT NULL,
+    enrolled_on TEXT,
+    unenrolled_on TEXT NOT NULL,
+    FOREIGN KEY (moduleCode) REFERENCES modules (moduleCode) ON DELETE CASCADE,
+    FOREIGN KEY (studentEmailAddress) REFERENCES users (emailAddress) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_module_enrollment_history_student
+    ON module_enrollment_history(moduleCode, studentEmailAddress);
+
+-- A student is expected at a class held during any of their enrollments
+DROP VIEW IF EXISTS class_students;
+
+CREATE VIEW class_students AS
+SELECT c.classID, ms.studentEmailAddress
+FROM classes c
+INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+WHERE (
+      ((ms.enrolled_on IS NULL OR c.date >= ms.enrolled_on)
+          AND (ms.unenrolled_on IS NULL OR c.date < ms.unenrolled_on))
+      OR EXISTS (
+          SELECT 1
+          FROM module_enrollment_history h
+          WHERE h.moduleCode = ms.moduleCode
+            AND h.studentEmailAddress = ms.studentEmailAddress
+            AND (h.enrolled_on IS NULL OR c.date >= h.enrolled_on)
+            AND c.date < h.unenrolled_on
+      )
+  )
+  AND (
+      NOT EXISTS (SELECT 1 FROM class_groups cg WHERE cg.classID = c.classID)
+      OR EXISTS (
+          SELECT 1
+          FROM class_groups cg
+          INNER JOIN group_students gs ON gs.groupID = cg.groupID
+          WHERE cg.classID = c.classID AND gs.studentEmailAddress = ms.studentEmailAddress
+      )
+  );
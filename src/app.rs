@@ -3,8 +3,8 @@ use crate::pages::{
     RolePage, StudentEditProfilePage, StudentHomePage, StudentLoginPage, StudentProfilePage,
     StudentStatisticsPage, Support, TermsAndConditions,
 };
-use crate::routes::{ ClassQrFullscreenPage, ClassQrPage, ClassesPage, EditClass, EditModule, Error,ForgotPassword, HomePage,
-    Login, NewClass, NewModule, Profile, Register, Statistics, Timetable,
+use crate::routes::{ AcademicCalendarPage, ClassQrFullscreenPage, ClassQrPage, ClassesPage, EditClass, EditModule, Error,ForgotPassword, HomePage, ImportTimetable,
+    Login, NewClass, NewModule, Profile, Register, Statistics, Timetable, Venues,
 };
 use leptos::prelude::*;
 use leptos_meta::{provide_meta_context, MetaTags, Stylesheet, Title};
@@ -102,10 +102,13 @@ fn AppShell() -> impl IntoView {
                     <Route path=StaticSegment("home") view=HomePage/>
                     <Route path=StaticSegment("classes") view=ClassesPage/>
                     <Route path=(StaticSegment("classes"), StaticSegment("new")) view=NewClass/>
+                    <Route path=(StaticSegment("classes"), StaticSegment("import")) view=ImportTimetable/>
                     <Route path=(StaticSegment("modules"), StaticSegment("new")) view=NewModule/>
                     <Route path=(StaticSegment("modules"), StaticSegment("edit")) view=EditModule/>
                     <Route path=StaticSegment("timetable") view=Timetable/>
                     <Route path=StaticSegment("statistics") view=Statistics/>
+                    <Route path=StaticSegment("venues") view=Venues/>
+                    <Route path=StaticSegment("calendar") view=AcademicCalendarPage/>
                     <Route path=(StaticSegment("lecturer"), StaticSegment("profile")) view=Profile/>
                     <Route path=(StaticSegment("tutor"), StaticSegment("profile")) view=Profile/>
                     <Route path=(StaticSegment("classes"), StaticSegment("edit")) view=EditClass/>
@@ -0,0 +1,419 @@
+//! Role and ownership checks shared by every `/api` server function.
+//!
+//! Identity comes from the session through [`current_user`]; these guards then
+//! decide whether that user may act on a module, class or session. Each `require_*`
+//! guard is an `authorize_*` check applied to the signed-in caller, so the checks can
+//! be exercised for any caller without a request. Lecturers own modules through
+//! `lecturer_module`, tutors assist through `module_tutor`, and students only
+//! ever act on their own records. A refused check returns
+//! `ClockItError::Forbidden` (HTTP 403) with [`FORBIDDEN_MESSAGE`] so the client sees
+//! the same error everywhere.
+
+use crate::database::DbPool;
+use crate::error::{db_error, ClockItError};
+use crate::session::{current_user, sign_in_required};
+use crate::types::UserProfile;
+
+pub const FORBIDDEN_MESSAGE: &str = "You do not have permission to perform this action";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Student,
+    Tutor,
+    Lecturer,
+}
+
+impl Role {
+    pub fn of(user: &UserProfile) -> Option<Role> {
+        match user.role.as_str() {
+            "student" => Some(Role::Student),
+            "tutor" => Some(Role::Tutor),
+            "lecturer" => Some(Role::Lecturer),
+            _ => None,
+        }
+    }
+}
+
+/// Lecturers and tutors
+pub const STAFF: &[Role] = &[Role::Lecturer, Role::Tutor];
+
+pub fn forbidden() -> ClockItError {
+    ClockItError::Forbidden(FORBIDDEN_MESSAGE.to_string())
+}
+
+/// `caller`, provided they hold one of `roles`. `None` is an anonymous request.
+pub fn authorize_role(
+    caller: Option<UserProfile>,
+    roles: &[Role],
+) -> Result<UserProfile, ClockItError> {
+    let user = caller.ok_or_else(sign_in_required)?;
+    match Role::of(&user) {
+        Some(role) if roles.contains(&role) => Ok(user),
+        _ => Err(forbidden()),
+    }
+}
+
+/// The signed-in user, provided they hold one of `roles`
+pub async fn require_role(pool: &DbPool, roles: &[Role]) -> Result<UserProfile, ClockItError> {
+    authorize_role(current_user(pool).await?, roles)
+}
+
+/// Whether `email` lectures `module_code`
+pub async fn is_module_lecturer(
+    pool: &DbPool,
+    module_code: &str,
+    email: &str,
+) -> Result<bool, ClockItError> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM lecturer_module WHERE moduleCode = ? AND lecturerEmailAddress = ?)",
+    )
+    .bind(module_code)
+    .bind(email)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to check module access"))
+}
+
+/// Whether `email` lectures or tutors `module_code`
+pub async fn is_module_staff(
+    pool: &DbPool,
+    module_code: &str,
+    email: &str,
+) -> Result<bool, ClockItError> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM lecturer_module WHERE moduleCode = ? AND lecturerEmailAddress = ?) OR EXISTS(SELECT 1 FROM module_tutor WHERE moduleCode = ? AND tutorEmailAddress = ?)",
+    )
+    .bind(module_code)
+    .bind(email)
+    .bind(module_code)
+    .bind(email)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to check module access"))
+}
+
+/// `caller`, provided they are a lecturer who owns `module_code`
+pub async fn authorize_module_owner(
+    pool: &DbPool,
+    caller: Option<UserProfile>,
+    module_code: &str,
+) -> Result<UserProfile, ClockItError> {
+    let user = authorize_role(caller, &[Role::Lecturer])?;
+    if is_module_lecturer(pool, module_code, &user.email_address).await? {
+        Ok(user)
+    } else {
+        Err(forbidden())
+    }
+}
+
+/// A lecturer who owns `module_code`; required for editing the module itself
+/// and for managing its enrolments and tutors
+pub async fn require_module_owner(
+    pool: &DbPool,
+    module_code: &str,
+) -> Result<UserProfile, ClockItError> {
+    authorize_module_owner(pool, current_user(pool).await?, module_code).await
+}
+
+/// `caller`, provided they lecture or tutor `module_code`
+pub async fn authorize_module_staff(
+    pool: &DbPool,
+    caller: Option<UserProfile>,
+    module_code: &str,
+) -> Result<UserProfile, ClockItError> {
+    let user = authorize_role(caller, STAFF)?;
+    if is_module_staff(pool, module_code, &user.email_address).await? {
+        Ok(user)
+    } else {
+        Err(forbidden())
+    }
+}
+
+/// A lecturer or tutor attached to `module_code`
+pub async fn require_module_staff(
+    pool: &DbPool,
+    module_code: &str,
+) -> Result<UserProfile, ClockItError> {
+    authorize_module_staff(pool, current_user(pool).await?, module_code).await
+}
+
+/// `caller`, provided they lecture or tutor the module `class_id` belongs to.
+/// Unknown classes are refused rather than reported, so IDs can't be probed.
+pub async fn authorize_class_staff(
+    pool: &DbPool,
+    caller: Option<UserProfile>,
+    class_id: i64,
+) -> Result<UserProfile, ClockItError> {
+    let user = authorize_role(caller, STAFF)?;
+    let module_code: Option<String> =
+        sqlx::query_scalar("SELECT CAST(moduleCode AS TEXT) FROM classes WHERE classID = ?")
+            .bind(class_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error("Failed to check class access"))?;
+
+    match module_code {
+        Some(code) if is_module_staff(pool, &code, &user.email_address).await? => Ok(user),
+        _ => Err(forbidden()),
+    }
+}
+
+/// A lecturer or tutor attached to the module that `class_id` belongs to
+pub async fn require_class_staff(
+    pool: &DbPool,
+    class_id: i64,
+) -> Result<UserProfile, ClockItError> {
+    authorize_class_staff(pool, current_user(pool).await?, class_id).await
+}
+
+/// `caller`, provided they lecture or tutor the class `session_id` was started for
+pub async fn authorize_session_staff(
+    pool: &DbPool,
+    caller: Option<UserProfile>,
+    session_id: i64,
+) -> Result<UserProfile, ClockItError> {
+    let class_id: Option<i64> =
+        sqlx::query_scalar("SELECT classID FROM class_sessions WHERE sessionID = ?")
+            .bind(session_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error("Failed to check session access"))?;
+
+    match class_id {
+        Some(class_id) => authorize_class_staff(pool, caller, class_id).await,
+        None => {
+            // Still report missing sign-in as 401 before refusing
+            caller.ok_or_else(sign_in_required)?;
+            Err(forbidden())
+        }
+    }
+}
+
+/// A lecturer or tutor attached to the class that `session_id` was started for
+pub async fn require_session_staff(
+    pool: &DbPool,
+    session_id: i64,
+) -> Result<UserProfile, ClockItError> {
+    authorize_session_staff(pool, current_user(pool).await?, session_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{
+        add_class, add_module, add_tutor, add_user, enroll, profile, run, test_pool,
+    };
+
+    const OWNER: &str = "owner@test.example";
+    const OTHER_LECTURER: &str = "other@test.example";
+    const TUTOR: &str = "tutor@test.example";
+    const STUDENT: &str = "student@test.example";
+
+    /// Who calls each guard, and the status they should get for the owner's module
+    /// as owner-only and as staff
+    const CALLERS: &[(Option<&str>, u16, u16)] = &[
+        (None, 401, 401),
+        (Some(STUDENT), 403, 403),
+        (Some(TUTOR), 403, 200),
+        (Some(OWNER), 200, 200),
+        (Some(OTHER_LECTURER), 403, 403),
+    ];
+
+    struct Fixture {
+        class_id: i64,
+        session_id: i64,
+        other_class_id: i64,
+        other_session_id: i64,
+    }
+
+    async fn start_session(pool: &DbPool, class_id: i64) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO class_sessions (classID, started_at) VALUES (?, '2025-03-10T07:00:00Z') RETURNING sessionID",
+        )
+        .bind(class_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    /// OWNER lectures AUTH101, which TUTOR tutors and STUDENT takes;
+    /// OTHER_LECTURER lectures AUTH202
+    async fn fixture(pool: &DbPool) -> Fixture {
+        add_user(pool, OWNER, "lecturer").await;
+        add_user(pool, OTHER_LECTURER, "lecturer").await;
+        add_user(pool, TUTOR, "tutor").await;
+        add_user(pool, STUDENT, "student").await;
+
+        add_module(pool, "AUTH101", OWNER).await;
+        add_tutor(pool, "AUTH101", TUTOR).await;
+        enroll(pool, "AUTH101", STUDENT).await;
+        add_module(pool, "AUTH202", OTHER_LECTURER).await;
+
+        let class_id = add_class(pool, "AUTH101", "A101", "2025-03-10", "09:00", 60, OWNER).await;
+        let other_class_id = add_class(
+            pool,
+            "AUTH202",
+            "B202",
+            "2025-03-10",
+            "09:00",
+            60,
+            OTHER_LECTURER,
+        )
+        .await;
+
+        Fixture {
+            class_id,
+            session_id: start_session(pool, class_id).await,
+            other_class_id,
+            other_session_id: start_session(pool, other_class_id).await,
+        }
+    }
+
+    async fn caller(pool: &DbPool, email: Option<&str>) -> Option<UserProfile> {
+        match email {
+            Some(email) => Some(profile(pool, email).await),
+            None => None,
+        }
+    }
+
+    fn status(result: Result<UserProfile, ClockItError>) -> u16 {
+        result.map(|_| 200).unwrap_or_else(|e| e.status_code())
+    }
+
+    #[test]
+    fn roles_are_checked_before_anything_else() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+
+            let student = caller(&pool, Some(STUDENT)).await;
+            let tutor = caller(&pool, Some(TUTOR)).await;
+            let owner = caller(&pool, Some(OWNER)).await;
+
+            assert_eq!(status(authorize_role(None, STAFF)), 401);
+            assert_eq!(status(authorize_role(student.clone(), STAFF)), 403);
+            assert_eq!(status(authorize_role(tutor.clone(), STAFF)), 200);
+            assert_eq!(status(authorize_role(owner, STAFF)), 200);
+            assert_eq!(status(authorize_role(student, &[Role::Student])), 200);
+            assert_eq!(status(authorize_role(tutor, &[Role::Lecturer])), 403);
+        });
+    }
+
+    #[test]
+    fn only_the_owning_lecturer_manages_a_module() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+
+            for &(email, owner_status, _) in CALLERS {
+                let user = caller(&pool, email).await;
+                assert_eq!(
+                    status(authorize_module_owner(&pool, user, "AUTH101").await),
+                    owner_status,
+                    "{:?} as module owner",
+                    email
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn lecturers_and_tutors_of_a_module_are_its_staff() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+
+            for &(email, _, staff_status) in CALLERS {
+                let user = caller(&pool, email).await;
+                assert_eq!(
+                    status(authorize_module_staff(&pool, user, "AUTH101").await),
+                    staff_status,
+                    "{:?} as module staff",
+                    email
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn class_and_session_access_follows_the_module() {
+        run(async {
+            let pool = test_pool().await;
+            let fixture = fixture(&pool).await;
+
+            for &(email, _, staff_status) in CALLERS {
+                let user = caller(&pool, email).await;
+                assert_eq!(
+                    status(authorize_class_staff(&pool, user.clone(), fixture.class_id).await),
+                    staff_status,
+                    "{:?} on the class",
+                    email
+                );
+                assert_eq!(
+                    status(authorize_session_staff(&pool, user, fixture.session_id).await),
+                    staff_status,
+                    "{:?} on the session",
+                    email
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn a_lecturer_cannot_reach_another_lecturers_classes() {
+        run(async {
+            let pool = test_pool().await;
+            let fixture = fixture(&pool).await;
+            let owner = caller(&pool, Some(OWNER)).await;
+
+            assert_eq!(
+                status(authorize_module_owner(&pool, owner.clone(), "AUTH202").await),
+                403
+            );
+            assert_eq!(
+                status(authorize_module_staff(&pool, owner.clone(), "AUTH202").await),
+                403
+            );
+            assert_eq!(
+                status(authorize_class_staff(&pool, owner.clone(), fixture.other_class_id).await),
+                403
+            );
+            assert_eq!(
+                status(
+                    authorize_session_staff(&pool, owner.clone(), fixture.other_session_id).await
+                ),
+                403
+            );
+
+            // Their own module's tutor is no further in
+            let tutor = caller(&pool, Some(TUTOR)).await;
+            assert_eq!(
+                status(authorize_class_staff(&pool, tutor, fixture.other_class_id).await),
+                403
+            );
+        });
+    }
+
+    #[test]
+    fn unknown_classes_and_sessions_are_refused_not_reported() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            let owner = caller(&pool, Some(OWNER)).await;
+
+            assert_eq!(
+                status(authorize_class_staff(&pool, owner.clone(), 999_999).await),
+                403
+            );
+            assert_eq!(
+                status(authorize_session_staff(&pool, owner, 999_999).await),
+                403
+            );
+            // An anonymous caller still hears that they need to sign in
+            assert_eq!(
+                status(authorize_session_staff(&pool, None, 999_999).await),
+                401
+            );
+        });
+    }
+}
@@ -0,0 +1,184 @@
+//! Personal, read-only iCalendar feeds that phone and desktop calendars subscribe to.
+//!
+//! Each user has a secret token (see `database::calendar_feeds`) and their feed is
+//! served at `/calendar/{token}.ics` without a login session, since calendar apps
+//! can't sign in. Every class becomes one VEVENT whose UID is derived from its
+//! class ID, so when a class is renamed, moved to another venue or cancelled, the
+//! next refresh updates the existing calendar entry instead of adding a new one.
+
+use crate::clock::clock;
+use crate::database::calendar_feeds::{feed_classes, feed_owner};
+use crate::database::classes::Class;
+use crate::database::universities::module_timezone;
+use crate::database::DbPool;
+use crate::utils::class_time::ClassWindow;
+use crate::utils::ics::{write_calendar, FeedEvent};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+
+/// Where the feed for `token` is served, relative to the site root
+pub fn feed_path(token: &str) -> String {
+    format!("/calendar/{}.ics", token)
+}
+
+/// Stable across every edit to the class, so calendar apps update it in place
+fn class_uid(class_id: i64) -> String {
+    format!("class-{}@clock-it", class_id)
+}
+
+// `updated_at` is RFC 3339 when written by the app and `YYYY-MM-DD HH:MM:SS` (UTC)
+// when it came from a column default
+fn parse_updated_at(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc).naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"))
+        .ok()
+}
+
+/// The calendar entry for `class`, scheduled in `tz`, or None if its date or time
+/// can't be read. Times are written in UTC, so the class shows at the right time in
+/// a calendar app set to any zone.
+fn class_event(class: &Class, tz: Tz, now: DateTime<Utc>) -> Option<FeedEvent> {
+    let window = ClassWindow::new(&class.date, &class.time, class.duration_minutes, tz)?;
+
+    let description = match class.description.as_deref().map(str::trim) {
+        Some(description) if !description.is_empty() => {
+            format!("{}\n\n{}", class.module_code, description)
+        }
+        _ => class.module_code.clone(),
+    };
+
+    Some(FeedEvent {
+        uid: class_uid(class.class_id),
+        summary: format!("{}: {}", class.module_code, class.title),
+        location: class
+            .venue
+            .as_deref()
+            .map(str::trim)
+            .filter(|venue| !venue.is_empty())
+            .map(str::to_string),
+        description: Some(description),
+        start: window.start.naive_utc(),
+        end: window.end.naive_utc(),
+        cancelled: class.status == "cancelled",
+        last_modified: parse_updated_at(&class.updated_at).unwrap_or_else(|| now.naive_utc()),
+    })
+}
+
+/// `GET /calendar/{file}`: the feed for the token in `file` (`{token}.ics`), or
+/// 404 when the token is unknown or has been reset
+pub async fn serve_feed(pool: DbPool, file: String) -> Response {
+    let Some(token) = file.strip_suffix(".ics") else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let user = match feed_owner(&pool, token).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let clock = clock();
+    let classes = match feed_classes(&pool, &user, &*clock).await {
+        Ok(classes) => classes,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    // Each class is at its own module's university
+    let mut timezones: HashMap<String, Tz> = HashMap::new();
+    let mut events = Vec::with_capacity(classes.len());
+    for class in &classes {
+        let tz = match timezones.get(&class.module_code) {
+            Some(tz) => *tz,
+            None => match module_timezone(&pool, &class.module_code).await {
+                Ok(tz) => *timezones.entry(class.module_code.clone()).or_insert(tz),
+                Err(e) => {
+                    eprintln!("❌ {}", e);
+                    return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            },
+        };
+        events.extend(class_event(class, tz, clock.now()));
+    }
+
+    let body = write_calendar(
+        &format!("Clock-It ({} {})", user.name, user.surname),
+        &events,
+    );
+
+    (
+        [
+            (header::CONTENT_TYPE, "text/calendar; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, max-age=900"),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::ics::write_calendar;
+
+    fn class(date: &str, time: &str) -> Class {
+        Class {
+            class_id: 7,
+            module_code: "CS101".to_string(),
+            title: "Lecture".to_string(),
+            venue: Some("A101".to_string()),
+            description: None,
+            recurring: None,
+            date: date.to_string(),
+            time: time.to_string(),
+            duration_minutes: 90,
+            status: "upcoming".to_string(),
+            created_by: None,
+            check_in_window: None,
+            series_id: None,
+            created_at: "2025-01-01T00:00:00Z".to_string(),
+            updated_at: "2025-01-01T00:00:00Z".to_string(),
+        }
+    }
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2025-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn events_are_written_in_utc_from_the_universitys_zone() {
+        let event = class_event(
+            &class("2025-03-10", "09:00"),
+            chrono_tz::Africa::Johannesburg,
+            now(),
+        )
+        .unwrap();
+        let calendar = write_calendar("Test", &[event]);
+
+        assert!(calendar.contains("DTSTART:20250310T070000Z\r\n"));
+        assert!(calendar.contains("DTEND:20250310T083000Z\r\n"));
+    }
+
+    #[test]
+    fn events_follow_daylight_saving_in_the_universitys_zone() {
+        let london = chrono_tz::Europe::London;
+
+        // The clocks go forward on 30 March 2025
+        let before = class_event(&class("2025-03-28", "09:00"), london, now()).unwrap();
+        let after = class_event(&class("2025-03-31", "09:00"), london, now()).unwrap();
+
+        assert_eq!(before.start.format("%H:%M").to_string(), "09:00");
+        assert_eq!(after.start.format("%H:%M").to_string(), "08:00");
+    }
+}
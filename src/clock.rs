@@ -0,0 +1,62 @@
+//! The current time, behind a trait so logic that depends on it can be tested.
+//! Server functions and components get the clock from context with [`clock`], which
+//! falls back to the system clock when none is provided. Tests provide a
+//! [`FixedClock`] and move it forward to watch sessions start, run and end.
+
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use std::sync::{Arc, Mutex};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Today's date in the machine's own timezone, for labels shown in the browser
+    fn local_today(&self) -> NaiveDate {
+        self.now().with_timezone(&Local).date_naive()
+    }
+}
+
+/// The real time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that stands still until it is set or advanced
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A clock shared by the server, its background tasks and its server functions
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The clock provided as context by `main`, or the system clock
+pub fn clock() -> SharedClock {
+    leptos::prelude::use_context::<SharedClock>().unwrap_or_else(|| Arc::new(SystemClock))
+}
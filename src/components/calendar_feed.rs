@@ -0,0 +1,102 @@
+use crate::routes::profile_functions::{get_calendar_feed_fn, reset_calendar_feed_fn};
+use leptos::prelude::*;
+
+/// The signed-in user's personal calendar feed link, with a button to subscribe
+/// from a phone or desktop calendar and one to replace a leaked link
+#[component]
+pub fn CalendarFeedPanel() -> impl IntoView {
+    let path = RwSignal::new(String::new());
+    let origin = RwSignal::new(String::new());
+    let feedback = RwSignal::new(None::<(bool, String)>);
+
+    // The feed link has to be absolute, and only the browser knows which host it reached
+    Effect::new(move |_| {
+        if let Ok(current) = location().origin() {
+            origin.set(current);
+        }
+    });
+
+    let load_action = Action::new(|_: &()| async move { get_calendar_feed_fn().await });
+    let reset_action = Action::new(|_: &()| async move { reset_calendar_feed_fn().await });
+    let reset_pending = reset_action.pending();
+
+    Effect::new(move |_| {
+        load_action.dispatch(());
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = load_action.value().get() {
+            match result {
+                Ok(response) => path.set(response.path),
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = reset_action.value().get() {
+            match result {
+                Ok(response) => {
+                    path.set(response.path);
+                    feedback.set(Some((response.success, response.message)));
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    let feed_url = move || {
+        let path = path.get();
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", origin.get(), path)
+        }
+    };
+
+    // webcal:// makes phones open their calendar app and subscribe rather than download
+    let subscribe_url = move || {
+        let url = feed_url();
+        url.strip_prefix("https://")
+            .or_else(|| url.strip_prefix("http://"))
+            .map(|rest| format!("webcal://{}", rest))
+            .unwrap_or(url)
+    };
+
+    let on_reset = move |_| {
+        feedback.set(None);
+        reset_action.dispatch(());
+    };
+
+    view! {
+        <div class="calendar-feed">
+            <p class="muted">
+                "Subscribe to your Clock-It classes from your phone or computer calendar. Changes to times, venues and cancellations show up automatically. Anyone with this link can see your timetable, so keep it private."
+            </p>
+            <input
+                class="input calendar-feed-url"
+                type="text"
+                readonly=true
+                aria-label="Calendar feed link"
+                prop:value=feed_url
+                on:focus=move |ev| {
+                    event_target::<web_sys::HtmlInputElement>(&ev).select();
+                }
+            />
+            <div class="calendar-feed-actions">
+                <a class="btn btn-primary" href=subscribe_url>"Add to Calendar"</a>
+                <button
+                    class="btn btn-outline"
+                    type="button"
+                    on:click=on_reset
+                    disabled=move || reset_pending.get() || path.get().is_empty()
+                >
+                    {move || if reset_pending.get() { "Resetting..." } else { "Reset Link" }}
+                </button>
+            </div>
+            {move || feedback.get().map(|(success, message)| view! {
+                <p class=if success { "success" } else { "error" }>{message}</p>
+            })}
+        </div>
+    }
+}
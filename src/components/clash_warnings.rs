@@ -0,0 +1,37 @@
+use crate::database::clashes::ClassClash;
+use leptos::prelude::*;
+
+/// The classes a save ran into, with a box to tick to save over them
+#[component]
+pub fn ClashWarnings(
+    #[prop(into)] clashes: Signal<Vec<ClassClash>>,
+    allow_clashes: RwSignal<bool>,
+) -> impl IntoView {
+    view! {
+        <Show when=move || !clashes.get().is_empty()>
+            <div class="clash-warnings">
+                <h4 class="heading">"Clashes"</h4>
+                <ul>
+                    {move || clashes.get().into_iter().map(|clash| view! {
+                        <li>
+                            <strong>{clash.kind.label()}</strong>
+                            {format!(
+                                ": {} ({}) on {} at {} for {} min. {}.",
+                                clash.title,
+                                clash.module_code,
+                                clash.date,
+                                clash.time,
+                                clash.duration_minutes,
+                                clash.detail,
+                            )}
+                        </li>
+                    }).collect_view()}
+                </ul>
+                <label class="clash-override">
+                    <input type="checkbox" bind:checked=allow_clashes/>
+                    "Save anyway"
+                </label>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,136 @@
+use crate::database::excuses::ExcuseAttachment;
+use crate::routes::excuse_functions::{submit_excuse_fn, MAX_REASON_LENGTH};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+// Keep in step with the server's limit in `uploads`
+const MAX_ATTACHMENT_MB: f64 = 2.0;
+
+/// Modal where a student explains an absence and optionally attaches a document
+#[component]
+pub fn ExcuseForm(
+    class_id: i64,
+    class_title: String,
+    on_close: Callback<()>,
+    on_submitted: Callback<String>,
+) -> impl IntoView {
+    let reason = RwSignal::new(String::new());
+    let attachment = RwSignal::new(None::<ExcuseAttachment>);
+    let error = RwSignal::new(None::<String>);
+    let submitting = RwSignal::new(false);
+    let reading_file = RwSignal::new(false);
+
+    let on_file_change = move |ev: leptos::ev::Event| {
+        error.set(None);
+        attachment.set(None);
+
+        #[cfg(feature = "hydrate")]
+        {
+            use wasm_bindgen::JsCast;
+
+            let Some(input) = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+
+            if file.size() > MAX_ATTACHMENT_MB * 1024.0 * 1024.0 {
+                error.set(Some(format!(
+                    "Attachments can be at most {} MB",
+                    MAX_ATTACHMENT_MB
+                )));
+                input.set_value("");
+                return;
+            }
+
+            let file = gloo::file::File::from(file);
+            reading_file.set(true);
+            spawn_local(async move {
+                match gloo::file::futures::read_as_data_url(&file).await {
+                    Ok(data_url) => {
+                        // data:<type>;base64,<data>
+                        let data = data_url
+                            .split_once(";base64,")
+                            .map(|(_, data)| data.to_string())
+                            .unwrap_or_default();
+                        attachment.set(Some(ExcuseAttachment {
+                            file_name: file.name(),
+                            content_type: file.raw_mime_type(),
+                            data_base64: data,
+                        }));
+                    }
+                    Err(e) => error.set(Some(format!("Could not read file: {}", e))),
+                }
+                reading_file.set(false);
+            });
+        }
+
+        #[cfg(not(feature = "hydrate"))]
+        let _ = ev;
+    };
+
+    let on_submit = move |_| {
+        let reason_val = reason.get();
+        if reason_val.trim().is_empty() {
+            error.set(Some("Please give a reason for your absence".to_string()));
+            return;
+        }
+
+        submitting.set(true);
+        error.set(None);
+        let attachment_val = attachment.get();
+        spawn_local(async move {
+            match submit_excuse_fn(class_id, reason_val, attachment_val).await {
+                Ok(response) if response.success => on_submitted.run(response.message),
+                Ok(response) => error.set(Some(response.message)),
+                Err(e) => error.set(Some(e.to_string())),
+            }
+            submitting.set(false);
+        });
+    };
+
+    view! {
+        <div class="modal-overlay" on:click=move |_| on_close.run(())>
+            <div class="modal-content excuse-form" on:click=|e| e.stop_propagation()>
+                <h2 class="modal-title">"Excuse Absence"</h2>
+                <p class="modal-text">{class_title}</p>
+
+                <label class="label">"Reason"</label>
+                <textarea
+                    class="textarea"
+                    placeholder="e.g. I was ill and have a doctor's note"
+                    maxlength=MAX_REASON_LENGTH.to_string()
+                    bind:value=reason
+                ></textarea>
+
+                <label class="label" style="margin-top:12px;">"Supporting document (optional)"</label>
+                <input
+                    class="input"
+                    type="file"
+                    accept="application/pdf,image/png,image/jpeg,image/webp"
+                    on:change=on_file_change
+                />
+                <p class="muted" style="margin-top:4px; font-size:0.8rem;">
+                    {format!("PDF or image, up to {} MB", MAX_ATTACHMENT_MB)}
+                </p>
+
+                {move || error.get().map(|message| view! { <p class="error">{message}</p> })}
+
+                <div class="modal-actions">
+                    <button class="btn btn-outline" on:click=move |_| on_close.run(())>"Cancel"</button>
+                    <button
+                        class="btn btn-accent"
+                        on:click=on_submit
+                        disabled=move || submitting.get() || reading_file.get()
+                    >
+                        {move || if submitting.get() { "Sending..." } else { "Send Excuse" }}
+                    </button>
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,119 @@
+use crate::routes::excuse_functions::{
+    decide_excuse_fn, get_excuse_attachment_fn, get_pending_excuses_fn,
+};
+use leptos::prelude::*;
+
+/// Pending excuse requests across the signed-in lecturer's or tutor's modules
+#[component]
+pub fn ExcuseReviewPanel() -> impl IntoView {
+    let refresh = RwSignal::new(0u32);
+    let feedback = RwSignal::new(None::<(bool, String)>);
+
+    let excuses_resource = Resource::new(
+        move || refresh.get(),
+        |_| async move {
+            match get_pending_excuses_fn().await {
+                Ok(response) if response.success => response.requests,
+                _ => Vec::new(),
+            }
+        },
+    );
+
+    let decide_action = Action::new(move |(request_id, approve): &(i64, bool)| {
+        let request_id = *request_id;
+        let approve = *approve;
+        async move { decide_excuse_fn(request_id, approve).await }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = decide_action.value().get() {
+            match result {
+                Ok(response) => feedback.set(Some((response.success, response.message))),
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+            refresh.update(|n| *n = n.wrapping_add(1));
+        }
+    });
+
+    let open_attachment = move |request_id: i64| {
+        #[cfg(feature = "hydrate")]
+        {
+            use leptos::task::spawn_local;
+            use wasm_bindgen::JsCast;
+
+            spawn_local(async move {
+                match get_excuse_attachment_fn(request_id).await {
+                    Ok(attachment) => {
+                        let href = format!(
+                            "data:{};base64,{}",
+                            attachment.content_type, attachment.data_base64
+                        );
+                        let document = web_sys::window().unwrap().document().unwrap();
+                        let a = document.create_element("a").unwrap();
+                        a.set_attribute("href", &href).unwrap();
+                        a.set_attribute("download", &attachment.file_name).unwrap();
+                        a.dyn_into::<web_sys::HtmlElement>().unwrap().click();
+                    }
+                    Err(e) => feedback.set(Some((false, e.to_string()))),
+                }
+            });
+        }
+
+        #[cfg(not(feature = "hydrate"))]
+        {
+            let _ = (request_id, get_excuse_attachment_fn);
+        }
+    };
+
+    view! {
+        <Suspense fallback=|| ()>
+            <Show when=move || {
+                feedback.get().is_some()
+                    || excuses_resource.get().is_some_and(|requests| !requests.is_empty())
+            }>
+                <div class="excuse-review">
+                    <h3 class="heading">"Excuse Requests"</h3>
+                    {move || feedback.get().map(|(success, message)| view! {
+                        <p class=if success { "success" } else { "error" }>{message}</p>
+                    })}
+                    <div class="excuse-list">
+                        {move || excuses_resource.get().unwrap_or_default().into_iter().map(|request| {
+                            let request_id = request.request_id;
+                            let full_name = format!("{} {}", request.student_name, request.student_surname);
+                            let class_label = format!(
+                                "{} · {} · {} {}",
+                                request.module_code, request.class_title, request.class_date, request.class_time
+                            );
+                            view! {
+                                <div class="excuse-item">
+                                    <div class="excuse-info">
+                                        <div class="student-name">{full_name}</div>
+                                        <div class="muted">{class_label}</div>
+                                        <p class="excuse-reason">{request.reason.clone()}</p>
+                                        {request.attachment_name.clone().map(|name| view! {
+                                            <button class="link excuse-attachment" on:click=move |_| open_attachment(request_id)>
+                                                {format!("📎 {}", name)}
+                                            </button>
+                                        })}
+                                    </div>
+                                    <div class="excuse-actions">
+                                        <button
+                                            class="btn btn-primary btn-small"
+                                            disabled=move || decide_action.pending().get()
+                                            on:click=move |_| { decide_action.dispatch((request_id, true)); }
+                                        >"Approve"</button>
+                                        <button
+                                            class="btn btn-outline btn-small"
+                                            disabled=move || decide_action.pending().get()
+                                            on:click=move |_| { decide_action.dispatch((request_id, false)); }
+                                        >"Reject"</button>
+                                    </div>
+                                </div>
+                            }
+                        }).collect_view()}
+                    </div>
+                </div>
+            </Show>
+        </Suspense>
+    }
+}
@@ -0,0 +1,58 @@
+use crate::routes::group_functions::get_module_groups_fn;
+use leptos::prelude::*;
+
+/// Checkboxes for the tutorial groups a class is for. None ticked means the whole
+/// module; modules without groups show nothing.
+#[component]
+pub fn GroupPicker(
+    #[prop(into)] module_code: Signal<String>,
+    selected: RwSignal<Vec<i64>>,
+) -> impl IntoView {
+    let groups_resource = Resource::new(
+        move || module_code.get(),
+        |code| async move {
+            if code.is_empty() {
+                return Vec::new();
+            }
+            match get_module_groups_fn(code).await {
+                Ok(response) if response.success => response.groups,
+                _ => Vec::new(),
+            }
+        },
+    );
+
+    view! {
+        <Suspense fallback=|| ()>
+            {move || {
+                let groups = groups_resource.get().unwrap_or_default();
+                (!groups.is_empty()).then(|| view! {
+                    <label class="label" style="margin-top:10px;">"Tutorial groups"</label>
+                    <div style="display:flex; flex-wrap:wrap; gap:12px;">
+                        {groups.into_iter().map(|group| {
+                            let group_id = group.group_id;
+                            view! {
+                                <label class="muted" style="display:flex; align-items:center; gap:4px;">
+                                    <input
+                                        type="checkbox"
+                                        prop:checked=move || selected.with(|ids| ids.contains(&group_id))
+                                        on:change=move |_| selected.update(|ids| {
+                                            if let Some(index) = ids.iter().position(|id| *id == group_id) {
+                                                ids.remove(index);
+                                            } else {
+                                                ids.push(group_id);
+                                            }
+                                        })
+                                    />
+                                    {format!("{} ({})", group.name, group.member_count)}
+                                </label>
+                            }
+                        }).collect_view()}
+                    </div>
+                    <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                        "Leave all unticked for the whole module."
+                    </p>
+                })
+            }}
+        </Suspense>
+    }
+}
@@ -3,24 +3,44 @@ pub mod nav_bar;
 pub use nav_bar::NavBar;
 
 pub mod calendar;
+pub mod calendar_feed;
+pub mod clash_warnings;
 pub mod class_list;
+pub mod excuse_form;
+pub mod excuse_review;
+pub mod group_picker;
 pub mod header;
 pub mod module_card;
+pub mod module_invitations;
 pub mod module_card_tailwind;
 pub mod qr_scanner;
+pub mod recurrence_fields;
 pub mod stat_tile;
 pub mod top_bar;
+pub mod tutorial_groups;
+pub mod university_timezone;
+pub mod venue_options;
 pub mod clockit_logo;
 pub mod theme_switcher;
 
 pub use calendar::Calendar;
+pub use calendar_feed::CalendarFeedPanel;
+pub use clash_warnings::ClashWarnings;
 pub use class_list::ClassList;
+pub use excuse_form::ExcuseForm;
+pub use excuse_review::ExcuseReviewPanel;
+pub use group_picker::GroupPicker;
 pub use header::Header;
 pub use module_card::ModuleCard as moduleCardOld;
 pub use module_card_tailwind::ModuleCard;
+pub use module_invitations::ModuleJoinPanel;
 pub use qr_scanner::QrScanner;
+pub use recurrence_fields::{RecurrenceFields, RecurrenceForm};
 pub use stat_tile::StatTile;
 pub use top_bar::TopBar;
+pub use tutorial_groups::TutorialGroupsPanel;
+pub use university_timezone::UniversityTimezonePanel;
+pub use venue_options::VenueOptions;
 pub use clockit_logo::*;
 pub use theme_switcher::ThemeSwitcher;
 
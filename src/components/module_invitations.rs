@@ -0,0 +1,180 @@
+use crate::database::invitations::ModuleInvitation;
+use crate::routes::invitation_functions::{
+    cancel_invitation_fn, get_join_code_fn, get_module_invitations_fn, reset_join_code_fn,
+};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+/// The module's join code and link for students to enroll themselves, and the emails
+/// invited by a bulk import that haven't registered yet. The invitations reload
+/// whenever `refresh` changes, e.g. after an import.
+#[component]
+pub fn ModuleJoinPanel(
+    #[prop(into)] module_code: Signal<String>,
+    #[prop(into)] refresh: Signal<usize>,
+) -> impl IntoView {
+    let code = RwSignal::new(String::new());
+    let path = RwSignal::new(String::new());
+    let origin = RwSignal::new(String::new());
+    let invitations = RwSignal::new(Vec::<ModuleInvitation>::new());
+    let feedback = RwSignal::new(None::<(bool, String)>);
+
+    // The link has to be absolute, and only the browser knows which host it reached
+    Effect::new(move |_| {
+        if let Ok(current) = location().origin() {
+            origin.set(current);
+        }
+    });
+
+    let reload = move || {
+        let module = module_code.get_untracked();
+        if module.is_empty() {
+            return;
+        }
+        spawn_local(async move {
+            match get_join_code_fn(module.clone()).await {
+                Ok(response) => {
+                    code.set(response.code);
+                    path.set(response.path);
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+            match get_module_invitations_fn(module).await {
+                Ok(response) => invitations.set(response.invitations),
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        module_code.track();
+        refresh.track();
+        reload();
+    });
+
+    let reset_action = Action::new(|module: &String| {
+        let module = module.clone();
+        async move { reset_join_code_fn(module).await }
+    });
+    let cancel_action = Action::new(|(module, email): &(String, String)| {
+        let (module, email) = (module.clone(), email.clone());
+        async move { cancel_invitation_fn(module, email).await }
+    });
+    let reset_pending = reset_action.pending();
+
+    Effect::new(move |_| {
+        if let Some(result) = reset_action.value().get() {
+            match result {
+                Ok(response) => {
+                    code.set(response.code);
+                    path.set(response.path);
+                    feedback.set(Some((response.success, response.message)));
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = cancel_action.value().get() {
+            match result {
+                Ok(response) => {
+                    invitations.set(response.invitations);
+                    feedback.set(Some((response.success, response.message)));
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    let join_url = move || {
+        let path = path.get();
+        if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", origin.get(), path)
+        }
+    };
+
+    let on_reset = move |_| {
+        feedback.set(None);
+        reset_action.dispatch(module_code.get());
+    };
+
+    view! {
+        <div class="heading">"Join Code"</div>
+        <p class="muted" style="font-size:0.85rem;">
+            "Students can enter this code on their home page, or open the link, to enroll themselves from today."
+        </p>
+
+        {move || feedback.get().map(|(ok, text)| view! {
+            <p class=if ok { "success center" } else { "error center" } style="margin-top:8px;">{text}</p>
+        })}
+
+        <div class="card" style="margin-top:10px; padding:16px;">
+            <div style="display:flex; align-items:center; gap:12px; flex-wrap:wrap;">
+                <span style="font-family:monospace; font-size:1.4rem; letter-spacing:0.15em;">
+                    {move || code.get()}
+                </span>
+                <button
+                    class="btn btn-outline btn-small"
+                    type="button"
+                    on:click=on_reset
+                    disabled=move || reset_pending.get() || code.get().is_empty()
+                >
+                    {move || if reset_pending.get() { "Resetting..." } else { "Reset Code" }}
+                </button>
+            </div>
+            <input
+                class="input"
+                type="text"
+                readonly=true
+                aria-label="Join link"
+                style="margin-top:10px;"
+                prop:value=join_url
+                on:focus=move |ev| {
+                    event_target::<web_sys::HtmlInputElement>(&ev).select();
+                }
+            />
+        </div>
+
+        <Show when=move || !invitations.get().is_empty()>
+            <label class="label" style="margin-top:14px;">"Waiting to register"</label>
+            <p class="muted" style="font-size:0.85rem;">
+                "These emails had no account when they were imported. They are enrolled as soon as they sign up."
+            </p>
+            <div class="card" style="padding:0; margin-top:6px;">
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>"Email"</th>
+                            <th>"Invited"</th>
+                            <th>"Action"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || invitations.get().into_iter().map(|invitation| {
+                            let email = invitation.email_address.clone();
+                            let invited = invitation.created_at.chars().take(10).collect::<String>();
+                            view! {
+                                <tr>
+                                    <td>{invitation.email_address.clone()}</td>
+                                    <td>{invited}</td>
+                                    <td>
+                                        <button
+                                            class="btn btn-outline btn-small"
+                                            style="color:#ef4444; border-color:#fecaca;"
+                                            on:click=move |_| {
+                                                cancel_action.dispatch((module_code.get(), email.clone()));
+                                            }
+                                        >"Cancel"</button>
+                                    </td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            </div>
+        </Show>
+    }
+}
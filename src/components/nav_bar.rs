@@ -17,6 +17,8 @@ pub fn NavBar() -> impl IntoView {
                 <NavLink href="/home" label="Home" icon_type="home"/>
                 <NavLink href="/timetable" label="Timetable" icon_type="calendar"/>
                 <NavLink href="/statistics" label="Statistics" icon_type="chart"/>
+                <NavLink href="/venues" label="Venues" icon_type="pin"/>
+                <NavLink href="/calendar" label="Calendar" icon_type="flag"/>
             </nav>
             <div class="sidebar-footer">
                 <button class="signout" on:click=handle_signout>"Sign Out"</button>
@@ -70,6 +72,18 @@ fn NavLink(href: &'static str, label: &'static str, icon_type: &'static str) ->
                             <line x1="6" y1="20" x2="6" y2="14"></line>
                         </svg>
                     }.into_any(),
+                    "pin" => view! {
+                        <svg class="nav-icon" xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                            <path d="M21 10c0 7-9 13-9 13s-9-6-9-13a9 9 0 0 1 18 0z"></path>
+                            <circle cx="12" cy="10" r="3"></circle>
+                        </svg>
+                    }.into_any(),
+                    "flag" => view! {
+                        <svg class="nav-icon" xmlns="http://www.w3.org/2000/svg" width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2" stroke-linecap="round" stroke-linejoin="round">
+                            <path d="M4 15s1-1 4-1 5 2 8 2 4-1 4-1V3s-1 1-4 1-5-2-8-2-4 1-4 1z"></path>
+                            <line x1="4" y1="22" x2="4" y2="15"></line>
+                        </svg>
+                    }.into_any(),
                     _ => view! { <span></span> }.into_any(),
                 }}
             </span>
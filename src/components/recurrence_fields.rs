@@ -0,0 +1,384 @@
+use crate::database::calendar::AcademicCalendar;
+use crate::database::class_series::Recurrence;
+use crate::routes::calendar_functions::get_academic_calendar_fn;
+use crate::utils::recurrence::{
+    avoid_blackouts, parse_exdates, BlackoutPolicy, Frequency, RecurrenceEnd, RecurrenceRule,
+    WeekdayNum, MAX_OCCURRENCES,
+};
+use chrono::{Datelike, NaiveDate, Weekday};
+use leptos::prelude::*;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// What the repeat fields of the class forms hold, kept as text for input binding
+#[derive(Clone, Copy)]
+pub struct RecurrenceForm {
+    /// "No repeat", "Daily", "Weekly" or "Monthly"
+    pub frequency: RwSignal<String>,
+    pub interval: RwSignal<String>,
+    /// Weekly only; none selected repeats on the start date's weekday
+    pub weekdays: RwSignal<Vec<Weekday>>,
+    /// Monthly only: "day" for the same day of the month, "weekday" for e.g. the 2nd Tuesday
+    pub monthly_by: RwSignal<String>,
+    /// "count" or "until"
+    pub ends: RwSignal<String>,
+    pub count: RwSignal<String>,
+    pub until: RwSignal<String>,
+    pub exdates: RwSignal<String>,
+    /// "skip" or "shift", for classes landing on a holiday or in a recess
+    pub blackouts: RwSignal<String>,
+}
+
+impl RecurrenceForm {
+    pub fn new() -> Self {
+        Self {
+            frequency: RwSignal::new("No repeat".to_string()),
+            interval: RwSignal::new("1".to_string()),
+            weekdays: RwSignal::new(Vec::new()),
+            monthly_by: RwSignal::new("day".to_string()),
+            ends: RwSignal::new("count".to_string()),
+            count: RwSignal::new("8".to_string()),
+            until: RwSignal::new(String::new()),
+            exdates: RwSignal::new(String::new()),
+            blackouts: RwSignal::new(BlackoutPolicy::Skip.as_str().to_string()),
+        }
+    }
+
+    /// Fill the fields from a saved series
+    pub fn load(&self, recurrence: &Recurrence) {
+        let Ok((rule, exdates)) = recurrence.parse() else {
+            return;
+        };
+        self.frequency.set(
+            match rule.frequency {
+                Frequency::Daily => "Daily",
+                Frequency::Weekly => "Weekly",
+                Frequency::Monthly => "Monthly",
+            }
+            .to_string(),
+        );
+        self.interval.set(rule.interval.to_string());
+        self.weekdays
+            .set(rule.by_day.iter().map(|day| day.weekday).collect());
+        self.monthly_by.set(
+            if rule.by_day.iter().any(|day| day.ordinal.is_some()) {
+                "weekday"
+            } else {
+                "day"
+            }
+            .to_string(),
+        );
+        match rule.end {
+            RecurrenceEnd::Count(count) => {
+                self.ends.set("count".to_string());
+                self.count.set(count.to_string());
+            }
+            RecurrenceEnd::Until(until) => {
+                self.ends.set("until".to_string());
+                self.until.set(until.format("%Y-%m-%d").to_string());
+            }
+        }
+        self.exdates.set(
+            exdates
+                .iter()
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        self.blackouts
+            .set(recurrence.blackouts.as_str().to_string());
+    }
+
+    /// The series the fields describe for classes starting on `start_date`, or `None`
+    /// for "No repeat"
+    pub fn recurrence(&self, start_date: &str) -> Result<Option<Recurrence>, String> {
+        let Some(rule) = self.rule(start_date)? else {
+            return Ok(None);
+        };
+        let exdates = parse_exdates(&self.exdates.get())?;
+        Ok(Some(Recurrence {
+            rrule: rule.to_string(),
+            exdates: exdates
+                .iter()
+                .map(|date| date.format("%Y-%m-%d").to_string())
+                .collect(),
+            blackouts: BlackoutPolicy::parse(&self.blackouts.get()),
+        }))
+    }
+
+    fn rule(&self, start_date: &str) -> Result<Option<RecurrenceRule>, String> {
+        let frequency = match self.frequency.get().as_str() {
+            "Daily" => Frequency::Daily,
+            "Weekly" => Frequency::Weekly,
+            "Monthly" => Frequency::Monthly,
+            _ => return Ok(None),
+        };
+        let start = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+            .map_err(|_| "Please select a date".to_string())?;
+        let interval = self
+            .interval
+            .get()
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|interval| *interval > 0)
+            .ok_or("Please enter how often the class repeats")?;
+
+        let by_day = match frequency {
+            Frequency::Daily => Vec::new(),
+            Frequency::Weekly => self
+                .weekdays
+                .get()
+                .into_iter()
+                .map(|weekday| WeekdayNum {
+                    ordinal: None,
+                    weekday,
+                })
+                .collect(),
+            Frequency::Monthly if self.monthly_by.get() == "weekday" => {
+                // The 5th of a weekday is always the month's last
+                let nth = (start.day() as i32 - 1) / 7 + 1;
+                vec![WeekdayNum {
+                    ordinal: Some(if nth == 5 { -1 } else { nth }),
+                    weekday: start.weekday(),
+                }]
+            }
+            Frequency::Monthly => Vec::new(),
+        };
+
+        let end = if self.ends.get() == "until" {
+            let until = NaiveDate::parse_from_str(&self.until.get(), "%Y-%m-%d")
+                .map_err(|_| "Please choose the date of the last class".to_string())?;
+            if until < start {
+                return Err("The last class can't be before the first".to_string());
+            }
+            RecurrenceEnd::Until(until)
+        } else {
+            let count = self
+                .count
+                .get()
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|count| (1..=MAX_OCCURRENCES as u32).contains(count))
+                .ok_or_else(|| {
+                    format!("Please enter between 1 and {} occurrences", MAX_OCCURRENCES)
+                })?;
+            RecurrenceEnd::Count(count)
+        };
+
+        Ok(Some(RecurrenceRule {
+            frequency,
+            interval,
+            by_day,
+            end,
+        }))
+    }
+}
+
+impl Default for RecurrenceForm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Repeat pattern fields shared by the new and edit class forms
+#[component]
+pub fn RecurrenceFields(
+    form: RecurrenceForm,
+    /// Date of the first class
+    #[prop(into)]
+    start_date: Signal<String>,
+) -> impl IntoView {
+    let repeating = move || form.frequency.get() != "No repeat";
+
+    let calendar_resource = Resource::new(
+        || (),
+        |_| async move {
+            match get_academic_calendar_fn().await {
+                Ok(response) if response.success => response.calendar,
+                _ => AcademicCalendar::default(),
+            }
+        },
+    );
+    let calendar = move || calendar_resource.get().unwrap_or_default();
+
+    // The term the first class falls in, to offer ending the series with it
+    let term = move || {
+        let start = NaiveDate::parse_from_str(&start_date.get(), "%Y-%m-%d").ok()?;
+        calendar().term_on(start).cloned()
+    };
+
+    let preview = move || {
+        let start = start_date.get();
+        let rule = match form.rule(&start) {
+            Ok(Some(rule)) => rule,
+            Ok(None) => return String::new(),
+            Err(e) => return e,
+        };
+        let exdates = match parse_exdates(&form.exdates.get()) {
+            Ok(exdates) => exdates,
+            Err(e) => return e,
+        };
+        let start = NaiveDate::parse_from_str(&start, "%Y-%m-%d").unwrap_or_default();
+        let pattern = rule.occurrences(start, &exdates);
+        let calendar = calendar();
+        let dates = avoid_blackouts(
+            &pattern,
+            |date| calendar.is_blackout(date),
+            BlackoutPolicy::parse(&form.blackouts.get()),
+        );
+        let on_blackouts = pattern
+            .iter()
+            .filter(|date| calendar.is_blackout(**date))
+            .count();
+        let Some(last) = dates.last() else {
+            return "Every class in this pattern is skipped".to_string();
+        };
+        let mut preview = format!(
+            "{}: this will create {} class instances, the last on {}",
+            rule.describe(),
+            dates.len(),
+            last.format("%a %Y-%m-%d")
+        );
+        if on_blackouts > 0 {
+            preview.push_str(&format!(
+                ". {} fall on holidays or recess and are {}.",
+                on_blackouts,
+                if form.blackouts.get() == "shift" {
+                    "moved where possible"
+                } else {
+                    "skipped"
+                }
+            ));
+        }
+        preview
+    };
+
+    view! {
+        <label class="label" style="margin-top:10px;">"Recurring"</label>
+        <select class="input" bind:value=form.frequency>
+            <option selected>"No repeat"</option>
+            <option>"Daily"</option>
+            <option>"Weekly"</option>
+            <option>"Monthly"</option>
+        </select>
+
+        <Show when=repeating>
+            <div style="margin-top:10px;">
+                <label class="label">"Repeat every"</label>
+                <div style="display:flex; align-items:center; gap:12px;">
+                    <input
+                        type="number"
+                        class="input"
+                        min="1"
+                        max="12"
+                        bind:value=form.interval
+                        style="max-width:120px;"
+                    />
+                    <span class="muted" style="font-size:13px;">
+                        {move || match form.frequency.get().as_str() {
+                            "Daily" => "days",
+                            "Weekly" => "weeks",
+                            "Monthly" => "months",
+                            _ => ""
+                        }}
+                    </span>
+                </div>
+
+                <Show when=move || form.frequency.get() == "Weekly">
+                    <label class="label" style="margin-top:10px;">"On"</label>
+                    <div style="display:flex; flex-wrap:wrap; gap:12px;">
+                        {WEEKDAYS.into_iter().map(|weekday| view! {
+                            <label class="muted" style="display:flex; align-items:center; gap:4px;">
+                                <input
+                                    type="checkbox"
+                                    prop:checked=move || form.weekdays.with(|days| days.contains(&weekday))
+                                    on:change=move |_| form.weekdays.update(|days| {
+                                        if let Some(index) = days.iter().position(|day| *day == weekday) {
+                                            days.remove(index);
+                                        } else {
+                                            days.push(weekday);
+                                            days.sort_by_key(|day| day.num_days_from_monday());
+                                        }
+                                    })
+                                />
+                                {weekday.to_string()}
+                            </label>
+                        }).collect_view()}
+                    </div>
+                </Show>
+
+                <Show when=move || form.frequency.get() == "Monthly">
+                    <label class="label" style="margin-top:10px;">"On"</label>
+                    <select class="input" bind:value=form.monthly_by>
+                        <option value="day">"The same day of the month"</option>
+                        <option value="weekday">"The same weekday, e.g. the 2nd Tuesday"</option>
+                    </select>
+                </Show>
+
+                <label class="label" style="margin-top:10px;">"Ends"</label>
+                <div style="display:flex; align-items:center; gap:12px;">
+                    <select class="input" bind:value=form.ends style="max-width:140px;">
+                        <option value="count">"After"</option>
+                        <option value="until">"On"</option>
+                    </select>
+                    <Show
+                        when=move || form.ends.get() == "until"
+                        fallback=move || view! {
+                            <input
+                                type="number"
+                                class="input"
+                                min="1"
+                                max=MAX_OCCURRENCES
+                                bind:value=form.count
+                                style="max-width:120px;"
+                            />
+                            <span class="muted" style="font-size:13px;">"occurrences"</span>
+                        }
+                    >
+                        <input type="date" class="input" bind:value=form.until style="max-width:180px;"/>
+                    </Show>
+                    <Suspense fallback=|| ()>
+                        {move || term().map(|term| {
+                            let end_date = term.end_date.clone();
+                            view! {
+                                <button
+                                    type="button"
+                                    class="btn btn-outline btn-small"
+                                    on:click=move |_| {
+                                        form.ends.set("until".to_string());
+                                        form.until.set(end_date.clone());
+                                    }
+                                >
+                                    {format!("End of {}", term.name)}
+                                </button>
+                            }
+                        })}
+                    </Suspense>
+                </div>
+
+                <label class="label" style="margin-top:10px;">"Skip dates"</label>
+                <input class="input" placeholder="2025-04-18, 2025-04-21" bind:value=form.exdates/>
+
+                <label class="label" style="margin-top:10px;">"On holidays and recess"</label>
+                <select class="input" bind:value=form.blackouts>
+                    <option value="skip">"Skip the class"</option>
+                    <option value="shift">"Move it to the next free weekday"</option>
+                </select>
+
+                <Suspense fallback=|| ()>
+                    <p class="muted" style="font-size:12px; margin-top:6px;">{preview}</p>
+                </Suspense>
+            </div>
+        </Show>
+    }
+}
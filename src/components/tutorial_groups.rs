@@ -0,0 +1,272 @@
+use crate::database::groups::{GroupRequest, TutorialGroup};
+use crate::routes::group_functions::{
+    delete_group_fn, get_group_members_fn, get_module_groups_fn, save_group_fn,
+    set_group_members_fn,
+};
+use crate::routes::student_functions::get_module_tutors;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+/// A module's tutorial groups: create, rename and give them a tutor, and set who is
+/// in each by pasting the members' emails
+#[component]
+pub fn TutorialGroupsPanel(#[prop(into)] module_code: Signal<String>) -> impl IntoView {
+    let groups = RwSignal::new(Vec::<TutorialGroup>::new());
+    let feedback = RwSignal::new(None::<(bool, String)>);
+
+    // The group being created (None) or renamed, while the form is open
+    let form_open = RwSignal::new(false);
+    let editing = RwSignal::new(None::<i64>);
+    let name = RwSignal::new(String::new());
+    let tutor = RwSignal::new(String::new());
+
+    // The group whose members are being edited, one email per line
+    let members_of = RwSignal::new(None::<TutorialGroup>);
+    let members_text = RwSignal::new(String::new());
+
+    let reload = move || {
+        let code = module_code.get_untracked();
+        if code.is_empty() {
+            return;
+        }
+        spawn_local(async move {
+            match get_module_groups_fn(code).await {
+                Ok(response) => groups.set(response.groups),
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        });
+    };
+
+    Effect::new(move |_| {
+        module_code.track();
+        reload();
+    });
+
+    let tutors_resource = Resource::new(
+        move || module_code.get(),
+        |code| async move {
+            if code.is_empty() {
+                return Vec::new();
+            }
+            get_module_tutors(code).await.unwrap_or_default()
+        },
+    );
+
+    let save_action = Action::new(
+        |(module, group_id, request): &(String, Option<i64>, GroupRequest)| {
+            let (module, group_id, request) = (module.clone(), *group_id, request.clone());
+            async move { save_group_fn(module, group_id, request).await }
+        },
+    );
+    let delete_action = Action::new(|group_id: &i64| {
+        let group_id = *group_id;
+        async move { delete_group_fn(group_id).await }
+    });
+    let members_action = Action::new(|(group_id, emails): &(i64, Vec<String>)| {
+        let (group_id, emails) = (*group_id, emails.clone());
+        async move { set_group_members_fn(group_id, emails).await }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = save_action.value().get() {
+            match result {
+                Ok(response) => {
+                    feedback.set(Some((response.success, response.message)));
+                    form_open.set(false);
+                    reload();
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = delete_action.value().get() {
+            match result {
+                Ok(response) => {
+                    feedback.set(Some((response.success, response.message)));
+                    reload();
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = members_action.value().get() {
+            match result {
+                Ok(response) => {
+                    feedback.set(Some((response.success, response.message)));
+                    members_of.set(None);
+                    reload();
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        }
+    });
+
+    let open_form = move |group: Option<TutorialGroup>| {
+        editing.set(group.as_ref().map(|g| g.group_id));
+        name.set(group.as_ref().map(|g| g.name.clone()).unwrap_or_default());
+        tutor.set(group.and_then(|g| g.tutor_email).unwrap_or_default());
+        members_of.set(None);
+        form_open.set(true);
+    };
+
+    let open_members = move |group: TutorialGroup| {
+        let group_id = group.group_id;
+        form_open.set(false);
+        members_text.set(String::new());
+        members_of.set(Some(group));
+        spawn_local(async move {
+            match get_group_members_fn(group_id).await {
+                Ok(response) => members_text.set(response.members.join("\n")),
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        });
+    };
+
+    let on_save = move |_| {
+        let request = GroupRequest {
+            name: name.get(),
+            tutor_email: Some(tutor.get()).filter(|email| !email.trim().is_empty()),
+        };
+        if let Err(e) = request.validate() {
+            feedback.set(Some((false, e)));
+            return;
+        }
+        save_action.dispatch((module_code.get(), editing.get(), request));
+    };
+
+    let on_save_members = move |_| {
+        let Some(group) = members_of.get() else {
+            return;
+        };
+        let emails = members_text
+            .get()
+            .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+            .map(str::trim)
+            .filter(|email| !email.is_empty())
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        members_action.dispatch((group.group_id, emails));
+    };
+
+    view! {
+        <div class="heading" style="display:flex; align-items:center; justify-content:space-between;">
+            <span>"Tutorial Groups"</span>
+            <button class="btn btn-accent" on:click=move |_| open_form(None)>"+ Add Group"</button>
+        </div>
+        <p class="muted" style="font-size:0.85rem;">
+            "Classes aimed at groups only expect their members. Classes without groups are for the whole module."
+        </p>
+
+        {move || feedback.get().map(|(ok, text)| view! {
+            <p class=if ok { "success center" } else { "error center" } style="margin-top:8px;">{text}</p>
+        })}
+
+        <Show when=move || form_open.get()>
+            <div class="card" style="margin-top:10px; padding:16px;">
+                <label class="label">"Group name"</label>
+                <input class="input" placeholder="Tutorial A" bind:value=name/>
+
+                <label class="label" style="margin-top:10px;">"Tutor"</label>
+                <input
+                    class="input"
+                    type="email"
+                    placeholder="Blank for no tutor"
+                    list="group-tutor-options"
+                    bind:value=tutor
+                />
+                <datalist id="group-tutor-options">
+                    <Suspense fallback=|| ()>
+                        {move || tutors_resource.get().unwrap_or_default().into_iter().map(|t| {
+                            let label = format!("{} {}", t.name, t.surname);
+                            view! { <option value=t.email_address>{label}</option> }
+                        }).collect_view()}
+                    </Suspense>
+                </datalist>
+
+                <div class="modal-actions" style="margin-top:12px;">
+                    <button class="btn btn-outline" on:click=move |_| form_open.set(false)>"Cancel"</button>
+                    <button class="btn btn-accent" on:click=on_save disabled=move || save_action.pending().get()>
+                        {move || if editing.get().is_some() { "Save Group" } else { "Create Group" }}
+                    </button>
+                </div>
+            </div>
+        </Show>
+
+        {move || members_of.get().map(|group| view! {
+            <div class="card" style="margin-top:10px; padding:16px;">
+                <label class="label">{format!("Members of {}", group.name)}</label>
+                <p class="muted" style="font-size:0.85rem;">
+                    "One email per line or comma-separated. Everyone has to be enrolled in the module."
+                </p>
+                <textarea
+                    class="textarea"
+                    placeholder="student1@university.ac.za\nstudent2@university.ac.za"
+                    bind:value=members_text
+                    style="min-height:160px;"
+                ></textarea>
+
+                <div class="modal-actions" style="margin-top:12px;">
+                    <button class="btn btn-outline" on:click=move |_| members_of.set(None)>"Cancel"</button>
+                    <button class="btn btn-accent" on:click=on_save_members disabled=move || members_action.pending().get()>
+                        "Save Members"
+                    </button>
+                </div>
+            </div>
+        })}
+
+        <div class="card" style="padding:0; margin-top:10px;">
+            <Show
+                when=move || !groups.get().is_empty()
+                fallback=|| view! {
+                    <p style="padding:20px; text-align:center; color:#6b7280;">
+                        "No tutorial groups yet. Every class is for the whole module."
+                    </p>
+                }
+            >
+                <table class="table">
+                    <thead>
+                        <tr>
+                            <th>"Group"</th>
+                            <th>"Tutor"</th>
+                            <th>"Members"</th>
+                            <th>"Action"</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        {move || groups.get().into_iter().map(|group| {
+                            let for_edit = group.clone();
+                            let for_members = group.clone();
+                            let group_id = group.group_id;
+                            view! {
+                                <tr>
+                                    <td>{group.name.clone()}</td>
+                                    <td>{group.tutor_email.clone().unwrap_or_else(|| "—".to_string())}</td>
+                                    <td>{group.member_count}</td>
+                                    <td style="display:flex; gap:6px;">
+                                        <button
+                                            class="btn btn-outline btn-small"
+                                            on:click=move |_| open_form(Some(for_edit.clone()))
+                                        >"Edit"</button>
+                                        <button
+                                            class="btn btn-outline btn-small"
+                                            on:click=move |_| open_members(for_members.clone())
+                                        >"Members"</button>
+                                        <button
+                                            class="btn btn-outline btn-small"
+                                            style="color:#ef4444; border-color:#fecaca;"
+                                            on:click=move |_| { delete_action.dispatch(group_id); }
+                                        >"🗑 Delete"</button>
+                                    </td>
+                                </tr>
+                            }
+                        }).collect_view()}
+                    </tbody>
+                </table>
+            </Show>
+        </div>
+    }
+}
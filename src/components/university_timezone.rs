@@ -0,0 +1,94 @@
+use crate::routes::university_functions::{get_university_timezone_fn, set_university_timezone_fn};
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+
+/// The timezone the user's university schedules classes in. Lecturers can change it;
+/// tutors only see it.
+#[component]
+pub fn UniversityTimezonePanel(#[prop(into)] can_edit: Signal<bool>) -> impl IntoView {
+    let university = RwSignal::new(String::new());
+    let timezone = RwSignal::new(String::new());
+    let saved = RwSignal::new(String::new());
+    let feedback = RwSignal::new(None::<(bool, String)>);
+    let busy = RwSignal::new(false);
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match get_university_timezone_fn().await {
+                Ok(response) => {
+                    university.set(response.university);
+                    timezone.set(response.timezone.clone());
+                    saved.set(response.timezone);
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+        });
+    });
+
+    let on_save = move |_| {
+        busy.set(true);
+        feedback.set(None);
+        let chosen = timezone.get();
+        spawn_local(async move {
+            match set_university_timezone_fn(chosen).await {
+                Ok(response) => {
+                    timezone.set(response.timezone.clone());
+                    saved.set(response.timezone);
+                    feedback.set(Some((response.success, response.message)));
+                }
+                Err(e) => feedback.set(Some((false, e.to_string()))),
+            }
+            busy.set(false);
+        });
+    };
+
+    view! {
+        <div class="card" style="margin-bottom:16px;">
+            <h3 class="heading">"Timezone"</h3>
+            <p class="muted" style="font-size:0.85rem;">
+                {move || format!(
+                    "Class times at {} are in this timezone. Changing it keeps every class at the same clock time in the new zone.",
+                    if university.get().is_empty() { "your university".to_string() } else { university.get() },
+                )}
+            </p>
+
+            {move || feedback.get().map(|(ok, text)| view! {
+                <p class=if ok { "success center" } else { "error center" } style="margin-top:8px;">{text}</p>
+            })}
+
+            <div style="display:flex; gap:12px; align-items:flex-end; margin-top:10px;">
+                <div style="flex:1;">
+                    <label class="label" for="university-timezone">"Timezone"</label>
+                    <select
+                        id="university-timezone"
+                        class="input"
+                        disabled=move || !can_edit.get()
+                        prop:value=move || timezone.get()
+                        on:change=move |ev| timezone.set(event_target_value(&ev))
+                    >
+                        {chrono_tz::TZ_VARIANTS
+                            .iter()
+                            .map(|tz| {
+                                let name = tz.name();
+                                view! {
+                                    <option value=name selected=move || timezone.get() == name>
+                                        {name}
+                                    </option>
+                                }
+                            })
+                            .collect_view()}
+                    </select>
+                </div>
+                <Show when=move || can_edit.get()>
+                    <button
+                        class="btn btn-accent"
+                        on:click=on_save
+                        disabled=move || busy.get() || timezone.get() == saved.get()
+                    >
+                        {move || if busy.get() { "Saving..." } else { "Save Timezone" }}
+                    </button>
+                </Show>
+            </div>
+        </div>
+    }
+}
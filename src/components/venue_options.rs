@@ -0,0 +1,31 @@
+use crate::routes::venue_functions::get_venues_fn;
+use leptos::prelude::*;
+
+/// `<datalist>` of saved venues, for a venue input's `list` attribute
+#[component]
+pub fn VenueOptions(id: &'static str) -> impl IntoView {
+    let venues_resource = Resource::new(
+        || (),
+        |_| async move {
+            match get_venues_fn().await {
+                Ok(response) if response.success => response.venues,
+                _ => Vec::new(),
+            }
+        },
+    );
+
+    view! {
+        <datalist id=id>
+            <Suspense fallback=|| ()>
+                {move || venues_resource.get().unwrap_or_default().into_iter().map(|venue| {
+                    let label = [venue.building.clone(), venue.campus.clone()]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    view! { <option value=venue.name>{label}</option> }
+                }).collect_view()}
+            </Suspense>
+        </datalist>
+    }
+}
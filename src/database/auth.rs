@@ -1,28 +1,151 @@
 #[cfg(feature = "ssr")]
 use crate::database::models::{CreateUserRequest, User};
 #[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
 use crate::types::UserProfile;
 #[cfg(feature = "ssr")]
 use chrono::Utc;
 #[cfg(feature = "ssr")]
 use sqlx::SqlitePool;
 
-/// Simple password hashing (for development only - replace with bcrypt in production)
+/// Prefix used by the original development-only `DefaultHasher` scheme.
+/// Hashes with this prefix are still accepted at login and upgraded in place.
+#[cfg(feature = "ssr")]
+const LEGACY_HASH_PREFIX: &str = "simple_hash_";
+
+/// Outcome of checking a password against a stored hash
 #[cfg(feature = "ssr")]
-fn hash_password(password: &str) -> String {
-    // Simple hash for development - in production use bcrypt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PasswordCheck {
+    Invalid,
+    Valid,
+    /// Password matched, but the stored hash is legacy or uses outdated Argon2 parameters
+    ValidNeedsRehash,
+}
+
+/// Read a positive integer tuning knob from the environment, falling back to `default`.
+#[cfg(feature = "ssr")]
+fn argon2_env_param(name: &str, default: u32) -> u32 {
+    match std::env::var(name) {
+        Ok(raw) => match raw.trim().parse::<u32>() {
+            Ok(value) if value > 0 => value,
+            _ => {
+                println!("⚠️  Invalid {} '{}'; using default {}.", name, raw, default);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Argon2id cost parameters. Defaults follow the OWASP recommendation
+/// (19 MiB, 2 iterations, 1 lane) and can be tuned per deployment.
+#[cfg(feature = "ssr")]
+fn argon2_params() -> Result<argon2::Params, ClockItError> {
+    argon2::Params::new(
+        argon2_env_param("CLOCK_IT_ARGON2_MEMORY_KIB", 19 * 1024),
+        argon2_env_param("CLOCK_IT_ARGON2_ITERATIONS", 2),
+        argon2_env_param("CLOCK_IT_ARGON2_PARALLELISM", 1),
+        None,
+    )
+    .map_err(|e| ClockItError::Internal(format!("Invalid Argon2 parameters: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+fn argon2_hasher() -> Result<argon2::Argon2<'static>, ClockItError> {
+    Ok(argon2::Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        argon2_params()?,
+    ))
+}
+
+/// Hash a password with Argon2id and a fresh per-user salt (PHC string format)
+#[cfg(feature = "ssr")]
+fn hash_password(password: &str) -> Result<String, ClockItError> {
+    use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+    let salt = SaltString::generate(&mut OsRng);
+    argon2_hasher()?
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| ClockItError::Internal(format!("Failed to hash password: {}", e)))
+}
+
+/// The original unsalted scheme, kept only to verify accounts that have not logged in since
+#[cfg(feature = "ssr")]
+fn legacy_hash_password(password: &str) -> String {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
 
     let mut hasher = DefaultHasher::new();
     password.hash(&mut hasher);
-    format!("simple_hash_{}", hasher.finish())
+    format!("{}{}", LEGACY_HASH_PREFIX, hasher.finish())
 }
 
-/// Simple password verification (for development only)
+/// Verify a password against either an Argon2 PHC string or a legacy `simple_hash_` value
 #[cfg(feature = "ssr")]
-fn verify_password(password: &str, hash: &str) -> bool {
-    hash_password(password) == hash
+fn verify_password(password: &str, stored_hash: &str) -> PasswordCheck {
+    use argon2::password_hash::{PasswordHash, PasswordVerifier};
+
+    if stored_hash.starts_with(LEGACY_HASH_PREFIX) {
+        return if legacy_hash_password(password) == stored_hash {
+            PasswordCheck::ValidNeedsRehash
+        } else {
+            PasswordCheck::Invalid
+        };
+    }
+
+    let parsed = match PasswordHash::new(stored_hash) {
+        Ok(parsed) => parsed,
+        Err(_) => return PasswordCheck::Invalid,
+    };
+
+    let Ok(hasher) = argon2_hasher() else {
+        return PasswordCheck::Invalid;
+    };
+
+    if hasher
+        .verify_password(password.as_bytes(), &parsed)
+        .is_err()
+    {
+        return PasswordCheck::Invalid;
+    }
+
+    let is_current = parsed.algorithm == argon2::Algorithm::Argon2id.ident()
+        && match (argon2::Params::try_from(&parsed), argon2_params()) {
+            (Ok(stored), Ok(current)) => {
+                stored.m_cost() == current.m_cost()
+                    && stored.t_cost() == current.t_cost()
+                    && stored.p_cost() == current.p_cost()
+            }
+            _ => false,
+        };
+
+    if is_current {
+        PasswordCheck::Valid
+    } else {
+        PasswordCheck::ValidNeedsRehash
+    }
+}
+
+/// Run Argon2 hashing off the async executor, since it is deliberately slow
+#[cfg(feature = "ssr")]
+async fn hash_password_blocking(password: &str) -> Result<String, ClockItError> {
+    let password = password.to_string();
+    tokio::task::spawn_blocking(move || hash_password(&password))
+        .await
+        .map_err(|e| ClockItError::Internal(format!("Password hashing task failed: {}", e)))?
+}
+
+#[cfg(feature = "ssr")]
+async fn verify_password_blocking(password: &str, stored_hash: &str) -> PasswordCheck {
+    let password = password.to_string();
+    let stored_hash = stored_hash.to_string();
+    tokio::task::spawn_blocking(move || verify_password(&password, &stored_hash))
+        .await
+        .unwrap_or(PasswordCheck::Invalid)
 }
 
 /// Create a new user account
@@ -30,20 +153,22 @@ fn verify_password(password: &str, hash: &str) -> bool {
 pub async fn create_user(
     pool: &SqlitePool,
     request: CreateUserRequest,
-) -> Result<UserProfile, String> {
+) -> Result<UserProfile, ClockItError> {
     // Check if user already exists
     let existing_user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE emailAddress = ?")
         .bind(&request.email)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     if existing_user.is_some() {
-        return Err("User with this email already exists".to_string());
+        return Err(ClockItError::Conflict(
+            "User with this email already exists".to_string(),
+        ));
     }
 
     // Hash the password
-    let password_hash = hash_password(&request.password);
+    let password_hash = hash_password_blocking(&request.password).await?;
 
     // Create timestamp
     let now = Utc::now().to_rfc3339();
@@ -65,7 +190,7 @@ pub async fn create_user(
     .bind(&now)
     .execute(pool)
     .await
-    .map_err(|e| format!("Failed to create user: {}", e))?;
+    .map_err(db_error("Failed to create user"))?;
 
     // Get the created user
     let user_id = result.last_insert_rowid();
@@ -74,7 +199,7 @@ pub async fn create_user(
         .bind(user_id)
         .fetch_one(pool)
         .await
-        .map_err(|e| format!("Failed to fetch created user: {}", e))?;
+        .map_err(db_error("Failed to fetch created user"))?;
 
     Ok(user.into())
 }
@@ -85,24 +210,38 @@ pub async fn authenticate_user(
     pool: &SqlitePool,
     email: &str,
     password: &str,
-) -> Result<UserProfile, String> {
+) -> Result<UserProfile, ClockItError> {
     // Find user by email
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE emailAddress = ?")
         .bind(email)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     let user = match user {
         Some(user) => user,
-        None => return Err("Invalid email or password".to_string()),
+        None => {
+            return Err(ClockItError::Unauthorized(
+                "Invalid email or password".to_string(),
+            ))
+        }
     };
 
     // Verify password
-    let password_valid = verify_password(password, &user.password);
-
-    if !password_valid {
-        return Err("Invalid email or password".to_string());
+    match verify_password_blocking(password, &user.password).await {
+        PasswordCheck::Invalid => {
+            return Err(ClockItError::Unauthorized(
+                "Invalid email or password".to_string(),
+            ))
+        }
+        PasswordCheck::Valid => {}
+        PasswordCheck::ValidNeedsRehash => {
+            // Upgrade legacy or outdated hashes now that we have the plaintext.
+            // A failure here must not block the login itself.
+            if let Err(e) = rehash_user_password(pool, user.user_id, password).await {
+                println!("⚠️  Failed to upgrade password hash for user {}: {}", user.user_id, e);
+            }
+        }
     }
 
     Ok(user.into())
@@ -113,12 +252,12 @@ pub async fn authenticate_user(
 pub async fn get_user_by_id(
     pool: &SqlitePool,
     user_id: i64,
-) -> Result<Option<UserProfile>, String> {
+) -> Result<Option<UserProfile>, ClockItError> {
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE userID = ?")
         .bind(user_id)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     Ok(user.map(|u| u.into()))
 }
@@ -128,32 +267,41 @@ pub async fn get_user_by_id(
 pub async fn get_user_by_email(
     pool: &SqlitePool,
     email: &str,
-) -> Result<Option<UserProfile>, String> {
+) -> Result<Option<UserProfile>, ClockItError> {
     let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE emailAddress = ?")
         .bind(email)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     Ok(user.map(|u| u.into()))
 }
 
+/// Replace a user's stored hash with a fresh Argon2id hash of `password`
 #[cfg(feature = "ssr")]
-pub fn print_test_hash() {
-    let password = "password123";
-    let hash = hash_password(password);
-    println!("\n=================================");
-    println!("Password: {}", password);
-    println!("Hash: {}", hash);
-    println!("=================================\n");
+async fn rehash_user_password(
+    pool: &SqlitePool,
+    user_id: i64,
+    password: &str,
+) -> Result<(), ClockItError> {
+    let hashed = hash_password_blocking(password).await?;
+    sqlx::query("UPDATE users SET password = ? WHERE userID = ?")
+        .bind(&hashed)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to store upgraded password hash"))?;
+
+    Ok(())
 }
+
 #[cfg(feature = "ssr")]
 pub async fn update_user_password_by_email(
     pool: &SqlitePool,
     email: &str,
     new_password: &str,
-) -> Result<(), String> {
-    let hashed = hash_password(new_password);
+) -> Result<(), ClockItError> {
+    let hashed = hash_password_blocking(new_password).await?;
     let now = Utc::now().to_rfc3339();
     let result =
         sqlx::query("UPDATE users SET password = ?, updated_at = ? WHERE emailAddress = ?")
@@ -162,10 +310,12 @@ pub async fn update_user_password_by_email(
             .bind(&email.to_lowercase())
             .execute(pool)
             .await
-            .map_err(|e| format!("Failed to update password: {}", e))?;
+            .map_err(db_error("Failed to update password"))?;
 
     if result.rows_affected() == 0 {
-        return Err("No user found with that email".to_string());
+        return Err(ClockItError::NotFound(
+            "No user found with that email".to_string(),
+        ));
     }
 
     Ok(())
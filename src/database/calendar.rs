@@ -0,0 +1,416 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use crate::utils::ics::IcsEvent;
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+/// A teaching term, e.g. "Semester 1 2025"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "ssr", derive(FromRow))]
+pub struct AcademicTerm {
+    #[cfg_attr(feature = "ssr", sqlx(rename = "termID"))]
+    pub term_id: i64,
+    pub name: String,
+    /// `YYYY-MM-DD`, inclusive
+    pub start_date: String,
+    /// `YYYY-MM-DD`, inclusive
+    pub end_date: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum BlackoutKind {
+    Holiday,
+    Recess,
+    Other,
+}
+
+impl BlackoutKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlackoutKind::Holiday => "holiday",
+            BlackoutKind::Recess => "recess",
+            BlackoutKind::Other => "other",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "holiday" => BlackoutKind::Holiday,
+            "recess" => BlackoutKind::Recess,
+            _ => BlackoutKind::Other,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlackoutKind::Holiday => "Public holiday",
+            BlackoutKind::Recess => "Recess",
+            BlackoutKind::Other => "No classes",
+        }
+    }
+}
+
+/// A day or run of days no classes are held on
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BlackoutDate {
+    pub blackout_id: i64,
+    pub name: String,
+    pub kind: BlackoutKind,
+    /// `YYYY-MM-DD`, inclusive
+    pub start_date: String,
+    /// `YYYY-MM-DD`, inclusive
+    pub end_date: String,
+}
+
+impl BlackoutDate {
+    pub fn covers(&self, date: NaiveDate) -> bool {
+        let date = date.format("%Y-%m-%d").to_string();
+        self.start_date <= date && date <= self.end_date
+    }
+}
+
+/// The terms and blackout dates recurring classes are planned around
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AcademicCalendar {
+    pub terms: Vec<AcademicTerm>,
+    pub blackouts: Vec<BlackoutDate>,
+}
+
+impl AcademicCalendar {
+    pub fn is_blackout(&self, date: NaiveDate) -> bool {
+        self.blackouts.iter().any(|blackout| blackout.covers(date))
+    }
+
+    /// The term `date` falls in, if any
+    pub fn term_on(&self, date: NaiveDate) -> Option<&AcademicTerm> {
+        let date = date.format("%Y-%m-%d").to_string();
+        self.terms
+            .iter()
+            .find(|term| term.start_date <= date && date <= term.end_date)
+    }
+}
+
+/// Fields a lecturer fills in when saving a term
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermRequest {
+    pub name: String,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+impl TermRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Term name is required".to_string());
+        }
+        validate_range(&self.start_date, &self.end_date)
+    }
+}
+
+/// Fields a lecturer fills in when saving a blackout
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlackoutRequest {
+    pub name: String,
+    pub kind: BlackoutKind,
+    pub start_date: String,
+    pub end_date: String,
+}
+
+impl BlackoutRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Please name the holiday or recess".to_string());
+        }
+        validate_range(&self.start_date, &self.end_date)
+    }
+}
+
+fn validate_range(start_date: &str, end_date: &str) -> Result<(), String> {
+    let parse = |date: &str| NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok();
+    match (parse(start_date), parse(end_date)) {
+        (Some(start), Some(end)) if end < start => {
+            Err("The last day can't be before the first".to_string())
+        }
+        (Some(_), Some(_)) => Ok(()),
+        (None, _) => Err("Please choose the first day".to_string()),
+        (_, None) => Err("Please choose the last day".to_string()),
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbBlackoutDate {
+    #[sqlx(rename = "blackoutID")]
+    blackout_id: i64,
+    name: String,
+    kind: String,
+    start_date: String,
+    end_date: String,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbBlackoutDate> for BlackoutDate {
+    fn from(db: DbBlackoutDate) -> Self {
+        Self {
+            blackout_id: db.blackout_id,
+            name: db.name,
+            kind: BlackoutKind::parse(&db.kind),
+            start_date: db.start_date,
+            end_date: db.end_date,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_academic_calendar(pool: &SqlitePool) -> Result<AcademicCalendar, ClockItError> {
+    let terms = sqlx::query_as::<_, AcademicTerm>(
+        "SELECT termID, name, start_date, end_date FROM academic_terms ORDER BY start_date",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to fetch terms"))?;
+
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(db_error("Failed to fetch blackout dates"))?;
+    let blackouts = fetch_blackouts(&mut conn).await?;
+
+    Ok(AcademicCalendar { terms, blackouts })
+}
+
+/// Every blackout, for planning a series inside an open transaction
+#[cfg(feature = "ssr")]
+pub(crate) async fn fetch_blackouts(
+    conn: &mut SqliteConnection,
+) -> Result<Vec<BlackoutDate>, ClockItError> {
+    let rows = sqlx::query_as::<_, DbBlackoutDate>(
+        "SELECT blackoutID, name, kind, start_date, end_date FROM blackout_dates ORDER BY start_date",
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(db_error("Failed to fetch blackout dates"))?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create_term(pool: &SqlitePool, request: &TermRequest) -> Result<i64, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let name = request.name.trim();
+
+    let result = sqlx::query(
+        "INSERT INTO academic_terms (name, start_date, end_date, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(request.start_date.trim())
+    .bind(request.end_date.trim())
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to create term")(e) {
+        ClockItError::Conflict(_) => {
+            ClockItError::Conflict(format!("A term called '{}' already exists", name))
+        }
+        other => other,
+    })?;
+
+    Ok(result.last_insert_rowid())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn update_term(
+    pool: &SqlitePool,
+    term_id: i64,
+    request: &TermRequest,
+) -> Result<(), ClockItError> {
+    let name = request.name.trim();
+
+    let result = sqlx::query(
+        "UPDATE academic_terms SET name = ?, start_date = ?, end_date = ?, updated_at = ? WHERE termID = ?",
+    )
+    .bind(name)
+    .bind(request.start_date.trim())
+    .bind(request.end_date.trim())
+    .bind(Utc::now().to_rfc3339())
+    .bind(term_id)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to update term")(e) {
+        ClockItError::Conflict(_) => {
+            ClockItError::Conflict(format!("A term called '{}' already exists", name))
+        }
+        other => other,
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(ClockItError::NotFound("Term not found".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn delete_term(pool: &SqlitePool, term_id: i64) -> Result<(), ClockItError> {
+    sqlx::query("DELETE FROM academic_terms WHERE termID = ?")
+        .bind(term_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to delete term"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create_blackout(
+    pool: &SqlitePool,
+    request: &BlackoutRequest,
+) -> Result<i64, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO blackout_dates (name, kind, start_date, end_date, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(request.name.trim())
+    .bind(request.kind.as_str())
+    .bind(request.start_date.trim())
+    .bind(request.end_date.trim())
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to create blackout")(e) {
+        ClockItError::Conflict(_) => ClockItError::Conflict(format!(
+            "'{}' is already on the calendar",
+            request.name.trim()
+        )),
+        other => other,
+    })?;
+
+    Ok(result.last_insert_rowid())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn update_blackout(
+    pool: &SqlitePool,
+    blackout_id: i64,
+    request: &BlackoutRequest,
+) -> Result<(), ClockItError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE blackout_dates
+        SET name = ?, kind = ?, start_date = ?, end_date = ?, updated_at = ?
+        WHERE blackoutID = ?
+        "#,
+    )
+    .bind(request.name.trim())
+    .bind(request.kind.as_str())
+    .bind(request.start_date.trim())
+    .bind(request.end_date.trim())
+    .bind(Utc::now().to_rfc3339())
+    .bind(blackout_id)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to update blackout")(e) {
+        ClockItError::Conflict(_) => ClockItError::Conflict(format!(
+            "'{}' is already on the calendar",
+            request.name.trim()
+        )),
+        other => other,
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(ClockItError::NotFound("Blackout not found".to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn delete_blackout(pool: &SqlitePool, blackout_id: i64) -> Result<(), ClockItError> {
+    sqlx::query("DELETE FROM blackout_dates WHERE blackoutID = ?")
+        .bind(blackout_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to delete blackout"))?;
+
+    Ok(())
+}
+
+/// Save holidays read from an .ics file. An event already imported (same UID, or
+/// the same name and first day) is updated in place. Returns how many were added
+/// or changed.
+#[cfg(feature = "ssr")]
+pub async fn import_holidays(
+    pool: &SqlitePool,
+    events: &[IcsEvent],
+) -> Result<usize, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let mut saved = 0;
+    for event in events {
+        let (first, last) = event.days();
+        let name = event.summary.trim();
+        let name = if name.is_empty() { "Holiday" } else { name };
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO blackout_dates (name, kind, start_date, end_date, uid, created_at, updated_at)
+            VALUES (?1, 'holiday', ?2, ?3, ?4, ?5, ?5)
+            ON CONFLICT (uid) DO UPDATE
+                SET name = excluded.name, start_date = excluded.start_date,
+                    end_date = excluded.end_date, updated_at = excluded.updated_at
+                WHERE name != excluded.name OR start_date != excluded.start_date
+                   OR end_date != excluded.end_date
+            ON CONFLICT (name, start_date) DO UPDATE
+                SET end_date = excluded.end_date, uid = COALESCE(uid, excluded.uid),
+                    updated_at = excluded.updated_at
+                WHERE end_date != excluded.end_date
+            "#,
+        )
+        .bind(name)
+        .bind(first.format("%Y-%m-%d").to_string())
+        .bind(last.format("%Y-%m-%d").to_string())
+        .bind(&event.uid)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to import holiday"))?;
+        saved += result.rows_affected() as usize;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(saved)
+}
+
+/// How many upcoming classes are scheduled between two dates, inclusive
+#[cfg(feature = "ssr")]
+pub async fn count_upcoming_classes_between(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+) -> Result<i64, ClockItError> {
+    sqlx::query_scalar(
+        "SELECT COUNT(*) FROM classes WHERE status = 'upcoming' AND date BETWEEN ? AND ?",
+    )
+    .bind(start_date.trim())
+    .bind(end_date.trim())
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to count classes"))
+}
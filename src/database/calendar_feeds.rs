@@ -0,0 +1,94 @@
+use crate::clock::Clock;
+use crate::database::auth::get_user_by_id;
+use crate::database::classes::{get_lecturer_classes, get_student_classes, Class};
+use crate::database::universities::user_timezone;
+use crate::error::{db_error, ClockItError};
+use crate::types::UserProfile;
+use crate::utils::class_time::local_today;
+use chrono::{Duration, Utc};
+use sqlx::SqlitePool;
+
+/// How far back a feed reaches, so last week's classes don't vanish from the calendar
+const FEED_HISTORY_DAYS: i64 = 90;
+
+fn new_token() -> String {
+    let bytes: [u8; 24] = rand::random();
+    hex::encode(bytes)
+}
+
+/// The user's feed token, creating one the first time they ask for it
+pub async fn feed_token_for_user(pool: &SqlitePool, user_id: i64) -> Result<String, ClockItError> {
+    sqlx::query(
+        "INSERT INTO calendar_feeds (userID, token, created_at) VALUES (?, ?, ?) ON CONFLICT(userID) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(new_token())
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to create calendar feed"))?;
+
+    sqlx::query_scalar("SELECT token FROM calendar_feeds WHERE userID = ?")
+        .bind(user_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_error("Failed to load calendar feed"))
+}
+
+/// Replace the user's feed token; calendars subscribed with the old link stop updating
+pub async fn reset_feed_token(pool: &SqlitePool, user_id: i64) -> Result<String, ClockItError> {
+    let token = new_token();
+    sqlx::query(
+        r#"
+        INSERT INTO calendar_feeds (userID, token, created_at) VALUES (?, ?, ?)
+        ON CONFLICT(userID) DO UPDATE SET token = excluded.token, created_at = excluded.created_at
+        "#,
+    )
+    .bind(user_id)
+    .bind(&token)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to reset calendar feed"))?;
+
+    Ok(token)
+}
+
+/// The user a feed token belongs to, if it is still current
+pub async fn feed_owner(pool: &SqlitePool, token: &str) -> Result<Option<UserProfile>, ClockItError> {
+    let user_id: Option<i64> = sqlx::query_scalar("SELECT userID FROM calendar_feeds WHERE token = ?")
+        .bind(token)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to look up calendar feed"))?;
+
+    match user_id {
+        Some(user_id) => get_user_by_id(pool, user_id).await,
+        None => Ok(None),
+    }
+}
+
+/// The classes in `user`'s feed: a student's enrolled modules, or the modules a
+/// lecturer or tutor teaches, from [`FEED_HISTORY_DAYS`] before today at their
+/// university onwards
+pub async fn feed_classes(
+    pool: &SqlitePool,
+    user: &UserProfile,
+    clock: &dyn Clock,
+) -> Result<Vec<Class>, ClockItError> {
+    let today = local_today(user_timezone(pool, &user.email_address).await?, clock.now());
+    let since = (today - Duration::days(FEED_HISTORY_DAYS))
+        .format("%Y-%m-%d")
+        .to_string();
+    let email = user.email_address.trim().to_lowercase();
+
+    if user.role == "student" {
+        get_student_classes(pool, &email, &since).await
+    } else {
+        let classes = get_lecturer_classes(pool, &user.email_address).await?;
+        Ok(classes
+            .into_iter()
+            .filter(|class| class.date >= since)
+            .collect())
+    }
+}
@@ -0,0 +1,529 @@
+//! Overlap checks run before a class is created or moved. Two classes overlap when
+//! they are on the same date and one starts before the other ends, using `time`
+//! and `duration_minutes`. An overlap is a clash when the classes share a venue,
+//! a member of staff, or students expected at both. Clashes are warnings: the
+//! lecturer can look at them and save anyway.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::database::groups::get_class_group_ids;
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::{NaiveTime, Timelike};
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqlitePool};
+#[cfg(feature = "ssr")]
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClashKind {
+    /// Both classes are booked into the same venue
+    Venue,
+    /// The same lecturer or tutor teaches both: whoever created each class (the
+    /// module's lecturers when unknown), and the tutors of its tutorial groups, or
+    /// of the whole module when it is for everyone
+    Staff,
+    /// Students are expected at both
+    Students,
+}
+
+impl ClashKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ClashKind::Venue => "Venue double-booked",
+            ClashKind::Staff => "Teaching at the same time",
+            ClashKind::Students => "Students have two classes",
+        }
+    }
+}
+
+/// An existing class that overlaps the one being saved
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassClash {
+    pub kind: ClashKind,
+    pub class_id: i64,
+    pub module_code: String,
+    pub title: String,
+    pub date: String,
+    pub time: String,
+    pub duration_minutes: i32,
+    /// What the two classes share, such as the venue or how many students
+    pub detail: String,
+}
+
+/// A class about to be written, on each of `dates`
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct ProposedClass<'a> {
+    pub module_code: &'a str,
+    pub venue: Option<&'a str>,
+    pub time: &'a str,
+    pub duration_minutes: i32,
+    /// Who teaches it; the module's lecturers when unknown
+    pub created_by: Option<&'a str>,
+    /// The tutorial groups it is for; empty for the whole module
+    pub group_ids: &'a [i64],
+    /// `YYYY-MM-DD`
+    pub dates: Vec<String>,
+    /// The class being edited, which can't clash with itself
+    pub class_id: Option<i64>,
+    /// The series being edited, whose classes are about to be replaced
+    pub series_id: Option<i64>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct Candidate {
+    #[sqlx(rename = "classID")]
+    class_id: i64,
+    #[sqlx(rename = "moduleCode")]
+    module_code: String,
+    title: String,
+    venue: Option<String>,
+    date: String,
+    time: String,
+    duration_minutes: i32,
+    created_by: Option<String>,
+}
+
+/// Minutes after midnight that a class starting at `time` (`HH:MM`) starts and ends
+#[cfg(feature = "ssr")]
+fn span(time: &str, duration_minutes: i32) -> Option<(i64, i64)> {
+    let start = NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?;
+    let start = (start.hour() * 60 + start.minute()) as i64;
+    Some((start, start + duration_minutes.max(0) as i64))
+}
+
+#[cfg(feature = "ssr")]
+fn same_venue(a: Option<&str>, b: Option<&str>) -> Option<String> {
+    let a = a.map(str::trim).filter(|venue| !venue.is_empty())?;
+    let b = b.map(str::trim).filter(|venue| !venue.is_empty())?;
+    a.eq_ignore_ascii_case(b).then(|| a.to_string())
+}
+
+/// Every clash `proposed` would have with an upcoming or running class
+#[cfg(feature = "ssr")]
+pub async fn find_clashes(
+    pool: &SqlitePool,
+    proposed: &ProposedClass<'_>,
+) -> Result<Vec<ClassClash>, ClockItError> {
+    let Some((start, end)) = span(proposed.time, proposed.duration_minutes) else {
+        return Ok(Vec::new());
+    };
+    if proposed.dates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let dates = serde_json::to_string(&proposed.dates)
+        .map_err(|e| ClockItError::Internal(format!("Failed to check for clashes: {}", e)))?;
+    let candidates = sqlx::query_as::<_, Candidate>(
+        r#"
+        SELECT classID, moduleCode, title, venue, date, time, duration_minutes, created_by
+        FROM classes
+        WHERE date IN (SELECT value FROM json_each(?))
+          AND status IN ('upcoming', 'in_progress')
+          AND (? IS NULL OR classID != ?)
+          AND (? IS NULL OR series_id IS NULL OR series_id != ?)
+        ORDER BY date, time
+        "#,
+    )
+    .bind(&dates)
+    .bind(proposed.class_id)
+    .bind(proposed.class_id)
+    .bind(proposed.series_id)
+    .bind(proposed.series_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to check for clashes"))?;
+
+    let overlapping: Vec<Candidate> = candidates
+        .into_iter()
+        .filter(|candidate| {
+            span(&candidate.time, candidate.duration_minutes)
+                .is_some_and(|(other_start, other_end)| other_start < end && start < other_end)
+        })
+        .collect();
+    if overlapping.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut modules: HashMap<String, ModuleStaff> = HashMap::new();
+    let mut clashes = Vec::new();
+    let group_ids = serde_json::to_string(proposed.group_ids)
+        .map_err(|e| ClockItError::Internal(format!("Failed to check for clashes: {}", e)))?;
+
+    let proposed_staff = class_staff(
+        pool,
+        &mut modules,
+        proposed.module_code,
+        proposed.created_by,
+        proposed.group_ids,
+    )
+    .await?;
+
+    for other in overlapping {
+        let clash = |kind: ClashKind, detail: String| ClassClash {
+            kind,
+            class_id: other.class_id,
+            module_code: other.module_code.clone(),
+            title: other.title.clone(),
+            date: other.date.clone(),
+            time: other.time.clone(),
+            duration_minutes: other.duration_minutes,
+            detail,
+        };
+
+        if let Some(venue) = same_venue(proposed.venue, other.venue.as_deref()) {
+            clashes.push(clash(ClashKind::Venue, format!("Both are in {}", venue)));
+        }
+
+        let other_groups = get_class_group_ids(pool, other.class_id).await?;
+        let other_staff = class_staff(
+            pool,
+            &mut modules,
+            &other.module_code,
+            other.created_by.as_deref(),
+            &other_groups,
+        )
+        .await?;
+        if let Some(email) = proposed_staff.iter().find(|email| {
+            other_staff
+                .iter()
+                .any(|other| other.eq_ignore_ascii_case(email))
+        }) {
+            clashes.push(clash(ClashKind::Staff, format!("{} teaches both", email)));
+        }
+
+        // Students expected at the other class who would also be expected at this one
+        let students: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(DISTINCT cs.studentEmailAddress)
+            FROM class_students cs
+            INNER JOIN module_students ms
+                ON ms.studentEmailAddress = cs.studentEmailAddress AND ms.moduleCode = ?1
+                AND ms.unenrolled_on IS NULL
+            WHERE cs.classID = ?2
+              AND (
+                  json_array_length(?3) = 0
+                  OR EXISTS (
+                      SELECT 1 FROM group_students gs
+                      WHERE gs.studentEmailAddress = cs.studentEmailAddress
+                        AND gs.groupID IN (SELECT value FROM json_each(?3))
+                  )
+              )
+            "#,
+        )
+        .bind(proposed.module_code)
+        .bind(other.class_id)
+        .bind(&group_ids)
+        .fetch_one(pool)
+        .await
+        .map_err(db_error("Failed to check for clashes"))?;
+        if students > 0 {
+            let detail = match students {
+                1 => "1 student is expected at both".to_string(),
+                n => format!("{} students are expected at both", n),
+            };
+            clashes.push(clash(ClashKind::Students, detail));
+        }
+    }
+
+    Ok(clashes)
+}
+
+/// A module's lecturers and tutors
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+struct ModuleStaff {
+    lecturers: Vec<String>,
+    tutors: Vec<String>,
+}
+
+/// Everyone teaching a class, as described on [`ClashKind::Staff`]
+#[cfg(feature = "ssr")]
+async fn class_staff(
+    pool: &SqlitePool,
+    modules: &mut HashMap<String, ModuleStaff>,
+    module_code: &str,
+    created_by: Option<&str>,
+    group_ids: &[i64],
+) -> Result<Vec<String>, ClockItError> {
+    let module = module_staff(pool, modules, module_code).await?;
+    let mut staff = match created_by {
+        Some(email) => vec![email.to_string()],
+        None => module.lecturers,
+    };
+
+    if group_ids.is_empty() {
+        staff.extend(module.tutors);
+    } else {
+        let group_ids = serde_json::to_string(group_ids)
+            .map_err(|e| ClockItError::Internal(format!("Failed to check for clashes: {}", e)))?;
+        let tutors: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT tutorEmailAddress FROM module_groups
+            WHERE groupID IN (SELECT value FROM json_each(?)) AND tutorEmailAddress IS NOT NULL
+            "#,
+        )
+        .bind(&group_ids)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error("Failed to check for clashes"))?;
+        staff.extend(tutors);
+    }
+
+    Ok(staff)
+}
+
+#[cfg(feature = "ssr")]
+async fn module_staff(
+    pool: &SqlitePool,
+    cache: &mut HashMap<String, ModuleStaff>,
+    module_code: &str,
+) -> Result<ModuleStaff, ClockItError> {
+    if let Some(staff) = cache.get(module_code) {
+        return Ok(staff.clone());
+    }
+    let lecturers: Vec<String> =
+        sqlx::query_scalar("SELECT lecturerEmailAddress FROM lecturer_module WHERE moduleCode = ?")
+            .bind(module_code)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error("Failed to check for clashes"))?;
+    let tutors: Vec<String> =
+        sqlx::query_scalar("SELECT tutorEmailAddress FROM module_tutor WHERE moduleCode = ?")
+            .bind(module_code)
+            .fetch_all(pool)
+            .await
+            .map_err(db_error("Failed to check for clashes"))?;
+    let staff = ModuleStaff { lecturers, tutors };
+    cache.insert(module_code.to_string(), staff.clone());
+    Ok(staff)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{
+        add_class, add_module, add_tutor, add_user, enroll, run, test_pool,
+    };
+
+    const DATE: &str = "2030-02-04";
+    const LECTURER: &str = "clash-lecturer@test.example";
+    const OTHER_LECTURER: &str = "clash-other@test.example";
+    const TUTOR: &str = "clash-tutor@test.example";
+    const STUDENT: &str = "clash-student@test.example";
+
+    /// CLA101 and CLA202, each with its own lecturer, and a CLA202 class in B202
+    /// from 10:00 to 11:00. Returns the class.
+    async fn fixture(pool: &SqlitePool) -> i64 {
+        add_user(pool, LECTURER, "lecturer").await;
+        add_user(pool, OTHER_LECTURER, "lecturer").await;
+        add_user(pool, TUTOR, "tutor").await;
+        add_user(pool, STUDENT, "student").await;
+        add_module(pool, "CLA101", LECTURER).await;
+        add_module(pool, "CLA202", OTHER_LECTURER).await;
+        add_class(pool, "CLA202", "B202", DATE, "10:00", 60, OTHER_LECTURER).await
+    }
+
+    async fn add_group(
+        pool: &SqlitePool,
+        module_code: &str,
+        name: &str,
+        tutor: Option<&str>,
+    ) -> i64 {
+        sqlx::query_scalar(
+            "INSERT INTO module_groups (moduleCode, name, tutorEmailAddress) VALUES (?, ?, ?) RETURNING groupID",
+        )
+        .bind(module_code)
+        .bind(name)
+        .bind(tutor)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    /// A CLA101 class by the lecturer in A101, for all of the module
+    fn proposed(time: &'static str) -> ProposedClass<'static> {
+        ProposedClass {
+            module_code: "CLA101",
+            venue: Some("A101"),
+            time,
+            duration_minutes: 60,
+            created_by: Some(LECTURER),
+            group_ids: &[],
+            dates: vec![DATE.to_string()],
+            class_id: None,
+            series_id: None,
+        }
+    }
+
+    async fn kinds(pool: &SqlitePool, proposed: &ProposedClass<'_>) -> Vec<ClashKind> {
+        find_clashes(pool, proposed)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|clash| clash.kind)
+            .collect()
+    }
+
+    #[test]
+    fn unrelated_overlapping_classes_do_not_clash() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+
+            assert_eq!(kinds(&pool, &proposed("10:30")).await, []);
+        });
+    }
+
+    #[test]
+    fn same_venue_clashes_whatever_its_case() {
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+
+            let clashes = find_clashes(
+                &pool,
+                &ProposedClass {
+                    venue: Some(" b202 "),
+                    ..proposed("10:30")
+                },
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(clashes.len(), 1);
+            assert_eq!(clashes[0].kind, ClashKind::Venue);
+            assert_eq!(clashes[0].class_id, class_id);
+            assert_eq!(clashes[0].detail, "Both are in b202");
+        });
+    }
+
+    #[test]
+    fn the_same_lecturer_clashes() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+
+            let by_other = ProposedClass {
+                created_by: Some(OTHER_LECTURER),
+                ..proposed("09:30")
+            };
+            assert_eq!(kinds(&pool, &by_other).await, [ClashKind::Staff]);
+
+            // With no creator, the module's lecturers teach the class
+            sqlx::query(
+                "INSERT INTO lecturer_module (moduleCode, lecturerEmailAddress, created_at) VALUES ('CLA101', ?, '2025-01-01T00:00:00Z')",
+            )
+            .bind(OTHER_LECTURER)
+            .execute(&pool)
+            .await
+            .unwrap();
+            let unknown = ProposedClass {
+                created_by: None,
+                ..proposed("09:30")
+            };
+            assert_eq!(kinds(&pool, &unknown).await, [ClashKind::Staff]);
+        });
+    }
+
+    #[test]
+    fn a_module_tutor_of_both_clashes() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            add_tutor(&pool, "CLA101", TUTOR).await;
+            add_tutor(&pool, "CLA202", TUTOR).await;
+
+            let clashes = find_clashes(&pool, &proposed("10:30")).await.unwrap();
+
+            assert_eq!(clashes.len(), 1);
+            assert_eq!(clashes[0].kind, ClashKind::Staff);
+            assert_eq!(clashes[0].detail, format!("{} teaches both", TUTOR));
+        });
+    }
+
+    #[test]
+    fn group_tutors_clash_only_through_their_own_groups() {
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+            // The tutor takes a CLA202 group that the existing class is for
+            let theirs = add_group(&pool, "CLA202", "T1", Some(TUTOR)).await;
+            sqlx::query("INSERT INTO class_groups (classID, groupID) VALUES (?, ?)")
+                .bind(class_id)
+                .bind(theirs)
+                .execute(&pool)
+                .await
+                .unwrap();
+            let tutored = add_group(&pool, "CLA101", "T1", Some(TUTOR)).await;
+            let untutored = add_group(&pool, "CLA101", "T2", None).await;
+
+            let for_tutored = [tutored];
+            let tutorial = ProposedClass {
+                group_ids: &for_tutored,
+                ..proposed("10:30")
+            };
+            assert_eq!(kinds(&pool, &tutorial).await, [ClashKind::Staff]);
+
+            let for_untutored = [untutored];
+            let tutorial = ProposedClass {
+                group_ids: &for_untutored,
+                ..proposed("10:30")
+            };
+            assert_eq!(kinds(&pool, &tutorial).await, []);
+        });
+    }
+
+    #[test]
+    fn students_of_both_modules_clash() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            enroll(&pool, "CLA101", STUDENT).await;
+            enroll(&pool, "CLA202", STUDENT).await;
+
+            let clashes = find_clashes(&pool, &proposed("10:30")).await.unwrap();
+            assert_eq!(clashes.len(), 1);
+            assert_eq!(clashes[0].kind, ClashKind::Students);
+            assert_eq!(clashes[0].detail, "1 student is expected at both");
+
+            // A tutorial for a group the student isn't in doesn't clash
+            let group = [add_group(&pool, "CLA101", "T1", None).await];
+            let tutorial = ProposedClass {
+                group_ids: &group,
+                ..proposed("10:30")
+            };
+            assert_eq!(kinds(&pool, &tutorial).await, []);
+        });
+    }
+
+    #[test]
+    fn back_to_back_classes_do_not_clash() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            add_tutor(&pool, "CLA101", TUTOR).await;
+            add_tutor(&pool, "CLA202", TUTOR).await;
+            enroll(&pool, "CLA101", STUDENT).await;
+            enroll(&pool, "CLA202", STUDENT).await;
+            let shared = |time| ProposedClass {
+                venue: Some("B202"),
+                created_by: Some(OTHER_LECTURER),
+                ..proposed(time)
+            };
+
+            // Ending as the other starts, and starting as it ends
+            assert_eq!(kinds(&pool, &shared("09:00")).await, []);
+            assert_eq!(kinds(&pool, &shared("11:00")).await, []);
+            // A minute of overlap clashes on every count
+            assert_eq!(
+                kinds(&pool, &shared("10:59")).await,
+                [ClashKind::Venue, ClashKind::Staff, ClashKind::Students]
+            );
+        });
+    }
+}
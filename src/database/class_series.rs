@@ -0,0 +1,1035 @@
+use crate::utils::recurrence::{parse_exdates, BlackoutPolicy, RecurrenceRule};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::database::calendar::fetch_blackouts;
+#[cfg(feature = "ssr")]
+use crate::database::classes::{
+    apply_class_update, get_class_by_id, insert_class, remove_class, Class, CreateClassRequest,
+    UpdateClassRequest,
+};
+#[cfg(feature = "ssr")]
+use crate::database::groups::get_class_group_ids;
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use crate::utils::recurrence::{avoid_blackouts, RecurrenceEnd};
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+/// The rule a run of recurring classes is generated from
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassSeries {
+    pub series_id: i64,
+    pub module_code: String,
+    pub recurrence: Recurrence,
+    /// Date of the first class, `YYYY-MM-DD`
+    pub dtstart: String,
+    pub created_by: Option<String>,
+}
+
+/// A repeat pattern as the lecturer entered it: an RRULE and the dates it skips
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Recurrence {
+    pub rrule: String,
+    /// `YYYY-MM-DD`
+    pub exdates: Vec<String>,
+    /// What happens to classes that land on a holiday or in a recess
+    #[serde(default)]
+    pub blackouts: BlackoutPolicy,
+}
+
+impl Recurrence {
+    pub fn parse(&self) -> Result<(RecurrenceRule, Vec<NaiveDate>), String> {
+        let rule = RecurrenceRule::parse(&self.rrule)?;
+        let exdates = parse_exdates(&self.exdates.join(","))?;
+        Ok((rule, exdates))
+    }
+}
+
+/// Which of a series' classes an edit applies to besides the one being edited.
+/// Classes that have already started or finished are never changed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SeriesEditScope {
+    /// The edited class and every later one; earlier classes keep the old rule
+    ThisAndFollowing,
+    /// Every upcoming class in the series
+    All,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbClassSeries {
+    #[sqlx(rename = "seriesID")]
+    series_id: i64,
+    #[sqlx(rename = "moduleCode")]
+    module_code: String,
+    rrule: String,
+    dtstart: String,
+    exdates: Option<String>,
+    blackout_policy: String,
+    created_by: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbClassSeries> for ClassSeries {
+    fn from(db: DbClassSeries) -> Self {
+        Self {
+            series_id: db.series_id,
+            module_code: db.module_code,
+            recurrence: Recurrence {
+                rrule: db.rrule,
+                exdates: db
+                    .exdates
+                    .map(|dates| dates.split(',').map(str::to_string).collect())
+                    .unwrap_or_default(),
+                blackouts: BlackoutPolicy::parse(&db.blackout_policy),
+            },
+            dtstart: db.dtstart,
+            created_by: db.created_by,
+        }
+    }
+}
+
+/// How many classes an edit through [`update_series`] wrote
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone)]
+pub struct SeriesUpdate {
+    pub class: Class,
+    pub updated: usize,
+    pub created: usize,
+    pub removed: usize,
+}
+
+/// A class of a series that an edit reaches
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct SeriesClass {
+    #[sqlx(rename = "classID")]
+    class_id: i64,
+    /// The date the series put the class on, `YYYY-MM-DD`
+    slot: String,
+    /// Whether the class was edited on its own
+    edited: bool,
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_series(
+    pool: &SqlitePool,
+    series_id: i64,
+) -> Result<Option<ClassSeries>, ClockItError> {
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(db_error("Failed to fetch series"))?;
+    fetch_series(&mut conn, series_id).await
+}
+
+/// The dates a series starting on `start` would have classes on, without saving it
+#[cfg(feature = "ssr")]
+pub async fn planned_dates(
+    pool: &SqlitePool,
+    start: &str,
+    recurrence: &Recurrence,
+) -> Result<Vec<String>, ClockItError> {
+    let (rule, exdates) = recurrence.parse().map_err(ClockItError::Validation)?;
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(db_error("Failed to plan series"))?;
+    let dates = plan_dates(
+        &mut conn,
+        &rule,
+        parse_date(start)?,
+        &exdates,
+        recurrence.blackouts,
+    )
+    .await?;
+    Ok(format_dates(&dates))
+}
+
+/// Create a series starting on `template.date` and a class on every date it produces.
+/// Returns the first class and how many were created.
+#[cfg(feature = "ssr")]
+pub async fn create_class_series(
+    pool: &SqlitePool,
+    template: &CreateClassRequest,
+    recurrence: &Recurrence,
+) -> Result<(Class, usize), ClockItError> {
+    let (rule, exdates) = recurrence.parse().map_err(ClockItError::Validation)?;
+    let start = parse_date(&template.date)?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let dates = plan_dates(&mut tx, &rule, start, &exdates, recurrence.blackouts).await?;
+    if dates.is_empty() {
+        return Err(ClockItError::Validation(
+            "The repeat pattern skips every class".to_string(),
+        ));
+    }
+
+    let series_id = insert_series(
+        &mut tx,
+        &template.module_code,
+        &rule,
+        start,
+        &exdates,
+        recurrence.blackouts,
+        template.created_by.as_deref(),
+    )
+    .await?;
+
+    let mut first_class_id = None;
+    for date in &dates {
+        let class_id = insert_class(
+            &mut tx,
+            &CreateClassRequest {
+                date: date.format("%Y-%m-%d").to_string(),
+                recurring: Some(rule.describe()),
+                series_id: Some(series_id),
+                ..template.clone()
+            },
+        )
+        .await?;
+        first_class_id.get_or_insert(class_id);
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    let class = get_class_by_id(pool, first_class_id.unwrap_or_default()).await?;
+    Ok((class, dates.len()))
+}
+
+/// Apply `request` to `class_id` and the other upcoming classes of its series in
+/// `scope`, in one transaction.
+///
+/// When the rule or the class's date changes, the classes in scope are regenerated
+/// from `recurrence`; otherwise each keeps its own date and takes the other details.
+/// With no `recurrence` the other classes in scope are removed and the class becomes
+/// a one-off. A class that isn't in a series yet starts one.
+///
+/// Other classes that were edited on their own through [`update_occurrence`] keep
+/// their details. Regenerating keeps them too, as long as the new pattern still has
+/// a class on their original date (moved along with the series); otherwise they go
+/// with the rest. The edited class itself follows the series again.
+#[cfg(feature = "ssr")]
+pub async fn update_series(
+    pool: &SqlitePool,
+    class_id: i64,
+    scope: SeriesEditScope,
+    request: &UpdateClassRequest,
+    recurrence: Option<&Recurrence>,
+) -> Result<SeriesUpdate, ClockItError> {
+    let parsed = recurrence
+        .map(Recurrence::parse)
+        .transpose()
+        .map_err(ClockItError::Validation)?;
+    let class = get_class_by_id(pool, class_id).await?;
+    let old_date = parse_date(&class.date)?;
+    let new_date = parse_date(&request.date)?;
+    // Regenerated classes are for the same groups as the edited one
+    let group_ids = match &request.group_ids {
+        Some(group_ids) => group_ids.clone(),
+        None => get_class_group_ids(pool, class_id).await?,
+    };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let series = match class.series_id {
+        Some(series_id) => fetch_series(&mut tx, series_id).await?,
+        None => None,
+    };
+
+    // The other upcoming classes the edit reaches, by the date the series gave them
+    let others: Vec<SeriesClass> = match &series {
+        Some(series) => sqlx::query_as(
+            r#"
+            SELECT classID, COALESCE(occurrence_date, date) AS slot, occurrence_date IS NOT NULL AS edited
+            FROM classes
+            WHERE series_id = ? AND classID != ? AND status = 'upcoming'
+              AND (?3 IS NULL OR COALESCE(occurrence_date, date) >= ?3)
+            ORDER BY slot, time
+            "#,
+        )
+        .bind(series.series_id)
+        .bind(class_id)
+        .bind(match scope {
+            SeriesEditScope::ThisAndFollowing => Some(&class.date),
+            SeriesEditScope::All => None,
+        })
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error("Failed to fetch series classes"))?,
+        None => Vec::new(),
+    };
+
+    let Some((rule, exdates)) = parsed else {
+        // Stop repeating: the class is kept as a one-off and the rest in scope go
+        for other in &others {
+            remove_class(&mut tx, other.class_id).await?;
+        }
+        let one_off = UpdateClassRequest {
+            recurring: None,
+            ..request.clone()
+        };
+        apply_class_update(&mut tx, class_id, &one_off).await?;
+        set_class_series(&mut tx, class_id, None).await?;
+        if let Some(series) = &series {
+            end_series_at_last_class(&mut tx, series).await?;
+        }
+        tx.commit()
+            .await
+            .map_err(db_error("Failed to commit transaction"))?;
+
+        return Ok(SeriesUpdate {
+            class: get_class_by_id(pool, class_id).await?,
+            updated: 1,
+            created: 0,
+            removed: others.len(),
+        });
+    };
+
+    let blackouts = recurrence.map(|r| r.blackouts).unwrap_or_default();
+    let regenerate = new_date != old_date
+        || series
+            .as_ref()
+            .is_none_or(|series| Some(&series.recurrence) != recurrence);
+    // Where the regenerated classes start from. Moving the edited class shifts the
+    // whole series by the same number of days.
+    let shift = new_date - old_date;
+    let (dtstart, from) = match (&series, scope) {
+        (Some(series), SeriesEditScope::All) => {
+            let earliest = others
+                .first()
+                .map(|other| parse_date(&other.slot))
+                .transpose()?
+                .map_or(old_date, |date| date.min(old_date));
+            (parse_date(&series.dtstart)? + shift, earliest + shift)
+        }
+        _ => (new_date, new_date),
+    };
+
+    let has_earlier = match &series {
+        Some(series) => {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT COUNT(*) FROM classes WHERE series_id = ? AND date < ?",
+            )
+            .bind(series.series_id)
+            .bind(&class.date)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(db_error("Failed to fetch series classes"))?
+                > 0
+        }
+        None => false,
+    };
+
+    let series_id = match &series {
+        // Split: the edited class and those after it move to a new series, and the
+        // old one is ended on its last remaining class below
+        Some(series) if scope == SeriesEditScope::ThisAndFollowing && has_earlier => {
+            insert_series(
+                &mut tx,
+                &series.module_code,
+                &rule,
+                dtstart,
+                &exdates,
+                blackouts,
+                series.created_by.as_deref(),
+            )
+            .await?
+        }
+        Some(series) => {
+            let series = ClassSeries {
+                recurrence: Recurrence {
+                    rrule: series.recurrence.rrule.clone(),
+                    exdates: format_dates(&exdates),
+                    blackouts,
+                },
+                ..series.clone()
+            };
+            save_series_rule(&mut tx, &series, &rule, dtstart).await?;
+            series.series_id
+        }
+        None => {
+            insert_series(
+                &mut tx,
+                &class.module_code,
+                &rule,
+                dtstart,
+                &exdates,
+                blackouts,
+                class.created_by.as_deref(),
+            )
+            .await?
+        }
+    };
+
+    let label = Some(rule.describe());
+    let edited = UpdateClassRequest {
+        recurring: label.clone(),
+        ..request.clone()
+    };
+    apply_class_update(&mut tx, class_id, &edited).await?;
+    set_class_series(&mut tx, class_id, Some(series_id)).await?;
+    set_occurrence_date(&mut tx, class_id, None).await?;
+
+    let mut update = SeriesUpdate {
+        class: class.clone(),
+        updated: 1,
+        created: 0,
+        removed: 0,
+    };
+
+    if regenerate {
+        let planned = plan_dates(&mut tx, &rule, dtstart, &exdates, blackouts).await?;
+
+        for other in &others {
+            let slot = parse_date(&other.slot)? + shift;
+            if other.edited && slot >= from && planned.contains(&slot) {
+                set_occurrence_date(&mut tx, other.class_id, Some(slot)).await?;
+                set_class_series(&mut tx, other.class_id, Some(series_id)).await?;
+            } else {
+                remove_class(&mut tx, other.class_id).await?;
+                update.removed += 1;
+            }
+        }
+
+        // Dates still taken by the edited class, the classes edited on their own and
+        // any that have already run
+        let taken: Vec<String> = sqlx::query_scalar(
+            "SELECT COALESCE(occurrence_date, date) FROM classes WHERE series_id = ?",
+        )
+        .bind(series_id)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error("Failed to fetch series classes"))?;
+
+        for date in planned {
+            let date = date.format("%Y-%m-%d").to_string();
+            if parse_date(&date)? < from || taken.contains(&date) {
+                continue;
+            }
+            insert_class(
+                &mut tx,
+                &CreateClassRequest {
+                    module_code: class.module_code.clone(),
+                    title: request.title.clone(),
+                    venue: request.venue.clone(),
+                    description: request.description.clone(),
+                    recurring: label.clone(),
+                    date,
+                    time: request.time.clone(),
+                    duration_minutes: request.duration_minutes,
+                    created_by: class.created_by.clone(),
+                    check_in_window: request.check_in_window,
+                    series_id: Some(series_id),
+                    group_ids: group_ids.clone(),
+                },
+            )
+            .await?;
+            update.created += 1;
+        }
+    } else {
+        for other in &others {
+            if !other.edited {
+                let keep_date = UpdateClassRequest {
+                    date: other.slot.clone(),
+                    ..edited.clone()
+                };
+                apply_class_update(&mut tx, other.class_id, &keep_date).await?;
+                update.updated += 1;
+            }
+            set_class_series(&mut tx, other.class_id, Some(series_id)).await?;
+        }
+    }
+
+    if let Some(series) = series
+        .as_ref()
+        .filter(|series| series.series_id != series_id)
+    {
+        end_series_at_last_class(&mut tx, series).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    update.class = get_class_by_id(pool, class_id).await?;
+    Ok(update)
+}
+
+/// Edit one class of a series without touching the others. The class stays in the
+/// series, remembering the date the series put it on so that later edits to the
+/// series don't undo this one.
+#[cfg(feature = "ssr")]
+pub async fn update_occurrence(
+    pool: &SqlitePool,
+    class_id: i64,
+    request: &UpdateClassRequest,
+) -> Result<Class, ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    sqlx::query(
+        "UPDATE classes SET occurrence_date = COALESCE(occurrence_date, date) WHERE classID = ? AND series_id IS NOT NULL",
+    )
+    .bind(class_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("Failed to update class"))?;
+    apply_class_update(&mut tx, class_id, request).await?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    get_class_by_id(pool, class_id).await
+}
+
+/// Add `date` to a series' skipped dates, so regenerating it doesn't bring back a
+/// class that was deleted, and drop the series once it has no classes left
+#[cfg(feature = "ssr")]
+pub(crate) async fn exclude_date(
+    conn: &mut SqliteConnection,
+    series_id: i64,
+    date: &str,
+) -> Result<(), ClockItError> {
+    let Some(series) = fetch_series(conn, series_id).await? else {
+        return Ok(());
+    };
+
+    let mut exdates = series.recurrence.exdates.clone();
+    if !exdates.iter().any(|existing| existing == date) {
+        exdates.push(date.to_string());
+        exdates.sort();
+    }
+    sqlx::query("UPDATE class_series SET exdates = ?, updated_at = ? WHERE seriesID = ?")
+        .bind(exdates.join(","))
+        .bind(Utc::now().to_rfc3339())
+        .bind(series_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to update series"))?;
+
+    delete_series_if_empty(conn, series_id).await
+}
+
+#[cfg(feature = "ssr")]
+async fn fetch_series(
+    conn: &mut SqliteConnection,
+    series_id: i64,
+) -> Result<Option<ClassSeries>, ClockItError> {
+    let series = sqlx::query_as::<_, DbClassSeries>(
+        "SELECT seriesID, moduleCode, rrule, dtstart, exdates, blackout_policy, created_by FROM class_series WHERE seriesID = ?",
+    )
+    .bind(series_id)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(db_error("Failed to fetch series"))?;
+
+    Ok(series.map(Into::into))
+}
+
+#[cfg(feature = "ssr")]
+async fn insert_series(
+    conn: &mut SqliteConnection,
+    module_code: &str,
+    rule: &RecurrenceRule,
+    dtstart: NaiveDate,
+    exdates: &[NaiveDate],
+    blackouts: BlackoutPolicy,
+    created_by: Option<&str>,
+) -> Result<i64, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO class_series (moduleCode, rrule, dtstart, exdates, blackout_policy, created_by, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(module_code)
+    .bind(rule.to_string())
+    .bind(dtstart.format("%Y-%m-%d").to_string())
+    .bind(Some(format_dates(exdates).join(",")).filter(|dates| !dates.is_empty()))
+    .bind(blackouts.as_str())
+    .bind(created_by)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error("Failed to create series"))?;
+
+    Ok(result.last_insert_rowid())
+}
+
+/// Store `rule` and `dtstart` for `series`, along with the skipped dates and
+/// blackout policy it holds
+#[cfg(feature = "ssr")]
+async fn save_series_rule(
+    conn: &mut SqliteConnection,
+    series: &ClassSeries,
+    rule: &RecurrenceRule,
+    dtstart: NaiveDate,
+) -> Result<(), ClockItError> {
+    let exdates = series.recurrence.exdates.join(",");
+    sqlx::query(
+        "UPDATE class_series SET rrule = ?, dtstart = ?, exdates = ?, blackout_policy = ?, updated_at = ? WHERE seriesID = ?",
+    )
+    .bind(rule.to_string())
+    .bind(dtstart.format("%Y-%m-%d").to_string())
+    .bind(Some(exdates).filter(|dates| !dates.is_empty()))
+    .bind(series.recurrence.blackouts.as_str())
+    .bind(Utc::now().to_rfc3339())
+    .bind(series.series_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error("Failed to update series"))?;
+
+    Ok(())
+}
+
+/// End `series` on the last class still linked to it, or delete it if there are none
+#[cfg(feature = "ssr")]
+async fn end_series_at_last_class(
+    conn: &mut SqliteConnection,
+    series: &ClassSeries,
+) -> Result<(), ClockItError> {
+    let last: Option<String> =
+        sqlx::query_scalar("SELECT MAX(date) FROM classes WHERE series_id = ?")
+            .bind(series.series_id)
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(db_error("Failed to fetch series classes"))?;
+
+    let Some(last) = last else {
+        return delete_series_if_empty(conn, series.series_id).await;
+    };
+    let (rule, _) = series.recurrence.parse().map_err(ClockItError::Internal)?;
+    let capped = RecurrenceRule {
+        end: RecurrenceEnd::Until(parse_date(&last)?),
+        ..rule
+    };
+    save_series_rule(conn, series, &capped, parse_date(&series.dtstart)?).await
+}
+
+/// The dates `rule` puts classes on, moved off or dropped from blackout dates
+#[cfg(feature = "ssr")]
+async fn plan_dates(
+    conn: &mut SqliteConnection,
+    rule: &RecurrenceRule,
+    dtstart: NaiveDate,
+    exdates: &[NaiveDate],
+    blackouts: BlackoutPolicy,
+) -> Result<Vec<NaiveDate>, ClockItError> {
+    let calendar = fetch_blackouts(conn).await?;
+    Ok(avoid_blackouts(
+        &rule.occurrences(dtstart, exdates),
+        |date| calendar.iter().any(|blackout| blackout.covers(date)),
+        blackouts,
+    ))
+}
+
+#[cfg(feature = "ssr")]
+async fn delete_series_if_empty(
+    conn: &mut SqliteConnection,
+    series_id: i64,
+) -> Result<(), ClockItError> {
+    sqlx::query(
+        "DELETE FROM class_series WHERE seriesID = ? AND NOT EXISTS (SELECT 1 FROM classes WHERE series_id = ?)",
+    )
+    .bind(series_id)
+    .bind(series_id)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error("Failed to delete series"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn set_class_series(
+    conn: &mut SqliteConnection,
+    class_id: i64,
+    series_id: Option<i64>,
+) -> Result<(), ClockItError> {
+    sqlx::query("UPDATE classes SET series_id = ? WHERE classID = ?")
+        .bind(series_id)
+        .bind(class_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to update class"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn set_occurrence_date(
+    conn: &mut SqliteConnection,
+    class_id: i64,
+    date: Option<NaiveDate>,
+) -> Result<(), ClockItError> {
+    sqlx::query("UPDATE classes SET occurrence_date = ? WHERE classID = ?")
+        .bind(date.map(|date| date.format("%Y-%m-%d").to_string()))
+        .bind(class_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to update class"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn parse_date(date: &str) -> Result<NaiveDate, ClockItError> {
+    NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+        .map_err(|_| ClockItError::Validation(format!("\"{}\" isn't a valid date", date)))
+}
+
+#[cfg(feature = "ssr")]
+fn format_dates(dates: &[NaiveDate]) -> Vec<String> {
+    dates
+        .iter()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .collect()
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::database::classes::delete_class;
+    use crate::database::test_support::{
+        add_class_records, add_module, add_user, fail_on, run, snapshot, test_pool,
+    };
+
+    const MODULE: &str = "SER101";
+    const LECTURER: &str = "series-lecturer@test.example";
+    const STUDENT: &str = "series-student@test.example";
+    const TOUCHED: &[&str] = &[
+        "classes",
+        "class_series",
+        "class_sessions",
+        "attendance",
+        "walk_in_requests",
+        "excuse_requests",
+        "class_groups",
+    ];
+
+    fn weekly(rrule: &str) -> Recurrence {
+        Recurrence {
+            rrule: rrule.to_string(),
+            exdates: Vec::new(),
+            blackouts: BlackoutPolicy::Skip,
+        }
+    }
+
+    fn edit(date: &str) -> UpdateClassRequest {
+        UpdateClassRequest {
+            title: "Lecture".to_string(),
+            description: None,
+            date: date.to_string(),
+            time: "09:00".to_string(),
+            duration_minutes: 60,
+            venue: Some("A101".to_string()),
+            recurring: None,
+            check_in_window: None,
+            group_ids: None,
+        }
+    }
+
+    /// The ids of a four-class weekly series from Monday 2030-02-04
+    async fn fixture(pool: &SqlitePool) -> Vec<i64> {
+        add_user(pool, LECTURER, "lecturer").await;
+        add_user(pool, STUDENT, "student").await;
+        add_module(pool, MODULE, LECTURER).await;
+
+        let template = CreateClassRequest {
+            module_code: MODULE.to_string(),
+            title: "Lecture".to_string(),
+            venue: Some("A101".to_string()),
+            description: None,
+            recurring: None,
+            date: "2030-02-04".to_string(),
+            time: "09:00".to_string(),
+            duration_minutes: 60,
+            created_by: Some(LECTURER.to_string()),
+            check_in_window: None,
+            series_id: None,
+            group_ids: Vec::new(),
+        };
+        create_class_series(pool, &template, &weekly("FREQ=WEEKLY;COUNT=4"))
+            .await
+            .unwrap();
+
+        sqlx::query_scalar("SELECT classID FROM classes WHERE moduleCode = ? ORDER BY date")
+            .bind(MODULE)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    #[test]
+    fn failed_rewrite_leaves_every_table_as_it_was() {
+        run(async {
+            for (scope, event, table) in [
+                // Regenerating removes the later classes, then fails adding new ones
+                (SeriesEditScope::All, "INSERT", "classes"),
+                (SeriesEditScope::All, "UPDATE", "class_series"),
+                // Splitting adds a series and fails moving the edited class to it
+                (SeriesEditScope::ThisAndFollowing, "UPDATE", "classes"),
+            ] {
+                let pool = test_pool().await;
+                let classes = fixture(&pool).await;
+                // A later class with a session, so regenerating it has records to remove
+                add_class_records(&pool, MODULE, classes[3], STUDENT).await;
+                let before = snapshot(&pool, TOUCHED).await;
+                fail_on(&pool, event, table).await;
+
+                let result = update_series(
+                    &pool,
+                    classes[1],
+                    scope,
+                    &edit("2030-02-11"),
+                    Some(&weekly("FREQ=WEEKLY;INTERVAL=2;COUNT=4")),
+                )
+                .await;
+
+                assert!(result.is_err(), "{:?} {} {}", scope, event, table);
+                assert_eq!(
+                    snapshot(&pool, TOUCHED).await,
+                    before,
+                    "{:?} {} {}",
+                    scope,
+                    event,
+                    table
+                );
+            }
+        });
+    }
+
+    /// Date, time and series of each of the module's classes, in date order
+    async fn timetable(pool: &SqlitePool) -> Vec<(String, String, Option<i64>)> {
+        sqlx::query_as(
+            "SELECT date, time, series_id FROM classes WHERE moduleCode = ? ORDER BY date",
+        )
+        .bind(MODULE)
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    fn at(date: &str, time: &str) -> UpdateClassRequest {
+        UpdateClassRequest {
+            time: time.to_string(),
+            ..edit(date)
+        }
+    }
+
+    #[test]
+    fn editing_one_class_leaves_the_rest_of_the_series() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+
+            let class = update_occurrence(&pool, classes[1], &at("2030-02-12", "14:00"))
+                .await
+                .unwrap();
+
+            assert!(class.series_id.is_some());
+            let times: Vec<_> = timetable(&pool)
+                .await
+                .into_iter()
+                .map(|(date, time, _)| (date, time))
+                .collect();
+            assert_eq!(
+                times,
+                [
+                    ("2030-02-04", "09:00"),
+                    ("2030-02-12", "14:00"),
+                    ("2030-02-18", "09:00"),
+                    ("2030-02-25", "09:00"),
+                ]
+                .map(|(date, time)| (date.to_string(), time.to_string()))
+            );
+        });
+    }
+
+    #[test]
+    fn editing_all_classes_keeps_one_edited_on_its_own() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+            update_occurrence(&pool, classes[1], &at("2030-02-12", "14:00"))
+                .await
+                .unwrap();
+            let rule = weekly("FREQ=WEEKLY;COUNT=4");
+
+            // Same pattern and date: the others take the new time in place
+            let update = update_series(
+                &pool,
+                classes[0],
+                SeriesEditScope::All,
+                &at("2030-02-04", "10:00"),
+                Some(&rule),
+            )
+            .await
+            .unwrap();
+            assert_eq!((update.updated, update.created, update.removed), (3, 0, 0));
+            let times: Vec<_> = timetable(&pool)
+                .await
+                .into_iter()
+                .map(|row| row.1)
+                .collect();
+            assert_eq!(times, ["10:00", "14:00", "10:00", "10:00"]);
+
+            // Moving the series a day on regenerates it around the edited class,
+            // which now stands in for the Tuesday it was moved to
+            let update = update_series(
+                &pool,
+                classes[0],
+                SeriesEditScope::All,
+                &at("2030-02-05", "10:00"),
+                Some(&rule),
+            )
+            .await
+            .unwrap();
+            assert_eq!((update.created, update.removed), (2, 2));
+            let dates: Vec<_> = timetable(&pool)
+                .await
+                .into_iter()
+                .map(|row| row.0)
+                .collect();
+            assert_eq!(
+                dates,
+                ["2030-02-05", "2030-02-12", "2030-02-19", "2030-02-26"]
+            );
+            let kept: (String, Option<String>) =
+                sqlx::query_as("SELECT time, occurrence_date FROM classes WHERE classID = ?")
+                    .bind(classes[1])
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(kept, ("14:00".to_string(), Some("2030-02-12".to_string())));
+        });
+    }
+
+    #[test]
+    fn a_pattern_without_the_edited_date_drops_the_edited_class() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+            update_occurrence(&pool, classes[1], &at("2030-02-12", "14:00"))
+                .await
+                .unwrap();
+
+            update_series(
+                &pool,
+                classes[0],
+                SeriesEditScope::All,
+                &edit("2030-02-04"),
+                Some(&weekly("FREQ=WEEKLY;INTERVAL=2;COUNT=4")),
+            )
+            .await
+            .unwrap();
+
+            let dates: Vec<_> = timetable(&pool)
+                .await
+                .into_iter()
+                .map(|row| row.0)
+                .collect();
+            assert_eq!(
+                dates,
+                ["2030-02-04", "2030-02-18", "2030-03-04", "2030-03-18"]
+            );
+        });
+    }
+
+    #[test]
+    fn editing_this_and_following_splits_the_series() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+
+            update_series(
+                &pool,
+                classes[2],
+                SeriesEditScope::ThisAndFollowing,
+                &at("2030-02-18", "11:00"),
+                Some(&weekly("FREQ=WEEKLY;COUNT=2")),
+            )
+            .await
+            .unwrap();
+
+            let rows = timetable(&pool).await;
+            let times: Vec<_> = rows.iter().map(|row| row.1.as_str()).collect();
+            assert_eq!(times, ["09:00", "09:00", "11:00", "11:00"]);
+            let (old, new) = (rows[0].2.unwrap(), rows[2].2.unwrap());
+            assert_eq!((rows[1].2, rows[3].2), (Some(old), Some(new)));
+            assert_ne!(old, new);
+
+            // The earlier classes keep the old pattern, now ending on the last of them
+            let old = get_series(&pool, old).await.unwrap().unwrap();
+            assert_eq!(old.recurrence.rrule, "FREQ=WEEKLY;UNTIL=20300211");
+            let new = get_series(&pool, new).await.unwrap().unwrap();
+            assert_eq!(new.dtstart, "2030-02-18");
+        });
+    }
+
+    #[test]
+    fn stopping_the_repeat_keeps_only_the_edited_class() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+
+            let update = update_series(
+                &pool,
+                classes[2],
+                SeriesEditScope::ThisAndFollowing,
+                &edit("2030-02-18"),
+                None,
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(update.removed, 1);
+            assert_eq!(update.class.series_id, None);
+            let dates: Vec<_> = timetable(&pool)
+                .await
+                .into_iter()
+                .map(|row| row.0)
+                .collect();
+            assert_eq!(dates, ["2030-02-04", "2030-02-11", "2030-02-18"]);
+        });
+    }
+
+    #[test]
+    fn deleting_an_edited_class_skips_its_original_date() {
+        run(async {
+            let pool = test_pool().await;
+            let classes = fixture(&pool).await;
+            let class = update_occurrence(&pool, classes[1], &at("2030-02-12", "14:00"))
+                .await
+                .unwrap();
+
+            delete_class(&pool, classes[1]).await.unwrap();
+
+            let series = get_series(&pool, class.series_id.unwrap())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(series.recurrence.exdates, ["2030-02-11"]);
+        });
+    }
+}
@@ -1,7 +1,17 @@
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
-use chrono::Utc;
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use crate::qr_tokens::generate_secret;
+#[cfg(feature = "ssr")]
+use crate::clock::Clock;
+#[cfg(feature = "ssr")]
+use crate::database::universities::timezone_or_default;
+#[cfg(feature = "ssr")]
+use crate::utils::class_time::ClassWindow;
+#[cfg(feature = "ssr")]
+use chrono::Duration;
 #[cfg(feature = "ssr")]
 use sqlx::{FromRow, SqlitePool};
 
@@ -16,6 +26,9 @@ pub struct ClassSession {
     pub start_longitude: Option<f64>,
     pub start_accuracy: Option<f64>,
     pub location_radius: Option<f64>,
+    /// Venue outline the session is fenced by instead of the radius, if it has one
+    pub location_polygon: Option<Vec<[f64; 2]>>,
+    pub qr_rotation_seconds: i64,
 }
 
 #[cfg(feature = "ssr")]
@@ -32,6 +45,8 @@ struct DbClassSession {
     start_longitude: Option<f64>,
     start_accuracy: Option<f64>,
     location_radius: Option<f64>,
+    location_polygon: Option<String>,
+    qr_rotation_seconds: i64,
 }
 
 #[cfg(feature = "ssr")]
@@ -47,6 +62,10 @@ impl From<DbClassSession> for ClassSession {
             start_longitude: db.start_longitude,
             start_accuracy: db.start_accuracy,
             location_radius: db.location_radius,
+            location_polygon: db
+                .location_polygon
+                .and_then(|polygon| serde_json::from_str(&polygon).ok()),
+            qr_rotation_seconds: db.qr_rotation_seconds,
         }
     }
 }
@@ -55,19 +74,20 @@ impl From<DbClassSession> for ClassSession {
 pub async fn get_active_session(
     pool: &SqlitePool,
     class_id: i64,
-) -> Result<Option<ClassSession>, String> {
+) -> Result<Option<ClassSession>, ClockItError> {
     let session = sqlx::query_as::<_, DbClassSession>(
         "SELECT * FROM class_sessions WHERE classID = ? AND ended_at IS NULL ORDER BY started_at DESC LIMIT 1"
     )
     .bind(class_id)
     .fetch_optional(pool)
     .await
-    .map_err(|e| format!("Failed to fetch active session: {}", e))?;
+    .map_err(db_error("Failed to fetch active session"))?;
 
     Ok(session.map(Into::into))
 }
 
 #[cfg(feature = "ssr")]
+#[allow(clippy::too_many_arguments)]
 pub async fn create_session(
     pool: &SqlitePool,
     class_id: i64,
@@ -76,14 +96,23 @@ pub async fn create_session(
     longitude: Option<f64>,
     accuracy: Option<f64>,
     radius: Option<f64>,
-) -> Result<ClassSession, String> {
+    polygon: Option<&[[f64; 2]]>,
+    qr_rotation_seconds: i64,
+    clock: &dyn Clock,
+) -> Result<ClassSession, ClockItError> {
     if get_active_session(pool, class_id).await?.is_some() {
-        return Err("A session is already active for this class".to_string());
+        return Err(ClockItError::Conflict(
+            "A session is already active for this class".to_string(),
+        ));
     }
 
-    let now = Utc::now().to_rfc3339();
+    let now = clock.now().to_rfc3339();
+    let polygon = polygon
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| ClockItError::Internal(format!("Failed to encode session geofence: {}", e)))?;
     let result = sqlx::query(
-        "INSERT INTO class_sessions (classID, started_at, started_by, start_latitude, start_longitude, start_accuracy, location_radius) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        "INSERT INTO class_sessions (classID, started_at, started_by, start_latitude, start_longitude, start_accuracy, location_radius, location_polygon, qr_secret, qr_rotation_seconds) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
     )
     .bind(class_id)
     .bind(&now)
@@ -92,9 +121,12 @@ pub async fn create_session(
     .bind(longitude)
     .bind(accuracy)
     .bind(radius)
+    .bind(polygon)
+    .bind(generate_secret())
+    .bind(qr_rotation_seconds)
     .execute(pool)
     .await
-    .map_err(|e| format!("Failed to create session: {}", e))?;
+    .map_err(db_error("Failed to create session"))?;
 
     let session_id = result.last_insert_rowid();
     let session =
@@ -102,14 +134,18 @@ pub async fn create_session(
             .bind(session_id)
             .fetch_one(pool)
             .await
-            .map_err(|e| format!("Failed to load created session: {}", e))?;
+            .map_err(db_error("Failed to load created session"))?;
 
     Ok(session.into())
 }
 
 #[cfg(feature = "ssr")]
-pub async fn end_session(pool: &SqlitePool, session_id: i64) -> Result<ClassSession, String> {
-    let now = Utc::now().to_rfc3339();
+pub async fn end_session(
+    pool: &SqlitePool,
+    session_id: i64,
+    clock: &dyn Clock,
+) -> Result<ClassSession, ClockItError> {
+    let now = clock.now().to_rfc3339();
 
     // First, get the session to find the class_id
     let session =
@@ -117,43 +153,43 @@ pub async fn end_session(pool: &SqlitePool, session_id: i64) -> Result<ClassSess
             .bind(session_id)
             .fetch_one(pool)
             .await
-            .map_err(|e| format!("Failed to fetch session: {}", e))?;
+            .map_err(db_error("Failed to fetch session"))?;
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
 
     // End the session
     sqlx::query("UPDATE class_sessions SET ended_at = ? WHERE sessionID = ? AND ended_at IS NULL")
         .bind(&now)
         .bind(session_id)
-        .execute(pool)
-        .await
-        .map_err(|e| format!("Failed to end session: {}", e))?;
-
-    // Get the module code for this class
-    let class_module: (String,) = sqlx::query_as("SELECT moduleCode FROM classes WHERE classID = ?")
-        .bind(session.class_id)
-        .fetch_one(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to fetch class module: {}", e))?;
+        .map_err(db_error("Failed to end session"))?;
 
-    // Mark all students who didn't attend as absent
+    // Mark the students expected at the class who didn't attend as absent
     sqlx::query(
         r#"
         INSERT INTO attendance (studentID, classID, status, recorded_at, notes)
-        SELECT u.userID, ?, 'absent', ?, 'Marked absent when session ended'
+        SELECT u.userID, ?1, 'absent', ?2, 'Marked absent when session ended'
         FROM users u
-        INNER JOIN module_students ms ON ms.studentEmailAddress = u.emailAddress
-        WHERE ms.moduleCode = ? AND u.role = 'student'
+        INNER JOIN class_students cs ON cs.studentEmailAddress = u.emailAddress
+        WHERE cs.classID = ?1 AND u.role = 'student'
           AND NOT EXISTS (
-              SELECT 1 FROM attendance a WHERE a.classID = ? AND a.studentID = u.userID
+              SELECT 1 FROM attendance a WHERE a.classID = ?1 AND a.studentID = u.userID
           )
         "#,
     )
     .bind(session.class_id)
     .bind(&now)
-    .bind(&class_module.0)
-    .bind(session.class_id)
-    .execute(pool)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| format!("Failed to mark absentees: {}", e))?;
+    .map_err(db_error("Failed to mark absentees"))?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
 
     // Fetch the updated session
     let updated_session =
@@ -161,22 +197,579 @@ pub async fn end_session(pool: &SqlitePool, session_id: i64) -> Result<ClassSess
             .bind(session_id)
             .fetch_one(pool)
             .await
-            .map_err(|e| format!("Failed to fetch ended session: {}", e))?;
+            .map_err(db_error("Failed to fetch ended session"))?;
 
     Ok(updated_session.into())
 }
 
+/// Outcome of one pass of [`sweep_session_states`]
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SessionSweep {
+    pub checked: usize,
+    pub updated: usize,
+}
+
+/// Classes that may have ended by a date, with the timezone to check them in
+#[cfg(feature = "ssr")]
+const SWEEP_OPEN_CLASSES: &str = r#"
+    SELECT c.classID, c.date, c.time, c.duration_minutes, un.timezone
+    FROM classes c
+    LEFT JOIN modules m ON m.moduleCode = c.moduleCode
+    LEFT JOIN universities un ON un.name = m.university
+    WHERE c.status IN ('in_progress', 'upcoming') AND c.date <= ?
+"#;
+
+/// Ends open sessions whose class is in the JSON list `?2` of ended classes, or which
+/// have run their own length, at `?1`
+#[cfg(feature = "ssr")]
+const SWEEP_END_SESSIONS: &str = r#"
+    UPDATE class_sessions
+    SET ended_at = ?1
+    WHERE ended_at IS NULL
+      AND EXISTS (
+          SELECT 1 FROM classes c
+          WHERE c.classID = class_sessions.classID
+            AND c.status IN ('in_progress', 'upcoming')
+            AND (
+                c.classID IN (SELECT value FROM json_each(?2))
+                OR datetime(class_sessions.started_at, '+' || MAX(c.duration_minutes, 15) || ' minutes') <= datetime(?1)
+            )
+      )
+"#;
+
+/// Marks the students expected at classes whose sessions ended at `?1` absent
+#[cfg(feature = "ssr")]
+const SWEEP_MARK_ABSENT: &str = r#"
+    INSERT INTO attendance (studentID, classID, status, recorded_at, notes)
+    SELECT DISTINCT u.userID, c.classID, 'absent', ?1, 'Marked absent when session ended'
+    FROM class_sessions s
+    INNER JOIN classes c ON c.classID = s.classID
+    INNER JOIN class_students cs ON cs.classID = c.classID
+    INNER JOIN users u ON u.emailAddress = cs.studentEmailAddress
+    WHERE s.ended_at = ?1 AND u.role = 'student'
+      AND NOT EXISTS (
+          SELECT 1 FROM attendance a WHERE a.classID = c.classID AND a.studentID = u.userID
+      )
+"#;
+
+/// Completes classes whose sessions ended at `?1`, and ended classes in `?2` that
+/// were never started
+#[cfg(feature = "ssr")]
+const SWEEP_COMPLETE_CLASSES: &str = r#"
+    UPDATE classes
+    SET status = 'completed', updated_at = ?1
+    WHERE status IN ('in_progress', 'upcoming')
+      AND (
+          classID IN (SELECT classID FROM class_sessions WHERE ended_at = ?1)
+          OR (
+              classID IN (SELECT value FROM json_each(?2))
+              AND NOT EXISTS (
+                  SELECT 1 FROM class_sessions s
+                  WHERE s.classID = classes.classID AND s.ended_at IS NULL
+              )
+          )
+      )
+"#;
+
+/// End sessions that have run past their class and mark finished classes completed.
+/// Sessions end once the class's scheduled end or the session's own duration has
+/// passed, and the students expected at them without attendance are marked absent, the
+/// same as [`end_session`]. Classes that were never started are completed once their
+/// scheduled end passes. Sessions are never started automatically.
+///
+/// Class dates and times are wall-clock times at each module's university; session
+/// timestamps are UTC.
+#[cfg(feature = "ssr")]
+pub async fn sweep_session_states(
+    pool: &SqlitePool,
+    clock: &dyn Clock,
+) -> Result<SessionSweep, ClockItError> {
+    let now_utc = clock.now();
+    let now = now_utc.to_rfc3339();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    // Whether a class has ended depends on its university's timezone, which SQLite
+    // can't apply, so the ended classes are found here. No timezone is more than a
+    // day ahead of UTC, so later dates can't have ended.
+    let open: Vec<(i64, String, String, i32, Option<String>)> = sqlx::query_as(SWEEP_OPEN_CLASSES)
+        .bind((now_utc + Duration::days(1)).format("%Y-%m-%d").to_string())
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(db_error("Failed to fetch classes"))?;
+
+    let ended: Vec<i64> = open
+        .into_iter()
+        .filter(|(_, date, time, duration_minutes, timezone)| {
+            let tz = timezone_or_default(timezone.as_deref());
+            ClassWindow::new(date, time, (*duration_minutes).max(15), tz)
+                .is_some_and(|window| window.has_ended(now_utc))
+        })
+        .map(|(class_id, ..)| class_id)
+        .collect();
+    let ended = serde_json::to_string(&ended)
+        .map_err(|e| ClockItError::Internal(format!("Failed to end sessions: {}", e)))?;
+
+    let checked: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM classes WHERE status IN ('in_progress', 'upcoming')",
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(db_error("Failed to count classes"))?;
+
+    // Every session ended in this pass gets the same `ended_at`, which the
+    // statements below use to find them again
+    sqlx::query(SWEEP_END_SESSIONS)
+        .bind(&now)
+        .bind(&ended)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to end sessions"))?;
+
+    sqlx::query(SWEEP_MARK_ABSENT)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to mark absentees"))?;
+
+    let updated = sqlx::query(SWEEP_COMPLETE_CLASSES)
+        .bind(&now)
+        .bind(&ended)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to complete classes"))?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(SessionSweep {
+        checked: checked as usize,
+        updated: updated.rows_affected() as usize,
+    })
+}
+
 #[cfg(feature = "ssr")]
 pub async fn get_session_by_id(
     pool: &SqlitePool,
     session_id: i64,
-) -> Result<Option<ClassSession>, String> {
+) -> Result<Option<ClassSession>, ClockItError> {
     let session =
         sqlx::query_as::<_, DbClassSession>("SELECT * FROM class_sessions WHERE sessionID = ?")
             .bind(session_id)
             .fetch_optional(pool)
             .await
-            .map_err(|e| format!("Failed to fetch session: {}", e))?;
+            .map_err(db_error("Failed to fetch session"))?;
 
     Ok(session.map(Into::into))
 }
+
+/// The signing key and rotation interval for a session's QR tokens.
+/// Kept out of `ClassSession` so the key never reaches the browser.
+#[cfg(feature = "ssr")]
+pub async fn get_session_qr_secret(
+    pool: &SqlitePool,
+    session_id: i64,
+) -> Result<Option<(String, i64)>, ClockItError> {
+    let row: Option<(Option<String>, i64)> = sqlx::query_as(
+        "SELECT qr_secret, qr_rotation_seconds FROM class_sessions WHERE sessionID = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch session key"))?;
+
+    Ok(row.and_then(|(secret, rotation)| secret.map(|secret| (secret, rotation))))
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::database::classes::get_class_by_id;
+    use crate::database::test_support::{
+        add_class, add_module, add_user, enroll, fail_on, run, snapshot, test_pool, utc,
+    };
+    use crate::database::universities::class_timezone;
+    use chrono::{DateTime, Utc};
+    use std::time::Instant;
+
+    const LECTURER: &str = "bench-lecturer@test.example";
+    const MODULES: i64 = 10;
+    const STUDENTS: i64 = 200;
+    /// 40 classes a day (four hour-long slots in each module) for 100 days
+    const CLASSES: i64 = 4_000;
+    const CLASSES_PER_DAY: i64 = 40;
+    /// 2025-03-10, the day the sweep runs
+    const TODAY: i64 = 35;
+
+    /// A semester's worth of classes. Every module has 40 of the students; one class a
+    /// day before today was started and left running, one student checked in to each,
+    /// and one class is running now.
+    async fn seed(pool: &SqlitePool) -> i64 {
+        add_user(pool, LECTURER, "lecturer").await;
+        for module in 0..MODULES {
+            add_module(pool, &format!("BEN{}", module), LECTURER).await;
+        }
+
+        sqlx::query(
+            r#"
+            WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i + 1 < ?)
+            INSERT INTO users (name, surname, emailAddress, password, university, role, created_at, updated_at)
+            SELECT 'Student', i, 'bench' || i || '@test.example', '', 'Stellenbosch University', 'student',
+                   '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'
+            FROM n
+            "#,
+        )
+        .bind(STUDENTS)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // Each student takes two neighbouring modules
+        sqlx::query(
+            r#"
+            WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i + 1 < ?1)
+            INSERT INTO module_students (moduleCode, studentEmailAddress, created_at)
+            SELECT 'BEN' || ((i + k) % ?2), 'bench' || i || '@test.example', '2025-01-01T00:00:00Z'
+            FROM n, (SELECT 0 AS k UNION ALL SELECT 1)
+            "#,
+        )
+        .bind(STUDENTS)
+        .bind(MODULES)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // Class i is on day i / 40, in module i % 10, at 08:00, 10:00, 12:00 or 14:00
+        sqlx::query(
+            r#"
+            WITH RECURSIVE n(i) AS (SELECT 0 UNION ALL SELECT i + 1 FROM n WHERE i + 1 < ?1)
+            INSERT INTO classes (moduleCode, title, venue, date, time, duration_minutes, status, created_by, created_at, updated_at)
+            SELECT 'BEN' || (i % ?2), 'Class ' || i, 'V' || (i % ?2),
+                   date('2025-02-03', '+' || (i / ?3) || ' days'),
+                   printf('%02d:00', 8 + 2 * ((i % ?3) / ?2)),
+                   60, 'upcoming', ?4, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z'
+            FROM n ORDER BY i
+            "#,
+        )
+        .bind(CLASSES)
+        .bind(MODULES)
+        .bind(CLASSES_PER_DAY)
+        .bind(LECTURER)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // BEN0's 08:00 class each day before today, started on time and never ended
+        sqlx::query(
+            r#"
+            INSERT INTO class_sessions (classID, started_at, started_by)
+            SELECT classID, date || 'T06:00:00+00:00', ?
+            FROM classes
+            WHERE moduleCode = 'BEN0' AND time = '08:00' AND date < '2025-03-10'
+            "#,
+        )
+        .bind(LECTURER)
+        .execute(pool)
+        .await
+        .unwrap();
+
+        sqlx::query(
+            r#"
+            INSERT INTO attendance (studentID, classID, status, recorded_at)
+            SELECT u.userID, s.classID, 'present', s.started_at
+            FROM class_sessions s, users u
+            WHERE u.emailAddress = 'bench0@test.example'
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // BEN0's 14:00 class today, which started at 14:00 local
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO class_sessions (classID, started_at, started_by)
+            SELECT classID, '2025-03-10T12:00:00+00:00', ?
+            FROM classes
+            WHERE moduleCode = 'BEN0' AND time = '14:00' AND date = '2025-03-10'
+            RETURNING sessionID
+            "#,
+        )
+        .bind(LECTURER)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    const BENCH_ABSENT: &str = "SELECT COUNT(*) FROM attendance a INNER JOIN classes c ON c.classID = a.classID WHERE c.moduleCode LIKE 'BEN%' AND a.status = 'absent'";
+    const BENCH_PRESENT: &str = "SELECT COUNT(*) FROM attendance a INNER JOIN classes c ON c.classID = a.classID WHERE c.moduleCode LIKE 'BEN%' AND a.status = 'present'";
+
+    async fn count(pool: &SqlitePool, sql: &str) -> i64 {
+        sqlx::query_scalar(sql).fetch_one(pool).await.unwrap()
+    }
+
+    async fn query_plan(pool: &SqlitePool, sql: &str) -> Vec<String> {
+        sqlx::query_as::<_, (i64, i64, i64, String)>(&format!("EXPLAIN QUERY PLAN {}", sql))
+            .bind("2025-03-10T12:00:00+00:00")
+            .bind("[]")
+            .fetch_all(pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(.., detail)| detail)
+            .collect()
+    }
+
+    /// The sweep as it was before it became set-based, for the benchmark below: each
+    /// open class is loaded and checked on its own, ending its session if it has run
+    /// its course. Returns how many classes changed status.
+    async fn per_class_sweep(pool: &SqlitePool, clock: &dyn Clock) -> usize {
+        let classes: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT classID, status FROM classes WHERE status IN ('in_progress', 'upcoming')",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+
+        let now = clock.now();
+        let mut updated = 0;
+        for (class_id, old_status) in classes {
+            let class = get_class_by_id(pool, class_id).await.unwrap();
+            let tz = class_timezone(pool, class_id).await.unwrap();
+            let duration_minutes = class.duration_minutes.max(15);
+            let window = ClassWindow::new(&class.date, &class.time, duration_minutes, tz).unwrap();
+
+            let completed = match get_active_session(pool, class_id).await.unwrap() {
+                None => window.has_ended(now),
+                Some(session) => {
+                    let started_at = DateTime::parse_from_rfc3339(&session.started_at)
+                        .map(|started| started.with_timezone(&Utc))
+                        .unwrap_or(window.start);
+                    let over = now >= started_at + Duration::minutes(duration_minutes as i64)
+                        || window.has_ended(now);
+                    if over {
+                        end_session(pool, session.session_id, clock).await.unwrap();
+                    }
+                    over
+                }
+            };
+            if completed {
+                sqlx::query(
+                    "UPDATE classes SET status = 'completed', updated_at = ? WHERE classID = ?",
+                )
+                .bind(now.to_rfc3339())
+                .bind(class_id)
+                .execute(pool)
+                .await
+                .unwrap();
+            }
+
+            let new_status: String =
+                sqlx::query_scalar("SELECT status FROM classes WHERE classID = ?")
+                    .bind(class_id)
+                    .fetch_one(pool)
+                    .await
+                    .unwrap();
+            if new_status != old_status {
+                updated += 1;
+            }
+        }
+        updated
+    }
+
+    /// What a sweep leaves behind in the seeded modules, to compare two sweeps by
+    async fn swept_rows(pool: &SqlitePool) -> Vec<String> {
+        sqlx::query_scalar(
+            r#"
+            SELECT 'class ' || classID || ' ' || status FROM classes WHERE moduleCode LIKE 'BEN%'
+            UNION ALL
+            SELECT 'session ' || s.sessionID || ' ' || COALESCE(s.ended_at, 'open')
+            FROM class_sessions s INNER JOIN classes c ON c.classID = s.classID
+            WHERE c.moduleCode LIKE 'BEN%'
+            UNION ALL
+            SELECT 'attendance ' || a.classID || ' ' || a.studentID || ' ' || a.status
+            FROM attendance a INNER JOIN classes c ON c.classID = a.classID
+            WHERE c.moduleCode LIKE 'BEN%'
+            ORDER BY 1
+            "#,
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap()
+    }
+
+    #[test]
+    fn sweep_of_a_seeded_semester_moves_the_right_rows() {
+        run(async {
+            let pool = test_pool().await;
+            let running_session = seed(&pool).await;
+            // 14:00 in Stellenbosch
+            let clock = FixedClock::new(utc("2025-03-10T12:00:00Z"));
+
+            let first = sweep_session_states(&pool, &clock).await.unwrap();
+
+            // Every class before today, and today's 08:00, 10:00 and 12:00 classes
+            let ended_classes = TODAY * CLASSES_PER_DAY + 3 * MODULES;
+            assert!(first.updated as i64 >= ended_classes);
+            assert_eq!(
+                count(&pool, "SELECT COUNT(*) FROM classes WHERE moduleCode LIKE 'BEN%' AND status = 'completed'").await,
+                ended_classes
+            );
+            assert_eq!(
+                count(&pool, "SELECT COUNT(*) FROM classes WHERE moduleCode LIKE 'BEN%' AND status = 'completed' AND (date > '2025-03-10' OR (date = '2025-03-10' AND time >= '14:00'))").await,
+                0
+            );
+
+            // Only the session running now is still open
+            let open: Vec<i64> =
+                sqlx::query_scalar("SELECT sessionID FROM class_sessions s INNER JOIN classes c ON c.classID = s.classID WHERE c.moduleCode LIKE 'BEN%' AND s.ended_at IS NULL")
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(open, vec![running_session]);
+
+            // BEN0's other 39 students at each of the 35 sessions, and nobody at
+            // classes that were never started
+            assert_eq!(count(&pool, BENCH_ABSENT).await, TODAY * 39);
+            assert_eq!(count(&pool, BENCH_PRESENT).await, TODAY);
+
+            // A second pass finds nothing left to do
+            let second = sweep_session_states(&pool, &clock).await.unwrap();
+            assert_eq!(second.updated, 0);
+            assert_eq!(count(&pool, BENCH_ABSENT).await, TODAY * 39);
+        });
+    }
+
+    /// Times the sweep against the per-class loop it replaced, on the same seed. Run
+    /// with `cargo test --features ssr -- --ignored --nocapture sweep_benchmark`.
+    #[test]
+    #[ignore = "benchmark"]
+    fn sweep_benchmark_against_the_per_class_loop() {
+        run(async {
+            let clock = FixedClock::new(utc("2025-03-10T12:00:00Z"));
+
+            let old_pool = test_pool().await;
+            seed(&old_pool).await;
+            let started = Instant::now();
+            let old_updated = per_class_sweep(&old_pool, &clock).await;
+            let old_pass = started.elapsed();
+
+            let pool = test_pool().await;
+            seed(&pool).await;
+            let started = Instant::now();
+            let sweep = sweep_session_states(&pool, &clock).await.unwrap();
+            let first_pass = started.elapsed();
+            let started = Instant::now();
+            sweep_session_states(&pool, &clock).await.unwrap();
+            let steady_pass = started.elapsed();
+
+            assert_eq!(sweep.updated, old_updated);
+            assert_eq!(swept_rows(&pool).await, swept_rows(&old_pool).await);
+            assert!(first_pass < old_pass);
+            println!(
+                "sweep of {} classes, {} students: per-class loop {:?}, first pass {:?}, steady-state pass {:?}",
+                CLASSES, STUDENTS, old_pass, first_pass, steady_pass
+            );
+        });
+    }
+
+    #[test]
+    fn sweep_statements_use_indexes() {
+        run(async {
+            let pool = test_pool().await;
+            seed(&pool).await;
+
+            let plans = [
+                ("open classes", query_plan(&pool, SWEEP_OPEN_CLASSES).await),
+                ("end sessions", query_plan(&pool, SWEEP_END_SESSIONS).await),
+                ("mark absent", query_plan(&pool, SWEEP_MARK_ABSENT).await),
+                (
+                    "complete classes",
+                    query_plan(&pool, SWEEP_COMPLETE_CLASSES).await,
+                ),
+            ];
+            for (name, plan) in &plans {
+                // Only json_each, the short list of ended classes, may be scanned
+                let scans: Vec<_> = plan
+                    .iter()
+                    .filter(|step| step.starts_with("SCAN") && !step.contains("json_each"))
+                    .collect();
+                assert!(scans.is_empty(), "{} scans a table: {:?}", name, scans);
+            }
+
+            for (step, index) in [
+                (0, "idx_classes_status_date"),
+                (1, "idx_class_sessions_ended"),
+                (2, "idx_class_sessions_ended"),
+                (3, "idx_classes_status_date"),
+                (3, "idx_class_sessions_ended"),
+            ] {
+                let (name, plan) = &plans[step];
+                assert!(
+                    plan.iter().any(|detail| detail.contains(index)),
+                    "{} doesn't use {}: {:?}",
+                    name,
+                    index,
+                    plan
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn failed_end_leaves_the_session_running() {
+        run(async {
+            let pool = test_pool().await;
+            add_user(&pool, LECTURER, "lecturer").await;
+            add_module(&pool, "END101", LECTURER).await;
+            for student in ["end-present@test.example", "end-absent@test.example"] {
+                add_user(&pool, student, "student").await;
+                enroll(&pool, "END101", student).await;
+            }
+            let class_id =
+                add_class(&pool, "END101", "A101", "2025-03-10", "09:00", 60, LECTURER).await;
+            let clock = FixedClock::new(utc("2025-03-10T07:00:00Z"));
+            let session = create_session(
+                &pool,
+                class_id,
+                Some(LECTURER.to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                30,
+                &clock,
+            )
+            .await
+            .unwrap();
+            sqlx::query(
+                r#"
+                INSERT INTO attendance (studentID, classID, status, recorded_at)
+                SELECT userID, ?, 'present', '2025-03-10T07:05:00Z' FROM users
+                WHERE emailAddress = 'end-present@test.example'
+                "#,
+            )
+            .bind(class_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            let touched = ["class_sessions", "attendance"];
+            let before = snapshot(&pool, &touched).await;
+            // Marking the absentee fails after the session has been ended
+            fail_on(&pool, "INSERT", "attendance").await;
+
+            assert!(end_session(&pool, session.session_id, &clock)
+                .await
+                .is_err());
+            assert_eq!(snapshot(&pool, &touched).await, before);
+        });
+    }
+}
@@ -1,7 +1,13 @@
 #[cfg(feature = "ssr")]
-use chrono::Utc;
+use crate::clock::clock;
 #[cfg(feature = "ssr")]
-use sqlx::SqlitePool;
+use crate::database::class_series::exclude_date;
+#[cfg(feature = "ssr")]
+use crate::database::groups::set_class_groups;
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use sqlx::{SqliteConnection, SqlitePool};
 
 use serde::{Deserialize, Serialize};
 
@@ -19,10 +25,84 @@ pub struct Class {
     pub duration_minutes: i32,
     pub status: String,
     pub created_by: Option<String>,
+    /// Overrides the module's check-in window for this class only
+    pub check_in_window: Option<CheckInWindow>,
+    /// The recurring series this class was generated from
+    pub series_id: Option<i64>,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// When a check-in is accepted, in minutes after the class's start time: up to
+/// `on_time_minutes` counts as present, up to `late_minutes` as late, and later
+/// scans are refused
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CheckInWindow {
+    pub on_time_minutes: i32,
+    pub late_minutes: i32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckInTiming {
+    OnTime,
+    Late,
+    Closed,
+}
+
+impl Default for CheckInWindow {
+    fn default() -> Self {
+        Self {
+            on_time_minutes: 10,
+            late_minutes: 30,
+        }
+    }
+}
+
+impl CheckInWindow {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.on_time_minutes < 0 {
+            return Err("The on-time window can't be negative".to_string());
+        }
+        if self.late_minutes < self.on_time_minutes {
+            return Err("The late window must end at or after the on-time window".to_string());
+        }
+        Ok(())
+    }
+
+    /// Read an optional per-class override from two form fields. Both blank means
+    /// "use the module's window".
+    pub fn parse_override(on_time: &str, late: &str) -> Result<Option<Self>, String> {
+        let (on_time, late) = (on_time.trim(), late.trim());
+        if on_time.is_empty() && late.is_empty() {
+            return Ok(None);
+        }
+
+        let parse = |value: &str| {
+            value
+                .parse::<i32>()
+                .map_err(|_| "Please enter the check-in window in whole minutes".to_string())
+        };
+        let window = Self {
+            on_time_minutes: parse(on_time)?,
+            late_minutes: parse(late)?,
+        };
+        window.validate()?;
+        Ok(Some(window))
+    }
+
+    /// Classify a check-in made `minutes_after_start` minutes after the class began
+    /// (negative when the session was opened early)
+    pub fn classify(&self, minutes_after_start: i64) -> CheckInTiming {
+        if minutes_after_start <= self.on_time_minutes as i64 {
+            CheckInTiming::OnTime
+        } else if minutes_after_start <= self.late_minutes as i64 {
+            CheckInTiming::Late
+        } else {
+            CheckInTiming::Closed
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateClassRequest {
     pub module_code: String,
@@ -34,6 +114,11 @@ pub struct CreateClassRequest {
     pub time: String,
     pub duration_minutes: i32,
     pub created_by: Option<String>,
+    pub check_in_window: Option<CheckInWindow>,
+    pub series_id: Option<i64>,
+    /// Tutorial groups the class is for; empty means the whole module
+    #[serde(default)]
+    pub group_ids: Vec<i64>,
 }
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UpdateClassRequest {
@@ -44,6 +129,29 @@ pub struct UpdateClassRequest {
     pub duration_minutes: i32,
     pub venue: Option<String>,
     pub recurring: Option<String>,
+    pub check_in_window: Option<CheckInWindow>,
+    /// Tutorial groups the class is for, empty for the whole module. `None` leaves
+    /// the class's groups as they are.
+    #[serde(default)]
+    pub group_ids: Option<Vec<i64>>,
+}
+
+impl UpdateClassRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.title.trim().is_empty() {
+            return Err("Class title is required".to_string());
+        }
+        if self.date.trim().is_empty() {
+            return Err("Class date is required".to_string());
+        }
+        if self.duration_minutes <= 0 {
+            return Err("Please choose a valid duration".to_string());
+        }
+        if let Some(window) = self.check_in_window {
+            window.validate()?;
+        }
+        Ok(())
+    }
 }
 
 // Server-side implementation
@@ -67,6 +175,9 @@ pub struct DbClass {
     duration_minutes: i32,
     status: String,
     created_by: Option<String>,
+    on_time_minutes: Option<i32>,
+    late_minutes: Option<i32>,
+    series_id: Option<i64>,
     created_at: String,
     updated_at: String,
 }
@@ -86,6 +197,14 @@ impl From<DbClass> for Class {
             duration_minutes: db.duration_minutes,
             status: db.status,
             created_by: db.created_by,
+            check_in_window: match (db.on_time_minutes, db.late_minutes) {
+                (Some(on_time_minutes), Some(late_minutes)) => Some(CheckInWindow {
+                    on_time_minutes,
+                    late_minutes,
+                }),
+                _ => None,
+            },
+            series_id: db.series_id,
             created_at: db.created_at,
             updated_at: db.updated_at,
         }
@@ -94,39 +213,67 @@ impl From<DbClass> for Class {
 
 /// Create a new class
 #[cfg(feature = "ssr")]
-pub async fn create_class(pool: &SqlitePool, request: CreateClassRequest) -> Result<Class, String> {
-    let now = Utc::now().to_rfc3339();
+pub async fn create_class(
+    pool: &SqlitePool,
+    request: CreateClassRequest,
+) -> Result<Class, ClockItError> {
+    // The class and its groups are saved together, so a bad group leaves no class
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+    let class_id = insert_class(&mut tx, &request).await?;
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    let class = sqlx::query_as::<_, DbClass>("SELECT * FROM classes WHERE classID = ?")
+        .bind(class_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_error("Failed to fetch created class"))?;
+
+    Ok(class.into())
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn insert_class(
+    conn: &mut SqliteConnection,
+    request: &CreateClassRequest,
+) -> Result<i64, ClockItError> {
+    let now = clock().now().to_rfc3339();
 
     let result = sqlx::query(
         r#"
-        INSERT INTO classes (moduleCode, title, venue, description, recurring, date, time, duration_minutes, status, created_by, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'upcoming', ?, ?, ?)
+        INSERT INTO classes (moduleCode, title, venue, venueID, description, recurring, date, time, duration_minutes, status, created_by, on_time_minutes, late_minutes, series_id, created_at, updated_at)
+        VALUES (?, ?, ?, (SELECT venueID FROM venues WHERE name = TRIM(?)), ?, ?, ?, ?, ?, 'upcoming', ?, ?, ?, ?, ?, ?)
         "#,
     )
     .bind(&request.module_code)
     .bind(&request.title)
     .bind(&request.venue)
+    .bind(&request.venue)
     .bind(&request.description)
     .bind(&request.recurring)
     .bind(&request.date)
     .bind(&request.time)
     .bind(request.duration_minutes)
     .bind(&request.created_by)
+    .bind(request.check_in_window.map(|w| w.on_time_minutes))
+    .bind(request.check_in_window.map(|w| w.late_minutes))
+    .bind(request.series_id)
     .bind(&now)
     .bind(&now)
-    .execute(pool)
+    .execute(&mut *conn)
     .await
-    .map_err(|e| format!("Failed to create class: {}", e))?;
+    .map_err(db_error("Failed to create class"))?;
 
     let class_id = result.last_insert_rowid();
+    if !request.group_ids.is_empty() {
+        set_class_groups(conn, class_id, &request.group_ids).await?;
+    }
 
-    let class = sqlx::query_as::<_, DbClass>("SELECT * FROM classes WHERE classID = ?")
-        .bind(class_id)
-        .fetch_one(pool)
-        .await
-        .map_err(|e| format!("Failed to fetch created class: {}", e))?;
-
-    Ok(class.into())
+    Ok(class_id)
 }
 
 /// Get all classes for a module
@@ -134,7 +281,7 @@ pub async fn create_class(pool: &SqlitePool, request: CreateClassRequest) -> Res
 pub async fn get_module_classes(
     pool: &SqlitePool,
     module_code: &str,
-) -> Result<Vec<Class>, String> {
+) -> Result<Vec<Class>, ClockItError> {
     let classes = sqlx::query_as::<_, DbClass>(
         r#"
         SELECT * FROM classes 
@@ -145,7 +292,7 @@ pub async fn get_module_classes(
     .bind(module_code)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(classes.into_iter().map(|c| c.into()).collect())
 }
@@ -155,7 +302,7 @@ pub async fn get_module_classes(
 pub async fn get_lecturer_classes(
     pool: &SqlitePool,
     lecturer_email: &str,
-) -> Result<Vec<Class>, String> {
+) -> Result<Vec<Class>, ClockItError> {
     let classes = sqlx::query_as::<_, DbClass>(
         r#"
         SELECT DISTINCT c.* FROM classes c
@@ -169,7 +316,32 @@ pub async fn get_lecturer_classes(
     .bind(lecturer_email)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
+
+    Ok(classes.into_iter().map(|c| c.into()).collect())
+}
+
+/// Classes on or after `from_date` that a student is expected at
+#[cfg(feature = "ssr")]
+pub async fn get_student_classes(
+    pool: &SqlitePool,
+    student_email: &str,
+    from_date: &str,
+) -> Result<Vec<Class>, ClockItError> {
+    let classes = sqlx::query_as::<_, DbClass>(
+        r#"
+        SELECT c.* FROM classes c
+        INNER JOIN class_students cs ON cs.classID = c.classID
+        WHERE cs.studentEmailAddress = ?
+          AND c.date >= ?
+        ORDER BY c.date, c.time
+        "#,
+    )
+    .bind(student_email)
+    .bind(from_date)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Database error"))?;
 
     Ok(classes.into_iter().map(|c| c.into()).collect())
 }
@@ -179,7 +351,7 @@ pub async fn get_lecturer_classes(
 pub async fn get_user_created_classes(
     pool: &SqlitePool,
     user_email: &str,
-) -> Result<Vec<Class>, String> {
+) -> Result<Vec<Class>, ClockItError> {
     let classes = sqlx::query_as::<_, DbClass>(
         r#"
         SELECT DISTINCT c.* FROM classes c
@@ -195,7 +367,7 @@ pub async fn get_user_created_classes(
     .bind(user_email)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(classes.into_iter().map(|c| c.into()).collect())
 }
@@ -206,7 +378,7 @@ pub async fn get_user_created_classes_for_module(
     pool: &SqlitePool,
     user_email: &str,
     module_code: &str,
-) -> Result<Vec<Class>, String> {
+) -> Result<Vec<Class>, ClockItError> {
     let classes = sqlx::query_as::<_, DbClass>(
         r#"
         SELECT c.* FROM classes c
@@ -218,58 +390,126 @@ pub async fn get_user_created_classes_for_module(
     .bind(module_code)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(classes.into_iter().map(|c| c.into()).collect())
 }
 
-/// Delete a class
+/// Delete a class. A class in a series is also skipped by the series from now on.
+#[cfg(feature = "ssr")]
+pub async fn delete_class(pool: &SqlitePool, class_id: i64) -> Result<(), ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    // A class edited on its own is skipped on the date the series gave it
+    let series: Option<(Option<i64>, String)> = sqlx::query_as(
+        "SELECT series_id, COALESCE(occurrence_date, date) FROM classes WHERE classID = ?",
+    )
+    .bind(class_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error("Failed to fetch class"))?;
+
+    remove_class(&mut tx, class_id).await?;
+
+    if let Some((Some(series_id), date)) = series {
+        exclude_date(&mut tx, series_id, &date).await?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))
+}
+
+/// Delete a class and everything recorded against it on `conn`
 #[cfg(feature = "ssr")]
-pub async fn delete_class(pool: &SqlitePool, class_id: i64) -> Result<(), String> {
+pub(crate) async fn remove_class(
+    conn: &mut SqliteConnection,
+    class_id: i64,
+) -> Result<(), ClockItError> {
     // Delete in the correct order to respect foreign key constraints
 
     // 1. First delete attendance records for this class
     sqlx::query("DELETE FROM attendance WHERE classID = ?")
         .bind(class_id)
-        .execute(pool)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to delete attendance records"))?;
+
+    // 2. Walk-in and excuse requests for this class, and the groups it is for
+    sqlx::query("DELETE FROM walk_in_requests WHERE classID = ?")
+        .bind(class_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to delete walk-in requests"))?;
+
+    sqlx::query("DELETE FROM excuse_requests WHERE classID = ?")
+        .bind(class_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to delete excuse requests"))?;
+
+    sqlx::query("DELETE FROM class_groups WHERE classID = ?")
+        .bind(class_id)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to delete attendance records: {}", e))?;
+        .map_err(db_error("Failed to delete class groups"))?;
 
-    // 2. Then delete any sessions for this class
+    // 3. Then delete any sessions for this class
     sqlx::query("DELETE FROM class_sessions WHERE classID = ?")
         .bind(class_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to delete class sessions: {}", e))?;
+        .map_err(db_error("Failed to delete class sessions"))?;
 
-    // 3. Finally delete the class itself
+    // 4. Finally delete the class itself
     sqlx::query("DELETE FROM classes WHERE classID = ?")
         .bind(class_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to delete class: {}", e))?;
+        .map_err(db_error("Failed to delete class"))?;
 
     Ok(())
 }
 
-/// Update an existing class
 /// Update an existing class
 #[cfg(feature = "ssr")]
 pub async fn update_class(
     pool: &SqlitePool,
     class_id: i64,
     request: UpdateClassRequest,
-) -> Result<Class, String> {
-    let now = Utc::now().to_rfc3339();
+) -> Result<Class, ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+    apply_class_update(&mut tx, class_id, &request).await?;
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    // Then fetch and return the updated class
+    get_class_by_id(pool, class_id).await
+}
+
+#[cfg(feature = "ssr")]
+pub(crate) async fn apply_class_update(
+    conn: &mut SqliteConnection,
+    class_id: i64,
+    request: &UpdateClassRequest,
+) -> Result<(), ClockItError> {
+    let now = clock().now().to_rfc3339();
 
     // Check if venue changed
     let old_venue: Option<String> = sqlx::query_scalar(
         "SELECT venue FROM classes WHERE classID = ?"
     )
     .bind(class_id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await
-    .map_err(|e| format!("Failed to fetch old venue: {}", e))?
+    .map_err(db_error("Failed to fetch old venue"))?
     .flatten();
 
     let venue_changed = old_venue != request.venue;
@@ -281,7 +521,9 @@ pub async fn update_class(
             r#"
             UPDATE classes 
             SET title = ?, description = ?, date = ?, time = ?, 
-                duration_minutes = ?, venue = ?, recurring = ?, updated_at = ?, venue_updated_at = ?
+                duration_minutes = ?, venue = ?, venueID = (SELECT venueID FROM venues WHERE name = TRIM(?)),
+                recurring = ?, on_time_minutes = ?, late_minutes = ?,
+                updated_at = ?, venue_updated_at = ?
             WHERE classID = ?
             "#,
         )
@@ -291,20 +533,25 @@ pub async fn update_class(
         .bind(&request.time)
         .bind(request.duration_minutes)
         .bind(&request.venue)
+        .bind(&request.venue)
         .bind(&request.recurring)
+        .bind(request.check_in_window.map(|w| w.on_time_minutes))
+        .bind(request.check_in_window.map(|w| w.late_minutes))
         .bind(&now)
         .bind(&now)  // Set venue_updated_at to now
         .bind(class_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update class: {}", e))?;
+        .map_err(db_error("Failed to update class"))?;
     } else {
         // Venue didn't change, don't update venue_updated_at
         sqlx::query(
             r#"
             UPDATE classes 
             SET title = ?, description = ?, date = ?, time = ?, 
-                duration_minutes = ?, venue = ?, recurring = ?, updated_at = ?
+                duration_minutes = ?, venue = ?, venueID = (SELECT venueID FROM venues WHERE name = TRIM(?)),
+                recurring = ?, on_time_minutes = ?, late_minutes = ?,
+                updated_at = ?
             WHERE classID = ?
             "#,
         )
@@ -314,26 +561,298 @@ pub async fn update_class(
         .bind(&request.time)
         .bind(request.duration_minutes)
         .bind(&request.venue)
+        .bind(&request.venue)
         .bind(&request.recurring)
+        .bind(request.check_in_window.map(|w| w.on_time_minutes))
+        .bind(request.check_in_window.map(|w| w.late_minutes))
         .bind(&now)
         .bind(class_id)
-        .execute(pool)
+        .execute(&mut *conn)
         .await
-        .map_err(|e| format!("Failed to update class: {}", e))?;
+        .map_err(db_error("Failed to update class"))?;
     }
 
-    // Then fetch and return the updated class
-    get_class_by_id(pool, class_id).await
+    if let Some(group_ids) = &request.group_ids {
+        set_class_groups(conn, class_id, group_ids).await?;
+    }
+
+    Ok(())
 }
 
 /// Get a single class by ID
 #[cfg(feature = "ssr")]
-pub async fn get_class_by_id(pool: &SqlitePool, class_id: i64) -> Result<Class, String> {
+pub async fn get_class_by_id(pool: &SqlitePool, class_id: i64) -> Result<Class, ClockItError> {
     let class = sqlx::query_as::<_, DbClass>("SELECT * FROM classes WHERE classID = ?")
         .bind(class_id)
-        .fetch_one(pool)
+        .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Failed to fetch class: {}", e))?;
+        .map_err(db_error("Failed to fetch class"))?
+        .ok_or_else(|| ClockItError::NotFound("Class not found".to_string()))?;
 
     Ok(class.into())
 }
+
+/// The check-in window that applies to a class: its own override, else its module's
+#[cfg(feature = "ssr")]
+pub async fn get_check_in_window(
+    pool: &SqlitePool,
+    class_id: i64,
+) -> Result<CheckInWindow, ClockItError> {
+    let (on_time_minutes, late_minutes): (i32, i32) = sqlx::query_as(
+        r#"
+        SELECT COALESCE(c.on_time_minutes, m.on_time_minutes), COALESCE(c.late_minutes, m.late_minutes)
+        FROM classes c
+        JOIN modules m ON m.moduleCode = c.moduleCode
+        WHERE c.classID = ?
+        "#,
+    )
+    .bind(class_id)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to fetch check-in window"))?;
+
+    Ok(CheckInWindow {
+        on_time_minutes,
+        late_minutes,
+    })
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, FixedClock, SharedClock};
+    use crate::database::class_series::{create_class_series, Recurrence};
+    use crate::database::test_support::{
+        add_class_records, add_module, add_user, fail_on, run, snapshot, test_pool, utc,
+    };
+    use chrono::Duration;
+
+    const MODULE: &str = "DEL101";
+    const LECTURER: &str = "del-lecturer@test.example";
+    const STUDENT: &str = "del-student@test.example";
+    const TOUCHED: &[&str] = &[
+        "classes",
+        "class_series",
+        "class_sessions",
+        "attendance",
+        "walk_in_requests",
+        "excuse_requests",
+        "class_groups",
+    ];
+
+    /// The first of a two-class weekly series, with a session and a student's records
+    async fn fixture(pool: &SqlitePool) -> i64 {
+        add_user(pool, LECTURER, "lecturer").await;
+        add_user(pool, STUDENT, "student").await;
+        add_module(pool, MODULE, LECTURER).await;
+
+        let template = CreateClassRequest {
+            module_code: MODULE.to_string(),
+            title: "Lecture".to_string(),
+            venue: Some("A101".to_string()),
+            description: None,
+            recurring: None,
+            date: "2030-02-04".to_string(),
+            time: "09:00".to_string(),
+            duration_minutes: 60,
+            created_by: Some(LECTURER.to_string()),
+            check_in_window: None,
+            series_id: None,
+            group_ids: Vec::new(),
+        };
+        let recurrence = Recurrence {
+            rrule: "FREQ=WEEKLY;COUNT=2".to_string(),
+            exdates: Vec::new(),
+            blackouts: Default::default(),
+        };
+        let (class, _) = create_class_series(pool, &template, &recurrence)
+            .await
+            .unwrap();
+        add_class_records(pool, MODULE, class.class_id, STUDENT).await;
+        class.class_id
+    }
+
+    #[test]
+    fn deleting_a_class_removes_its_records_and_skips_its_date() {
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+
+            delete_class(&pool, class_id).await.unwrap();
+
+            for table in &TOUCHED[2..] {
+                let left: i64 = sqlx::query_scalar(&format!(
+                    "SELECT COUNT(*) FROM {} WHERE classID = ?",
+                    table
+                ))
+                .bind(class_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+                assert_eq!(left, 0, "{}", table);
+            }
+            let exdates: Option<String> =
+                sqlx::query_scalar("SELECT exdates FROM class_series WHERE moduleCode = ?")
+                    .bind(MODULE)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+            assert_eq!(exdates.as_deref(), Some("2030-02-04"));
+        });
+    }
+
+    #[test]
+    fn failed_delete_leaves_every_table_as_it_was() {
+        run(async {
+            // Statements early, midway and last in removing the class, then the series update
+            for (event, table) in [
+                ("DELETE", "attendance"),
+                ("DELETE", "class_sessions"),
+                ("DELETE", "classes"),
+                ("UPDATE", "class_series"),
+            ] {
+                let pool = test_pool().await;
+                let class_id = fixture(&pool).await;
+                let before = snapshot(&pool, TOUCHED).await;
+                fail_on(&pool, event, table).await;
+
+                assert!(
+                    delete_class(&pool, class_id).await.is_err(),
+                    "{} {}",
+                    event,
+                    table
+                );
+                assert_eq!(
+                    snapshot(&pool, TOUCHED).await,
+                    before,
+                    "{} {}",
+                    event,
+                    table
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn a_group_from_another_module_saves_no_class_change() {
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+            add_module(&pool, "OTH101", LECTURER).await;
+            let foreign: i64 = sqlx::query_scalar(
+                "INSERT INTO module_groups (moduleCode, name) VALUES ('OTH101', 'Tutorial A') RETURNING groupID",
+            )
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            let before = snapshot(&pool, TOUCHED).await;
+
+            let class = get_class_by_id(&pool, class_id).await.unwrap();
+            let created = create_class(
+                &pool,
+                CreateClassRequest {
+                    module_code: MODULE.to_string(),
+                    title: "Tutorial".to_string(),
+                    venue: None,
+                    description: None,
+                    recurring: None,
+                    date: "2030-02-05".to_string(),
+                    time: "10:00".to_string(),
+                    duration_minutes: 45,
+                    created_by: Some(LECTURER.to_string()),
+                    check_in_window: None,
+                    series_id: None,
+                    group_ids: vec![foreign],
+                },
+            )
+            .await;
+            assert!(matches!(created, Err(ClockItError::Validation(_))));
+
+            let updated = update_class(
+                &pool,
+                class_id,
+                UpdateClassRequest {
+                    title: "Moved".to_string(),
+                    description: class.description,
+                    date: "2030-02-06".to_string(),
+                    time: class.time,
+                    duration_minutes: class.duration_minutes,
+                    venue: class.venue,
+                    recurring: class.recurring,
+                    check_in_window: class.check_in_window,
+                    group_ids: Some(vec![foreign]),
+                },
+            )
+            .await;
+            assert!(matches!(updated, Err(ClockItError::Validation(_))));
+
+            assert_eq!(snapshot(&pool, TOUCHED).await, before);
+        });
+    }
+
+    #[test]
+    fn saved_classes_are_stamped_by_the_provided_clock() {
+        let owner = leptos::prelude::Owner::new();
+        owner.set();
+        let clock = std::sync::Arc::new(FixedClock::new(utc("2025-03-10T12:00:00Z")));
+        leptos::prelude::provide_context::<SharedClock>(clock.clone());
+
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+            let stamps = |class_id: i64| {
+                sqlx::query_as::<_, (String, String)>(
+                    "SELECT created_at, updated_at FROM classes WHERE classID = ?",
+                )
+                .bind(class_id)
+                .fetch_one(&pool)
+            };
+
+            let created = create_class(
+                &pool,
+                CreateClassRequest {
+                    module_code: MODULE.to_string(),
+                    title: "Tutorial".to_string(),
+                    venue: None,
+                    description: None,
+                    recurring: None,
+                    date: "2030-02-05".to_string(),
+                    time: "10:00".to_string(),
+                    duration_minutes: 45,
+                    created_by: Some(LECTURER.to_string()),
+                    check_in_window: None,
+                    series_id: None,
+                    group_ids: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+            let created_at = clock.now().to_rfc3339();
+            assert_eq!(
+                stamps(created.class_id).await.unwrap(),
+                (created_at.clone(), created_at.clone())
+            );
+
+            clock.advance(Duration::hours(1));
+            let class = get_class_by_id(&pool, class_id).await.unwrap();
+            update_class(
+                &pool,
+                class_id,
+                UpdateClassRequest {
+                    title: "Moved".to_string(),
+                    description: class.description,
+                    date: class.date,
+                    time: class.time,
+                    duration_minutes: class.duration_minutes,
+                    venue: class.venue,
+                    recurring: class.recurring,
+                    check_in_window: class.check_in_window,
+                    group_ids: None,
+                },
+            )
+            .await
+            .unwrap();
+            assert_eq!(stamps(class_id).await.unwrap().1, clock.now().to_rfc3339());
+        });
+    }
+}
@@ -1,10 +1,19 @@
-use sqlx::{sqlite::SqlitePool, Pool, Sqlite};
+use crate::error::ClockItError;
+use leptos::prelude::use_context;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Pool, Sqlite};
 use std::env;
 use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
 
 pub type DbPool = Pool<Sqlite>;
 
-/// Initialize the SQLite database connection pool
+/// How long a connection waits on another writer's lock before giving up
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Initialize the SQLite database connection pool. This runs once at startup;
+/// server functions get the pool from context with [`db_pool`].
 pub async fn init_db_pool() -> Result<DbPool, sqlx::Error> {
     // Get database URL from environment or use default
     let database_url =
@@ -30,17 +39,26 @@ pub async fn init_db_pool() -> Result<DbPool, sqlx::Error> {
         }
     }
 
-    // Create connection pool with create-if-missing option
-    let connection_string = format!("{}?mode=rwc", database_url);
-    println!("🔗 Using connection string: {}", connection_string);
+    // WAL lets readers carry on while a request writes
+    let options = SqliteConnectOptions::from_str(&database_url)?
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .foreign_keys(true)
+        .busy_timeout(BUSY_TIMEOUT);
 
-    let pool = SqlitePool::connect(&connection_string).await?;
+    let pool = SqlitePoolOptions::new().connect_with(options).await?;
 
     println!("✅ Database connection successful!");
 
     Ok(pool)
 }
 
+/// The shared pool, provided to server functions as context by `main`
+pub fn db_pool() -> Result<DbPool, ClockItError> {
+    use_context::<DbPool>()
+        .ok_or_else(|| ClockItError::Internal("Database connection is not available".to_string()))
+}
+
 /// Test the database connection
 pub async fn test_db_connection(pool: &DbPool) -> Result<(), sqlx::Error> {
     println!("🧪 Testing database connection...");
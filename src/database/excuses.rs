@@ -0,0 +1,315 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqlitePool};
+
+/// A student's request to have their absence from a class excused
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcuseRequest {
+    pub request_id: i64,
+    pub class_id: i64,
+    pub class_title: String,
+    pub class_date: String,
+    pub class_time: String,
+    pub module_code: String,
+    pub student_id: i64,
+    pub student_name: String,
+    pub student_surname: String,
+    pub student_email: String,
+    pub reason: String,
+    pub attachment_name: Option<String>,
+    pub status: String,
+    pub submitted_at: String,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+}
+
+/// A file sent with an excuse, base64-encoded for transport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcuseAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub data_base64: String,
+}
+
+/// Where an excuse's attachment is kept on disk
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+pub struct StoredAttachment {
+    #[sqlx(rename = "attachment_path")]
+    pub path: String,
+    #[sqlx(rename = "attachment_name")]
+    pub file_name: String,
+    #[sqlx(rename = "attachment_type")]
+    pub content_type: String,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbExcuseRequest {
+    #[sqlx(rename = "requestID")]
+    request_id: i64,
+    #[sqlx(rename = "classID")]
+    class_id: i64,
+    class_title: String,
+    class_date: String,
+    class_time: String,
+    module_code: String,
+    #[sqlx(rename = "studentID")]
+    student_id: i64,
+    student_name: String,
+    student_surname: String,
+    student_email: String,
+    reason: String,
+    attachment_name: Option<String>,
+    status: String,
+    submitted_at: String,
+    decided_by: Option<String>,
+    decided_at: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbExcuseRequest> for ExcuseRequest {
+    fn from(db: DbExcuseRequest) -> Self {
+        Self {
+            request_id: db.request_id,
+            class_id: db.class_id,
+            class_title: db.class_title,
+            class_date: db.class_date,
+            class_time: db.class_time,
+            module_code: db.module_code,
+            student_id: db.student_id,
+            student_name: db.student_name,
+            student_surname: db.student_surname,
+            student_email: db.student_email,
+            reason: db.reason,
+            attachment_name: db.attachment_name,
+            status: db.status,
+            submitted_at: db.submitted_at,
+            decided_by: db.decided_by,
+            decided_at: db.decided_at,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const EXCUSE_SELECT: &str = r#"
+    SELECT e.requestID, e.classID,
+           c.title AS class_title, c.date AS class_date, c.time AS class_time, c.moduleCode AS module_code,
+           e.studentID,
+           u.name AS student_name, u.surname AS student_surname, u.emailAddress AS student_email,
+           e.reason, e.attachment_name, e.status, e.submitted_at, e.decided_by, e.decided_at
+    FROM excuse_requests e
+    INNER JOIN classes c ON c.classID = e.classID
+    INNER JOIN users u ON u.userID = e.studentID
+"#;
+
+/// Submit (or resubmit) an excuse for `student_id`. A new attachment replaces the
+/// old one; without one the previous attachment is kept. Resubmitting a rejected
+/// excuse puts it back up for review. Returns the replaced attachment, if any, so
+/// the caller can remove the file.
+#[cfg(feature = "ssr")]
+pub async fn upsert_excuse_request(
+    pool: &SqlitePool,
+    class_id: i64,
+    student_id: i64,
+    reason: &str,
+    attachment: Option<&StoredAttachment>,
+) -> Result<Option<StoredAttachment>, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+
+    let previous = if attachment.is_some() {
+        get_stored_attachment_for(pool, class_id, student_id).await?
+    } else {
+        None
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO excuse_requests (classID, studentID, reason, attachment_path, attachment_name, attachment_type, status, submitted_at)
+        VALUES (?, ?, ?, ?, ?, ?, 'pending', ?)
+        ON CONFLICT (classID, studentID) DO UPDATE SET
+            reason = excluded.reason,
+            attachment_path = COALESCE(excluded.attachment_path, excuse_requests.attachment_path),
+            attachment_name = COALESCE(excluded.attachment_name, excuse_requests.attachment_name),
+            attachment_type = COALESCE(excluded.attachment_type, excuse_requests.attachment_type),
+            status = 'pending',
+            submitted_at = excluded.submitted_at,
+            decided_by = NULL,
+            decided_at = NULL
+        WHERE excuse_requests.status != 'approved'
+        "#,
+    )
+    .bind(class_id)
+    .bind(student_id)
+    .bind(reason)
+    .bind(attachment.map(|a| a.path.as_str()))
+    .bind(attachment.map(|a| a.file_name.as_str()))
+    .bind(attachment.map(|a| a.content_type.as_str()))
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to save excuse request"))?;
+
+    Ok(previous)
+}
+
+/// The status of `student_id`'s excuse for a class, if they have submitted one
+#[cfg(feature = "ssr")]
+pub async fn get_excuse_status(
+    pool: &SqlitePool,
+    class_id: i64,
+    student_id: i64,
+) -> Result<Option<String>, ClockItError> {
+    sqlx::query_scalar("SELECT status FROM excuse_requests WHERE classID = ? AND studentID = ?")
+        .bind(class_id)
+        .bind(student_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to fetch excuse request"))
+}
+
+/// Pending excuses for every module `staff_email` lectures or tutors, oldest first
+#[cfg(feature = "ssr")]
+pub async fn get_pending_excuses_for_staff(
+    pool: &SqlitePool,
+    staff_email: &str,
+) -> Result<Vec<ExcuseRequest>, ClockItError> {
+    let rows = sqlx::query_as::<_, DbExcuseRequest>(&format!(
+        r#"{} WHERE e.status = 'pending'
+          AND c.moduleCode IN (
+              SELECT moduleCode FROM lecturer_module WHERE lecturerEmailAddress = ?
+              UNION
+              SELECT moduleCode FROM module_tutor WHERE tutorEmailAddress = ?
+          )
+        ORDER BY e.submitted_at"#,
+        EXCUSE_SELECT
+    ))
+    .bind(staff_email)
+    .bind(staff_email)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to fetch excuse requests"))?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_excuse_request(
+    pool: &SqlitePool,
+    request_id: i64,
+) -> Result<Option<ExcuseRequest>, ClockItError> {
+    let row = sqlx::query_as::<_, DbExcuseRequest>(&format!(
+        "{} WHERE e.requestID = ?",
+        EXCUSE_SELECT
+    ))
+    .bind(request_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch excuse request"))?;
+
+    Ok(row.map(Into::into))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_stored_attachment(
+    pool: &SqlitePool,
+    request_id: i64,
+) -> Result<Option<StoredAttachment>, ClockItError> {
+    sqlx::query_as::<_, StoredAttachment>(
+        r#"
+        SELECT attachment_path, attachment_name, attachment_type
+        FROM excuse_requests
+        WHERE requestID = ? AND attachment_path IS NOT NULL
+        "#,
+    )
+    .bind(request_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch attachment"))
+}
+
+#[cfg(feature = "ssr")]
+async fn get_stored_attachment_for(
+    pool: &SqlitePool,
+    class_id: i64,
+    student_id: i64,
+) -> Result<Option<StoredAttachment>, ClockItError> {
+    sqlx::query_as::<_, StoredAttachment>(
+        r#"
+        SELECT attachment_path, attachment_name, attachment_type
+        FROM excuse_requests
+        WHERE classID = ? AND studentID = ? AND attachment_path IS NOT NULL
+        "#,
+    )
+    .bind(class_id)
+    .bind(student_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch attachment"))
+}
+
+/// Approve or reject a pending excuse. Approving marks the student excused for the
+/// class. Returns false if the request was already decided.
+#[cfg(feature = "ssr")]
+pub async fn decide_excuse_request(
+    pool: &SqlitePool,
+    request_id: i64,
+    approve: bool,
+    decided_by: &str,
+) -> Result<bool, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let status = if approve { "approved" } else { "rejected" };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let updated = sqlx::query(
+        "UPDATE excuse_requests SET status = ?, decided_by = ?, decided_at = ? WHERE requestID = ? AND status = 'pending'",
+    )
+    .bind(status)
+    .bind(decided_by)
+    .bind(&now)
+    .bind(request_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("Failed to update excuse request"))?;
+
+    if updated.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    if approve {
+        sqlx::query(
+            r#"
+            INSERT INTO attendance (studentID, classID, status, recorded_at, notes)
+            SELECT studentID, classID, 'excused', ?, 'Excuse approved by lecturer'
+            FROM excuse_requests WHERE requestID = ?
+            ON CONFLICT (studentID, classID) DO UPDATE SET
+                status = 'excused',
+                recorded_at = excluded.recorded_at,
+                notes = excluded.notes,
+                check_latitude = NULL,
+                check_longitude = NULL,
+                location_accuracy = NULL
+            "#,
+        )
+        .bind(&now)
+        .bind(request_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to record attendance"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(true)
+}
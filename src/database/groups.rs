@@ -0,0 +1,527 @@
+//! Tutorial groups split a module's students into smaller sets, each with a tutor.
+//! A class aimed at groups is only expected to be attended by their members: the
+//! `class_students` view is the roster that absentee marking, schedules and
+//! statistics use, and it falls back to the whole module for classes without groups.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqliteConnection, SqlitePool};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TutorialGroup {
+    pub group_id: i64,
+    pub module_code: String,
+    pub name: String,
+    pub tutor_email: Option<String>,
+    pub member_count: i64,
+}
+
+/// Fields a lecturer fills in when saving a group
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRequest {
+    pub name: String,
+    /// One of the module's tutors or lecturers
+    pub tutor_email: Option<String>,
+}
+
+impl GroupRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Group name is required".to_string());
+        }
+        Ok(())
+    }
+
+    fn tutor(&self) -> Option<&str> {
+        self.tutor_email
+            .as_deref()
+            .map(str::trim)
+            .filter(|email| !email.is_empty())
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbGroup {
+    #[sqlx(rename = "groupID")]
+    group_id: i64,
+    #[sqlx(rename = "moduleCode")]
+    module_code: String,
+    name: String,
+    #[sqlx(rename = "tutorEmailAddress")]
+    tutor_email: Option<String>,
+    member_count: i64,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbGroup> for TutorialGroup {
+    fn from(db: DbGroup) -> Self {
+        Self {
+            group_id: db.group_id,
+            module_code: db.module_code,
+            name: db.name,
+            tutor_email: db.tutor_email,
+            member_count: db.member_count,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const GROUP_SELECT: &str = r#"
+    SELECT g.groupID, g.moduleCode, g.name, g.tutorEmailAddress,
+           (SELECT COUNT(*)
+            FROM group_students gs
+            INNER JOIN module_students ms
+                ON ms.moduleCode = g.moduleCode AND ms.studentEmailAddress = gs.studentEmailAddress
+            WHERE gs.groupID = g.groupID AND ms.unenrolled_on IS NULL) AS member_count
+    FROM module_groups g
+"#;
+
+#[cfg(feature = "ssr")]
+pub async fn get_module_groups(
+    pool: &SqlitePool,
+    module_code: &str,
+) -> Result<Vec<TutorialGroup>, ClockItError> {
+    let rows = sqlx::query_as::<_, DbGroup>(&format!(
+        "{} WHERE g.moduleCode = ? ORDER BY g.name",
+        GROUP_SELECT
+    ))
+    .bind(module_code)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to fetch groups"))?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_group(pool: &SqlitePool, group_id: i64) -> Result<TutorialGroup, ClockItError> {
+    sqlx::query_as::<_, DbGroup>(&format!("{} WHERE g.groupID = ?", GROUP_SELECT))
+        .bind(group_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to fetch group"))?
+        .map(Into::into)
+        .ok_or_else(|| ClockItError::NotFound("Group not found".to_string()))
+}
+
+/// A group's tutor has to teach on its module
+#[cfg(feature = "ssr")]
+async fn check_tutor(
+    pool: &SqlitePool,
+    module_code: &str,
+    tutor_email: Option<&str>,
+) -> Result<(), ClockItError> {
+    let Some(email) = tutor_email else {
+        return Ok(());
+    };
+
+    let teaches: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM module_tutor WHERE moduleCode = ?1 AND LOWER(tutorEmailAddress) = LOWER(?2))
+            OR EXISTS(SELECT 1 FROM lecturer_module WHERE moduleCode = ?1 AND LOWER(lecturerEmailAddress) = LOWER(?2))
+        "#,
+    )
+    .bind(module_code)
+    .bind(email)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to check tutor"))?;
+
+    if !teaches {
+        return Err(ClockItError::Validation(format!(
+            "{} doesn't teach on {}. Add them as a tutor first.",
+            email, module_code
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn name_taken(name: &str) -> impl FnOnce(sqlx::Error) -> ClockItError + '_ {
+    move |e| match db_error("Failed to save group")(e) {
+        ClockItError::Conflict(_) => {
+            ClockItError::Conflict(format!("A group called '{}' already exists", name))
+        }
+        other => other,
+    }
+}
+
+#[cfg(feature = "ssr")]
+pub async fn create_group(
+    pool: &SqlitePool,
+    module_code: &str,
+    request: &GroupRequest,
+) -> Result<TutorialGroup, ClockItError> {
+    let name = request.name.trim();
+    check_tutor(pool, module_code, request.tutor()).await?;
+
+    let now = Utc::now().to_rfc3339();
+    let result = sqlx::query(
+        r#"
+        INSERT INTO module_groups (moduleCode, name, tutorEmailAddress, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(module_code)
+    .bind(name)
+    .bind(request.tutor())
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(name_taken(name))?;
+
+    get_group(pool, result.last_insert_rowid()).await
+}
+
+/// Rename a group or change its tutor
+#[cfg(feature = "ssr")]
+pub async fn update_group(
+    pool: &SqlitePool,
+    group_id: i64,
+    request: &GroupRequest,
+) -> Result<TutorialGroup, ClockItError> {
+    let group = get_group(pool, group_id).await?;
+    let name = request.name.trim();
+    check_tutor(pool, &group.module_code, request.tutor()).await?;
+
+    sqlx::query(
+        "UPDATE module_groups SET name = ?, tutorEmailAddress = ?, updated_at = ? WHERE groupID = ?",
+    )
+    .bind(name)
+    .bind(request.tutor())
+    .bind(Utc::now().to_rfc3339())
+    .bind(group_id)
+    .execute(pool)
+    .await
+    .map_err(name_taken(name))?;
+
+    get_group(pool, group_id).await
+}
+
+/// Delete a group. Classes aimed only at it go back to being for the whole module.
+#[cfg(feature = "ssr")]
+pub async fn delete_group(pool: &SqlitePool, group_id: i64) -> Result<(), ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    for statement in [
+        "DELETE FROM class_groups WHERE groupID = ?",
+        "DELETE FROM group_students WHERE groupID = ?",
+        "DELETE FROM module_groups WHERE groupID = ?",
+    ] {
+        sqlx::query(statement)
+            .bind(group_id)
+            .execute(&mut *tx)
+            .await
+            .map_err(db_error("Failed to delete group"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))
+}
+
+/// Emails of a group's members who are still enrolled in its module
+#[cfg(feature = "ssr")]
+pub async fn get_group_members(
+    pool: &SqlitePool,
+    group_id: i64,
+) -> Result<Vec<String>, ClockItError> {
+    sqlx::query_scalar(
+        r#"
+        SELECT gs.studentEmailAddress
+        FROM group_students gs
+        INNER JOIN module_groups g ON g.groupID = gs.groupID
+        INNER JOIN module_students ms
+            ON ms.moduleCode = g.moduleCode AND ms.studentEmailAddress = gs.studentEmailAddress
+        WHERE gs.groupID = ? AND ms.unenrolled_on IS NULL
+        ORDER BY gs.studentEmailAddress
+        "#,
+    )
+    .bind(group_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to fetch group members"))
+}
+
+/// Replace a group's members with `emails`, every one of whom has to be enrolled in
+/// the group's module. Students can be in more than one group. Students who have
+/// left the module stay in the group for the classes they were enrolled for.
+#[cfg(feature = "ssr")]
+pub async fn set_group_members(
+    pool: &SqlitePool,
+    group_id: i64,
+    emails: &[String],
+) -> Result<usize, ClockItError> {
+    let group = get_group(pool, group_id).await?;
+
+    let mut emails: Vec<String> = emails
+        .iter()
+        .map(|email| email.trim().to_string())
+        .filter(|email| !email.is_empty())
+        .collect();
+    emails.sort_by_key(|email| email.to_lowercase());
+    emails.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+
+    let list = serde_json::to_string(&emails)
+        .map_err(|e| ClockItError::Internal(format!("Failed to save group members: {}", e)))?;
+
+    // Members are stored with the email their enrollment uses
+    let enrolled: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT ms.studentEmailAddress
+        FROM module_students ms
+        WHERE ms.moduleCode = ?
+          AND ms.unenrolled_on IS NULL
+          AND LOWER(ms.studentEmailAddress) IN (SELECT LOWER(value) FROM json_each(?))
+        "#,
+    )
+    .bind(&group.module_code)
+    .bind(&list)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to check enrollments"))?;
+
+    let missing: Vec<&str> = emails
+        .iter()
+        .filter(|email| !enrolled.iter().any(|e| e.eq_ignore_ascii_case(email)))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(ClockItError::Validation(format!(
+            "Not enrolled in {}: {}",
+            group.module_code,
+            missing.join(", ")
+        )));
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    sqlx::query(
+        r#"
+        DELETE FROM group_students
+        WHERE groupID = ?1
+          AND studentEmailAddress IN (
+              SELECT studentEmailAddress FROM module_students
+              WHERE moduleCode = ?2 AND unenrolled_on IS NULL
+          )
+        "#,
+    )
+    .bind(group_id)
+    .bind(&group.module_code)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("Failed to save group members"))?;
+
+    let now = Utc::now().to_rfc3339();
+    for email in &enrolled {
+        sqlx::query(
+            "INSERT OR IGNORE INTO group_students (groupID, studentEmailAddress, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(group_id)
+        .bind(email)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to save group members"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(enrolled.len())
+}
+
+/// Whether `student_email` is on the class's roster: enrolled on its date, and in
+/// one of its groups if it has any
+#[cfg(feature = "ssr")]
+pub async fn is_expected_at_class(
+    pool: &SqlitePool,
+    class_id: i64,
+    student_email: &str,
+) -> Result<bool, ClockItError> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM class_students WHERE classID = ? AND studentEmailAddress = ?)",
+    )
+    .bind(class_id)
+    .bind(student_email)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to check the class roster"))
+}
+
+/// The groups a class is aimed at; empty when it is for the whole module
+#[cfg(feature = "ssr")]
+pub async fn get_class_group_ids(
+    pool: &SqlitePool,
+    class_id: i64,
+) -> Result<Vec<i64>, ClockItError> {
+    sqlx::query_scalar("SELECT groupID FROM class_groups WHERE classID = ? ORDER BY groupID")
+        .bind(class_id)
+        .fetch_all(pool)
+        .await
+        .map_err(db_error("Failed to fetch class groups"))
+}
+
+/// Aim a class at `group_ids`, which must belong to its module
+#[cfg(feature = "ssr")]
+pub(crate) async fn set_class_groups(
+    conn: &mut SqliteConnection,
+    class_id: i64,
+    group_ids: &[i64],
+) -> Result<(), ClockItError> {
+    let mut group_ids = group_ids.to_vec();
+    group_ids.sort_unstable();
+    group_ids.dedup();
+    let list = serde_json::to_string(&group_ids)
+        .map_err(|e| ClockItError::Internal(format!("Failed to save class groups: {}", e)))?;
+
+    let known: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*)
+        FROM module_groups g
+        INNER JOIN classes c ON c.moduleCode = g.moduleCode
+        WHERE c.classID = ? AND g.groupID IN (SELECT value FROM json_each(?))
+        "#,
+    )
+    .bind(class_id)
+    .bind(&list)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(db_error("Failed to check class groups"))?;
+    if known != group_ids.len() as i64 {
+        return Err(ClockItError::Validation(
+            "Classes can only be aimed at groups in their own module".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM class_groups WHERE classID = ?")
+        .bind(class_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to save class groups"))?;
+
+    sqlx::query("INSERT INTO class_groups (classID, groupID) SELECT ?, value FROM json_each(?)")
+        .bind(class_id)
+        .bind(&list)
+        .execute(&mut *conn)
+        .await
+        .map_err(db_error("Failed to save class groups"))?;
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::database::test_support::{
+        add_class, add_module, add_user, enroll, run, test_pool, utc,
+    };
+    use crate::database::{create_session, end_session};
+    use chrono::Duration;
+
+    const MODULE: &str = "GRP101";
+    const LECTURER: &str = "lecturer@grp.example";
+    const IN_GROUP: &str = "member@grp.example";
+    const NOT_IN_GROUP: &str = "other@grp.example";
+
+    async fn attendance_status(pool: &SqlitePool, class_id: i64, email: &str) -> Option<String> {
+        sqlx::query_scalar(
+            r#"
+            SELECT a.status FROM attendance a
+            JOIN users u ON u.userID = a.studentID
+            WHERE a.classID = ? AND u.emailAddress = ?
+            "#,
+        )
+        .bind(class_id)
+        .bind(email)
+        .fetch_optional(pool)
+        .await
+        .expect("attendance")
+    }
+
+    #[test]
+    fn only_a_class_groups_are_marked_absent() {
+        run(async {
+            let pool = test_pool().await;
+            add_user(&pool, LECTURER, "lecturer").await;
+            add_module(&pool, MODULE, LECTURER).await;
+            for student in [IN_GROUP, NOT_IN_GROUP] {
+                add_user(&pool, student, "student").await;
+                enroll(&pool, MODULE, student).await;
+            }
+            // 09:00 in Stellenbosch is 07:00 UTC
+            let class_id =
+                add_class(&pool, MODULE, "A101", "2025-03-10", "09:00", 60, LECTURER).await;
+            let clock = FixedClock::new(utc("2025-03-10T07:00:00Z"));
+
+            let group = create_group(
+                &pool,
+                MODULE,
+                &GroupRequest {
+                    name: "Tutorial A".to_string(),
+                    tutor_email: None,
+                },
+            )
+            .await
+            .unwrap();
+            set_group_members(&pool, group.group_id, &[IN_GROUP.to_string()])
+                .await
+                .unwrap();
+            set_class_groups(
+                &mut pool.acquire().await.unwrap(),
+                class_id,
+                &[group.group_id],
+            )
+            .await
+            .unwrap();
+            assert!(is_expected_at_class(&pool, class_id, IN_GROUP)
+                .await
+                .unwrap());
+            assert!(!is_expected_at_class(&pool, class_id, NOT_IN_GROUP)
+                .await
+                .unwrap());
+
+            let session_id = create_session(
+                &pool,
+                class_id,
+                Some(LECTURER.to_string()),
+                None,
+                None,
+                None,
+                None,
+                None,
+                30,
+                &clock,
+            )
+            .await
+            .unwrap()
+            .session_id;
+            clock.advance(Duration::minutes(60));
+            end_session(&pool, session_id, &clock).await.unwrap();
+
+            assert_eq!(
+                attendance_status(&pool, class_id, IN_GROUP)
+                    .await
+                    .as_deref(),
+                Some("absent")
+            );
+            // Enrolled in the module but not in the class's group
+            assert_eq!(attendance_status(&pool, class_id, NOT_IN_GROUP).await, None);
+        });
+    }
+}
@@ -0,0 +1,371 @@
+//! Ways for students to get into a module without the lecturer enrolling an existing
+//! account: a per-module join code they redeem themselves, and invitations for emails
+//! that had no account when the lecturer imported them, which turn into enrollments
+//! once that email registers.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::clock::{clock, Clock};
+#[cfg(feature = "ssr")]
+use crate::database::modules::enroll_in_module;
+#[cfg(feature = "ssr")]
+use crate::database::universities::module_today;
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::NaiveDate;
+#[cfg(feature = "ssr")]
+use rand::Rng;
+#[cfg(feature = "ssr")]
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// An email waiting to register before it is enrolled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModuleInvitation {
+    pub email_address: String,
+    pub created_at: String,
+}
+
+/// Join codes leave out 0/O and 1/I so they survive being read off a slide
+#[cfg(feature = "ssr")]
+const JOIN_CODE_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+#[cfg(feature = "ssr")]
+const JOIN_CODE_LENGTH: usize = 8;
+/// Attempts at a code no other module has before giving up
+#[cfg(feature = "ssr")]
+const JOIN_CODE_ATTEMPTS: usize = 5;
+
+#[cfg(feature = "ssr")]
+fn new_join_code() -> String {
+    let mut rng = rand::thread_rng();
+    (0..JOIN_CODE_LENGTH)
+        .map(|_| JOIN_CODE_ALPHABET[rng.gen_range(0..JOIN_CODE_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Store a fresh code for `module_code`, replacing the current one only if `replace`
+#[cfg(feature = "ssr")]
+async fn store_join_code(
+    pool: &SqlitePool,
+    module_code: &str,
+    replace: bool,
+) -> Result<(), ClockItError> {
+    let sql = if replace {
+        "UPDATE modules SET join_code = ? WHERE moduleCode = ?"
+    } else {
+        "UPDATE modules SET join_code = ? WHERE moduleCode = ? AND join_code IS NULL"
+    };
+
+    for _ in 0..JOIN_CODE_ATTEMPTS {
+        let result = sqlx::query(sql)
+            .bind(new_join_code())
+            .bind(module_code)
+            .execute(pool)
+            .await
+            .map_err(db_error("Failed to save join code"));
+
+        match result {
+            Ok(_) => return Ok(()),
+            // Another module already has this code; draw again
+            Err(ClockItError::Conflict(_)) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(ClockItError::Database(
+        "Failed to find an unused join code".to_string(),
+    ))
+}
+
+/// The module's join code, creating one the first time it is asked for
+#[cfg(feature = "ssr")]
+pub async fn join_code_for_module(
+    pool: &SqlitePool,
+    module_code: &str,
+) -> Result<String, ClockItError> {
+    store_join_code(pool, module_code, false).await?;
+
+    sqlx::query_scalar::<_, Option<String>>("SELECT join_code FROM modules WHERE moduleCode = ?")
+        .bind(module_code)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to load join code"))?
+        .flatten()
+        .ok_or_else(|| ClockItError::NotFound("Module not found".to_string()))
+}
+
+/// Replace the module's join code; the old code and link stop working
+#[cfg(feature = "ssr")]
+pub async fn reset_join_code(pool: &SqlitePool, module_code: &str) -> Result<String, ClockItError> {
+    store_join_code(pool, module_code, true).await?;
+    join_code_for_module(pool, module_code).await
+}
+
+/// The module a join code belongs to. Codes are matched ignoring case and spacing.
+#[cfg(feature = "ssr")]
+pub async fn module_for_join_code(
+    pool: &SqlitePool,
+    code: &str,
+) -> Result<Option<String>, ClockItError> {
+    let code: String = code
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase();
+    if code.is_empty() {
+        return Ok(None);
+    }
+
+    sqlx::query_scalar("SELECT moduleCode FROM modules WHERE join_code = ?")
+        .bind(code)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to look up join code"))
+}
+
+/// Enroll `student_email` through a join code from `enrolled_on`, dropping any
+/// invitation they had to the module. Returns false when they are already enrolled.
+#[cfg(feature = "ssr")]
+pub async fn join_module(
+    pool: &SqlitePool,
+    module_code: &str,
+    student_email: &str,
+    enrolled_on: NaiveDate,
+) -> Result<bool, ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let enrolled = enroll_in_module(&mut tx, module_code, student_email, enrolled_on).await?;
+
+    sqlx::query("DELETE FROM module_invitations WHERE moduleCode = ? AND emailAddress = ?")
+        .bind(module_code)
+        .bind(student_email)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to remove invitation"))?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(enrolled)
+}
+
+/// Invite an email that has no account yet. Returns false when it was already invited.
+#[cfg(feature = "ssr")]
+pub async fn invite_student(
+    conn: &mut SqliteConnection,
+    module_code: &str,
+    email: &str,
+) -> Result<bool, ClockItError> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO module_invitations (moduleCode, emailAddress, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(module_code)
+    .bind(email)
+    .bind(clock().now().to_rfc3339())
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| ClockItError::Database(format!("Failed to invite {}: {}", email, e)))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// The module's pending invitations, oldest first
+#[cfg(feature = "ssr")]
+pub async fn get_module_invitations(
+    pool: &SqlitePool,
+    module_code: &str,
+) -> Result<Vec<ModuleInvitation>, ClockItError> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT emailAddress, created_at FROM module_invitations WHERE moduleCode = ? ORDER BY created_at, emailAddress",
+    )
+    .bind(module_code)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to load invitations"))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(email_address, created_at)| ModuleInvitation {
+            email_address,
+            created_at,
+        })
+        .collect())
+}
+
+/// Withdraw an invitation. Returns false when there was none.
+#[cfg(feature = "ssr")]
+pub async fn cancel_invitation(
+    pool: &SqlitePool,
+    module_code: &str,
+    email: &str,
+) -> Result<bool, ClockItError> {
+    let result =
+        sqlx::query("DELETE FROM module_invitations WHERE moduleCode = ? AND emailAddress = ?")
+            .bind(module_code)
+            .bind(email.trim().to_lowercase())
+            .execute(pool)
+            .await
+            .map_err(db_error("Failed to cancel invitation"))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Turn every invitation for a newly registered student into an enrollment starting
+/// today at each module's university. Returns the codes of the modules they joined.
+#[cfg(feature = "ssr")]
+pub async fn accept_invitations(
+    pool: &SqlitePool,
+    email: &str,
+    clock: &dyn Clock,
+) -> Result<Vec<String>, ClockItError> {
+    let email = email.trim().to_lowercase();
+    let module_codes: Vec<String> = sqlx::query_scalar(
+        "SELECT moduleCode FROM module_invitations WHERE emailAddress = ? ORDER BY moduleCode",
+    )
+    .bind(&email)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to load invitations"))?;
+
+    if module_codes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Each module's today can differ, so look them up before taking the write lock
+    let mut starts = Vec::with_capacity(module_codes.len());
+    for module_code in module_codes {
+        let enrolled_on = module_today(pool, &module_code, clock).await?;
+        starts.push((module_code, enrolled_on));
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let mut joined = Vec::new();
+    for (module_code, enrolled_on) in starts {
+        if enroll_in_module(&mut tx, &module_code, &email, enrolled_on).await? {
+            joined.push(module_code);
+        }
+    }
+
+    sqlx::query("DELETE FROM module_invitations WHERE emailAddress = ?")
+        .bind(&email)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to remove invitations"))?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(joined)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::database::modules::bulk_enroll_students;
+    use crate::database::test_support::{
+        add_class, add_module, add_user, enroll, run, test_pool, utc,
+    };
+
+    const MODULE: &str = "INV101";
+    const LECTURER: &str = "inv-lecturer@test.example";
+    const STUDENT: &str = "inv-student@test.example";
+
+    /// A module with one enrolled student and a class at 09:00 on 10 March 2025.
+    /// Returns the class ID.
+    async fn fixture(pool: &SqlitePool) -> i64 {
+        add_user(pool, LECTURER, "lecturer").await;
+        add_user(pool, STUDENT, "student").await;
+        add_module(pool, MODULE, LECTURER).await;
+        enroll(pool, MODULE, STUDENT).await;
+        add_class(pool, MODULE, "A101", "2025-03-10", "09:00", 60, LECTURER).await
+    }
+
+    #[test]
+    fn invited_students_are_enrolled_when_they_register() {
+        run(async {
+            let pool = test_pool().await;
+            let class_id = fixture(&pool).await;
+            let day = |text| NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap();
+            let newcomer = "inv-new@test.example";
+
+            let outcome = bulk_enroll_students(
+                &pool,
+                MODULE,
+                &["Inv-New@Test.example".to_string(), LECTURER.to_string()],
+                day("2025-03-09"),
+            )
+            .await
+            .unwrap();
+            assert_eq!(outcome.enrolled, 0);
+            assert_eq!(outcome.invited, vec![newcomer.to_string()]);
+            assert_eq!(outcome.not_found, vec![LECTURER.to_string()]);
+
+            // They sign up the day after the class, so it isn't held against them
+            add_user(&pool, newcomer, "student").await;
+            let clock = FixedClock::new(utc("2025-03-11T07:00:00Z"));
+            let joined = accept_invitations(&pool, newcomer, &clock).await.unwrap();
+            assert_eq!(joined, vec![MODULE.to_string()]);
+            assert!(get_module_invitations(&pool, MODULE)
+                .await
+                .unwrap()
+                .is_empty());
+
+            let enrolled_on: Option<String> = sqlx::query_scalar(
+                "SELECT enrolled_on FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ?",
+            )
+            .bind(MODULE)
+            .bind(newcomer)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(enrolled_on.as_deref(), Some("2025-03-11"));
+
+            let expected: i64 = sqlx::query_scalar(
+                "SELECT COUNT(*) FROM class_students WHERE classID = ? AND studentEmailAddress = ?",
+            )
+            .bind(class_id)
+            .bind(newcomer)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(expected, 0);
+        });
+    }
+
+    #[test]
+    fn join_codes_find_their_module_until_reset() {
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            let day = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+
+            let code = join_code_for_module(&pool, MODULE).await.unwrap();
+            assert_eq!(join_code_for_module(&pool, MODULE).await.unwrap(), code);
+            assert_eq!(
+                module_for_join_code(&pool, &format!(" {} ", code.to_lowercase()))
+                    .await
+                    .unwrap()
+                    .as_deref(),
+                Some(MODULE)
+            );
+
+            // Already enrolled students can't join twice
+            assert!(!join_module(&pool, MODULE, STUDENT, day).await.unwrap());
+
+            let replaced = reset_join_code(&pool, MODULE).await.unwrap();
+            assert_ne!(replaced, code);
+            assert_eq!(module_for_join_code(&pool, &code).await.unwrap(), None);
+        });
+    }
+}
@@ -0,0 +1,315 @@
+//! A class from scheduling to completion, driven by a clock the test moves forward
+//! instead of waiting for real time to pass.
+
+use crate::clock::{Clock, FixedClock};
+use crate::database::{
+    create_session, end_enrollment, end_session, get_active_session, get_class_by_id, issue_otp,
+    purge_expired_otps, run_migrations, set_enrollment_start, sweep_session_states,
+    verify_otp_code, OtpIssue, OtpVerification,
+};
+use crate::types::OtpPurpose;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use std::future::Future;
+
+const MODULE: &str = "TST101";
+const LECTURER: &str = "lecturer@test.example";
+const PRESENT_STUDENT: &str = "present@test.example";
+const ABSENT_STUDENT: &str = "absent@test.example";
+
+fn run<F: Future>(test: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("test runtime")
+        .block_on(test)
+}
+
+fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+}
+
+async fn test_pool() -> SqlitePool {
+    // A single connection, since every connection to :memory: is its own database
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+async fn add_user(pool: &SqlitePool, email: &str, role: &str) -> i64 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO users (name, surname, emailAddress, password, university, role, created_at, updated_at)
+        VALUES ('Test', 'User', ?, '', 'Stellenbosch University', ?, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z')
+        RETURNING userID
+        "#,
+    )
+    .bind(email)
+    .bind(role)
+    .fetch_one(pool)
+    .await
+    .expect("user")
+}
+
+/// A module with two enrolled students and one 60 minute class at 09:00 on 10 March
+/// 2025 in Stellenbosch, which is 07:00 UTC. Returns the class ID.
+async fn schedule_class(pool: &SqlitePool) -> i64 {
+    add_user(pool, LECTURER, "lecturer").await;
+    add_user(pool, PRESENT_STUDENT, "student").await;
+    add_user(pool, ABSENT_STUDENT, "student").await;
+
+    sqlx::query(
+        r#"
+        INSERT INTO modules (moduleCode, moduleTitle, created_at, updated_at, university)
+        VALUES (?, 'Lifecycle Testing', '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z', 'Stellenbosch University')
+        "#,
+    )
+    .bind(MODULE)
+    .execute(pool)
+    .await
+    .expect("module");
+
+    sqlx::query(
+        "INSERT INTO lecturer_module (moduleCode, lecturerEmailAddress, created_at) VALUES (?, ?, '2025-01-01T00:00:00Z')",
+    )
+    .bind(MODULE)
+    .bind(LECTURER)
+    .execute(pool)
+    .await
+    .expect("lecturer");
+
+    for student in [PRESENT_STUDENT, ABSENT_STUDENT] {
+        sqlx::query(
+            "INSERT INTO module_students (moduleCode, studentEmailAddress, created_at) VALUES (?, ?, '2025-01-01T00:00:00Z')",
+        )
+        .bind(MODULE)
+        .bind(student)
+        .execute(pool)
+        .await
+        .expect("enrollment");
+    }
+
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO classes (moduleCode, title, venue, date, time, duration_minutes, status, created_by, created_at, updated_at)
+        VALUES (?, 'Lecture 1', 'A101', '2025-03-10', '09:00', 60, 'upcoming', ?, '2025-01-01T00:00:00Z', '2025-01-01T00:00:00Z')
+        RETURNING classID
+        "#,
+    )
+    .bind(MODULE)
+    .bind(LECTURER)
+    .fetch_one(pool)
+    .await
+    .expect("class")
+}
+
+async fn start(pool: &SqlitePool, class_id: i64, clock: &dyn Clock) -> i64 {
+    create_session(
+        pool,
+        class_id,
+        Some(LECTURER.to_string()),
+        Some(-33.93),
+        Some(18.86),
+        None,
+        Some(30.0),
+        None,
+        30,
+        clock,
+    )
+    .await
+    .expect("session")
+    .session_id
+}
+
+async fn check_in(pool: &SqlitePool, class_id: i64, email: &str, clock: &dyn Clock) {
+    sqlx::query(
+        r#"
+        INSERT INTO attendance (studentID, classID, status, recorded_at)
+        SELECT userID, ?, 'present', ? FROM users WHERE emailAddress = ?
+        "#,
+    )
+    .bind(class_id)
+    .bind(clock.now().to_rfc3339())
+    .bind(email)
+    .execute(pool)
+    .await
+    .expect("check-in");
+}
+
+async fn attendance_status(pool: &SqlitePool, class_id: i64, email: &str) -> Option<String> {
+    sqlx::query_scalar(
+        r#"
+        SELECT a.status FROM attendance a
+        INNER JOIN users u ON u.userID = a.studentID
+        WHERE a.classID = ? AND u.emailAddress = ?
+        "#,
+    )
+    .bind(class_id)
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+    .expect("attendance")
+}
+
+#[test]
+fn sweep_ends_a_session_once_the_class_is_over() {
+    run(async {
+        let pool = test_pool().await;
+        let class_id = schedule_class(&pool).await;
+        // 08:55 in Stellenbosch
+        let clock = FixedClock::new(utc("2025-03-10T06:55:00Z"));
+
+        let sweep = sweep_session_states(&pool, &clock).await.unwrap();
+        assert_eq!(sweep.updated, 0);
+        assert_eq!(get_class_by_id(&pool, class_id).await.unwrap().status, "upcoming");
+
+        clock.advance(Duration::minutes(7));
+        let session_id = start(&pool, class_id, &clock).await;
+        sqlx::query("UPDATE classes SET status = 'in_progress' WHERE classID = ?")
+            .bind(class_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        check_in(&pool, class_id, PRESENT_STUDENT, &clock).await;
+
+        // Half way through, nothing changes
+        clock.advance(Duration::minutes(30));
+        sweep_session_states(&pool, &clock).await.unwrap();
+        let session = get_active_session(&pool, class_id).await.unwrap();
+        assert_eq!(session.map(|s| s.session_id), Some(session_id));
+        assert_eq!(get_class_by_id(&pool, class_id).await.unwrap().status, "in_progress");
+
+        // 10:00 local, the scheduled end
+        clock.set(utc("2025-03-10T08:00:00Z"));
+        let sweep = sweep_session_states(&pool, &clock).await.unwrap();
+        assert!(sweep.updated > 0);
+
+        assert!(get_active_session(&pool, class_id).await.unwrap().is_none());
+        assert_eq!(get_class_by_id(&pool, class_id).await.unwrap().status, "completed");
+        assert_eq!(
+            attendance_status(&pool, class_id, PRESENT_STUDENT).await.as_deref(),
+            Some("present")
+        );
+        assert_eq!(
+            attendance_status(&pool, class_id, ABSENT_STUDENT).await.as_deref(),
+            Some("absent")
+        );
+    });
+}
+
+#[test]
+fn ending_a_session_early_records_the_clock_time() {
+    run(async {
+        let pool = test_pool().await;
+        let class_id = schedule_class(&pool).await;
+        let clock = FixedClock::new(utc("2025-03-10T07:00:00Z"));
+
+        let session_id = start(&pool, class_id, &clock).await;
+        clock.advance(Duration::minutes(20));
+        let session = end_session(&pool, session_id, &clock).await.unwrap();
+
+        let ended_at = DateTime::parse_from_rfc3339(session.ended_at.as_deref().unwrap())
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(ended_at, utc("2025-03-10T07:20:00Z"));
+        assert_eq!(
+            attendance_status(&pool, class_id, ABSENT_STUDENT).await.as_deref(),
+            Some("absent")
+        );
+    });
+}
+
+#[test]
+fn students_are_only_expected_while_enrolled() {
+    run(async {
+        let pool = test_pool().await;
+        let class_id = schedule_class(&pool).await;
+        let clock = FixedClock::new(utc("2025-03-10T07:00:00Z"));
+        let day = |text| NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap();
+
+        // One joined the day after the class, the other left the day before it
+        set_enrollment_start(&pool, MODULE, ABSENT_STUDENT, Some(day("2025-03-11")))
+            .await
+            .unwrap();
+        assert!(end_enrollment(&pool, MODULE, PRESENT_STUDENT, day("2025-03-10"))
+            .await
+            .unwrap());
+
+        let session_id = start(&pool, class_id, &clock).await;
+        clock.advance(Duration::minutes(60));
+        end_session(&pool, session_id, &clock).await.unwrap();
+
+        assert_eq!(attendance_status(&pool, class_id, ABSENT_STUDENT).await, None);
+        assert_eq!(attendance_status(&pool, class_id, PRESENT_STUDENT).await, None);
+
+        // Back to the first class: they would have been expected after all
+        set_enrollment_start(&pool, MODULE, ABSENT_STUDENT, None)
+            .await
+            .unwrap();
+        let expected: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM class_students WHERE classID = ?")
+                .bind(class_id)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(expected, 1);
+    });
+}
+
+#[test]
+fn class_never_started_is_completed_after_its_end() {
+    run(async {
+        let pool = test_pool().await;
+        let class_id = schedule_class(&pool).await;
+        // 09:59 local: still running on paper
+        let clock = FixedClock::new(utc("2025-03-10T07:59:00Z"));
+
+        sweep_session_states(&pool, &clock).await.unwrap();
+        assert_eq!(get_class_by_id(&pool, class_id).await.unwrap().status, "upcoming");
+
+        clock.advance(Duration::minutes(1));
+        sweep_session_states(&pool, &clock).await.unwrap();
+        assert_eq!(get_class_by_id(&pool, class_id).await.unwrap().status, "completed");
+        // Nobody is marked absent for a class that never ran
+        assert_eq!(attendance_status(&pool, class_id, ABSENT_STUDENT).await, None);
+    });
+}
+
+#[test]
+fn one_time_codes_expire_with_the_clock() {
+    run(async {
+        let pool = test_pool().await;
+        let clock = FixedClock::new(utc("2025-03-10T07:00:00Z"));
+        let email = "new@test.example";
+        let other = "other@test.example";
+
+        let OtpIssue::Issued(code) = issue_otp(&pool, email, OtpPurpose::Signup, &clock)
+            .await
+            .unwrap()
+        else {
+            panic!("expected a fresh code");
+        };
+        issue_otp(&pool, other, OtpPurpose::Signup, &clock).await.unwrap();
+
+        clock.advance(Duration::seconds(30));
+        assert_eq!(
+            issue_otp(&pool, email, OtpPurpose::Signup, &clock).await.unwrap(),
+            OtpIssue::Cooldown(30)
+        );
+
+        clock.advance(Duration::minutes(5));
+        assert_eq!(
+            verify_otp_code(&pool, email, OtpPurpose::Signup, &code, &clock)
+                .await
+                .unwrap(),
+            OtpVerification::Expired
+        );
+        // The expired code was discarded when checked; the other one is left to purge
+        assert_eq!(purge_expired_otps(&pool, &clock).await.unwrap(), 1);
+    });
+}
@@ -10,9 +10,30 @@ pub mod models;
 #[cfg(feature = "ssr")]
 pub mod auth;
 
+#[cfg(feature = "ssr")]
+pub mod otp;
+
+#[cfg(feature = "ssr")]
+pub mod calendar_feeds;
+
+pub mod calendar;
+pub mod class_series;
 pub mod class_sessions;
+pub mod clashes;
 pub mod classes;
+pub mod excuses;
+pub mod groups;
+pub mod invitations;
 pub mod modules;
+pub mod universities;
+pub mod venues;
+pub mod walk_ins;
+
+#[cfg(all(test, feature = "ssr"))]
+mod lifecycle_tests;
+
+#[cfg(all(test, feature = "ssr"))]
+pub(crate) mod test_support;
 
 #[cfg(feature = "ssr")]
 pub use connection::*;
@@ -26,9 +47,21 @@ pub use models::*;
 #[cfg(feature = "ssr")]
 pub use auth::*;
 
+#[cfg(feature = "ssr")]
+pub use otp::*;
+
+#[cfg(feature = "ssr")]
+pub use calendar_feeds::*;
+
+pub use calendar::*;
+pub use class_series::*;
 pub use class_sessions::*;
+pub use clashes::*;
 pub use classes::*;
+pub use excuses::*;
+pub use groups::*;
+pub use invitations::*;
 pub use modules::*;
-
-#[cfg(feature = "ssr")]
-pub use auth::print_test_hash;
+pub use universities::*;
+pub use venues::*;
+pub use walk_ins::*;
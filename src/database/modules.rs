@@ -1,7 +1,12 @@
+use crate::database::classes::CheckInWindow;
 #[cfg(feature = "ssr")]
-use chrono::Utc;
+use crate::error::{db_error, ClockItError};
 #[cfg(feature = "ssr")]
-use sqlx::SqlitePool;
+use chrono::{NaiveDate, Utc};
+#[cfg(feature = "ssr")]
+use crate::database::invitations::invite_student;
+#[cfg(feature = "ssr")]
+use sqlx::{Connection, SqliteConnection, SqlitePool};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +16,10 @@ pub struct Module {
     pub module_code: String,
     pub module_title: String,
     pub description: Option<String>,
+    /// Whether students outside the module may scan in, pending lecturer approval
+    pub allow_walk_ins: bool,
+    /// Default check-in window for the module's classes
+    pub check_in_window: CheckInWindow,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -27,6 +36,8 @@ pub struct UpdateModuleRequest {
     pub module_code: String,
     pub module_title: String,
     pub description: Option<String>,
+    pub allow_walk_ins: bool,
+    pub check_in_window: CheckInWindow,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +61,9 @@ struct DbModule {
     #[sqlx(rename = "moduleTitle")]
     module_title: String,
     description: Option<String>,
+    allow_walk_ins: bool,
+    on_time_minutes: i32,
+    late_minutes: i32,
     created_at: String,
     updated_at: String,
 }
@@ -61,6 +75,11 @@ impl From<DbModule> for Module {
             module_code: db.module_code,
             module_title: db.module_title,
             description: db.description,
+            allow_walk_ins: db.allow_walk_ins,
+            check_in_window: CheckInWindow {
+                on_time_minutes: db.on_time_minutes,
+                late_minutes: db.late_minutes,
+            },
             created_at: db.created_at,
             updated_at: db.updated_at,
         }
@@ -73,25 +92,33 @@ pub async fn create_module(
     pool: &SqlitePool,
     lecturer_email: &str,
     request: CreateModuleRequest,
-) -> Result<Module, String> {
+) -> Result<Module, ClockItError> {
     // Check if module code already exists
     let existing = sqlx::query_as::<_, DbModule>("SELECT * FROM modules WHERE moduleCode = ?")
         .bind(&request.module_code)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     if existing.is_some() {
-        return Err("Module with this code already exists".to_string());
+        return Err(ClockItError::Conflict(
+            "Module with this code already exists".to_string(),
+        ));
     }
 
     let now = Utc::now().to_rfc3339();
 
-    // Insert module
+    // Insert module, at the lecturer's university so its classes use that timezone
     sqlx::query(
         r#"
-        INSERT INTO modules (moduleCode, moduleTitle, description, created_at, updated_at)
-        VALUES (CAST(? AS TEXT), ?, ?, ?, ?)
+        INSERT INTO modules (moduleCode, moduleTitle, description, created_at, updated_at, university)
+        VALUES (
+            CAST(? AS TEXT), ?, ?, ?, ?,
+            COALESCE(
+                (SELECT NULLIF(TRIM(university), '') FROM users WHERE emailAddress = ?),
+                'Stellenbosch University'
+            )
+        )
         "#,
     )
     .bind(&request.module_code)
@@ -99,9 +126,10 @@ pub async fn create_module(
     .bind(&request.description)
     .bind(&now)
     .bind(&now)
+    .bind(lecturer_email)
     .execute(pool)
     .await
-    .map_err(|e| format!("Failed to create module: {}", e))?;
+    .map_err(db_error("Failed to create module"))?;
 
     // Link module to lecturer
     sqlx::query(
@@ -115,14 +143,14 @@ pub async fn create_module(
     .bind(&now)
     .execute(pool)
     .await
-    .map_err(|e| format!("Failed to link module to lecturer: {}", e))?;
+    .map_err(db_error("Failed to link module to lecturer"))?;
 
     // Fetch and return the created module
     let module = sqlx::query_as::<_, DbModule>("SELECT * FROM modules WHERE moduleCode = ?")
         .bind(&request.module_code)
         .fetch_one(pool)
         .await
-        .map_err(|e| format!("Failed to fetch created module: {}", e))?;
+        .map_err(db_error("Failed to fetch created module"))?;
 
     Ok(module.into())
 }
@@ -132,10 +160,10 @@ pub async fn create_module(
 pub async fn get_lecturer_modules(
     pool: &SqlitePool,
     lecturer_email: &str,
-) -> Result<Vec<Module>, String> {
+) -> Result<Vec<Module>, ClockItError> {
     let modules = sqlx::query_as::<_, DbModule>(
         r#"
-        SELECT CAST(moduleCode AS TEXT) as moduleCode, moduleTitle, description, created_at, updated_at 
+        SELECT CAST(m.moduleCode AS TEXT) as moduleCode, moduleTitle, description, allow_walk_ins, on_time_minutes, late_minutes, m.created_at, m.updated_at
         FROM modules m
         INNER JOIN lecturer_module lm ON m.moduleCode = lm.moduleCode
         WHERE lm.lecturerEmailAddress = ?
@@ -145,7 +173,7 @@ pub async fn get_lecturer_modules(
     .bind(lecturer_email)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(modules.into_iter().map(|m| m.into()).collect())
 }
@@ -154,7 +182,7 @@ pub async fn get_lecturer_modules(
 pub async fn get_lecturer_modules_with_stats(
     pool: &SqlitePool,
     lecturer_email: &str,
-) -> Result<Vec<ModuleWithStats>, String> {
+) -> Result<Vec<ModuleWithStats>, ClockItError> {
     let modules = sqlx::query_as::<_, (String, String, Option<String>, i32, i32)>(
         r#"
         SELECT 
@@ -167,7 +195,7 @@ pub async fn get_lecturer_modules_with_stats(
         LEFT JOIN lecturer_module lm ON m.moduleCode = lm.moduleCode
         LEFT JOIN module_tutor mt ON m.moduleCode = mt.moduleCode
         LEFT JOIN classes c ON m.moduleCode = c.moduleCode
-        LEFT JOIN module_students ms ON m.moduleCode = ms.moduleCode
+        LEFT JOIN module_students ms ON m.moduleCode = ms.moduleCode AND ms.unenrolled_on IS NULL
         WHERE lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?
         GROUP BY m.moduleCode, m.moduleTitle, m.description
         ORDER BY m.moduleTitle
@@ -177,7 +205,7 @@ pub async fn get_lecturer_modules_with_stats(
     .bind(lecturer_email)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(modules
         .into_iter()
@@ -198,7 +226,7 @@ pub async fn get_lecturer_modules_with_stats(
 pub async fn get_tutor_modules_with_stats(
     pool: &SqlitePool,
     tutor_email: &str,
-) -> Result<Vec<ModuleWithStats>, String> {
+) -> Result<Vec<ModuleWithStats>, ClockItError> {
     let modules = sqlx::query_as::<_, (String, String, Option<String>, i32, i32)>(
         r#"
         SELECT 
@@ -210,7 +238,7 @@ pub async fn get_tutor_modules_with_stats(
         FROM modules m
         INNER JOIN module_tutor mt ON m.moduleCode = mt.moduleCode
         LEFT JOIN classes c ON m.moduleCode = c.moduleCode
-        LEFT JOIN module_students ms ON m.moduleCode = ms.moduleCode
+        LEFT JOIN module_students ms ON m.moduleCode = ms.moduleCode AND ms.unenrolled_on IS NULL
         WHERE mt.tutorEmailAddress = ?
         GROUP BY m.moduleCode, m.moduleTitle, m.description
         ORDER BY m.moduleTitle
@@ -219,7 +247,7 @@ pub async fn get_tutor_modules_with_stats(
     .bind(tutor_email)
     .fetch_all(pool)
     .await
-    .map_err(|e| format!("Database error: {}", e))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(modules
         .into_iter()
@@ -237,84 +265,445 @@ pub async fn get_tutor_modules_with_stats(
 
 /// Get a single module by code
 #[cfg(feature = "ssr")]
-pub async fn get_module(pool: &SqlitePool, module_code: &str) -> Result<Option<Module>, String> {
+pub async fn get_module(
+    pool: &SqlitePool,
+    module_code: &str,
+) -> Result<Option<Module>, ClockItError> {
     let module = sqlx::query_as::<_, DbModule>("SELECT * FROM modules WHERE moduleCode = ?")
         .bind(module_code)
         .fetch_optional(pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(db_error("Database error"))?;
 
     Ok(module.map(|m| m.into()))
 }
 
+/// Whether `student_email` is currently enrolled in `module_code`
+#[cfg(feature = "ssr")]
+pub async fn is_student_enrolled(
+    pool: &SqlitePool,
+    module_code: &str,
+    student_email: &str,
+) -> Result<bool, ClockItError> {
+    sqlx::query_scalar(
+        "SELECT EXISTS(SELECT 1 FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ? AND unenrolled_on IS NULL)",
+    )
+    .bind(module_code)
+    .bind(student_email)
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to check enrollment"))
+}
+
+/// Enroll a student from `enrolled_on`. A student whose earlier enrollment was ended
+/// is enrolled again from that date, and the ended enrollment is moved to their
+/// history. Returns false when they are already enrolled.
+#[cfg(feature = "ssr")]
+pub async fn enroll_in_module(
+    conn: &mut SqliteConnection,
+    module_code: &str,
+    student_email: &str,
+    enrolled_on: NaiveDate,
+) -> Result<bool, ClockItError> {
+    let enroll_error = |e: sqlx::Error| {
+        ClockItError::Database(format!("Failed to enroll {}: {}", student_email, e))
+    };
+    let mut tx = conn.begin().await.map_err(enroll_error)?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO module_enrollment_history (moduleCode, studentEmailAddress, enrolled_on, unenrolled_on)
+        SELECT moduleCode, studentEmailAddress, enrolled_on, unenrolled_on
+        FROM module_students
+        WHERE moduleCode = ? AND studentEmailAddress = ? AND unenrolled_on IS NOT NULL
+        "#,
+    )
+    .bind(module_code)
+    .bind(student_email)
+    .execute(&mut *tx)
+    .await
+    .map_err(enroll_error)?;
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO module_students (moduleCode, studentEmailAddress, created_at, enrolled_on)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT (moduleCode, studentEmailAddress) DO UPDATE
+            SET enrolled_on = excluded.enrolled_on, unenrolled_on = NULL
+            WHERE module_students.unenrolled_on IS NOT NULL
+        "#,
+    )
+    .bind(module_code)
+    .bind(student_email)
+    .bind(Utc::now().to_rfc3339())
+    .bind(enrolled_on.format("%Y-%m-%d").to_string())
+    .execute(&mut *tx)
+    .await
+    .map_err(enroll_error)?;
+
+    tx.commit().await.map_err(enroll_error)?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// End a student's enrollment, keeping it so their attendance up to `unenrolled_on`
+/// still counts. Returns false when they weren't enrolled.
+#[cfg(feature = "ssr")]
+pub async fn end_enrollment(
+    pool: &SqlitePool,
+    module_code: &str,
+    student_email: &str,
+    unenrolled_on: NaiveDate,
+) -> Result<bool, ClockItError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE module_students
+        SET unenrolled_on = ?
+        WHERE moduleCode = ? AND studentEmailAddress = ? AND unenrolled_on IS NULL
+        "#,
+    )
+    .bind(unenrolled_on.format("%Y-%m-%d").to_string())
+    .bind(module_code)
+    .bind(student_email)
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to unenroll student"))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Move the day a current enrollment starts, e.g. back to when a late joiner was
+/// actually added. `None` counts it from the module's first class.
+#[cfg(feature = "ssr")]
+pub async fn set_enrollment_start(
+    pool: &SqlitePool,
+    module_code: &str,
+    student_email: &str,
+    enrolled_on: Option<NaiveDate>,
+) -> Result<(), ClockItError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE module_students
+        SET enrolled_on = ?
+        WHERE moduleCode = ? AND studentEmailAddress = ? AND unenrolled_on IS NULL
+        "#,
+    )
+    .bind(enrolled_on.map(|date| date.format("%Y-%m-%d").to_string()))
+    .bind(module_code)
+    .bind(student_email)
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to update enrollment"))?;
+
+    if result.rows_affected() == 0 {
+        return Err(ClockItError::NotFound(
+            "Student is not enrolled in this module".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Outcome of [`bulk_enroll_students`]
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, Default)]
+pub struct BulkEnrollment {
+    pub enrolled: usize,
+    /// Emails with no account yet, invited to join once they register
+    pub invited: Vec<String>,
+    /// Emails that belong to a lecturer or tutor
+    pub not_found: Vec<String>,
+}
+
+/// Enroll every listed student who has an account and isn't already enrolled, from
+/// `enrolled_on`. Emails nobody has registered yet are invited instead; emails of
+/// staff accounts are reported back. Any database error rolls back the whole import.
+#[cfg(feature = "ssr")]
+pub async fn bulk_enroll_students(
+    pool: &SqlitePool,
+    module_code: &str,
+    student_emails: &[String],
+    enrolled_on: NaiveDate,
+) -> Result<BulkEnrollment, ClockItError> {
+    let mut outcome = BulkEnrollment::default();
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    for email in student_emails {
+        let email = email.trim().to_lowercase();
+        if email.is_empty() {
+            continue;
+        }
+
+        let role: Option<String> =
+            sqlx::query_scalar("SELECT role FROM users WHERE emailAddress = ?")
+                .bind(&email)
+                .fetch_optional(&mut *tx)
+                .await
+                .map_err(|e| {
+                    ClockItError::Database(format!("Failed to look up {}: {}", email, e))
+                })?;
+
+        match role.as_deref() {
+            Some("student") => {}
+            Some(_) => {
+                outcome.not_found.push(email);
+                continue;
+            }
+            None => {
+                invite_student(&mut tx, module_code, &email).await?;
+                outcome.invited.push(email);
+                continue;
+            }
+        }
+
+        // Already enrolled students are skipped silently
+        if enroll_in_module(&mut tx, module_code, &email, enrolled_on).await? {
+            outcome.enrolled += 1;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(outcome)
+}
+
 /// Update a module
 #[cfg(feature = "ssr")]
 pub async fn update_module(
     pool: &SqlitePool,
     request: UpdateModuleRequest,
-) -> Result<Module, String> {
+) -> Result<Module, ClockItError> {
     let now = Utc::now().to_rfc3339();
 
     sqlx::query(
         r#"
         UPDATE modules
-        SET moduleTitle = ?, description = ?, updated_at = ?
+        SET moduleTitle = ?, description = ?, allow_walk_ins = ?, on_time_minutes = ?, late_minutes = ?, updated_at = ?
         WHERE moduleCode = ?
         "#,
     )
     .bind(&request.module_title)
     .bind(&request.description)
+    .bind(request.allow_walk_ins)
+    .bind(request.check_in_window.on_time_minutes)
+    .bind(request.check_in_window.late_minutes)
     .bind(&now)
     .bind(&request.module_code)
     .execute(pool)
     .await
-    .map_err(|e| format!("Failed to update module: {}", e))?;
+    .map_err(db_error("Failed to update module"))?;
 
     let module = sqlx::query_as::<_, DbModule>("SELECT * FROM modules WHERE moduleCode = ?")
         .bind(&request.module_code)
         .fetch_one(pool)
         .await
-        .map_err(|e| format!("Failed to fetch updated module: {}", e))?;
+        .map_err(db_error("Failed to fetch updated module"))?;
 
     Ok(module.into())
 }
 
 /// Delete a module
 #[cfg(feature = "ssr")]
-pub async fn delete_module(pool: &SqlitePool, module_code: &str) -> Result<(), String> {
+pub async fn delete_module(pool: &SqlitePool, module_code: &str) -> Result<(), ClockItError> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
     // Delete related records first (foreign key constraints)
     sqlx::query("DELETE FROM lecturer_module WHERE moduleCode = ?")
         .bind(module_code)
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to delete lecturer-module links: {}", e))?;
+        .map_err(db_error("Failed to delete lecturer-module links"))?;
 
     sqlx::query("DELETE FROM module_tutor WHERE moduleCode = ?")
         .bind(module_code)
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to delete tutor-module links: {}", e))?;
+        .map_err(db_error("Failed to delete tutor-module links"))?;
 
     // Note: You might want to handle classes and attendance differently
     // For now, we'll delete them as well
     sqlx::query("DELETE FROM attendance WHERE classID IN (SELECT classID FROM classes WHERE moduleCode = ?)")
         .bind(module_code)
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to delete attendance records: {}", e))?;
+        .map_err(db_error("Failed to delete attendance records"))?;
+
+    sqlx::query("DELETE FROM walk_in_requests WHERE classID IN (SELECT classID FROM classes WHERE moduleCode = ?)")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete walk-in requests"))?;
+
+    sqlx::query("DELETE FROM excuse_requests WHERE classID IN (SELECT classID FROM classes WHERE moduleCode = ?)")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete excuse requests"))?;
+
+    sqlx::query("DELETE FROM class_sessions WHERE classID IN (SELECT classID FROM classes WHERE moduleCode = ?)")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete class sessions"))?;
+
+    sqlx::query("DELETE FROM class_groups WHERE groupID IN (SELECT groupID FROM module_groups WHERE moduleCode = ?)")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete class groups"))?;
+
+    sqlx::query("DELETE FROM group_students WHERE groupID IN (SELECT groupID FROM module_groups WHERE moduleCode = ?)")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete group members"))?;
+
+    sqlx::query("DELETE FROM module_groups WHERE moduleCode = ?")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete tutorial groups"))?;
+
+    sqlx::query("DELETE FROM module_invitations WHERE moduleCode = ?")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete invitations"))?;
 
     sqlx::query("DELETE FROM classes WHERE moduleCode = ?")
         .bind(module_code)
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to delete classes: {}", e))?;
+        .map_err(db_error("Failed to delete classes"))?;
+
+    sqlx::query("DELETE FROM class_series WHERE moduleCode = ?")
+        .bind(module_code)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to delete class series"))?;
 
     // Finally delete the module
     sqlx::query("DELETE FROM modules WHERE moduleCode = ?")
         .bind(module_code)
-        .execute(pool)
+        .execute(&mut *tx)
         .await
-        .map_err(|e| format!("Failed to delete module: {}", e))?;
+        .map_err(db_error("Failed to delete module"))?;
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{
+        add_class, add_class_records, add_module, add_tutor, add_user, enroll, fail_on, run,
+        snapshot, test_pool,
+    };
+
+    const MODULE: &str = "MOD101";
+    const LECTURER: &str = "mod-lecturer@test.example";
+    const TUTOR: &str = "mod-tutor@test.example";
+    const STUDENT: &str = "mod-student@test.example";
+
+    /// A module with a tutor, a student, an invitation, and a class the student attended
+    async fn fixture(pool: &SqlitePool) {
+        add_user(pool, LECTURER, "lecturer").await;
+        add_user(pool, TUTOR, "tutor").await;
+        add_user(pool, STUDENT, "student").await;
+        add_module(pool, MODULE, LECTURER).await;
+        add_tutor(pool, MODULE, TUTOR).await;
+        enroll(pool, MODULE, STUDENT).await;
+
+        let mut tx = pool.begin().await.unwrap();
+        invite_student(&mut tx, MODULE, "mod-invited@test.example")
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let class_id = add_class(pool, MODULE, "A101", "2030-02-04", "09:00", 60, LECTURER).await;
+        add_class_records(pool, MODULE, class_id, STUDENT).await;
+    }
+
+    #[test]
+    fn failed_module_delete_leaves_every_table_as_it_was() {
+        const TOUCHED: &[&str] = &[
+            "modules",
+            "lecturer_module",
+            "module_tutor",
+            "module_students",
+            "module_invitations",
+            "module_groups",
+            "group_students",
+            "classes",
+            "class_groups",
+            "class_sessions",
+            "attendance",
+            "walk_in_requests",
+            "excuse_requests",
+        ];
+
+        run(async {
+            for (event, table) in [
+                ("DELETE", "module_tutor"),
+                ("DELETE", "class_sessions"),
+                ("DELETE", "classes"),
+                ("DELETE", "modules"),
+            ] {
+                let pool = test_pool().await;
+                fixture(&pool).await;
+                let before = snapshot(&pool, TOUCHED).await;
+                fail_on(&pool, event, table).await;
+
+                assert!(
+                    delete_module(&pool, MODULE).await.is_err(),
+                    "{} {}",
+                    event,
+                    table
+                );
+                assert_eq!(
+                    snapshot(&pool, TOUCHED).await,
+                    before,
+                    "{} {}",
+                    event,
+                    table
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn failed_import_enrolls_and_invites_nobody() {
+        const TOUCHED: &[&str] = &["module_students", "module_invitations"];
+
+        run(async {
+            let pool = test_pool().await;
+            fixture(&pool).await;
+            add_user(&pool, "mod-other@test.example", "student").await;
+            let before = snapshot(&pool, TOUCHED).await;
+            fail_on(&pool, "INSERT", "module_invitations").await;
+
+            // The first student is enrolled before the invitation fails
+            let emails = [
+                "mod-other@test.example".to_string(),
+                "mod-unregistered@test.example".to_string(),
+            ];
+            let enrolled_on = NaiveDate::from_ymd_opt(2030, 2, 1).unwrap();
+            let result = bulk_enroll_students(&pool, MODULE, &emails, enrolled_on).await;
+
+            assert!(result.is_err());
+            assert_eq!(snapshot(&pool, TOUCHED).await, before);
+        });
+    }
+}
@@ -0,0 +1,247 @@
+use crate::clock::Clock;
+use crate::error::{db_error, ClockItError};
+use crate::types::OtpPurpose;
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+
+/// How long an emailed code stays valid (the emails promise 5 minutes)
+const OTP_TTL_MINUTES: i64 = 5;
+/// Wrong guesses allowed before the code is locked and a new one must be requested
+pub const OTP_MAX_ATTEMPTS: i64 = 5;
+/// Minimum gap between two codes for the same email and purpose
+const OTP_RESEND_COOLDOWN_SECONDS: i64 = 60;
+/// Time allowed between verifying a code and finishing signup/reset with it
+const OTP_VERIFIED_GRACE_MINUTES: i64 = 15;
+
+/// Result of asking for a new code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OtpIssue {
+    /// A fresh code; send it and never store it in plain text
+    Issued(String),
+    /// A code was sent too recently; retry after this many seconds
+    Cooldown(i64),
+}
+
+/// Result of checking a submitted code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpVerification {
+    Verified,
+    Invalid { attempts_left: i64 },
+    Expired,
+    TooManyAttempts,
+    NotFound,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct DbOtp {
+    code_salt: String,
+    code_hash: String,
+    attempts: i64,
+    created_at: String,
+    expires_at: String,
+    verified_at: Option<String>,
+}
+
+/// Fixed-width UTC timestamps so `expires_at` compares correctly as text in SQL
+fn timestamp(at: DateTime<Utc>) -> String {
+    at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn code_mac(salt: &str, code: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(code.as_bytes());
+    mac
+}
+
+/// Generate a 6-digit code for `email`, replacing any earlier one for the same purpose
+pub async fn issue_otp(
+    pool: &SqlitePool,
+    email: &str,
+    purpose: OtpPurpose,
+    clock: &dyn Clock,
+) -> Result<OtpIssue, ClockItError> {
+    let now = clock.now();
+
+    let last_sent: Option<String> = sqlx::query_scalar(
+        "SELECT created_at FROM email_otps WHERE emailAddress = ? AND purpose = ?",
+    )
+    .bind(email)
+    .bind(purpose.as_str())
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to check existing code"))?;
+
+    if let Some(sent_at) = last_sent.as_deref().and_then(parse_timestamp) {
+        let wait = OTP_RESEND_COOLDOWN_SECONDS - (now - sent_at).num_seconds();
+        if wait > 0 {
+            return Ok(OtpIssue::Cooldown(wait));
+        }
+    }
+
+    let (code, salt) = {
+        let mut rng = rand::thread_rng();
+        let code: String = (0..6).map(|_| rng.gen_range(0..10).to_string()).collect();
+        let salt: [u8; 16] = rng.gen();
+        (code, hex::encode(salt))
+    };
+    let code_hash = hex::encode(code_mac(&salt, &code).finalize().into_bytes());
+
+    sqlx::query(
+        r#"
+        INSERT INTO email_otps (emailAddress, purpose, code_salt, code_hash, attempts, created_at, expires_at, verified_at)
+        VALUES (?, ?, ?, ?, 0, ?, ?, NULL)
+        ON CONFLICT (emailAddress, purpose) DO UPDATE SET
+            code_salt = excluded.code_salt,
+            code_hash = excluded.code_hash,
+            attempts = 0,
+            created_at = excluded.created_at,
+            expires_at = excluded.expires_at,
+            verified_at = NULL
+        "#,
+    )
+    .bind(email)
+    .bind(purpose.as_str())
+    .bind(&salt)
+    .bind(&code_hash)
+    .bind(timestamp(now))
+    .bind(timestamp(now + Duration::minutes(OTP_TTL_MINUTES)))
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to store code"))?;
+
+    Ok(OtpIssue::Issued(code))
+}
+
+/// Forget the code for `email`, e.g. when the email carrying it could not be sent
+pub async fn discard_otp(
+    pool: &SqlitePool,
+    email: &str,
+    purpose: OtpPurpose,
+) -> Result<(), ClockItError> {
+    sqlx::query("DELETE FROM email_otps WHERE emailAddress = ? AND purpose = ?")
+        .bind(email)
+        .bind(purpose.as_str())
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to discard code"))?;
+    Ok(())
+}
+
+/// Check a submitted code. Every wrong guess is counted, and a correct one marks the
+/// code verified so the signup or reset it belongs to can be completed.
+pub async fn verify_otp_code(
+    pool: &SqlitePool,
+    email: &str,
+    purpose: OtpPurpose,
+    code: &str,
+    clock: &dyn Clock,
+) -> Result<OtpVerification, ClockItError> {
+    let row = sqlx::query_as::<_, DbOtp>(
+        "SELECT code_salt, code_hash, attempts, created_at, expires_at, verified_at FROM email_otps WHERE emailAddress = ? AND purpose = ?",
+    )
+    .bind(email)
+    .bind(purpose.as_str())
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to load code"))?;
+
+    let Some(row) = row else {
+        return Ok(OtpVerification::NotFound);
+    };
+
+    let now = clock.now();
+    let expired = parse_timestamp(&row.expires_at)
+        .map(|expires_at| now >= expires_at)
+        .unwrap_or(true);
+    if expired {
+        discard_otp(pool, email, purpose).await?;
+        return Ok(OtpVerification::Expired);
+    }
+
+    if row.verified_at.is_some() {
+        return Ok(OtpVerification::Verified);
+    }
+
+    if row.attempts >= OTP_MAX_ATTEMPTS {
+        return Ok(OtpVerification::TooManyAttempts);
+    }
+
+    let Ok(expected) = hex::decode(&row.code_hash) else {
+        return Ok(OtpVerification::NotFound);
+    };
+
+    if code_mac(&row.code_salt, code).verify_slice(&expected).is_ok() {
+        sqlx::query(
+            "UPDATE email_otps SET verified_at = ?, expires_at = ? WHERE emailAddress = ? AND purpose = ? AND created_at = ?",
+        )
+        .bind(timestamp(now))
+        .bind(timestamp(now + Duration::minutes(OTP_VERIFIED_GRACE_MINUTES)))
+        .bind(email)
+        .bind(purpose.as_str())
+        .bind(&row.created_at)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to mark code verified"))?;
+        return Ok(OtpVerification::Verified);
+    }
+
+    // Increment in SQL so concurrent guesses can't share one attempt
+    let attempts: i64 = sqlx::query_scalar(
+        "UPDATE email_otps SET attempts = attempts + 1 WHERE emailAddress = ? AND purpose = ? RETURNING attempts",
+    )
+    .bind(email)
+    .bind(purpose.as_str())
+    .fetch_one(pool)
+    .await
+    .map_err(db_error("Failed to record attempt"))?;
+
+    if attempts >= OTP_MAX_ATTEMPTS {
+        Ok(OtpVerification::TooManyAttempts)
+    } else {
+        Ok(OtpVerification::Invalid {
+            attempts_left: OTP_MAX_ATTEMPTS - attempts,
+        })
+    }
+}
+
+/// Use up a verified, unexpired code. Returns false if there is none, so a signup or
+/// password reset can't go ahead without the email having been confirmed first.
+pub async fn consume_verified_otp(
+    pool: &SqlitePool,
+    email: &str,
+    purpose: OtpPurpose,
+    clock: &dyn Clock,
+) -> Result<bool, ClockItError> {
+    let result = sqlx::query(
+        "DELETE FROM email_otps WHERE emailAddress = ? AND purpose = ? AND verified_at IS NOT NULL AND expires_at > ?",
+    )
+    .bind(email)
+    .bind(purpose.as_str())
+    .bind(timestamp(clock.now()))
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to consume code"))?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Delete every expired code; returns how many were removed
+pub async fn purge_expired_otps(pool: &SqlitePool, clock: &dyn Clock) -> Result<u64, ClockItError> {
+    let result = sqlx::query("DELETE FROM email_otps WHERE expires_at <= ?")
+        .bind(timestamp(clock.now()))
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to purge expired codes"))?;
+
+    Ok(result.rows_affected())
+}
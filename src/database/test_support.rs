@@ -0,0 +1,256 @@
+//! Fixtures shared by the database tests: a migrated in-memory database and a few
+//! inserts for the users, modules and classes most tests start from.
+
+use crate::database::run_migrations;
+use crate::types::UserProfile;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::SqlitePool;
+use std::future::Future;
+
+pub const CREATED_AT: &str = "2025-01-01T00:00:00Z";
+
+pub fn run<F: Future>(test: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("test runtime")
+        .block_on(test)
+}
+
+pub fn utc(text: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(text)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+pub async fn test_pool() -> SqlitePool {
+    // A single connection, since every connection to :memory: is its own database
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("in-memory database");
+    run_migrations(&pool).await.expect("migrations");
+    pool
+}
+
+pub async fn add_user(pool: &SqlitePool, email: &str, role: &str) -> i64 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO users (name, surname, emailAddress, password, university, role, created_at, updated_at)
+        VALUES ('Test', 'User', ?, '', 'Stellenbosch University', ?, ?, ?)
+        RETURNING userID
+        "#,
+    )
+    .bind(email)
+    .bind(role)
+    .bind(CREATED_AT)
+    .bind(CREATED_AT)
+    .fetch_one(pool)
+    .await
+    .expect("user")
+}
+
+/// A user as the session layer would hand it to the guards
+pub async fn profile(pool: &SqlitePool, email: &str) -> UserProfile {
+    crate::database::get_user_by_email(pool, email)
+        .await
+        .expect("user lookup")
+        .expect("user exists")
+}
+
+/// A module at Stellenbosch, lectured by `lecturer`
+pub async fn add_module(pool: &SqlitePool, module_code: &str, lecturer: &str) {
+    sqlx::query(
+        r#"
+        INSERT INTO modules (moduleCode, moduleTitle, created_at, updated_at, university)
+        VALUES (?, 'Testing', ?, ?, 'Stellenbosch University')
+        "#,
+    )
+    .bind(module_code)
+    .bind(CREATED_AT)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("module");
+
+    sqlx::query(
+        "INSERT INTO lecturer_module (moduleCode, lecturerEmailAddress, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(module_code)
+    .bind(lecturer)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("lecturer");
+}
+
+pub async fn add_tutor(pool: &SqlitePool, module_code: &str, tutor: &str) {
+    sqlx::query(
+        "INSERT INTO module_tutor (moduleCode, tutorEmailAddress, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(module_code)
+    .bind(tutor)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("tutor");
+}
+
+/// Enroll a student with no start date, as enrollments made before dates were kept
+pub async fn enroll(pool: &SqlitePool, module_code: &str, student: &str) {
+    sqlx::query(
+        "INSERT INTO module_students (moduleCode, studentEmailAddress, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(module_code)
+    .bind(student)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("enrollment");
+}
+
+/// A class created by `created_by`; `date` and `time` are local to the university
+pub async fn add_class(
+    pool: &SqlitePool,
+    module_code: &str,
+    venue: &str,
+    date: &str,
+    time: &str,
+    duration_minutes: i64,
+    created_by: &str,
+) -> i64 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO classes (moduleCode, title, venue, date, time, duration_minutes, status, created_by, created_at, updated_at)
+        VALUES (?, 'Lecture', ?, ?, ?, ?, 'upcoming', ?, ?, ?)
+        RETURNING classID
+        "#,
+    )
+    .bind(module_code)
+    .bind(venue)
+    .bind(date)
+    .bind(time)
+    .bind(duration_minutes)
+    .bind(created_by)
+    .bind(CREATED_AT)
+    .bind(CREATED_AT)
+    .fetch_one(pool)
+    .await
+    .expect("class")
+}
+
+/// Make every `event` (`INSERT`, `UPDATE` or `DELETE`) on `table` fail, to abort a
+/// transaction partway through
+pub async fn fail_on(pool: &SqlitePool, event: &str, table: &str) {
+    sqlx::query(&format!(
+        "CREATE TRIGGER fail_{event}_{table} BEFORE {event} ON {table} BEGIN SELECT RAISE(ABORT, 'forced failure'); END",
+    ))
+    .execute(pool)
+    .await
+    .expect("trigger");
+}
+
+/// Every row of each of `tables`, to check a failed write left them as they were
+pub async fn snapshot(pool: &SqlitePool, tables: &[&str]) -> Vec<(String, Vec<String>)> {
+    let mut snapshot = Vec::new();
+    for table in tables {
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+            .bind(table)
+            .fetch_all(pool)
+            .await
+            .expect("columns");
+        let row = columns
+            .iter()
+            .map(|column| format!("quote({})", column))
+            .collect::<Vec<_>>()
+            .join(" || ',' || ");
+        let rows = sqlx::query_scalar(&format!("SELECT {} FROM {} ORDER BY 1", row, table))
+            .fetch_all(pool)
+            .await
+            .expect("rows");
+        snapshot.push((table.to_string(), rows));
+    }
+    snapshot
+}
+
+/// A session of `class_id` with `student`'s attendance, walk-in and excuse, and a
+/// tutorial group holding the student that the class is for. Returns the session.
+pub async fn add_class_records(
+    pool: &SqlitePool,
+    module_code: &str,
+    class_id: i64,
+    student: &str,
+) -> i64 {
+    let student_id: i64 = sqlx::query_scalar("SELECT userID FROM users WHERE emailAddress = ?")
+        .bind(student)
+        .fetch_one(pool)
+        .await
+        .expect("student");
+
+    let session_id: i64 = sqlx::query_scalar(
+        "INSERT INTO class_sessions (classID, started_at) VALUES (?, ?) RETURNING sessionID",
+    )
+    .bind(class_id)
+    .bind(CREATED_AT)
+    .fetch_one(pool)
+    .await
+    .expect("session");
+
+    sqlx::query(
+        "INSERT INTO attendance (studentID, classID, status, recorded_at) VALUES (?, ?, 'present', ?)",
+    )
+    .bind(student_id)
+    .bind(class_id)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("attendance");
+
+    sqlx::query(
+        "INSERT INTO walk_in_requests (classID, sessionID, studentID, requested_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(class_id)
+    .bind(session_id)
+    .bind(student_id)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("walk-in");
+
+    sqlx::query(
+        "INSERT INTO excuse_requests (classID, studentID, reason, submitted_at) VALUES (?, ?, 'Ill', ?)",
+    )
+    .bind(class_id)
+    .bind(student_id)
+    .bind(CREATED_AT)
+    .execute(pool)
+    .await
+    .expect("excuse");
+
+    let group_id: i64 = sqlx::query_scalar(
+        "INSERT INTO module_groups (moduleCode, name) VALUES (?, 'Group ' || ?) RETURNING groupID",
+    )
+    .bind(module_code)
+    .bind(class_id)
+    .fetch_one(pool)
+    .await
+    .expect("group");
+
+    sqlx::query("INSERT INTO group_students (groupID, studentEmailAddress) VALUES (?, ?)")
+        .bind(group_id)
+        .bind(student)
+        .execute(pool)
+        .await
+        .expect("group member");
+
+    sqlx::query("INSERT INTO class_groups (classID, groupID) VALUES (?, ?)")
+        .bind(class_id)
+        .bind(group_id)
+        .execute(pool)
+        .await
+        .expect("class group");
+
+    session_id
+}
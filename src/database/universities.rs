@@ -0,0 +1,240 @@
+//! The timezone each university schedules classes in. A module belongs to the
+//! university of the lecturer who created it, and a student or lecturer to the one on
+//! their profile. A university is added with [`DEFAULT_TIMEZONE`] the first time a
+//! profile names it, and its lecturers can change the timezone from then on.
+
+use crate::utils::class_time::DEFAULT_TIMEZONE;
+use chrono_tz::Tz;
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use crate::clock::Clock;
+#[cfg(feature = "ssr")]
+use crate::utils::class_time::{local_today, parse_timezone};
+#[cfg(feature = "ssr")]
+use chrono::{NaiveDate, Utc};
+#[cfg(feature = "ssr")]
+use sqlx::{SqliteConnection, SqlitePool};
+
+/// The zone a stored timezone name refers to. Names that no longer parse fall back
+/// to the default rather than leaving classes without a time.
+pub fn timezone_or_default(name: Option<&str>) -> Tz {
+    name.and_then(|name| name.parse().ok())
+        .unwrap_or(DEFAULT_TIMEZONE)
+}
+
+/// The timezone `module_code`'s classes are scheduled in
+#[cfg(feature = "ssr")]
+pub async fn module_timezone(pool: &SqlitePool, module_code: &str) -> Result<Tz, ClockItError> {
+    let name: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT un.timezone
+        FROM modules m
+        INNER JOIN universities un ON un.name = m.university
+        WHERE m.moduleCode = ?
+        "#,
+    )
+    .bind(module_code)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch module timezone"))?;
+
+    Ok(timezone_or_default(name.as_deref()))
+}
+
+/// Today's date at `module_code`'s university, which enrollments start and end on
+#[cfg(feature = "ssr")]
+pub async fn module_today(
+    pool: &SqlitePool,
+    module_code: &str,
+    clock: &dyn Clock,
+) -> Result<NaiveDate, ClockItError> {
+    Ok(local_today(module_timezone(pool, module_code).await?, clock.now()))
+}
+
+/// The timezone a class is scheduled in, from its module
+#[cfg(feature = "ssr")]
+pub async fn class_timezone(pool: &SqlitePool, class_id: i64) -> Result<Tz, ClockItError> {
+    let name: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT un.timezone
+        FROM classes c
+        INNER JOIN modules m ON m.moduleCode = c.moduleCode
+        INNER JOIN universities un ON un.name = m.university
+        WHERE c.classID = ?
+        "#,
+    )
+    .bind(class_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch class timezone"))?;
+
+    Ok(timezone_or_default(name.as_deref()))
+}
+
+/// The timezone of the university on a user's profile
+#[cfg(feature = "ssr")]
+pub async fn user_timezone(pool: &SqlitePool, email: &str) -> Result<Tz, ClockItError> {
+    let name: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT un.timezone
+        FROM users u
+        INNER JOIN universities un ON un.name = TRIM(u.university)
+        WHERE LOWER(u.emailAddress) = LOWER(?)
+        "#,
+    )
+    .bind(email.trim())
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch user timezone"))?;
+
+    Ok(timezone_or_default(name.as_deref()))
+}
+
+/// Add the university named on a profile if it isn't known yet, in the default
+/// timezone. Universities already known keep theirs.
+#[cfg(feature = "ssr")]
+pub async fn ensure_university(
+    conn: &mut SqliteConnection,
+    name: &str,
+) -> Result<(), ClockItError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(());
+    }
+
+    let now = Utc::now().to_rfc3339();
+    sqlx::query(
+        "INSERT OR IGNORE INTO universities (name, timezone, created_at, updated_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(name)
+    .bind(DEFAULT_TIMEZONE.name())
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *conn)
+    .await
+    .map_err(db_error("Failed to add university"))?;
+
+    Ok(())
+}
+
+/// The timezone `name`'s classes are scheduled in
+#[cfg(feature = "ssr")]
+pub async fn university_timezone(pool: &SqlitePool, name: &str) -> Result<Tz, ClockItError> {
+    let timezone: Option<String> =
+        sqlx::query_scalar("SELECT timezone FROM universities WHERE name = ?")
+            .bind(name.trim())
+            .fetch_optional(pool)
+            .await
+            .map_err(db_error("Failed to fetch university timezone"))?;
+
+    Ok(timezone_or_default(timezone.as_deref()))
+}
+
+/// Set the IANA timezone a university's classes are scheduled in, adding the
+/// university if it isn't known yet
+#[cfg(feature = "ssr")]
+pub async fn set_university_timezone(
+    pool: &SqlitePool,
+    name: &str,
+    timezone: &str,
+) -> Result<Tz, ClockItError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(ClockItError::Validation(
+            "University name is required".to_string(),
+        ));
+    }
+    let tz = parse_timezone(timezone).map_err(ClockItError::Validation)?;
+
+    let now = Utc::now().to_rfc3339();
+    sqlx::query(
+        r#"
+        INSERT INTO universities (name, timezone, created_at, updated_at)
+        VALUES (?, ?, ?, ?)
+        ON CONFLICT(name) DO UPDATE SET timezone = excluded.timezone, updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(name)
+    .bind(tz.name())
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to save university timezone"))?;
+
+    Ok(tz)
+}
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::database::test_support::{add_module, add_user, run, test_pool};
+
+    #[test]
+    fn a_new_university_starts_in_the_default_zone_until_changed() {
+        run(async {
+            let pool = test_pool().await;
+
+            ensure_university(
+                &mut pool.acquire().await.unwrap(),
+                " University of Cape Town ",
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                university_timezone(&pool, "university of cape town")
+                    .await
+                    .unwrap(),
+                DEFAULT_TIMEZONE
+            );
+
+            set_university_timezone(&pool, "University of Cape Town", "Europe/London")
+                .await
+                .unwrap();
+            // Naming it again on a profile doesn't reset the zone
+            ensure_university(
+                &mut pool.acquire().await.unwrap(),
+                "University of Cape Town",
+            )
+            .await
+            .unwrap();
+            assert_eq!(
+                university_timezone(&pool, "University of Cape Town")
+                    .await
+                    .unwrap(),
+                chrono_tz::Europe::London
+            );
+        });
+    }
+
+    #[test]
+    fn modules_take_their_universitys_zone() {
+        run(async {
+            let pool = test_pool().await;
+            add_user(&pool, "tz-lecturer@test.example", "lecturer").await;
+            add_module(&pool, "TZ101", "tz-lecturer@test.example").await;
+
+            set_university_timezone(&pool, "Stellenbosch University", "Asia/Tokyo")
+                .await
+                .unwrap();
+
+            assert_eq!(
+                module_timezone(&pool, "TZ101").await.unwrap(),
+                chrono_tz::Asia::Tokyo
+            );
+            assert_eq!(
+                user_timezone(&pool, "tz-lecturer@test.example")
+                    .await
+                    .unwrap(),
+                chrono_tz::Asia::Tokyo
+            );
+            assert!(
+                set_university_timezone(&pool, "Stellenbosch University", "Mars/Olympus")
+                    .await
+                    .is_err()
+            );
+        });
+    }
+}
@@ -0,0 +1,288 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqlitePool};
+
+/// A room classes are held in, with the geofence check-ins there are held to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Venue {
+    pub venue_id: i64,
+    pub name: String,
+    pub building: Option<String>,
+    pub campus: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_meters: Option<f64>,
+    /// `[latitude, longitude]` outline; takes precedence over the radius
+    pub polygon: Option<Vec<[f64; 2]>>,
+    pub capacity: Option<i64>,
+}
+
+impl Venue {
+    /// Whether a session can be fenced by this venue without the lecturer's location
+    pub fn has_geofence(&self) -> bool {
+        self.polygon.is_some() || (self.latitude.is_some() && self.longitude.is_some())
+    }
+}
+
+/// Fields a lecturer fills in when saving a venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VenueRequest {
+    pub name: String,
+    pub building: Option<String>,
+    pub campus: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub radius_meters: Option<f64>,
+    pub polygon: Option<Vec<[f64; 2]>>,
+    pub capacity: Option<i64>,
+}
+
+impl VenueRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Venue name is required".to_string());
+        }
+        match (self.latitude, self.longitude) {
+            (Some(lat), Some(lng)) => crate::utils::geofence::validate_coordinates(lat, lng)?,
+            (None, None) => {}
+            _ => return Err("Enter both a latitude and a longitude, or neither".to_string()),
+        }
+        if let Some(radius) = self.radius_meters {
+            if !radius.is_finite() || radius <= 0.0 {
+                return Err("Radius must be greater than 0 metres".to_string());
+            }
+            if self.latitude.is_none() {
+                return Err("A radius needs a centre latitude and longitude".to_string());
+            }
+        }
+        if let Some(polygon) = &self.polygon {
+            if polygon.len() < 3 {
+                return Err("A venue outline needs at least three points".to_string());
+            }
+        }
+        if self.capacity.is_some_and(|capacity| capacity <= 0) {
+            return Err("Capacity must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbVenue {
+    #[sqlx(rename = "venueID")]
+    venue_id: i64,
+    name: String,
+    building: Option<String>,
+    campus: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    radius_meters: Option<f64>,
+    polygon: Option<String>,
+    capacity: Option<i64>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbVenue> for Venue {
+    fn from(db: DbVenue) -> Self {
+        Self {
+            venue_id: db.venue_id,
+            name: db.name,
+            building: db.building,
+            campus: db.campus,
+            latitude: db.latitude,
+            longitude: db.longitude,
+            radius_meters: db.radius_meters,
+            polygon: db
+                .polygon
+                .and_then(|polygon| serde_json::from_str(&polygon).ok()),
+            capacity: db.capacity,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const VENUE_SELECT: &str = r#"
+    SELECT venueID, name, building, campus, latitude, longitude, radius_meters, polygon, capacity
+    FROM venues
+"#;
+
+#[cfg(feature = "ssr")]
+fn encode_polygon(polygon: &Option<Vec<[f64; 2]>>) -> Result<Option<String>, ClockItError> {
+    polygon
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| ClockItError::Internal(format!("Failed to encode venue outline: {}", e)))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_all_venues(pool: &SqlitePool) -> Result<Vec<Venue>, ClockItError> {
+    let rows = sqlx::query_as::<_, DbVenue>(&format!("{} ORDER BY name", VENUE_SELECT))
+        .fetch_all(pool)
+        .await
+        .map_err(db_error("Failed to fetch venues"))?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_venue(pool: &SqlitePool, venue_id: i64) -> Result<Option<Venue>, ClockItError> {
+    let row = sqlx::query_as::<_, DbVenue>(&format!("{} WHERE venueID = ?", VENUE_SELECT))
+        .bind(venue_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(db_error("Failed to fetch venue"))?;
+
+    Ok(row.map(Into::into))
+}
+
+/// The saved venue a class is held in, if its venue is registered
+#[cfg(feature = "ssr")]
+pub async fn get_class_venue(
+    pool: &SqlitePool,
+    class_id: i64,
+) -> Result<Option<Venue>, ClockItError> {
+    let row = sqlx::query_as::<_, DbVenue>(&format!(
+        "{} WHERE venueID = (SELECT venueID FROM classes WHERE classID = ?)",
+        VENUE_SELECT
+    ))
+    .bind(class_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch class venue"))?;
+
+    Ok(row.map(Into::into))
+}
+
+/// Save a new venue and link any classes already using its name
+#[cfg(feature = "ssr")]
+pub async fn create_venue(
+    pool: &SqlitePool,
+    request: &VenueRequest,
+) -> Result<Venue, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let name = request.name.trim();
+
+    let result = sqlx::query(
+        r#"
+        INSERT INTO venues (name, building, campus, latitude, longitude, radius_meters, polygon, capacity, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(name)
+    .bind(&request.building)
+    .bind(&request.campus)
+    .bind(request.latitude)
+    .bind(request.longitude)
+    .bind(request.radius_meters)
+    .bind(encode_polygon(&request.polygon)?)
+    .bind(request.capacity)
+    .bind(&now)
+    .bind(&now)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to create venue")(e) {
+        ClockItError::Conflict(_) => {
+            ClockItError::Conflict(format!("A venue called '{}' already exists", name))
+        }
+        other => other,
+    })?;
+
+    let venue_id = result.last_insert_rowid();
+    link_classes_to_venue(pool, venue_id, name).await?;
+
+    get_venue(pool, venue_id)
+        .await?
+        .ok_or_else(|| ClockItError::Internal("Failed to load created venue".to_string()))
+}
+
+/// Update a venue. Renaming it renames the venue on every class held there.
+#[cfg(feature = "ssr")]
+pub async fn update_venue(
+    pool: &SqlitePool,
+    venue_id: i64,
+    request: &VenueRequest,
+) -> Result<Venue, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let name = request.name.trim();
+
+    let result = sqlx::query(
+        r#"
+        UPDATE venues
+        SET name = ?, building = ?, campus = ?, latitude = ?, longitude = ?,
+            radius_meters = ?, polygon = ?, capacity = ?, updated_at = ?
+        WHERE venueID = ?
+        "#,
+    )
+    .bind(name)
+    .bind(&request.building)
+    .bind(&request.campus)
+    .bind(request.latitude)
+    .bind(request.longitude)
+    .bind(request.radius_meters)
+    .bind(encode_polygon(&request.polygon)?)
+    .bind(request.capacity)
+    .bind(&now)
+    .bind(venue_id)
+    .execute(pool)
+    .await
+    .map_err(|e| match db_error("Failed to update venue")(e) {
+        ClockItError::Conflict(_) => {
+            ClockItError::Conflict(format!("A venue called '{}' already exists", name))
+        }
+        other => other,
+    })?;
+
+    if result.rows_affected() == 0 {
+        return Err(ClockItError::NotFound("Venue not found".to_string()));
+    }
+
+    sqlx::query("UPDATE classes SET venue = ?, updated_at = ? WHERE venueID = ?")
+        .bind(name)
+        .bind(&now)
+        .bind(venue_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to rename venue on classes"))?;
+    link_classes_to_venue(pool, venue_id, name).await?;
+
+    get_venue(pool, venue_id)
+        .await?
+        .ok_or_else(|| ClockItError::NotFound("Venue not found".to_string()))
+}
+
+/// Delete a venue. Its classes keep the venue name but lose the saved geofence.
+#[cfg(feature = "ssr")]
+pub async fn delete_venue(pool: &SqlitePool, venue_id: i64) -> Result<(), ClockItError> {
+    sqlx::query("UPDATE classes SET venueID = NULL WHERE venueID = ?")
+        .bind(venue_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to unlink classes from venue"))?;
+
+    sqlx::query("DELETE FROM venues WHERE venueID = ?")
+        .bind(venue_id)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to delete venue"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+async fn link_classes_to_venue(pool: &SqlitePool, venue_id: i64, name: &str) -> Result<(), ClockItError> {
+    sqlx::query("UPDATE classes SET venueID = ? WHERE venueID IS NULL AND TRIM(venue) = ? COLLATE NOCASE")
+        .bind(venue_id)
+        .bind(name)
+        .execute(pool)
+        .await
+        .map_err(db_error("Failed to link classes to venue"))?;
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssr")]
+use crate::error::{db_error, ClockItError};
+#[cfg(feature = "ssr")]
+use chrono::Utc;
+#[cfg(feature = "ssr")]
+use sqlx::{FromRow, SqlitePool};
+
+/// A QR check-in from a student outside the module, waiting on the lecturer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkInRequest {
+    pub request_id: i64,
+    pub class_id: i64,
+    pub session_id: i64,
+    pub student_id: i64,
+    pub student_name: String,
+    pub student_surname: String,
+    pub student_email: String,
+    pub status: String,
+    pub requested_at: String,
+    pub decided_by: Option<String>,
+    pub decided_at: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+#[derive(Debug, Clone, FromRow)]
+struct DbWalkInRequest {
+    #[sqlx(rename = "requestID")]
+    request_id: i64,
+    #[sqlx(rename = "classID")]
+    class_id: i64,
+    #[sqlx(rename = "sessionID")]
+    session_id: i64,
+    #[sqlx(rename = "studentID")]
+    student_id: i64,
+    student_name: String,
+    student_surname: String,
+    student_email: String,
+    status: String,
+    requested_at: String,
+    decided_by: Option<String>,
+    decided_at: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+impl From<DbWalkInRequest> for WalkInRequest {
+    fn from(db: DbWalkInRequest) -> Self {
+        Self {
+            request_id: db.request_id,
+            class_id: db.class_id,
+            session_id: db.session_id,
+            student_id: db.student_id,
+            student_name: db.student_name,
+            student_surname: db.student_surname,
+            student_email: db.student_email,
+            status: db.status,
+            requested_at: db.requested_at,
+            decided_by: db.decided_by,
+            decided_at: db.decided_at,
+        }
+    }
+}
+
+#[cfg(feature = "ssr")]
+const WALK_IN_SELECT: &str = r#"
+    SELECT w.requestID, w.classID, w.sessionID, w.studentID,
+           u.name AS student_name, u.surname AS student_surname, u.emailAddress AS student_email,
+           w.status, w.requested_at, w.decided_by, w.decided_at
+    FROM walk_in_requests w
+    INNER JOIN users u ON u.userID = w.studentID
+"#;
+
+/// Record (or refresh) a walk-in request for `student_id`. A request the lecturer
+/// already rejected stays rejected, so a student can't keep re-submitting it.
+#[cfg(feature = "ssr")]
+pub async fn upsert_walk_in_request(
+    pool: &SqlitePool,
+    class_id: i64,
+    session_id: i64,
+    student_id: i64,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    accuracy: Option<f64>,
+) -> Result<String, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+
+    sqlx::query(
+        r#"
+        INSERT INTO walk_in_requests (classID, sessionID, studentID, status, requested_at, check_latitude, check_longitude, location_accuracy)
+        VALUES (?, ?, ?, 'pending', ?, ?, ?, ?)
+        ON CONFLICT (classID, studentID) DO UPDATE SET
+            sessionID = excluded.sessionID,
+            requested_at = excluded.requested_at,
+            check_latitude = excluded.check_latitude,
+            check_longitude = excluded.check_longitude,
+            location_accuracy = excluded.location_accuracy
+        WHERE walk_in_requests.status = 'pending'
+        "#,
+    )
+    .bind(class_id)
+    .bind(session_id)
+    .bind(student_id)
+    .bind(&now)
+    .bind(latitude)
+    .bind(longitude)
+    .bind(accuracy)
+    .execute(pool)
+    .await
+    .map_err(db_error("Failed to record walk-in request"))?;
+
+    sqlx::query_scalar("SELECT status FROM walk_in_requests WHERE classID = ? AND studentID = ?")
+        .bind(class_id)
+        .bind(student_id)
+        .fetch_one(pool)
+        .await
+        .map_err(db_error("Failed to fetch walk-in request"))
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_pending_walk_ins(
+    pool: &SqlitePool,
+    class_id: i64,
+) -> Result<Vec<WalkInRequest>, ClockItError> {
+    let rows = sqlx::query_as::<_, DbWalkInRequest>(&format!(
+        "{} WHERE w.classID = ? AND w.status = 'pending' ORDER BY w.requested_at",
+        WALK_IN_SELECT
+    ))
+    .bind(class_id)
+    .fetch_all(pool)
+    .await
+    .map_err(db_error("Failed to fetch walk-in requests"))?;
+
+    Ok(rows.into_iter().map(Into::into).collect())
+}
+
+#[cfg(feature = "ssr")]
+pub async fn get_walk_in_request(
+    pool: &SqlitePool,
+    request_id: i64,
+) -> Result<Option<WalkInRequest>, ClockItError> {
+    let row = sqlx::query_as::<_, DbWalkInRequest>(&format!(
+        "{} WHERE w.requestID = ?",
+        WALK_IN_SELECT
+    ))
+    .bind(request_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(db_error("Failed to fetch walk-in request"))?;
+
+    Ok(row.map(Into::into))
+}
+
+/// Accept or reject a pending request. Accepting marks the student present with the
+/// location captured at scan time. Returns false if the request was already decided.
+#[cfg(feature = "ssr")]
+pub async fn decide_walk_in_request(
+    pool: &SqlitePool,
+    request_id: i64,
+    accept: bool,
+    decided_by: &str,
+) -> Result<bool, ClockItError> {
+    let now = Utc::now().to_rfc3339();
+    let status = if accept { "accepted" } else { "rejected" };
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    let updated = sqlx::query(
+        "UPDATE walk_in_requests SET status = ?, decided_by = ?, decided_at = ? WHERE requestID = ? AND status = 'pending'",
+    )
+    .bind(status)
+    .bind(decided_by)
+    .bind(&now)
+    .bind(request_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(db_error("Failed to update walk-in request"))?;
+
+    if updated.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    if accept {
+        sqlx::query(
+            r#"
+            INSERT INTO attendance (studentID, classID, status, recorded_at, notes, check_latitude, check_longitude, location_accuracy)
+            SELECT studentID, classID, 'present', requested_at, 'Walk-in accepted by lecturer', check_latitude, check_longitude, location_accuracy
+            FROM walk_in_requests WHERE requestID = ?
+            ON CONFLICT (studentID, classID) DO UPDATE SET
+                status = 'present',
+                recorded_at = excluded.recorded_at,
+                notes = excluded.notes,
+                check_latitude = excluded.check_latitude,
+                check_longitude = excluded.check_longitude,
+                location_accuracy = excluded.location_accuracy
+            "#,
+        )
+        .bind(request_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to record attendance"))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to commit transaction"))?;
+
+    Ok(true)
+}
@@ -0,0 +1,422 @@
+//! Outgoing email.
+//!
+//! Mail goes through an [`EmailSender`] chosen once from the environment:
+//!
+//! * `resend`: the Resend HTTP API (`RESEND_API_KEY`, `RESEND_FROM_EMAIL`)
+//! * `smtp`: any SMTP relay via lettre (`SMTP_HOST`, `SMTP_PORT`, `SMTP_TLS`,
+//!   `SMTP_USERNAME`, `SMTP_PASSWORD`, `SMTP_FROM_EMAIL`, `SMTP_FROM_NAME`)
+//! * `file`: writes each message as an `.eml` file under `CLOCK_IT_MAIL_DIR`
+//!   (default `mail-outbox`), so development and tests can read sent mail
+//!   without a network
+//!
+//! `CLOCK_IT_EMAIL_BACKEND` picks one explicitly. Without it, Resend is used when
+//! `RESEND_API_KEY` is set, then SMTP when `SMTP_USERNAME` is set. Only a debug
+//! build falls back to the file sink on its own; a release build with nothing
+//! configured refuses to start, rather than quietly writing codes to disk.
+
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+const DEFAULT_FROM_NAME: &str = "Clock It";
+const DEFAULT_MAIL_DIR: &str = "mail-outbox";
+
+/// A rendered HTML email for a single recipient
+#[derive(Clone, Debug)]
+pub struct OutgoingEmail {
+    pub to: String,
+    pub subject: String,
+    pub html: String,
+}
+
+pub trait EmailSender {
+    fn send(&self, email: &OutgoingEmail) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+pub struct ResendSender {
+    api_key: String,
+    from: String,
+    client: reqwest::Client,
+}
+
+impl ResendSender {
+    pub fn from_env() -> Result<Self, String> {
+        let api_key = std::env::var("RESEND_API_KEY")
+            .map_err(|_| "RESEND_API_KEY environment variable not set".to_string())?;
+        let from = std::env::var("RESEND_FROM_EMAIL")
+            .unwrap_or_else(|_| "onboarding@resend.dev".to_string());
+
+        Ok(Self {
+            api_key,
+            from,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+impl EmailSender for ResendSender {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), String> {
+        let body = serde_json::json!({
+            "from": self.from,
+            "to": [email.to],
+            "subject": email.subject,
+            "html": email.html
+        });
+
+        println!("📧 Sending email via Resend to: {}", email.to);
+
+        let response = self
+            .client
+            .post("https://api.resend.com/emails")
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body)
+            .timeout(std::time::Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+
+        if response.status().is_success() {
+            println!("✅ Email sent successfully via Resend");
+            Ok(())
+        } else {
+            let error_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unknown error".to_string());
+            Err(format!("Resend API error: {}", error_text))
+        }
+    }
+}
+
+pub struct SmtpSender {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpSender {
+    pub fn from_env() -> Result<Self, String> {
+        let username = std::env::var("SMTP_USERNAME")
+            .map_err(|_| "SMTP_USERNAME environment variable not set".to_string())?;
+        let password = std::env::var("SMTP_PASSWORD")
+            .map_err(|_| "SMTP_PASSWORD environment variable not set".to_string())?;
+        let host = std::env::var("SMTP_HOST").unwrap_or_else(|_| "smtp.gmail.com".to_string());
+        let port = match std::env::var("SMTP_PORT") {
+            Ok(value) => Some(
+                value
+                    .trim()
+                    .parse::<u16>()
+                    .map_err(|_| format!("Invalid SMTP_PORT: {}", value))?,
+            ),
+            Err(_) => None,
+        };
+        let tls = smtp_tls(std::env::var("SMTP_TLS").ok().as_deref(), port)?;
+        let from_email = std::env::var("SMTP_FROM_EMAIL").unwrap_or_else(|_| username.clone());
+
+        let builder = match tls {
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&host),
+            SmtpTls::Starttls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&host),
+            SmtpTls::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(
+                &host,
+            )),
+        }
+        .map_err(|e| format!("Invalid SMTP host {}: {}", host, e))?;
+        let builder = match port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+
+        Ok(Self {
+            from: mailbox(from_name("SMTP_FROM_NAME"), &from_email)?,
+            transport: builder.credentials(Credentials::new(username, password)).build(),
+        })
+    }
+}
+
+impl EmailSender for SmtpSender {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), String> {
+        let message = build_message(&self.from, email)?;
+
+        println!("📧 Sending email via SMTP to: {}", email.to);
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| format!("SMTP error: {}", e))?;
+
+        println!("✅ Email sent successfully via SMTP");
+        Ok(())
+    }
+}
+
+/// How the SMTP connection is secured
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SmtpTls {
+    /// TLS from the first byte, as on port 465
+    Tls,
+    /// Plain connection upgraded with STARTTLS, as on 587 and 25
+    Starttls,
+    /// No encryption at all, for a relay on a trusted network
+    None,
+}
+
+/// The TLS mode from `SMTP_TLS` (`tls`, `starttls` or `none`), or else from the
+/// port: implicit TLS on 465 and when no port is given (lettre then uses 465),
+/// STARTTLS on any other port.
+fn smtp_tls(setting: Option<&str>, port: Option<u16>) -> Result<SmtpTls, String> {
+    let setting = setting
+        .map(|value| value.trim().to_lowercase())
+        .filter(|value| !value.is_empty());
+
+    match setting.as_deref() {
+        Some("tls") => Ok(SmtpTls::Tls),
+        Some("starttls") => Ok(SmtpTls::Starttls),
+        Some("none") => Ok(SmtpTls::None),
+        Some(other) => Err(format!(
+            "Unknown SMTP_TLS '{}' (expected tls, starttls or none)",
+            other
+        )),
+        None => match port {
+            None | Some(465) => Ok(SmtpTls::Tls),
+            Some(_) => Ok(SmtpTls::Starttls),
+        },
+    }
+}
+
+/// Writes every message to `<dir>/<uuid>.eml` instead of delivering it
+pub struct FileSender {
+    from: Mailbox,
+    dir: PathBuf,
+    transport: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileSender {
+    pub fn new(dir: impl Into<PathBuf>, from: Mailbox) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create mail directory {}: {}", dir.display(), e))?;
+
+        Ok(Self {
+            from,
+            transport: AsyncFileTransport::new(&dir),
+            dir,
+        })
+    }
+
+    pub fn from_env() -> Result<Self, String> {
+        let dir = std::env::var("CLOCK_IT_MAIL_DIR").unwrap_or_else(|_| DEFAULT_MAIL_DIR.to_string());
+        let from_email = std::env::var("CLOCK_IT_MAIL_FROM")
+            .unwrap_or_else(|_| "no-reply@clock-it.local".to_string());
+
+        Self::new(dir, mailbox(from_name("SMTP_FROM_NAME"), &from_email)?)
+    }
+
+    pub fn dir(&self) -> &PathBuf {
+        &self.dir
+    }
+}
+
+impl EmailSender for FileSender {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), String> {
+        let message = build_message(&self.from, email)?;
+
+        let id = self
+            .transport
+            .send(message)
+            .await
+            .map_err(|e| format!("Failed to write email: {}", e))?;
+
+        println!(
+            "📨 Email to {} written to {}",
+            email.to,
+            self.dir.join(format!("{}.eml", id)).display()
+        );
+        Ok(())
+    }
+}
+
+/// The sender picked from the environment at startup
+pub enum Mailer {
+    Resend(ResendSender),
+    Smtp(SmtpSender),
+    File(FileSender),
+}
+
+impl Mailer {
+    pub fn from_env() -> Result<Self, String> {
+        let backend = std::env::var("CLOCK_IT_EMAIL_BACKEND")
+            .ok()
+            .map(|value| value.trim().to_lowercase())
+            .filter(|value| !value.is_empty());
+
+        let backend = match backend {
+            Some(backend) => backend,
+            None => default_backend(
+                std::env::var("RESEND_API_KEY").is_ok(),
+                std::env::var("SMTP_USERNAME").is_ok(),
+                cfg!(debug_assertions),
+            )?
+            .to_string(),
+        };
+
+        match backend.as_str() {
+            "resend" => ResendSender::from_env().map(Mailer::Resend),
+            "smtp" => SmtpSender::from_env().map(Mailer::Smtp),
+            "file" => FileSender::from_env().map(Mailer::File),
+            other => Err(format!(
+                "Unknown CLOCK_IT_EMAIL_BACKEND '{}' (expected resend, smtp or file)",
+                other
+            )),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Mailer::Resend(_) => "Resend API".to_string(),
+            Mailer::Smtp(_) => "SMTP".to_string(),
+            Mailer::File(sender) => format!("files in {}", sender.dir().display()),
+        }
+    }
+}
+
+impl EmailSender for Mailer {
+    async fn send(&self, email: &OutgoingEmail) -> Result<(), String> {
+        match self {
+            Mailer::Resend(sender) => sender.send(email).await,
+            Mailer::Smtp(sender) => sender.send(email).await,
+            Mailer::File(sender) => sender.send(email).await,
+        }
+    }
+}
+
+/// The backend for when `CLOCK_IT_EMAIL_BACKEND` isn't set, from which credentials
+/// are present. The file sink is only a default in debug builds.
+fn default_backend(
+    has_resend_key: bool,
+    has_smtp_username: bool,
+    debug_build: bool,
+) -> Result<&'static str, String> {
+    if has_resend_key {
+        Ok("resend")
+    } else if has_smtp_username {
+        Ok("smtp")
+    } else if debug_build {
+        Ok("file")
+    } else {
+        Err("No email backend configured: set RESEND_API_KEY or SMTP_USERNAME, or CLOCK_IT_EMAIL_BACKEND=file to write mail to disk".to_string())
+    }
+}
+
+/// The configured mailer, built on first use. `main` builds it at startup and
+/// refuses to start on a configuration error.
+pub fn mailer() -> Result<&'static Mailer, String> {
+    static MAILER: OnceLock<Result<Mailer, String>> = OnceLock::new();
+    MAILER.get_or_init(Mailer::from_env).as_ref().map_err(Clone::clone)
+}
+
+/// Send through the configured mailer
+pub async fn send_email(email: &OutgoingEmail) -> Result<(), String> {
+    mailer()?.send(email).await
+}
+
+fn from_name(var: &str) -> String {
+    std::env::var(var).unwrap_or_else(|_| DEFAULT_FROM_NAME.to_string())
+}
+
+fn mailbox(name: String, email: &str) -> Result<Mailbox, String> {
+    let address = email
+        .trim()
+        .parse()
+        .map_err(|e| format!("Invalid sender address {}: {}", email, e))?;
+    Ok(Mailbox::new(Some(name), address))
+}
+
+fn build_message(from: &Mailbox, email: &OutgoingEmail) -> Result<Message, String> {
+    let to: Mailbox = email
+        .to
+        .parse()
+        .map_err(|e| format!("Invalid recipient {}: {}", email.to, e))?;
+
+    Message::builder()
+        .from(from.clone())
+        .to(to)
+        .subject(email.subject.as_str())
+        .header(ContentType::TEXT_HTML)
+        .body(email.html.clone())
+        .map_err(|e| format!("Failed to build email: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn credentials_pick_the_backend() {
+        assert_eq!(default_backend(true, true, false), Ok("resend"));
+        assert_eq!(default_backend(false, true, false), Ok("smtp"));
+        assert_eq!(default_backend(false, false, true), Ok("file"));
+    }
+
+    #[test]
+    fn release_build_without_credentials_has_no_backend() {
+        assert!(default_backend(false, false, false).is_err());
+    }
+
+    #[test]
+    fn port_picks_the_smtp_tls_mode() {
+        assert_eq!(smtp_tls(None, None), Ok(SmtpTls::Tls));
+        assert_eq!(smtp_tls(None, Some(465)), Ok(SmtpTls::Tls));
+        assert_eq!(smtp_tls(None, Some(587)), Ok(SmtpTls::Starttls));
+        assert_eq!(smtp_tls(None, Some(25)), Ok(SmtpTls::Starttls));
+        assert_eq!(smtp_tls(None, Some(2525)), Ok(SmtpTls::Starttls));
+        assert_eq!(smtp_tls(Some(""), Some(587)), Ok(SmtpTls::Starttls));
+    }
+
+    #[test]
+    fn smtp_tls_setting_overrides_the_port() {
+        assert_eq!(smtp_tls(Some("tls"), Some(587)), Ok(SmtpTls::Tls));
+        assert_eq!(
+            smtp_tls(Some(" STARTTLS "), Some(465)),
+            Ok(SmtpTls::Starttls)
+        );
+        assert_eq!(smtp_tls(Some("none"), Some(25)), Ok(SmtpTls::None));
+        assert!(smtp_tls(Some("ssl"), Some(465)).is_err());
+    }
+
+    #[test]
+    fn file_sink_writes_a_readable_message() {
+        let dir = std::env::temp_dir().join(format!("clock-it-mail-{}", uuid::Uuid::new_v4()));
+        let from = mailbox("Clock It".to_string(), "no-reply@clock-it.local").unwrap();
+        let sender = FileSender::new(&dir, from).unwrap();
+
+        let email = OutgoingEmail {
+            to: "student@test.example".to_string(),
+            subject: "Your verification code".to_string(),
+            html: "<p>Your code is 123456</p>".to_string(),
+        };
+        tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(sender.send(&email))
+            .unwrap();
+
+        let files: Vec<PathBuf> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().and_then(|e| e.to_str()), Some("eml"));
+
+        let message = std::fs::read_to_string(&files[0]).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(message.contains("From: \"Clock It\" <no-reply@clock-it.local>"));
+        assert!(message.contains("To: student@test.example"));
+        assert!(message.contains("Subject: Your verification code"));
+        assert!(message.contains("Content-Type: text/html"));
+        assert!(message.contains("<p>Your code is 123456</p>"));
+    }
+}
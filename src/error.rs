@@ -0,0 +1,117 @@
+//! The error type shared by the database layer, server functions and the client.
+//!
+//! Server functions return `Result<_, ClockItError>`, so the kind survives the trip
+//! to the browser and pages can branch on it instead of parsing messages. When a
+//! server function fails, the error also sets the HTTP status of the response to
+//! [`ClockItError::status_code`], so API consumers see 404, 409 and so on rather than
+//! a blanket 500.
+
+use leptos::server_fn::codec::JsonEncoding;
+use leptos::server_fn::error::{FromServerFnError, ServerFnErrorErr};
+use leptos::server_fn::{Bytes, Encodes};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClockItError {
+    /// Nobody is signed in
+    Unauthorized(String),
+    /// Signed in, but not allowed to do this
+    Forbidden(String),
+    /// The record asked for doesn't exist
+    NotFound(String),
+    /// The change clashes with existing data, such as a duplicate email or module code
+    Conflict(String),
+    /// The input was rejected before anything was written
+    Validation(String),
+    /// Asked again too soon, such as requesting another email code during its cooldown
+    RateLimited(String),
+    /// A query failed
+    Database(String),
+    /// Anything else, including failures reaching the server at all
+    Internal(String),
+}
+
+impl ClockItError {
+    /// The human-readable message, without the kind
+    pub fn message(&self) -> &str {
+        match self {
+            ClockItError::Unauthorized(message)
+            | ClockItError::Forbidden(message)
+            | ClockItError::NotFound(message)
+            | ClockItError::Conflict(message)
+            | ClockItError::Validation(message)
+            | ClockItError::RateLimited(message)
+            | ClockItError::Database(message)
+            | ClockItError::Internal(message) => message,
+        }
+    }
+
+    pub fn status_code(&self) -> u16 {
+        match self {
+            ClockItError::Unauthorized(_) => 401,
+            ClockItError::Forbidden(_) => 403,
+            ClockItError::NotFound(_) => 404,
+            ClockItError::Conflict(_) => 409,
+            ClockItError::Validation(_) => 422,
+            ClockItError::RateLimited(_) => 429,
+            ClockItError::Database(_) | ClockItError::Internal(_) => 500,
+        }
+    }
+}
+
+impl fmt::Display for ClockItError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for ClockItError {}
+
+impl FromServerFnError for ClockItError {
+    type Encoder = JsonEncoding;
+
+    fn from_server_fn_error(value: ServerFnErrorErr) -> Self {
+        ClockItError::Internal(value.to_string())
+    }
+
+    /// Runs on the server while the failed call's response is being built, which
+    /// makes it the one place every error passes through on its way out
+    fn ser(&self) -> Bytes {
+        #[cfg(feature = "ssr")]
+        if let Some(response) = leptos::prelude::use_context::<leptos_axum::ResponseOptions>() {
+            if let Ok(status) = axum::http::StatusCode::from_u16(self.status_code()) {
+                response.set_status(status);
+            }
+        }
+
+        // Every variant is a single string, so JSON encoding can't fail
+        JsonEncoding::encode(self).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "ssr")]
+impl From<sqlx::Error> for ClockItError {
+    fn from(error: sqlx::Error) -> Self {
+        db_error("Database query failed")(error)
+    }
+}
+
+/// Turn a sqlx error into a [`ClockItError`] prefixed with what was being attempted,
+/// as in `.map_err(db_error("Failed to fetch class"))`. Missing rows become
+/// `NotFound` and constraint violations become `Conflict`.
+#[cfg(feature = "ssr")]
+pub fn db_error(context: &'static str) -> impl Fn(sqlx::Error) -> ClockItError {
+    move |error| {
+        let message = format!("{}: {}", context, error);
+        match &error {
+            sqlx::Error::RowNotFound => ClockItError::NotFound(message),
+            sqlx::Error::Database(db)
+                if db.is_unique_violation() || db.is_foreign_key_violation() =>
+            {
+                ClockItError::Conflict(message)
+            }
+            _ => ClockItError::Database(message),
+        }
+    }
+}
@@ -1,11 +1,25 @@
 #![recursion_limit = "512"]
 
 pub mod app;
+pub mod clock;
+#[cfg(feature = "ssr")]
+pub mod authorization;
+#[cfg(feature = "ssr")]
+pub mod calendar_feed;
 pub mod components;
 pub mod database;
+pub mod error;
+#[cfg(feature = "ssr")]
+pub mod email;
 pub mod pages;
+#[cfg(feature = "ssr")]
+pub mod qr_tokens;
 pub mod routes;
+#[cfg(feature = "ssr")]
+pub mod session;
 pub mod types;
+#[cfg(feature = "ssr")]
+pub mod uploads;
 pub mod user_context;
 pub mod utils;
 
@@ -1,3 +1,5 @@
+#![recursion_limit = "512"]
+
 #[cfg(feature = "ssr")]
 use leptos::logging::log;
 
@@ -152,6 +154,30 @@ fn resolve_port(use_tls: bool) -> u16 {
     }
 }
 
+#[cfg(feature = "ssr")]
+fn resolve_session_sweep_interval() -> std::time::Duration {
+    use std::env;
+
+    const DEFAULT_SECONDS: u64 = 60;
+
+    let seconds = match env::var("CLOCK_IT_SESSION_SWEEP_SECONDS") {
+        Ok(raw) if !raw.trim().is_empty() => match raw.trim().parse::<u64>() {
+            Ok(seconds) if seconds > 0 => seconds,
+            _ => {
+                log!(
+                    "⚠️  Invalid CLOCK_IT_SESSION_SWEEP_SECONDS '{}'; using default {} instead.",
+                    raw,
+                    DEFAULT_SECONDS
+                );
+                DEFAULT_SECONDS
+            }
+        },
+        _ => DEFAULT_SECONDS,
+    };
+
+    std::time::Duration::from_secs(seconds)
+}
+
 #[cfg(feature = "ssr")]
 fn server_scheme(use_tls: bool) -> &'static str {
     if use_tls {
@@ -185,10 +211,12 @@ async fn debug_files() -> String {
 #[cfg(feature = "ssr")]
 #[tokio::main]
 async fn main() {
+    use axum::extract::Path;
     use axum::Router;
     use axum::routing::get;
     use axum_server::tls_rustls::RustlsConfig;
     use clock_it::app::*;
+    use clock_it::clock::{SharedClock, SystemClock};
     use clock_it::database::{
         init_db_pool, run_migrations, test_database_structure, test_db_connection,
     };
@@ -200,14 +228,12 @@ async fn main() {
 
     println!("🚀 Starting Clock-It server...");
 
-    clock_it::database::print_test_hash();
-
     let conf = get_configuration(Some("Cargo.toml")).unwrap();
     let leptos_options = conf.leptos_options;
 
     // Initialize database
     println!("🗄️ Initializing database...");
-    let _pool = match init_db_pool().await {
+    let pool = match init_db_pool().await {
         Ok(pool) => {
             if let Err(e) = test_db_connection(&pool).await {
                 eprintln!("❌ Database connection test failed: {}", e);
@@ -232,6 +258,64 @@ async fn main() {
         }
     };
 
+    match clock_it::email::mailer() {
+        Ok(mailer) => println!("📧 Email delivery: {}", mailer.describe()),
+        Err(e) => {
+            // Sign-up and password reset depend on mail, so don't start without it
+            eprintln!("❌ Email delivery is not configured: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Everything that asks for the time goes through this, so tests can swap it out
+    let clock: SharedClock = std::sync::Arc::new(SystemClock);
+
+    // Expired one-time codes are rejected on use; this just keeps the table small
+    let otp_pool = pool.clone();
+    let otp_clock = clock.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(10 * 60));
+        loop {
+            interval.tick().await;
+            match clock_it::database::purge_expired_otps(&otp_pool, otp_clock.as_ref()).await {
+                Ok(0) => {}
+                Ok(removed) => println!("🧹 Removed {} expired one-time codes", removed),
+                Err(e) => eprintln!("❌ {}", e),
+            }
+        }
+    });
+
+    // End overdue sessions and complete finished classes without waiting for a client to ask
+    let sweep_pool = pool.clone();
+    let sweep_clock = clock.clone();
+    let sweep_interval = resolve_session_sweep_interval();
+    println!("⏱️ Session lifecycle check every {}s", sweep_interval.as_secs());
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(sweep_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            match clock_it::database::sweep_session_states(&sweep_pool, sweep_clock.as_ref()).await {
+                Ok(sweep) => println!(
+                    "⏱️ Session lifecycle check: {} classes checked, {} updated",
+                    sweep.checked, sweep.updated
+                ),
+                Err(e) => eprintln!("❌ Session lifecycle check failed: {}", e),
+            }
+        }
+    });
+
+    let use_tls = resolve_use_tls();
+
+    // Server-side login sessions, stored alongside the application data
+    let session_layer = match clock_it::session::session_layer(&pool, use_tls).await {
+        Ok(layer) => layer,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
+
     // Generate routes
     let routes = generate_route_list(App);
 
@@ -254,15 +338,33 @@ async fn main() {
 
     let app = Router::new()
         .route("/debug/files", get(debug_files))
-        .leptos_routes(&leptos_options, routes, {
-            let leptos_options = leptos_options.clone();
-            move || shell(leptos_options.clone())
-        })
+        // Calendar apps fetch this without a login session; the token is the credential
+        .route("/calendar/{file}", get({
+            let pool = pool.clone();
+            move |Path(file): Path<String>| clock_it::calendar_feed::serve_feed(pool.clone(), file)
+        }))
+        .leptos_routes_with_context(
+            &leptos_options,
+            routes,
+            {
+                // Server functions share the startup pool and clock rather than opening their own
+                let pool = pool.clone();
+                let clock = clock.clone();
+                move || {
+                    provide_context(pool.clone());
+                    provide_context(clock.clone());
+                }
+            },
+            {
+                let leptos_options = leptos_options.clone();
+                move || shell(leptos_options.clone())
+            },
+        )
         .nest_service("/pkg", ServeDir::new(pkg_path))
         .fallback(leptos_axum::file_and_error_handler(shell))
-        .with_state(leptos_options);
+        .with_state(leptos_options)
+        .layer(session_layer);
 
-    let use_tls = resolve_use_tls();
     let port = resolve_port(use_tls);
     let scheme = server_scheme(use_tls);
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
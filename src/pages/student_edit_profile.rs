@@ -25,7 +25,6 @@ pub fn StudentEditProfilePage() -> impl IntoView {
     let current_user = get_current_user();
 
     let student_id = RwSignal::new(String::new());
-    let user_id = RwSignal::new(0i64);
     let email = RwSignal::new(String::new());
     let first_name = RwSignal::new(String::new());
     let last_name = RwSignal::new(String::new());
@@ -37,7 +36,6 @@ pub fn StudentEditProfilePage() -> impl IntoView {
     Effect::new({
         let current_user = current_user.clone();
         let student_id = student_id.clone();
-        let user_id = user_id.clone();
         let email = email.clone();
         let first_name = first_name.clone();
         let last_name = last_name.clone();
@@ -45,7 +43,6 @@ pub fn StudentEditProfilePage() -> impl IntoView {
         let avatar_src = avatar_src.clone();
         move |_| {
             if let Some(user) = current_user.get() {
-                user_id.set(user.user_id);
                 student_id.set(format!("STU-{0:06}", user.user_id));
                 email.set(user.email_address.clone());
                 first_name.set(user.name.clone());
@@ -114,7 +111,6 @@ pub fn StudentEditProfilePage() -> impl IntoView {
         };
 
         let request = UpdateProfileRequest {
-            user_id: user_id.get(),
             name: first_name.get().trim().to_string(),
             surname: last_name.get().trim().to_string(),
             email_address: email.get().trim().to_string(),
@@ -1,11 +1,12 @@
 use crate::components::QrScanner;
 use crate::routes::class_functions::record_session_attendance_fn;
+use crate::routes::invitation_functions::join_module_fn;
 use crate::routes::student_functions::{get_student_schedule, StudentScheduleItem};
 use crate::user_context::get_current_user;
 use crate::utils::module_visuals::{module_visual, ModuleVisual};
 use leptos::prelude::*;
 use leptos::task::spawn_local;
-use leptos_router::hooks::use_navigate;
+use leptos_router::hooks::{use_navigate, use_query_map};
 use urlencoding::encode;
 use std::collections::HashMap;
 
@@ -148,8 +149,8 @@ pub fn StudentHomePage() -> impl IntoView {
             let schedule_feedback = schedule_feedback.clone();
             move |params: Option<(String, String)>| async move {
                 match params {
-                    Some((email, date)) => {
-                        match get_student_schedule(email, Some(date.clone())).await {
+                    Some((_, date)) => {
+                        match get_student_schedule(Some(date.clone())).await {
                             Ok(response) => {
                                 if (!response.success || response.classes.is_empty())
                                     && !response.message.is_empty()
@@ -184,8 +185,7 @@ pub fn StudentHomePage() -> impl IntoView {
         Callback::new(move |data: String| {
             set_scanned_data.set(Some(data.clone()));
             set_show_scanner.set(false);
-            if let Some(user) = current_user.get() {
-                let email = user.email_address.clone();
+            if current_user.get().is_some() {
                 let set_feedback_with_timeout = set_feedback_with_timeout.clone();
                 let payload = data.clone();
                 spawn_local(async move {
@@ -195,7 +195,6 @@ pub fn StudentHomePage() -> impl IntoView {
                             Ok(location) => {
                                 match record_session_attendance_fn(
                                     payload.clone(),
-                                    email.clone(),
                                     Some(location.latitude),
                                     Some(location.longitude),
                                     location.accuracy,
@@ -233,6 +232,37 @@ pub fn StudentHomePage() -> impl IntoView {
         })
     };
 
+    // A lecturer's join link lands here with the module's code filled in
+    let query = use_query_map();
+    let join_code = RwSignal::new(String::new());
+    let joining = RwSignal::new(false);
+
+    Effect::new(move |_| {
+        if let Some(code) = query.with(|q| q.get("join")) {
+            join_code.set(code);
+        }
+    });
+
+    let on_join = move |ev: leptos::ev::SubmitEvent| {
+        ev.prevent_default();
+        let code = join_code.get().trim().to_string();
+        if code.is_empty() || joining.get_untracked() {
+            return;
+        }
+        joining.set(true);
+        spawn_local(async move {
+            match join_module_fn(code).await {
+                Ok(resp) => {
+                    join_code.set(String::new());
+                    schedule_resource.refetch();
+                    set_feedback_with_timeout(Some((resp.success, resp.message)));
+                }
+                Err(e) => set_feedback_with_timeout(Some((false, e.to_string()))),
+            }
+            joining.set(false);
+        });
+    };
+
     let handle_close_scanner = Callback::new(move |_| {
         set_show_scanner.set(false);
     });
@@ -278,6 +308,25 @@ pub fn StudentHomePage() -> impl IntoView {
                     <p class="student-date-subtitle">{subtitle_text}</p>
                 </section>
 
+                {/* Join a module with a code from the lecturer */}
+                <form class="student-join-form" on:submit=on_join>
+                    <input
+                        class="student-join-input"
+                        type="text"
+                        placeholder="Module join code"
+                        aria-label="Module join code"
+                        autocomplete="off"
+                        bind:value=join_code
+                    />
+                    <button
+                        class="student-join-button"
+                        type="submit"
+                        disabled=move || joining.get() || join_code.get().trim().is_empty()
+                    >
+                        {move || if joining.get() { "Joining..." } else { "Join" }}
+                    </button>
+                </form>
+
                 {/* Module cards */}
                 <Suspense fallback=move || view! { <div class="student-modules-list"><div class="student-module-card loading">"Loading your schedule…"</div></div> }>
                     {move || {
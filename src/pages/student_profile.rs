@@ -1,3 +1,4 @@
+use crate::components::CalendarFeedPanel;
 use crate::user_context::{clear_current_user, get_current_user};
 use crate::routes::auth_functions::ResetPassword;
 use leptos::prelude::*;
@@ -46,7 +47,7 @@ pub fn StudentProfilePage() -> impl IntoView {
     // Handle sign out
     let navigate_logout = navigate.clone();
     let handle_sign_out = move |_| {
-        // Clear the current user from context and localStorage
+        // Clear the current user and end the server session
         clear_current_user();
         // Redirect to login page
         navigate_logout("/", Default::default());
@@ -295,7 +296,21 @@ pub fn StudentProfilePage() -> impl IntoView {
 
                 
 
-                {/* Other Options */}
+                {/* Calendar Subscription */}
+                <section class="student-profile-section">
+                    <h3 class="student-section-title">
+                        <svg width="20" height="20" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="2">
+                            <rect x="3" y="4" width="18" height="18" rx="2" ry="2"></rect>
+                            <line x1="16" y1="2" x2="16" y2="6"></line>
+                            <line x1="8" y1="2" x2="8" y2="6"></line>
+                            <line x1="3" y1="10" x2="21" y2="10"></line>
+                        </svg>
+                        "Calendar Subscription"
+                    </h3>
+                    <CalendarFeedPanel/>
+                </section>
+
+                                {/* Other Options */}
                 <section class="student-profile-section">
                     <A href="/student/support" attr:class="student-settings-item" attr:style="text-decoration: none;">
                         <div class="student-settings-icon student-settings-icon-gray">
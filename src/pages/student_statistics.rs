@@ -1,4 +1,4 @@
-use crate::components::QrScanner;
+use crate::components::{ExcuseForm, QrScanner};
 use crate::routes::class_functions::record_session_attendance_fn;
 use crate::routes::student_functions::{
     get_student_module_breakdown, get_student_recent_activity, get_student_stats_summary,
@@ -65,8 +65,7 @@ pub fn StudentStatisticsPage() -> impl IntoView {
         Callback::new(move |data: String| {
             set_scanned_data.set(Some(data.clone()));
             set_show_scanner.set(false);
-            if let Some(user) = current_user.get() {
-                let email = user.email_address.clone();
+            if current_user.get().is_some() {
                 let set_feedback_with_timeout = set_feedback_with_timeout.clone();
                 let payload = data.clone();
                 spawn_local(async move {
@@ -76,7 +75,6 @@ pub fn StudentStatisticsPage() -> impl IntoView {
                             Ok(location) => {
                                 match record_session_attendance_fn(
                                     payload.clone(),
-                                    email.clone(),
                                     Some(location.latitude),
                                     Some(location.longitude),
                                     location.accuracy,
@@ -153,7 +151,7 @@ pub fn StudentStatisticsPage() -> impl IntoView {
         move || student_email.get(),
         |maybe_email| async move {
             match maybe_email {
-                Some(email) => get_student_stats_summary(email).await,
+                Some(_) => get_student_stats_summary().await,
                 None => Ok(empty_summary()),
             }
         },
@@ -163,7 +161,7 @@ pub fn StudentStatisticsPage() -> impl IntoView {
         move || student_email.get(),
         |maybe_email| async move {
             match maybe_email {
-                Some(email) => get_student_weekly_attendance(email).await,
+                Some(_) => get_student_weekly_attendance().await,
                 None => Ok(Vec::new()),
             }
         },
@@ -173,17 +171,21 @@ pub fn StudentStatisticsPage() -> impl IntoView {
         move || student_email.get(),
         |maybe_email| async move {
             match maybe_email {
-                Some(email) => get_student_module_breakdown(email).await,
+                Some(_) => get_student_module_breakdown().await,
                 None => Ok(Vec::new()),
             }
         },
     );
 
+    // Class the student is writing an excuse for, if the form is open
+    let excuse_target = RwSignal::new(None::<(i64, String)>);
+    let recent_refresh = RwSignal::new(0u32);
+
     let recent = Resource::new(
-        move || student_email.get(),
-        |maybe_email| async move {
+        move || (student_email.get(), recent_refresh.get()),
+        |(maybe_email, _)| async move {
             match maybe_email {
-                Some(email) => get_student_recent_activity(email).await,
+                Some(_) => get_student_recent_activity().await,
                 None => Ok(Vec::new()),
             }
         },
@@ -227,12 +229,18 @@ pub fn StudentStatisticsPage() -> impl IntoView {
                     {move || summary.get().map(|result| match result {
                         Ok(stats) => {
                             let weekly_sessions_text = if stats.week_recorded > 0 {
-                                format!("{} of {} sessions", stats.week_present, stats.week_recorded)
+                                with_late(
+                                    format!("{} of {} sessions", stats.week_present, stats.week_recorded),
+                                    stats.week_late,
+                                )
                             } else {
                                 "No sessions recorded this week".to_string()
                             };
                             let overall_sessions_text = if stats.total_recorded > 0 {
-                                format!("{} of {} sessions", stats.total_present, stats.total_recorded)
+                                with_late(
+                                    format!("{} of {} sessions", stats.total_present, stats.total_recorded),
+                                    stats.total_late,
+                                )
                             } else {
                                 "No recorded sessions yet".to_string()
                             };
@@ -463,6 +471,14 @@ pub fn StudentStatisticsPage() -> impl IntoView {
                                             .map(|entry| {
                                                 let (status_class, status_text) = format_status_label(&entry.status);
                                                 let label = format_recent_time(&entry.date, &entry.time);
+                                                let excuse_text = match entry.excuse_status.as_deref() {
+                                                    Some("pending") => " · Excuse pending",
+                                                    Some("rejected") => " · Excuse declined",
+                                                    _ => "",
+                                                };
+                                                let can_excuse = matches!(entry.status.as_str(), "absent" | "upcoming")
+                                                    && !matches!(entry.excuse_status.as_deref(), Some("pending") | Some("approved"));
+                                                let excuse_class = (entry.class_id, entry.title.clone());
                                                 view! {
                                                     <div class="activity-item">
                                                         <div class={format!("activity-icon {}", status_class)}>
@@ -490,8 +506,14 @@ pub fn StudentStatisticsPage() -> impl IntoView {
                                                         </div>
                                                         <div class="activity-content">
                                                             <h4>{entry.title.clone()}</h4>
-                                                            <p>{format!("{} · {}", label, status_text)}</p>
+                                                            <p>{format!("{} · {}{}", label, status_text, excuse_text)}</p>
                                                         </div>
+                                                        {can_excuse.then(|| view! {
+                                                            <button
+                                                                class="activity-excuse-button"
+                                                                on:click=move |_| excuse_target.set(Some(excuse_class.clone()))
+                                                            >"Excuse"</button>
+                                                        })}
                                                     </div>
                                                 }
                                             })
@@ -535,6 +557,19 @@ pub fn StudentStatisticsPage() -> impl IntoView {
                 <QrScanner on_scan=handle_scan on_close=handle_close_scanner/>
             </Show>
 
+            {move || excuse_target.get().map(|(class_id, class_title)| view! {
+                <ExcuseForm
+                    class_id=class_id
+                    class_title=class_title
+                    on_close=Callback::new(move |_| excuse_target.set(None))
+                    on_submitted=Callback::new(move |message: String| {
+                        excuse_target.set(None);
+                        feedback.set(Some((true, message)));
+                        recent_refresh.update(|n| *n = n.wrapping_add(1));
+                    })
+                />
+            })}
+
             {move || feedback.get().map(|(success, message)| {
                 let feedback_class = if success {
                     "feedback-message success"
@@ -551,14 +586,24 @@ pub fn StudentStatisticsPage() -> impl IntoView {
     }
 }
 
+fn with_late(text: String, late: i64) -> String {
+    if late > 0 {
+        format!("{} ({} late)", text, late)
+    } else {
+        text
+    }
+}
+
 fn empty_summary() -> StudentStatsSummary {
     StudentStatsSummary {
         overall_attendance_rate: 0.0,
         weekly_attendance_rate: 0.0,
         total_present: 0,
+        total_late: 0,
         total_recorded: 0,
         upcoming_classes: 0,
         week_present: 0,
+        week_late: 0,
         week_recorded: 0,
     }
 }
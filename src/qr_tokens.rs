@@ -0,0 +1,253 @@
+//! Rotating check-in tokens for class session QR codes.
+//!
+//! Every session has its own random signing key. The QR page asks the server for
+//! the token of the current time window, which looks like
+//! `session:{session_id}:class:{class_id}:w:{window}:{signature}`, where `window`
+//! is the Unix time divided by the session's rotation interval and `signature`
+//! is a truncated HMAC-SHA256 over the other fields. A token is accepted during
+//! its own window and the one after it, so a student whose scan lands just
+//! after a rotation (or whose request is slow) is not turned away.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DEFAULT_ROTATION_SECONDS: i64 = 30;
+pub const MIN_ROTATION_SECONDS: i64 = 5;
+pub const MAX_ROTATION_SECONDS: i64 = 300;
+
+/// Bytes of the HMAC kept in the token; keeps the QR code small enough to scan from the back row
+const SIGNATURE_BYTES: usize = 16;
+
+/// Rotation interval for sessions that don't choose one (`CLOCK_IT_QR_ROTATION_SECONDS`)
+pub fn default_rotation_seconds() -> i64 {
+    std::env::var("CLOCK_IT_QR_ROTATION_SECONDS")
+        .ok()
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .map(clamp_rotation_seconds)
+        .unwrap_or(DEFAULT_ROTATION_SECONDS)
+}
+
+pub fn clamp_rotation_seconds(seconds: i64) -> i64 {
+    seconds.clamp(MIN_ROTATION_SECONDS, MAX_ROTATION_SECONDS)
+}
+
+/// A fresh per-session signing key, hex encoded for storage
+pub fn generate_secret() -> String {
+    let bytes: [u8; 32] = rand::random();
+    hex::encode(bytes)
+}
+
+fn window_at(unix_seconds: i64, rotation_seconds: i64) -> i64 {
+    unix_seconds.div_euclid(rotation_seconds.max(1))
+}
+
+fn mac_for(secret: &str, session_id: i64, class_id: i64, window: i64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}:{}:{}", session_id, class_id, window).as_bytes());
+    mac
+}
+
+/// The QR payload for the window containing `now_unix`, and how many seconds it has left
+pub fn issue(
+    secret: &str,
+    session_id: i64,
+    class_id: i64,
+    rotation_seconds: i64,
+    now_unix: i64,
+) -> (String, i64) {
+    let rotation_seconds = rotation_seconds.max(1);
+    let window = window_at(now_unix, rotation_seconds);
+    let tag = mac_for(secret, session_id, class_id, window).finalize().into_bytes();
+    let payload = format!(
+        "session:{}:class:{}:w:{}:{}",
+        session_id,
+        class_id,
+        window,
+        hex::encode(&tag[..SIGNATURE_BYTES])
+    );
+    let expires_in = (window + 1) * rotation_seconds - now_unix;
+    (payload, expires_in)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedToken {
+    pub session_id: i64,
+    pub class_id: i64,
+    window: i64,
+    signature: Vec<u8>,
+}
+
+/// Split a scanned payload into its fields; `None` if it isn't a check-in token at all
+pub fn parse(payload: &str) -> Option<ParsedToken> {
+    let parts: Vec<&str> = payload.trim().split(':').collect();
+    if parts.len() != 7 || parts[0] != "session" || parts[2] != "class" || parts[4] != "w" {
+        return None;
+    }
+
+    let signature = hex::decode(parts[6]).ok()?;
+    if signature.len() != SIGNATURE_BYTES {
+        return None;
+    }
+
+    Some(ParsedToken {
+        session_id: parts[1].parse().ok()?,
+        class_id: parts[3].parse().ok()?,
+        window: parts[5].parse().ok()?,
+        signature,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenCheck {
+    Valid,
+    Expired,
+    Forged,
+}
+
+/// Check the signature against the session's key, then that the token is from the
+/// current window or the one just before it
+pub fn verify(
+    secret: &str,
+    token: &ParsedToken,
+    rotation_seconds: i64,
+    now_unix: i64,
+) -> TokenCheck {
+    let mac = mac_for(secret, token.session_id, token.class_id, token.window);
+    if mac.verify_truncated_left(&token.signature).is_err() {
+        return TokenCheck::Forged;
+    }
+
+    let current = window_at(now_unix, rotation_seconds);
+    if token.window > current {
+        // Only the server mints tokens, so a future window can't be legitimate
+        TokenCheck::Forged
+    } else if current - token.window <= 1 {
+        TokenCheck::Valid
+    } else {
+        TokenCheck::Expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0f1e2d3c4b5a69788796a5b4c3d2e1f00f1e2d3c4b5a69788796a5b4c3d2e1f0";
+    const ROTATION: i64 = 30;
+    /// The start of a window, so offsets below read as seconds into it
+    const NOW: i64 = 1_741_590_000;
+
+    fn token_at(now_unix: i64) -> ParsedToken {
+        let (payload, _) = issue(SECRET, 7, 42, ROTATION, now_unix);
+        parse(&payload).expect("issued tokens parse")
+    }
+
+    #[test]
+    fn issued_token_round_trips_and_counts_down() {
+        let (payload, expires_in) = issue(SECRET, 7, 42, ROTATION, NOW + 10);
+        let token = parse(&payload).unwrap();
+
+        assert!(payload.starts_with("session:7:class:42:w:"));
+        assert_eq!((token.session_id, token.class_id), (7, 42));
+        assert_eq!(expires_in, 20);
+    }
+
+    #[test]
+    fn token_is_valid_in_its_own_window() {
+        let token = token_at(NOW);
+
+        assert_eq!(verify(SECRET, &token, ROTATION, NOW), TokenCheck::Valid);
+        assert_eq!(
+            verify(SECRET, &token, ROTATION, NOW + 29),
+            TokenCheck::Valid
+        );
+    }
+
+    #[test]
+    fn previous_window_is_accepted_as_grace() {
+        let token = token_at(NOW);
+
+        // Scanned just before the rotation, checked just after it
+        assert_eq!(
+            verify(SECRET, &token, ROTATION, NOW + 31),
+            TokenCheck::Valid
+        );
+        assert_eq!(
+            verify(SECRET, &token, ROTATION, NOW + 59),
+            TokenCheck::Valid
+        );
+    }
+
+    #[test]
+    fn token_expires_two_windows_on() {
+        let token = token_at(NOW);
+
+        assert_eq!(
+            verify(SECRET, &token, ROTATION, NOW + 60),
+            TokenCheck::Expired
+        );
+        assert_eq!(
+            verify(SECRET, &token, ROTATION, NOW + 3600),
+            TokenCheck::Expired
+        );
+    }
+
+    #[test]
+    fn token_from_a_future_window_is_refused() {
+        let token = token_at(NOW + ROTATION);
+
+        assert_eq!(verify(SECRET, &token, ROTATION, NOW), TokenCheck::Forged);
+    }
+
+    #[test]
+    fn tampered_signature_is_refused() {
+        let (payload, _) = issue(SECRET, 7, 42, ROTATION, NOW);
+        let (fields, signature) = payload.rsplit_once(':').unwrap();
+        let flipped = if signature.starts_with('0') { "1" } else { "0" };
+        let tampered = parse(&format!("{}:{}{}", fields, flipped, &signature[1..])).unwrap();
+
+        assert_eq!(verify(SECRET, &tampered, ROTATION, NOW), TokenCheck::Forged);
+    }
+
+    #[test]
+    fn token_only_works_for_its_own_session() {
+        let (payload, _) = issue(SECRET, 7, 42, ROTATION, NOW);
+
+        // Another session has its own key
+        let token = parse(&payload).unwrap();
+        let other_secret = generate_secret();
+        assert_eq!(
+            verify(&other_secret, &token, ROTATION, NOW),
+            TokenCheck::Forged
+        );
+
+        // Pointing the token at another session or class breaks the signature
+        let moved = parse(&payload.replacen("session:7:", "session:8:", 1)).unwrap();
+        assert_eq!(verify(SECRET, &moved, ROTATION, NOW), TokenCheck::Forged);
+        let moved = parse(&payload.replacen(":class:42:", ":class:43:", 1)).unwrap();
+        assert_eq!(verify(SECRET, &moved, ROTATION, NOW), TokenCheck::Forged);
+    }
+
+    #[test]
+    fn malformed_payloads_do_not_parse() {
+        let (payload, _) = issue(SECRET, 7, 42, ROTATION, NOW);
+        let (fields, signature) = payload.rsplit_once(':').unwrap();
+
+        for bad in [
+            "",
+            "https://example.com/checkin",
+            "session:7:class:42",
+            "session:x:class:42:w:1:00",
+            &payload.replacen("session:", "lesson:", 1),
+            &format!("{}:{}", fields, "zz"),
+            // A signature shortened by a byte
+            &format!("{}:{}", fields, &signature[2..]),
+            &format!("{}:extra", payload),
+        ] {
+            assert_eq!(parse(bad), None, "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,382 @@
+use crate::components::UniversityTimezonePanel;
+use crate::database::calendar::{
+    AcademicCalendar, AcademicTerm, BlackoutDate, BlackoutKind, BlackoutRequest, TermRequest,
+};
+use crate::error::ClockItError;
+use crate::routes::calendar_functions::{
+    delete_blackout_fn, delete_term_fn, get_academic_calendar_fn, save_blackout_fn, save_term_fn,
+    CalendarResponse,
+};
+use crate::user_context::get_current_user;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::components::A;
+
+/// Teaching terms, and the public holidays and recess weeks recurring classes avoid
+#[component]
+pub fn AcademicCalendarPage() -> impl IntoView {
+    let current_user = get_current_user();
+    let can_edit = Signal::derive(move || {
+        current_user
+            .get()
+            .map(|user| user.role == "lecturer")
+            .unwrap_or(false)
+    });
+
+    let calendar = RwSignal::new(None::<AcademicCalendar>);
+    let message = RwSignal::new(String::new());
+    let success = RwSignal::new(false);
+    let busy = RwSignal::new(false);
+
+    let editing_term = RwSignal::new(None::<i64>);
+    let term_name = RwSignal::new(String::new());
+    let term_start = RwSignal::new(String::new());
+    let term_end = RwSignal::new(String::new());
+
+    let editing_blackout = RwSignal::new(None::<i64>);
+    let blackout_name = RwSignal::new(String::new());
+    let blackout_kind = RwSignal::new("holiday".to_string());
+    let blackout_start = RwSignal::new(String::new());
+    let blackout_end = RwSignal::new(String::new());
+
+    let clear_term_form = move || {
+        editing_term.set(None);
+        for field in [term_name, term_start, term_end] {
+            field.set(String::new());
+        }
+    };
+
+    let clear_blackout_form = move || {
+        editing_blackout.set(None);
+        blackout_kind.set("holiday".to_string());
+        for field in [blackout_name, blackout_start, blackout_end] {
+            field.set(String::new());
+        }
+    };
+
+    let handle_response = move |result: Result<CalendarResponse, ClockItError>| {
+        busy.set(false);
+        match result {
+            Ok(response) => {
+                message.set(response.message);
+                success.set(response.success);
+                if response.success {
+                    calendar.set(Some(response.calendar));
+                    clear_term_form();
+                    clear_blackout_form();
+                }
+            }
+            Err(e) => {
+                message.set(e.to_string());
+                success.set(false);
+            }
+        }
+    };
+
+    Effect::new(move |_| {
+        spawn_local(async move {
+            match get_academic_calendar_fn().await {
+                Ok(response) => calendar.set(Some(response.calendar)),
+                Err(e) => {
+                    calendar.set(Some(AcademicCalendar::default()));
+                    message.set(e.to_string());
+                }
+            }
+        });
+    });
+
+    let edit_term = move |term: AcademicTerm| {
+        editing_term.set(Some(term.term_id));
+        term_name.set(term.name);
+        term_start.set(term.start_date);
+        term_end.set(term.end_date);
+        message.set(String::new());
+    };
+
+    let edit_blackout = move |blackout: BlackoutDate| {
+        editing_blackout.set(Some(blackout.blackout_id));
+        blackout_name.set(blackout.name);
+        blackout_kind.set(blackout.kind.as_str().to_string());
+        blackout_start.set(blackout.start_date);
+        blackout_end.set(blackout.end_date);
+        message.set(String::new());
+    };
+
+    let on_save_term = move |_| {
+        let request = TermRequest {
+            name: term_name.get().trim().to_string(),
+            start_date: term_start.get(),
+            end_date: term_end.get(),
+        };
+        if let Err(e) = request.validate() {
+            message.set(e);
+            success.set(false);
+            return;
+        }
+        let term_id = editing_term.get();
+        busy.set(true);
+        spawn_local(async move { handle_response(save_term_fn(term_id, request).await) });
+    };
+
+    let on_save_blackout = move |_| {
+        // A single day only needs its first day filled in
+        let start_date = blackout_start.get();
+        let end_date = Some(blackout_end.get())
+            .filter(|end| !end.trim().is_empty())
+            .unwrap_or_else(|| start_date.clone());
+        let request = BlackoutRequest {
+            name: blackout_name.get().trim().to_string(),
+            kind: BlackoutKind::parse(&blackout_kind.get()),
+            start_date,
+            end_date,
+        };
+        if let Err(e) = request.validate() {
+            message.set(e);
+            success.set(false);
+            return;
+        }
+        let blackout_id = editing_blackout.get();
+        busy.set(true);
+        spawn_local(async move { handle_response(save_blackout_fn(blackout_id, request).await) });
+    };
+
+    let on_import_file = move |ev: leptos::ev::Event| {
+        #[cfg(feature = "hydrate")]
+        {
+            use crate::routes::calendar_functions::import_holidays_fn;
+            use wasm_bindgen::JsCast;
+
+            let Some(input) = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let file = gloo::file::File::from(file);
+            busy.set(true);
+            spawn_local(async move {
+                match gloo::file::futures::read_as_text(&file).await {
+                    Ok(text) => handle_response(import_holidays_fn(text).await),
+                    Err(e) => {
+                        busy.set(false);
+                        message.set(format!("Could not read file: {}", e));
+                        success.set(false);
+                    }
+                }
+            });
+        }
+
+        #[cfg(not(feature = "hydrate"))]
+        let _ = ev;
+    };
+
+    let date_range = |start: &str, end: &str| {
+        if start == end {
+            start.to_string()
+        } else {
+            format!("{} – {}", start, end)
+        }
+    };
+
+    view! {
+        <section class="calendar-page">
+            <div class="page-header" style="display:flex;align-items:center;gap:8px;">
+                <A href="/home" attr:class="link">"←"</A>
+                <h1 class="page-title">"Academic Calendar"</h1>
+            </div>
+            <p class="muted" style="margin-bottom:12px;">
+                "Repeating classes skip, or move off, holidays and recess days. Classes held on them don't count towards attendance statistics."
+            </p>
+
+            <Show when=move || !message.get().is_empty()>
+                <p class=move || if success.get() { "success center" } else { "error center" } style="margin-bottom:12px;">
+                    {message}
+                </p>
+            </Show>
+
+            <UniversityTimezonePanel can_edit=can_edit/>
+
+            <div class="card" style="margin-bottom:16px;">
+                <h3 class="heading">"Terms"</h3>
+
+                <Show when=move || can_edit.get()>
+                    <div style="display:flex; gap:12px; align-items:flex-end; margin-bottom:12px;">
+                        <div style="flex:2;">
+                            <label class="label">"Name"</label>
+                            <input class="input" placeholder="e.g., Semester 1 2025" bind:value=term_name/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"First day"</label>
+                            <input class="input" type="date" bind:value=term_start/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Last day"</label>
+                            <input class="input" type="date" bind:value=term_end/>
+                        </div>
+                        <button class="btn btn-accent" on:click=on_save_term disabled=move || busy.get()>
+                            {move || if editing_term.get().is_some() { "Save Term" } else { "Add Term" }}
+                        </button>
+                        <Show when=move || editing_term.get().is_some()>
+                            <button class="btn btn-outline" on:click=move |_| clear_term_form()>"Cancel"</button>
+                        </Show>
+                    </div>
+                </Show>
+
+                {move || calendar.get().map(|calendar| {
+                    if calendar.terms.is_empty() {
+                        return view! { <p class="muted">"No terms saved yet."</p> }.into_any();
+                    }
+                    view! {
+                        <table class="table">
+                            <thead>
+                                <tr>
+                                    <th>"Term"</th>
+                                    <th>"Dates"</th>
+                                    <Show when=move || can_edit.get()>
+                                        <th>"Action"</th>
+                                    </Show>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {calendar.terms.into_iter().map(|term| {
+                                    let term_id = term.term_id;
+                                    let dates = date_range(&term.start_date, &term.end_date);
+                                    let name = term.name.clone();
+                                    let term = StoredValue::new(term);
+                                    view! {
+                                        <tr>
+                                            <td>{name}</td>
+                                            <td>{dates}</td>
+                                            <Show when=move || can_edit.get()>
+                                                <td style="display:flex; gap:6px;">
+                                                    <button
+                                                        class="btn btn-outline btn-small"
+                                                        on:click=move |_| edit_term(term.get_value())
+                                                    >"Edit"</button>
+                                                    <button
+                                                        class="btn btn-outline btn-small"
+                                                        style="color:#ef4444; border-color:#fecaca;"
+                                                        disabled=move || busy.get()
+                                                        on:click=move |_| {
+                                                            busy.set(true);
+                                                            spawn_local(async move { handle_response(delete_term_fn(term_id).await) });
+                                                        }
+                                                    >"🗑 Delete"</button>
+                                                </td>
+                                            </Show>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }.into_any()
+                })}
+            </div>
+
+            <div class="card">
+                <h3 class="heading">"Holidays and recess"</h3>
+
+                <Show when=move || can_edit.get()>
+                    <div style="display:flex; gap:12px; align-items:flex-end; margin-bottom:8px;">
+                        <div style="flex:2;">
+                            <label class="label">"Name"</label>
+                            <input class="input" placeholder="e.g., Good Friday" bind:value=blackout_name/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Type"</label>
+                            <select class="input" bind:value=blackout_kind>
+                                <option value="holiday">{BlackoutKind::Holiday.label()}</option>
+                                <option value="recess">{BlackoutKind::Recess.label()}</option>
+                                <option value="other">{BlackoutKind::Other.label()}</option>
+                            </select>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"First day"</label>
+                            <input class="input" type="date" bind:value=blackout_start/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Last day"</label>
+                            <input class="input" type="date" bind:value=blackout_end/>
+                        </div>
+                        <button class="btn btn-accent" on:click=on_save_blackout disabled=move || busy.get()>
+                            {move || if editing_blackout.get().is_some() { "Save" } else { "Add" }}
+                        </button>
+                        <Show when=move || editing_blackout.get().is_some()>
+                            <button class="btn btn-outline" on:click=move |_| clear_blackout_form()>"Cancel"</button>
+                        </Show>
+                    </div>
+
+                    <label class="label" style="margin-top:6px;">"Import public holidays (.ics)"</label>
+                    <input
+                        class="input"
+                        type="file"
+                        accept=".ics,text/calendar"
+                        disabled=move || busy.get()
+                        on:change=on_import_file
+                    />
+                    <p class="muted" style="margin:4px 0 12px; font-size:0.85rem;">
+                        "Importing the same file again updates its holidays rather than adding them twice."
+                    </p>
+                </Show>
+
+                {move || calendar.get().map(|calendar| {
+                    if calendar.blackouts.is_empty() {
+                        return view! { <p class="muted">"No holidays or recess saved yet."</p> }.into_any();
+                    }
+                    view! {
+                        <table class="table">
+                            <thead>
+                                <tr>
+                                    <th>"Name"</th>
+                                    <th>"Type"</th>
+                                    <th>"Dates"</th>
+                                    <Show when=move || can_edit.get()>
+                                        <th>"Action"</th>
+                                    </Show>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {calendar.blackouts.into_iter().map(|blackout| {
+                                    let blackout_id = blackout.blackout_id;
+                                    let dates = date_range(&blackout.start_date, &blackout.end_date);
+                                    let name = blackout.name.clone();
+                                    let kind = blackout.kind.label();
+                                    let blackout = StoredValue::new(blackout);
+                                    view! {
+                                        <tr>
+                                            <td>{name}</td>
+                                            <td>{kind}</td>
+                                            <td>{dates}</td>
+                                            <Show when=move || can_edit.get()>
+                                                <td style="display:flex; gap:6px;">
+                                                    <button
+                                                        class="btn btn-outline btn-small"
+                                                        on:click=move |_| edit_blackout(blackout.get_value())
+                                                    >"Edit"</button>
+                                                    <button
+                                                        class="btn btn-outline btn-small"
+                                                        style="color:#ef4444; border-color:#fecaca;"
+                                                        disabled=move || busy.get()
+                                                        on:click=move |_| {
+                                                            busy.set(true);
+                                                            spawn_local(async move { handle_response(delete_blackout_fn(blackout_id).await) });
+                                                        }
+                                                    >"🗑 Delete"</button>
+                                                </td>
+                                            </Show>
+                                        </tr>
+                                    }
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                    }.into_any()
+                })}
+            </div>
+        </section>
+    }
+}
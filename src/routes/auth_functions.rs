@@ -1,121 +1,128 @@
 #[cfg(feature = "ssr")]
+use crate::clock::clock;
+#[cfg(feature = "ssr")]
 use crate::database::models::User;
 #[cfg(feature = "ssr")]
-use crate::database::{
-    authenticate_user, create_user, init_db_pool, update_user_password_by_email, CreateUserRequest,
-};
-use crate::types::{AuthResponse, BasicResponse, RegisterData};
-use leptos::prelude::*;
+use crate::email::{send_email, OutgoingEmail};
 #[cfg(feature = "ssr")]
-use std::collections::HashMap;
+use crate::error::db_error;
 #[cfg(feature = "ssr")]
-use std::sync::Mutex;
+use crate::session::{current_user, sign_in, sign_out};
 #[cfg(feature = "ssr")]
-use rand::Rng;
+use crate::database::{
+    accept_invitations, authenticate_user, consume_verified_otp, create_user, db_pool, discard_otp, issue_otp,
+    update_user_password_by_email, verify_otp_code, CreateUserRequest, OtpIssue,
+    OtpVerification,
+};
+use crate::error::ClockItError;
+use crate::types::{AuthResponse, BasicResponse, OtpPurpose, RegisterData, UserProfile};
+use leptos::prelude::*;
 
 #[server(RegisterUser, "/api")]
-pub async fn register_user(data: RegisterData) -> Result<AuthResponse, ServerFnError> {
+pub async fn register_user(data: RegisterData) -> Result<AuthResponse, ClockItError> {
     // Validate input
     if data.name.trim().is_empty() {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Name is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Name is required".to_string()));
     }
 
     if data.surname.trim().is_empty() {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Surname is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Surname is required".to_string()));
     }
 
     if data.email.trim().is_empty() {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Email is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
     if data.password.len() < 6 {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Password must be at least 6 characters".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation(
+            "Password must be at least 6 characters".to_string(),
+        ));
     }
 
     if data.password != data.confirm_password {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Passwords do not match".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation(
+            "Passwords do not match".to_string(),
+        ));
     }
 
     if !["lecturer", "tutor", "student"].contains(&data.role.as_str()) {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Invalid role selected".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation(
+            "Invalid role selected".to_string(),
+        ));
     }
 
     // Initialize database connection
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
+
+    let email = data.email.trim().to_lowercase();
+
+    // The email must have been confirmed with a code first
+    let verified = consume_verified_otp(&pool, &email, OtpPurpose::Signup, &*clock()).await?;
+    if !verified {
+        return Err(ClockItError::Forbidden(
+            "Please verify your email address before creating an account".to_string(),
+        ));
+    }
 
     // Create user request
     let create_request = CreateUserRequest {
         name: data.name.trim().to_string(),
         surname: data.surname.trim().to_string(),
-        email: data.email.trim().to_lowercase(),
+        email,
         password: data.password,
         role: data.role,
     };
 
     // Create user
-    match create_user(&pool, create_request).await {
-        Ok(user) => Ok(AuthResponse {
-            success: true,
-            message: "Account created successfully!".to_string(),
-            user: Some(user),
-        }),
-        Err(e) => Ok(AuthResponse {
-            success: false,
-            message: e,
-            user: None,
-        }),
-    }
+    let user = create_user(&pool, create_request).await?;
+
+    // Modules the lecturer imported this email into before it had an account. The
+    // account exists by now, so a failure here doesn't fail the sign-up; the
+    // invitations are left waiting for the lecturer to enroll the student by hand.
+    let joined = if user.role == "student" {
+        match accept_invitations(&pool, &user.email_address, &*clock()).await {
+            Ok(joined) => joined,
+            Err(e) => {
+                eprintln!(
+                    "❌ Failed to enroll {} in the modules they were invited to: {}",
+                    user.email_address, e
+                );
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let message = if joined.is_empty() {
+        "Account created successfully!".to_string()
+    } else {
+        format!(
+            "Account created successfully! You've been enrolled in {}.",
+            joined.join(", ")
+        )
+    };
+
+    Ok(AuthResponse {
+        success: true,
+        message,
+        user: Some(user),
+    })
 }
 
 #[server(LoginUser, "/api")]
-pub async fn login_user(email: String, password: String) -> Result<AuthResponse, ServerFnError> {
+pub async fn login_user(email: String, password: String) -> Result<AuthResponse, ClockItError> {
     // Validate input
     if email.trim().is_empty() {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Email is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
     if password.trim().is_empty() {
-        return Ok(AuthResponse {
-            success: false,
-            message: "Password is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Password is required".to_string()));
     }
 
     // Initialize database connection
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
 
     let identifier = email.trim();
     let candidate_email = if identifier.contains('@') {
@@ -123,55 +130,58 @@ pub async fn login_user(email: String, password: String) -> Result<AuthResponse,
     } else {
         let digits: String = identifier.chars().filter(|c| c.is_ascii_digit()).collect();
         if digits.is_empty() {
-            return Ok(AuthResponse {
-                success: false,
-                message: "Please enter a valid email address or student ID".to_string(),
-                user: None,
-            });
+            return Err(ClockItError::Validation(
+                "Please enter a valid email address or student ID".to_string(),
+            ));
         }
 
-        let student_id = match digits.parse::<i64>() {
-            Ok(id) => id,
-            Err(_) => {
-                return Ok(AuthResponse {
-                    success: false,
-                    message: "Invalid student ID format".to_string(),
-                    user: None,
-                });
-            }
-        };
+        let student_id = digits
+            .parse::<i64>()
+            .map_err(|_| ClockItError::Validation("Invalid student ID format".to_string()))?;
 
         let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE userID = ?")
             .bind(student_id)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            .map_err(db_error("Database error"))?;
 
         match user {
             Some(u) => u.email_address.to_lowercase(),
             None => {
-                return Ok(AuthResponse {
-                    success: false,
-                    message: "No account found with that student ID".to_string(),
-                    user: None,
-                });
+                return Err(ClockItError::Unauthorized(
+                    "No account found with that student ID".to_string(),
+                ));
             }
         }
     };
 
     // Authenticate user
-    match authenticate_user(&pool, &candidate_email, &password).await {
-        Ok(user) => Ok(AuthResponse {
-            success: true,
-            message: "Login successful!".to_string(),
-            user: Some(user),
-        }),
-        Err(e) => Ok(AuthResponse {
-            success: false,
-            message: e,
-            user: None,
-        }),
-    }
+    let user = authenticate_user(&pool, &candidate_email, &password).await?;
+    sign_in(&user).await?;
+
+    Ok(AuthResponse {
+        success: true,
+        message: "Login successful!".to_string(),
+        user: Some(user),
+    })
+}
+
+#[server(LogoutUser, "/api")]
+pub async fn logout_user() -> Result<BasicResponse, ClockItError> {
+    sign_out().await?;
+
+    Ok(BasicResponse {
+        success: true,
+        message: "Signed out".to_string(),
+    })
+}
+
+/// The user attached to the caller's session, used to restore the client-side user state
+#[server(GetSessionUser, "/api")]
+pub async fn get_session_user() -> Result<Option<UserProfile>, ClockItError> {
+    let pool = db_pool()?;
+
+    current_user(&pool).await
 }
 
 #[server(ResetPassword, "/api")]
@@ -179,60 +189,52 @@ pub async fn reset_password(
     email: String,
     new_password: String,
     confirm_password: String,
-) -> Result<BasicResponse, ServerFnError> {
+) -> Result<BasicResponse, ClockItError> {
     if email.trim().is_empty() {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Email is required".to_string(),
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
     if new_password.len() < 6 {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Password must be at least 6 characters".to_string(),
-        });
+        return Err(ClockItError::Validation(
+            "Password must be at least 6 characters".to_string(),
+        ));
     }
 
     if new_password != confirm_password {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Passwords do not match".to_string(),
-        });
+        return Err(ClockItError::Validation(
+            "Passwords do not match".to_string(),
+        ));
     }
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
 
-    match update_user_password_by_email(&pool, &email.trim().to_lowercase(), &new_password).await {
-        Ok(_) => Ok(BasicResponse {
-            success: true,
-            message: "Password updated successfully. You can now sign in with your new password."
-                .to_string(),
-        }),
-        Err(e) => Ok(BasicResponse {
-            success: false,
-            message: e,
-        }),
+    let email = email.trim().to_lowercase();
+
+    // Signed-in users may change their own password directly; everyone else
+    // needs a verified reset code for the account
+    let own_account = current_user(&pool)
+        .await?
+        .is_some_and(|user| user.email_address.to_lowercase() == email);
+    if !own_account {
+        let verified = consume_verified_otp(&pool, &email, OtpPurpose::PasswordReset, &*clock()).await?;
+        if !verified {
+            return Err(ClockItError::Forbidden(
+                "Please verify the reset code sent to your email first".to_string(),
+            ));
+        }
     }
-}
 
-// Simple in-memory OTP storage (in production, use Redis or database)
-#[cfg(feature = "ssr")]
-lazy_static::lazy_static! {
-    static ref OTP_STORE: Mutex<HashMap<String, (String, std::time::SystemTime)>> = Mutex::new(HashMap::new());
+    update_user_password_by_email(&pool, &email, &new_password).await?;
+
+    Ok(BasicResponse {
+        success: true,
+        message: "Password updated successfully. You can now sign in with your new password."
+            .to_string(),
+    })
 }
 
 #[cfg(feature = "ssr")]
 async fn send_email_otp(to_email: &str, otp: &str) -> Result<(), String> {
-    let resend_api_key = std::env::var("RESEND_API_KEY")
-        .map_err(|_| "RESEND_API_KEY environment variable not set")?;
-    let from_email = std::env::var("RESEND_FROM_EMAIL")
-        .unwrap_or_else(|_| "onboarding@resend.dev".to_string());
-
-    let client = reqwest::Client::new();
-    
     let html_body = format!(
         r#"
         <div style="font-family: Arial, sans-serif; max-width: 600px; margin: 0 auto; padding: 20px;">
@@ -264,43 +266,16 @@ async fn send_email_otp(to_email: &str, otp: &str) -> Result<(), String> {
         "#, otp
     );
 
-    let body = serde_json::json!({
-        "from": from_email,
-        "to": [to_email],
-        "subject": "Your Clock It Verification Code",
-        "html": html_body
-    });
-
-    println!("📧 Sending email via Resend to: {}", to_email);
-
-    let response = client
-        .post("https://api.resend.com/emails")
-        .header("Authorization", format!("Bearer {}", resend_api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if response.status().is_success() {
-        println!("✅ Email sent successfully via Resend");
-        Ok(())
-    } else {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("Resend API error: {}", error_text))
-    }
+    send_email(&OutgoingEmail {
+        to: to_email.to_string(),
+        subject: "Your Clock It Verification Code".to_string(),
+        html: html_body,
+    })
+    .await
 }
 
 #[cfg(feature = "ssr")]
 async fn send_password_reset_email(to_email: &str, otp: &str) -> Result<(), String> {
-    let resend_api_key = std::env::var("RESEND_API_KEY")
-        .map_err(|_| "RESEND_API_KEY environment variable not set")?;
-    let from_email = std::env::var("RESEND_FROM_EMAIL")
-        .unwrap_or_else(|_| "onboarding@resend.dev".to_string());
-
-    let client = reqwest::Client::new();
-    
     let html_body = format!(
         r#"
         <div style="font-family: Arial, sans-serif; max-width: 600px; margin: 0 auto; padding: 20px;">
@@ -332,58 +307,29 @@ async fn send_password_reset_email(to_email: &str, otp: &str) -> Result<(), Stri
         "#, otp
     );
 
-    let body = serde_json::json!({
-        "from": from_email,
-        "to": [to_email],
-        "subject": "Reset Your Clock It Password",
-        "html": html_body
-    });
-
-    println!("📧 Sending password reset email via Resend to: {}", to_email);
-
-    let response = client
-        .post("https://api.resend.com/emails")
-        .header("Authorization", format!("Bearer {}", resend_api_key))
-        .header("Content-Type", "application/json")
-        .json(&body)
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
-
-    if response.status().is_success() {
-        println!("✅ Password reset email sent successfully via Resend");
-        Ok(())
-    } else {
-        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-        Err(format!("Resend API error: {}", error_text))
-    }
+    send_email(&OutgoingEmail {
+        to: to_email.to_string(),
+        subject: "Reset Your Clock It Password".to_string(),
+        html: html_body,
+    })
+    .await
 }
 
 #[server(SendOTP, "/api")]
-pub async fn send_otp(email: String) -> Result<BasicResponse, ServerFnError> {
+pub async fn send_otp(email: String) -> Result<BasicResponse, ClockItError> {
     if email.trim().is_empty() {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Email is required".to_string(),
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
     let email = email.trim().to_lowercase();
-    
-    // Generate 6-digit OTP (thread-safe)
-    let otp: String = {
-        let mut rng = rand::thread_rng();
-        (0..6).map(|_| rng.gen_range(0..10).to_string()).collect()
+
+    let pool = db_pool()?;
+
+    let otp = match issue_otp(&pool, &email, OtpPurpose::Signup, &*clock()).await? {
+        OtpIssue::Issued(code) => code,
+        OtpIssue::Cooldown(seconds) => return Err(cooldown_error(seconds)),
     };
-    
-    // Store OTP with 5-minute expiry
-    let expiry = std::time::SystemTime::now() + std::time::Duration::from_secs(300);
-    {
-        let mut store = OTP_STORE.lock().unwrap();
-        store.insert(email.clone(), (otp.clone(), expiry));
-    }
-    
+
     // Send email with OTP
     match send_email_otp(&email, &otp).await {
         Ok(()) => {
@@ -397,96 +343,83 @@ pub async fn send_otp(email: String) -> Result<BasicResponse, ServerFnError> {
             println!("❌ Failed to send OTP email to {}: {}", email, e);
             // For development, still log the OTP so you can test
             println!("🔑 OTP for testing: {}", otp);
-            Ok(BasicResponse {
-                success: false,
-                message: format!("Failed to send email: {}. Please try again.", e),
-            })
+            // Don't hold the caller to the resend cooldown for an email that never went out
+            discard_otp(&pool, &email, OtpPurpose::Signup).await?;
+            Err(ClockItError::Internal(format!(
+                "Failed to send email: {}. Please try again.",
+                e
+            )))
         }
     }
 }
 
 #[server(VerifyOTP, "/api")]
-pub async fn verify_otp(email: String, otp: String) -> Result<BasicResponse, ServerFnError> {
+pub async fn verify_otp(
+    email: String,
+    otp: String,
+    purpose: OtpPurpose,
+) -> Result<BasicResponse, ClockItError> {
     if email.trim().is_empty() || otp.trim().is_empty() {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Email and OTP are required".to_string(),
-        });
+        return Err(ClockItError::Validation(
+            "Email and OTP are required".to_string(),
+        ));
     }
 
     let email = email.trim().to_lowercase();
     let otp = otp.trim();
-    
-    let mut store = OTP_STORE.lock().unwrap();
-    
-    if let Some((stored_otp, expiry)) = store.get(&email) {
-        // Check if OTP is expired
-        if std::time::SystemTime::now() > *expiry {
-            store.remove(&email);
-            return Ok(BasicResponse {
-                success: false,
-                message: "OTP has expired. Please request a new one.".to_string(),
-            });
-        }
-        
-        // Check if OTP matches
-        if stored_otp == otp {
-            store.remove(&email); // Remove used OTP
-            return Ok(BasicResponse {
-                success: true,
-                message: "OTP verified successfully!".to_string(),
-            });
-        }
+
+    let pool = db_pool()?;
+
+    match verify_otp_code(&pool, &email, purpose, otp, &*clock()).await? {
+        OtpVerification::Verified => Ok(BasicResponse {
+            success: true,
+            message: "OTP verified successfully!".to_string(),
+        }),
+        OtpVerification::Invalid { attempts_left } => Err(ClockItError::Validation(format!(
+            "Invalid OTP. {} attempt{} left.",
+            attempts_left,
+            if attempts_left == 1 { "" } else { "s" }
+        ))),
+        OtpVerification::Expired => Err(ClockItError::Validation(
+            "OTP has expired. Please request a new one.".to_string(),
+        )),
+        OtpVerification::TooManyAttempts => Err(ClockItError::RateLimited(
+            "Too many incorrect attempts. Please request a new code.".to_string(),
+        )),
+        OtpVerification::NotFound => Err(ClockItError::NotFound(
+            "No active code for this email. Please request a new one.".to_string(),
+        )),
     }
-    
-    Ok(BasicResponse {
-        success: false,
-        message: "Invalid OTP. Please try again.".to_string(),
-    })
 }
 
 #[server(SendPasswordResetOTP, "/api")]
-pub async fn send_password_reset_otp(email: String) -> Result<BasicResponse, ServerFnError> {
+pub async fn send_password_reset_otp(email: String) -> Result<BasicResponse, ClockItError> {
     if email.trim().is_empty() {
-        return Ok(BasicResponse {
-            success: false,
-            message: "Email is required".to_string(),
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
     let email = email.trim().to_lowercase();
     
     // Check if user exists
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
     
     let user_exists = sqlx::query("SELECT emailAddress FROM users WHERE emailAddress = ?")
         .bind(&email)
         .fetch_optional(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+        .map_err(db_error("Database error"))?;
     
     if user_exists.is_none() {
-        return Ok(BasicResponse {
-            success: false,
-            message: "No account found with that email address".to_string(),
-        });
+        return Err(ClockItError::NotFound(
+            "No account found with that email address".to_string(),
+        ));
     }
     
-    // Generate 6-digit OTP (thread-safe)
-    let otp: String = {
-        let mut rng = rand::thread_rng();
-        (0..6).map(|_| rng.gen_range(0..10).to_string()).collect()
+    let otp = match issue_otp(&pool, &email, OtpPurpose::PasswordReset, &*clock()).await? {
+        OtpIssue::Issued(code) => code,
+        OtpIssue::Cooldown(seconds) => return Err(cooldown_error(seconds)),
     };
-    
-    // Store OTP with 5-minute expiry
-    let expiry = std::time::SystemTime::now() + std::time::Duration::from_secs(300);
-    {
-        let mut store = OTP_STORE.lock().unwrap();
-        store.insert(email.clone(), (otp.clone(), expiry));
-    }
-    
+
     // Send password reset email
     match send_password_reset_email(&email, &otp).await {
         Ok(()) => {
@@ -500,10 +433,19 @@ pub async fn send_password_reset_otp(email: String) -> Result<BasicResponse, Ser
             println!("❌ Failed to send password reset OTP email to {}: {}", email, e);
             // For development, still log the OTP so you can test
             println!("🔑 Password reset OTP for testing: {}", otp);
-            Ok(BasicResponse {
-                success: false,
-                message: format!("Failed to send email: {}. Please try again.", e),
-            })
+            discard_otp(&pool, &email, OtpPurpose::PasswordReset).await?;
+            Err(ClockItError::Internal(format!(
+                "Failed to send email: {}. Please try again.",
+                e
+            )))
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(feature = "ssr")]
+fn cooldown_error(seconds: i64) -> ClockItError {
+    ClockItError::RateLimited(format!(
+        "A code was sent recently. Please wait {} seconds before requesting another.",
+        seconds
+    ))
+}
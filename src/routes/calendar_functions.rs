@@ -0,0 +1,167 @@
+use crate::database::calendar::{AcademicCalendar, BlackoutRequest, TermRequest};
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::database::{
+    calendar::{
+        count_upcoming_classes_between, create_blackout, create_term, delete_blackout, delete_term,
+        get_academic_calendar, import_holidays, update_blackout, update_term,
+    },
+    db_pool,
+};
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_role, Role, STAFF};
+#[cfg(feature = "ssr")]
+use crate::utils::ics::parse_events;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CalendarResponse {
+    pub success: bool,
+    pub message: String,
+    pub calendar: AcademicCalendar,
+}
+
+/// Terms and blackout dates, for lecturers and tutors planning classes
+#[server(GetAcademicCalendar, "/api")]
+pub async fn get_academic_calendar_fn() -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, STAFF).await?;
+
+    Ok(CalendarResponse {
+        success: true,
+        message: "Calendar retrieved".to_string(),
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
+
+/// Create a term, or update it when `term_id` is given
+#[server(SaveTerm, "/api")]
+pub async fn save_term_fn(
+    term_id: Option<i64>,
+    request: TermRequest,
+) -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    request.validate().map_err(ClockItError::Validation)?;
+
+    match term_id {
+        Some(term_id) => update_term(&pool, term_id, &request).await?,
+        None => {
+            create_term(&pool, &request).await?;
+        }
+    }
+
+    Ok(CalendarResponse {
+        success: true,
+        message: format!("Saved {}", request.name.trim()),
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
+
+#[server(DeleteTerm, "/api")]
+pub async fn delete_term_fn(term_id: i64) -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    delete_term(&pool, term_id).await?;
+
+    Ok(CalendarResponse {
+        success: true,
+        message: "Term deleted".to_string(),
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
+
+/// Create a holiday or recess, or update it when `blackout_id` is given. Classes
+/// already scheduled in it stay on the timetable but no longer count in statistics.
+#[server(SaveBlackout, "/api")]
+pub async fn save_blackout_fn(
+    blackout_id: Option<i64>,
+    request: BlackoutRequest,
+) -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    request.validate().map_err(ClockItError::Validation)?;
+
+    match blackout_id {
+        Some(blackout_id) => update_blackout(&pool, blackout_id, &request).await?,
+        None => {
+            create_blackout(&pool, &request).await?;
+        }
+    }
+
+    let affected =
+        count_upcoming_classes_between(&pool, &request.start_date, &request.end_date).await?;
+    let message = match affected {
+        0 => format!("Saved {}", request.name.trim()),
+        1 => format!(
+            "Saved {}. 1 class is still scheduled then and won't count towards attendance.",
+            request.name.trim()
+        ),
+        n => format!(
+            "Saved {}. {} classes are still scheduled then and won't count towards attendance.",
+            request.name.trim(),
+            n
+        ),
+    };
+
+    Ok(CalendarResponse {
+        success: true,
+        message,
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
+
+#[server(DeleteBlackout, "/api")]
+pub async fn delete_blackout_fn(blackout_id: i64) -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    delete_blackout(&pool, blackout_id).await?;
+
+    Ok(CalendarResponse {
+        success: true,
+        message: "Removed from the calendar".to_string(),
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
+
+/// Add the public holidays in an .ics file's text to the calendar
+#[server(ImportHolidays, "/api")]
+pub async fn import_holidays_fn(ics: String) -> Result<CalendarResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    let events = parse_events(&ics).map_err(ClockItError::Validation)?;
+    if events.is_empty() {
+        return Err(ClockItError::Validation(
+            "The file doesn't contain any events".to_string(),
+        ));
+    }
+
+    let saved = import_holidays(&pool, &events).await?;
+    let message = match saved {
+        0 => format!("All {} holidays are already on the calendar", events.len()),
+        n if n == events.len() => format!("Imported {} holidays", n),
+        n => format!(
+            "Imported {} holidays; {} were already on the calendar",
+            n,
+            events.len() - n
+        ),
+    };
+
+    Ok(CalendarResponse {
+        success: true,
+        message,
+        calendar: get_academic_calendar(&pool).await?,
+    })
+}
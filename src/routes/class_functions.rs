@@ -1,25 +1,63 @@
+use crate::database::class_series::{ClassSeries, Recurrence, SeriesEditScope};
 use crate::database::class_sessions::ClassSession;
-use crate::database::classes::{Class, CreateClassRequest, UpdateClassRequest};
-use gloo_net::http::Request;
+use crate::database::clashes::ClassClash;
+use crate::database::classes::{CheckInWindow, Class, CreateClassRequest, UpdateClassRequest};
+use crate::database::walk_ins::WalkInRequest;
+use crate::error::ClockItError;
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
 use crate::database::{
-    class_sessions::{create_session, end_session, get_active_session, get_session_by_id},
+    class_series::{
+        create_class_series, get_series, planned_dates, update_occurrence, update_series,
+    },
+    class_sessions::{
+        create_session, end_session, get_active_session, get_session_by_id, get_session_qr_secret,
+        sweep_session_states,
+    },
+    clashes::{find_clashes, ProposedClass},
     classes::{
-        create_class, delete_class, get_class_by_id, get_lecturer_classes, get_module_classes,
-        get_user_created_classes, get_user_created_classes_for_module, update_class,
+        create_class, delete_class, get_check_in_window, get_class_by_id, get_lecturer_classes,
+        get_module_classes, get_user_created_classes, get_user_created_classes_for_module,
+        update_class, CheckInTiming,
+    },
+    db_pool,
+    groups::{get_class_group_ids, is_expected_at_class},
+    modules::{get_module, is_student_enrolled},
+    universities::{class_timezone, module_timezone},
+    venues::get_class_venue,
+    walk_ins::{
+        decide_walk_in_request, get_pending_walk_ins, get_walk_in_request, upsert_walk_in_request,
     },
-    init_db_pool,
 };
 #[cfg(feature = "ssr")]
-use chrono::{DateTime, Duration as ChronoDuration, Local, NaiveDate, NaiveTime, Utc};
+use crate::authorization::{
+    forbidden, require_class_staff, require_module_staff, require_role, require_session_staff,
+    Role, STAFF,
+};
+#[cfg(feature = "ssr")]
+use crate::clock::{clock, Clock};
+#[cfg(feature = "ssr")]
+use crate::error::db_error;
+#[cfg(feature = "ssr")]
+use crate::qr_tokens::{self, TokenCheck};
+#[cfg(feature = "ssr")]
+use crate::utils::class_time::ClassWindow;
+#[cfg(feature = "ssr")]
+use crate::utils::geofence::{distance_outside_polygon, haversine_distance};
+#[cfg(feature = "ssr")]
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+#[cfg(feature = "ssr")]
+use chrono_tz::Tz;
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ClassResponse {
     pub success: bool,
     pub message: String,
     pub class: Option<Class>,
+    /// Classes the save overlaps. Unless they were overridden, nothing was saved.
+    #[serde(default)]
+    pub clashes: Vec<ClassClash>,
 }
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
@@ -29,6 +67,13 @@ pub struct ClassesListResponse {
     pub classes: Vec<Class>,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ClassSeriesResponse {
+    pub success: bool,
+    pub message: String,
+    pub series: Option<ClassSeries>,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ClassSessionResponse {
     pub success: bool,
@@ -37,39 +82,72 @@ pub struct ClassSessionResponse {
     pub class_status: Option<String>,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct QrTokenResponse {
+    pub success: bool,
+    pub message: String,
+    pub payload: Option<String>,
+    pub expires_in_seconds: i64,
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct RecordAttendanceResponse {
     pub success: bool,
     pub message: String,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct WalkInRequestsResponse {
+    pub success: bool,
+    pub message: String,
+    pub requests: Vec<WalkInRequest>,
+}
+
+/// Sent back instead of saving when the class clashes and the lecturer hasn't chosen
+/// to save anyway
+#[cfg(feature = "ssr")]
+fn clash_response(clashes: Vec<ClassClash>) -> ClassResponse {
+    let message = match clashes.len() {
+        1 => "This class clashes with another class.".to_string(),
+        n => format!("This class has {} clashes with other classes.", n),
+    };
+    ClassResponse {
+        success: false,
+        message: format!("{} Review them below, or save anyway to keep it.", message),
+        class: None,
+        clashes,
+    }
+}
+
 #[cfg(feature = "ssr")]
 async fn ensure_session_state(
     pool: &sqlx::SqlitePool,
     class_id: i64,
-) -> Result<Option<ClassSession>, String> {
+    clock: &dyn Clock,
+) -> Result<Option<ClassSession>, ClockItError> {
     let class = get_class_by_id(pool, class_id).await?;
-    let date = NaiveDate::parse_from_str(&class.date, "%Y-%m-%d")
-        .map_err(|e| format!("Invalid class date: {}", e))?;
-    let time = NaiveTime::parse_from_str(&class.time, "%H:%M")
-        .map_err(|e| format!("Invalid class time: {}", e))?;
-    let start_naive = date.and_time(time);
-    let duration_minutes = class.duration_minutes.max(15) as i64;
-    let now_naive = Local::now().naive_local();
+    let tz = class_timezone(pool, class_id).await?;
+    let duration_minutes = class.duration_minutes.max(15);
+    let window = ClassWindow::new(&class.date, &class.time, duration_minutes, tz).ok_or_else(|| {
+        ClockItError::Internal(format!(
+            "Invalid class date or time: {} {}",
+            class.date, class.time
+        ))
+    })?;
+    let now = clock.now();
 
     let mut session = get_active_session(pool, class_id).await?;
-    let expected_end = start_naive + ChronoDuration::minutes(duration_minutes);
 
     // Check if class has completely passed without being started
-    if session.is_none() && now_naive >= expected_end {
+    if session.is_none() && window.has_ended(now) {
         // Class time has completely passed, mark as completed
-        let now_utc = Utc::now().to_rfc3339();
+        let now_utc = now.to_rfc3339();
         sqlx::query("UPDATE classes SET status = 'completed', updated_at = ? WHERE classID = ?")
             .bind(&now_utc)
             .bind(class_id)
             .execute(pool)
             .await
-            .map_err(|e| format!("Failed to update class status: {}", e))?;
+            .map_err(db_error("Failed to update class status"))?;
         return Ok(None);
     }
 
@@ -78,15 +156,16 @@ async fn ensure_session_state(
 
     if let Some(existing) = session.clone() {
         if existing.ended_at.is_none() {
-            let started_naive = DateTime::parse_from_rfc3339(&existing.started_at)
-                .map(|dt| dt.with_timezone(&Local).naive_local())
-                .unwrap_or(start_naive);
-            let session_expected_end = started_naive + ChronoDuration::minutes(duration_minutes);
-            
+            let started_at = DateTime::parse_from_rfc3339(&existing.started_at)
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or(window.start);
+            let session_expected_end =
+                started_at + ChronoDuration::minutes(duration_minutes as i64);
+
             // End session if either the session duration has passed OR the class end time has passed
-            if now_naive >= session_expected_end || now_naive >= expected_end {
-                end_session(pool, existing.session_id).await?;
-                let now_utc = Utc::now().to_rfc3339();
+            if now >= session_expected_end || window.has_ended(now) {
+                end_session(pool, existing.session_id, clock).await?;
+                let now_utc = now.to_rfc3339();
                 sqlx::query(
                     "UPDATE classes SET status = 'completed', updated_at = ? WHERE classID = ?",
                 )
@@ -94,7 +173,7 @@ async fn ensure_session_state(
                 .bind(class_id)
                 .execute(pool)
                 .await
-                .map_err(|e| format!("Failed to update class status: {}", e))?;
+                .map_err(db_error("Failed to update class status"))?;
                 session = None;
             }
         }
@@ -103,65 +182,64 @@ async fn ensure_session_state(
     Ok(session)
 }
 
-/// Create a new class (with optional recurring pattern)
+/// Create a new class, or a series of them when `recurrence` is given
 #[server(CreateClass, "/api")]
 pub async fn create_class_fn(
     module_code: String,
     title: String,
     venue: Option<String>,
     description: Option<String>,
-    recurring: Option<String>,
+    recurrence: Option<Recurrence>, // None creates a single class
     date: String,
     time: String,
     duration_minutes: i32,
-    recurrence_count: Option<i32>, // How many instances to create
-    created_by: String, // Email of the user creating the class
-) -> Result<ClassResponse, ServerFnError> {
+    check_in_window: Option<CheckInWindow>, // None uses the module's window
+    group_ids: Vec<i64>,                    // Empty for the whole module
+    allow_clashes: bool,
+) -> Result<ClassResponse, ClockItError> {
     // Add logging
     println!("Creating class for module: '{}'", module_code);
 
     if title.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Class title is required".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Class title is required".to_string(),
+        ));
     }
 
     if date.trim().is_empty() || time.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Date and time are required".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Date and time are required".to_string(),
+        ));
     }
 
     if duration_minutes <= 0 {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Please choose a valid duration".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Please choose a valid duration".to_string(),
+        ));
     }
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    if let Some(Err(e)) = check_in_window.map(|window| window.validate()) {
+        return Err(ClockItError::Validation(e));
+    }
+
+    let pool = db_pool()?;
+
+    // Classes are attributed to the signed-in user
+    let created_by = require_module_staff(&pool, &module_code).await?.email_address;
 
     // Verify the module exists
     let module_exists = sqlx::query("SELECT 1 FROM modules WHERE moduleCode = ?")
         .bind(&module_code)
         .fetch_optional(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to check module: {}", e)))?;
+        .map_err(db_error("Failed to check module"))?;
 
     if module_exists.is_none() {
         println!("Module '{}' not found in database!", module_code);
-        return Ok(ClassResponse {
-            success: false,
-            message: format!("Module '{}' does not exist", module_code),
-            class: None,
-        });
+        return Err(ClockItError::NotFound(format!(
+            "Module '{}' does not exist",
+            module_code
+        )));
     }
 
     let request = CreateClassRequest {
@@ -181,92 +259,66 @@ pub async fn create_class_fn(
                 Some(s.clone())
             }
         }),
-        recurring: recurring
-            .clone()
-            .filter(|s| !s.trim().is_empty() && s != "No repeat"),
+        recurring: None,
         date: date.clone(),
         time: time.clone(),
         duration_minutes,
         created_by: Some(created_by.clone()),
+        check_in_window,
+        series_id: None,
+        group_ids,
     };
 
-    // Create the first class
-    let first_class = match create_class(&pool, request).await {
-        Ok(class) => class,
-        Err(e) => {
-            println!("Failed to create class: {}", e);
-            return Ok(ClassResponse {
-                success: false,
-                message: e,
-                class: None,
-            });
-        }
+    let dates = match &recurrence {
+        Some(recurrence) => planned_dates(&pool, &request.date, recurrence).await?,
+        None => vec![request.date.clone()],
     };
+    let clashes = find_clashes(
+        &pool,
+        &ProposedClass {
+            module_code: &module_code,
+            venue: request.venue.as_deref(),
+            time: &request.time,
+            duration_minutes,
+            created_by: Some(&created_by),
+            group_ids: &request.group_ids,
+            dates,
+            class_id: None,
+            series_id: None,
+        },
+    )
+    .await?;
+    if !clashes.is_empty() && !allow_clashes {
+        return Ok(clash_response(clashes));
+    }
 
-    // If recurring, create additional instances
-    if let Some(recur_pattern) = &recurring {
-        if recur_pattern != "No repeat" {
-            let count = recurrence_count.unwrap_or(8); // Default to 8 weeks if not specified
-
-            // Parse the start date
-            if let Ok(start_date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
-                let interval = match recur_pattern.as_str() {
-                    "Daily" => ChronoDuration::days(1),
-                    "Weekly" => ChronoDuration::weeks(1),
-                    "Monthly" => ChronoDuration::days(30), // Approximate
-                    _ => ChronoDuration::weeks(1),         // Default to weekly
-                };
-
-                // Create additional class instances
-                for i in 1..count {
-                    let next_date = start_date + (interval * (i as i32));
-                    let next_date_str = next_date.format("%Y-%m-%d").to_string();
-
-                    let recurring_request = CreateClassRequest {
-                        module_code: module_code.clone(),
-                        title: title.trim().to_string(),
-                        venue: venue.as_ref().and_then(|s| {
-                            if s.trim().is_empty() {
-                                None
-                            } else {
-                                Some(s.clone())
-                            }
-                        }),
-                        description: description.as_ref().and_then(|s| {
-                            if s.trim().is_empty() {
-                                None
-                            } else {
-                                Some(s.clone())
-                            }
-                        }),
-                        recurring: Some(recur_pattern.clone()),
-                        date: next_date_str,
-                        time: time.clone(),
-                        duration_minutes,
-                        created_by: Some(created_by.clone()),
-                    };
-
-                    // Create each recurring instance
-                    if let Err(e) = create_class(&pool, recurring_request).await {
-                        println!("Warning: Failed to create recurring instance {}: {}", i, e);
-                        // Continue creating other instances even if one fails
-                    }
-                }
-            }
-        }
+    // A repeating class is created as a series, with one class per date of the rule
+    if let Some(recurrence) = &recurrence {
+        let (first_class, count) = create_class_series(&pool, &request, recurrence)
+            .await
+            .inspect_err(|e| println!("Failed to create class series: {}", e))?;
+
+        return Ok(ClassResponse {
+            success: true,
+            message: format!("Created {} recurring class instances successfully!", count),
+            class: Some(first_class),
+            clashes,
+        });
     }
 
-    let message = if recurring.is_some() && recurring.as_ref().unwrap() != "No repeat" {
-        let count = recurrence_count.unwrap_or(8);
-        format!("Created {} recurring class instances successfully!", count)
-    } else {
-        "Class created successfully!".to_string()
+    let class = match create_class(&pool, request).await {
+        Ok(class) => class,
+        Err(e) => {
+            println!("Failed to create class: {}", e);
+            return Err(e);
+        }
     };
 
     Ok(ClassResponse {
         success: true,
-        message,
-        class: Some(first_class),
+        message: "Class created successfully!".to_string(),
+        class: Some(class),
+        clashes,
     })
 }
 
@@ -274,79 +326,61 @@ pub async fn create_class_fn(
 #[server(GetModuleClasses, "/api")]
 pub async fn get_module_classes_fn(
     module_code: String,
-) -> Result<ClassesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<ClassesListResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    match get_module_classes(&pool, &module_code).await {
-        Ok(classes) => Ok(ClassesListResponse {
-            success: true,
-            message: "Classes fetched successfully".to_string(),
-            classes,
-        }),
-        Err(e) => Ok(ClassesListResponse {
-            success: false,
-            message: e,
-            classes: vec![],
-        }),
-    }
+    require_module_staff(&pool, &module_code).await?;
+
+    let classes = get_module_classes(&pool, &module_code).await?;
+
+    Ok(ClassesListResponse {
+        success: true,
+        message: "Classes fetched successfully".to_string(),
+        classes,
+    })
 }
 
 /// Delete a class
 #[server(DeleteClass, "/api")]
-pub async fn delete_class_fn(class_id: i64) -> Result<ClassResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn delete_class_fn(class_id: i64) -> Result<ClassResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    match delete_class(&pool, class_id).await {
-        Ok(_) => Ok(ClassResponse {
-            success: true,
-            message: "Class deleted successfully!".to_string(),
-            class: None,
-        }),
-        Err(e) => Ok(ClassResponse {
-            success: false,
-            message: e,
-            class: None,
-        }),
-    }
+    require_class_staff(&pool, class_id).await?;
+
+    delete_class(&pool, class_id).await?;
+
+    Ok(ClassResponse {
+        success: true,
+        message: "Class deleted successfully!".to_string(),
+        class: None,
+        clashes: Vec::new(),
+    })
 }
 
 /// Get all classes for a lecturer
 #[server(GetLecturerClasses, "/api")]
-pub async fn get_lecturer_classes_fn(
-    lecturer_email: String,
-) -> Result<ClassesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_lecturer_classes_fn() -> Result<ClassesListResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    match get_lecturer_classes(&pool, &lecturer_email).await {
-        Ok(classes) => Ok(ClassesListResponse {
-            success: true,
-            message: "Classes fetched successfully".to_string(),
-            classes,
-        }),
-        Err(e) => Ok(ClassesListResponse {
-            success: false,
-            message: e,
-            classes: vec![],
-        }),
-    }
+    let lecturer_email = require_role(&pool, STAFF).await?.email_address;
+
+    let classes = get_lecturer_classes(&pool, &lecturer_email).await?;
+
+    Ok(ClassesListResponse {
+        success: true,
+        message: "Classes fetched successfully".to_string(),
+        classes,
+    })
 }
 
 /// Get classes created by a specific user (for tutors)
 #[server(GetUserCreatedClasses, "/api")]
-pub async fn get_user_created_classes_fn(user_email: String) -> Result<ClassesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_user_created_classes_fn() -> Result<ClassesListResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let classes = get_user_created_classes(&pool, &user_email)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get user classes: {}", e)))?;
+    let user_email = require_role(&pool, STAFF).await?.email_address;
+
+    let classes = get_user_created_classes(&pool, &user_email).await?;
 
     Ok(ClassesListResponse {
         success: true,
@@ -358,16 +392,13 @@ pub async fn get_user_created_classes_fn(user_email: String) -> Result<ClassesLi
 /// Get classes created by a specific user for a specific module (for tutors on classes page)
 #[server(GetUserCreatedClassesForModule, "/api")]
 pub async fn get_user_created_classes_for_module_fn(
-    user_email: String, 
     module_code: String
-) -> Result<ClassesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<ClassesListResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let classes = get_user_created_classes_for_module(&pool, &user_email, &module_code)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get user classes for module: {}", e)))?;
+    let user_email = require_module_staff(&pool, &module_code).await?.email_address;
+
+    let classes = get_user_created_classes_for_module(&pool, &user_email, &module_code).await?;
 
     Ok(ClassesListResponse {
         success: true,
@@ -378,10 +409,10 @@ pub async fn get_user_created_classes_for_module_fn(
 
 /// Get a single class by ID
 #[server(GetClass, "/api")]
-pub async fn get_class_fn(class_id: i64) -> Result<ClassResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_class_fn(class_id: i64) -> Result<ClassResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_class_staff(&pool, class_id).await?;
 
     let class = sqlx::query_as::<_, crate::database::classes::DbClass>(
         "SELECT * FROM classes WHERE classID = ?",
@@ -389,20 +420,15 @@ pub async fn get_class_fn(class_id: i64) -> Result<ClassResponse, ServerFnError>
     .bind(class_id)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?
+    .ok_or_else(|| ClockItError::NotFound("Class not found".to_string()))?;
 
-    match class {
-        Some(c) => Ok(ClassResponse {
-            success: true,
-            message: "Class found".to_string(),
-            class: Some(c.into()),
-        }),
-        None => Ok(ClassResponse {
-            success: false,
-            message: "Class not found".to_string(),
-            class: None,
-        }),
-    }
+    Ok(ClassResponse {
+        success: true,
+        message: "Class found".to_string(),
+        class: Some(class.into()),
+        clashes: Vec::new(),
+    })
 }
 
 /// Update a class
@@ -416,35 +442,37 @@ pub async fn update_class_fn(
     duration_minutes: i32,
     venue: Option<String>,
     recurring: Option<String>,
-) -> Result<ClassResponse, ServerFnError> {
+    check_in_window: Option<CheckInWindow>,
+    group_ids: Option<Vec<i64>>, // None keeps the class's groups
+    allow_clashes: bool,
+) -> Result<ClassResponse, ClockItError> {
     if title.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Class title is required".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Class title is required".to_string(),
+        ));
     }
 
     if date.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Class date is required".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Class date is required".to_string(),
+        ));
     }
 
     if duration_minutes <= 0 {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Please choose a valid duration".to_string(),
-            class: None,
-        });
+        return Err(ClockItError::Validation(
+            "Please choose a valid duration".to_string(),
+        ));
     }
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    if let Some(Err(e)) = check_in_window.map(|window| window.validate()) {
+        return Err(ClockItError::Validation(e));
+    }
+
+    let pool = db_pool()?;
 
+    require_class_staff(&pool, class_id).await?;
+
+    let current = get_class_by_id(&pool, class_id).await?;
     let request = UpdateClassRequest {
         title: title.trim().to_string(),
         description: description.filter(|s| !s.trim().is_empty()),
@@ -453,281 +481,194 @@ pub async fn update_class_fn(
         duration_minutes,
         venue: venue.filter(|s| !s.trim().is_empty()),
         recurring: recurring.filter(|s| !s.trim().is_empty()),
+        check_in_window,
+        group_ids,
+    };
+    let group_ids = match &request.group_ids {
+        Some(ids) => ids.clone(),
+        None => get_class_group_ids(&pool, class_id).await?,
     };
 
-    match update_class(&pool, class_id, request).await {
-        Ok(class) => Ok(ClassResponse {
-            success: true,
-            message: "Class updated successfully!".to_string(),
-            class: Some(class),
-        }),
-        Err(e) => Ok(ClassResponse {
-            success: false,
-            message: e,
-            class: None,
-        }),
+    let clashes = find_clashes(
+        &pool,
+        &ProposedClass {
+            module_code: &current.module_code,
+            venue: request.venue.as_deref(),
+            time: &request.time,
+            duration_minutes,
+            created_by: current.created_by.as_deref(),
+            group_ids: &group_ids,
+            dates: vec![request.date.clone()],
+            class_id: Some(class_id),
+            series_id: None,
+        },
+    )
+    .await?;
+    if !clashes.is_empty() && !allow_clashes {
+        return Ok(clash_response(clashes));
     }
+
+    let class = update_class(&pool, class_id, request).await?;
+
+    Ok(ClassResponse {
+        success: true,
+        message: "Class updated successfully!".to_string(),
+        class: Some(class),
+        clashes,
+    })
 }
 
-/// Update a single class that's part of a series (removes it from the series)
-#[server(UpdateSingleInSeries, "/api")]
-pub async fn update_single_in_series_fn(
+/// Edit one class of a series without touching the others. It stays in the series,
+/// and later edits to the whole series leave it as it is.
+#[server(SaveSingleInstance, "/api")]
+pub async fn save_single_instance_fn(
     class_id: i64,
-    title: String,
-    description: Option<String>,
-    date: String,
-    time: String,
-    duration_minutes: i32,
-    venue: Option<String>,
-) -> Result<ClassResponse, ServerFnError> {
-    if title.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Class title is required".to_string(),
-            class: None,
-        });
-    }
-
-    if date.trim().is_empty() {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Class date is required".to_string(),
-            class: None,
-        });
-    }
+    request: UpdateClassRequest,
+    allow_clashes: bool,
+) -> Result<ClassResponse, ClockItError> {
+    request.validate().map_err(ClockItError::Validation)?;
 
-    if duration_minutes <= 0 {
-        return Ok(ClassResponse {
-            success: false,
-            message: "Please choose a valid duration".to_string(),
-            class: None,
-        });
-    }
+    let pool = db_pool()?;
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    require_class_staff(&pool, class_id).await?;
 
-    // Update the class and remove it from the series by setting recurring to NULL
+    let class = get_class_by_id(&pool, class_id).await?;
     let request = UpdateClassRequest {
-        title: title.trim().to_string(),
-        description: description.filter(|s| !s.trim().is_empty()),
-        date: date.trim().to_string(),
-        time: time.trim().to_string(),
-        duration_minutes,
-        venue: venue.filter(|s| !s.trim().is_empty()),
-        recurring: None, // Remove from series
+        title: request.title.trim().to_string(),
+        description: request.description.filter(|s| !s.trim().is_empty()),
+        venue: request.venue.filter(|s| !s.trim().is_empty()),
+        recurring: class.recurring,
+        ..request
+    };
+    let group_ids = match &request.group_ids {
+        Some(ids) => ids.clone(),
+        None => get_class_group_ids(&pool, class_id).await?,
     };
 
-    match update_class(&pool, class_id, request).await {
-        Ok(class) => Ok(ClassResponse {
-            success: true,
-            message: "Class updated successfully and removed from series!".to_string(),
-            class: Some(class),
-        }),
-        Err(e) => Ok(ClassResponse {
-            success: false,
-            message: e,
-            class: None,
-        }),
-    }
-}
-
-/// Update all classes in a recurring series
-#[server(UpdateRecurringSeries, "/api")]
-pub async fn update_recurring_series_fn(
-    module_code: String,
-    original_title: String,
-    original_recurring: String,
-    new_title: String,
-    new_description: Option<String>,
-    new_venue: Option<String>,
-    new_time: String,
-) -> Result<ClassResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    let now = chrono::Utc::now().to_rfc3339();
-
-    // Update all classes that match the original series
-    let result = sqlx::query(
-        r#"
-        UPDATE classes 
-        SET title = ?, description = ?, venue = ?, time = ?, updated_at = ?
-        WHERE moduleCode = ? AND title = ? AND recurring = ? AND status = 'upcoming'
-        "#,
+    let clashes = find_clashes(
+        &pool,
+        &ProposedClass {
+            module_code: &class.module_code,
+            venue: request.venue.as_deref(),
+            time: &request.time,
+            duration_minutes: request.duration_minutes,
+            created_by: class.created_by.as_deref(),
+            group_ids: &group_ids,
+            dates: vec![request.date.clone()],
+            class_id: Some(class_id),
+            series_id: None,
+        },
     )
-    .bind(&new_title)
-    .bind(&new_description)
-    .bind(&new_venue)
-    .bind(&new_time)
-    .bind(&now)
-    .bind(&module_code)
-    .bind(&original_title)
-    .bind(&original_recurring)
-    .execute(&pool)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to update series: {}", e)))?;
+    .await?;
+    if !clashes.is_empty() && !allow_clashes {
+        return Ok(clash_response(clashes));
+    }
 
-    let count = result.rows_affected();
+    let class = update_occurrence(&pool, class_id, &request).await?;
 
     Ok(ClassResponse {
         success: true,
-        message: format!("Updated {} classes in the series", count),
-        class: None,
+        message: "Class updated successfully!".to_string(),
+        class: Some(class),
+        clashes,
     })
 }
 
-/// Rewrite a recurring series when the recurrence pattern changes
-#[server(RewriteRecurringSeries, "/api")]
-pub async fn rewrite_recurring_series_fn(
+/// Edit a class together with the following or all upcoming classes of its series,
+/// regenerating them when the repeat pattern or date changes. No `recurrence` stops
+/// the series repeating from this class on.
+#[server(SaveRecurringSeries, "/api")]
+pub async fn save_recurring_series_fn(
     class_id: i64,
-    module_code: String,
-    original_title: String,
-    original_recurring: Option<String>,
-    new_title: String,
-    new_description: Option<String>,
-    new_venue: Option<String>,
-    new_date: String,
-    new_time: String,
-    new_duration_minutes: i32,
-    new_recurring: Option<String>,
-    new_recurrence_count: Option<i32>,
-) -> Result<ClassResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    scope: SeriesEditScope,
+    request: UpdateClassRequest,
+    recurrence: Option<Recurrence>,
+    allow_clashes: bool,
+) -> Result<ClassResponse, ClockItError> {
+    request.validate().map_err(ClockItError::Validation)?;
 
-    // Fetch the original class to preserve created_by field
-    let original_class = get_class_by_id(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to fetch original class: {}", e)))?;
+    let pool = db_pool()?;
 
-    // 1) Find all upcoming classes that belong to the original series
-    let series_class_ids: Vec<i64> = if let Some(orig_rec) = &original_recurring {
-        sqlx::query_scalar(
-            r#"SELECT classID FROM classes
-               WHERE moduleCode = ? AND title = ? AND recurring = ? AND status = 'upcoming'"#,
-        )
-        .bind(&module_code)
-        .bind(&original_title)
-        .bind(orig_rec)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to query original series: {}", e)))?
-    } else {
-        sqlx::query_scalar(
-            r#"SELECT classID FROM classes
-               WHERE moduleCode = ? AND title = ? AND recurring IS NULL AND status = 'upcoming'"#,
-        )
-        .bind(&module_code)
-        .bind(&original_title)
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to query original series: {}", e)))?
-    };
+    require_class_staff(&pool, class_id).await?;
 
-    let total_in_series = series_class_ids.len();
-
-    // 2) Update the currently edited class to the new details
-    let update_request = UpdateClassRequest {
-        title: new_title.clone(),
-        description: new_description.clone(),
-        date: new_date.clone(),
-        time: new_time.clone(),
-        duration_minutes: new_duration_minutes,
-        venue: new_venue.clone(),
-        recurring: new_recurring.clone(),
+    let request = UpdateClassRequest {
+        title: request.title.trim().to_string(),
+        description: request.description.filter(|s| !s.trim().is_empty()),
+        venue: request.venue.filter(|s| !s.trim().is_empty()),
+        ..request
     };
 
-    let updated_class = match update_class(&pool, class_id, update_request).await {
-        Ok(class) => class,
-        Err(e) => {
-            return Ok(ClassResponse {
-                success: false,
-                message: e,
-                class: None,
-            })
-        }
+    // The series' own classes are being rewritten, so they can't clash with the edit
+    let class = get_class_by_id(&pool, class_id).await?;
+    let group_ids = match &request.group_ids {
+        Some(ids) => ids.clone(),
+        None => get_class_group_ids(&pool, class_id).await?,
     };
-
-    // 3) Remove all other upcoming classes from the original series (they will be recreated)
-    for id in series_class_ids {
-        if id != class_id {
-            if let Err(e) = delete_class(&pool, id).await {
-                // Continue but report failure in the message later
-                leptos::logging::log!(
-                    "Failed to delete class {} while rewriting series: {}",
-                    id,
-                    e
-                );
-            }
-        }
+    let dates = match &recurrence {
+        Some(recurrence) => planned_dates(&pool, &request.date, recurrence).await?,
+        None => vec![request.date.clone()],
+    };
+    let clashes = find_clashes(
+        &pool,
+        &ProposedClass {
+            module_code: &class.module_code,
+            venue: request.venue.as_deref(),
+            time: &request.time,
+            duration_minutes: request.duration_minutes,
+            created_by: class.created_by.as_deref(),
+            group_ids: &group_ids,
+            dates,
+            class_id: Some(class_id),
+            series_id: class.series_id,
+        },
+    )
+    .await?;
+    if !clashes.is_empty() && !allow_clashes {
+        return Ok(clash_response(clashes));
     }
 
-    // 4) Recreate the remaining instances using the new recurrence
-    let mut created = 0usize;
-    if let Some(recur) = &new_recurring {
-        // If user provided a new recurrence count, use it; otherwise keep original series length
-        let target_total: usize = new_recurrence_count
-            .map(|c| c.max(1) as usize)
-            .unwrap_or_else(|| total_in_series.max(1));
-
-        if target_total > 1 {
-            if let Ok(start_date) = NaiveDate::parse_from_str(&new_date, "%Y-%m-%d") {
-                let interval = match recur.as_str() {
-                    "Daily" => ChronoDuration::days(1),
-                    "Weekly" => ChronoDuration::weeks(1),
-                    "Monthly" => ChronoDuration::days(30),
-                    _ => ChronoDuration::weeks(1),
-                };
-
-                for i in 1..target_total {
-                    let next_date = start_date + (interval * (i as i32));
-                    let next_date_str = next_date.format("%Y-%m-%d").to_string();
-
-                    let req = CreateClassRequest {
-                        module_code: module_code.clone(),
-                        title: new_title.clone(),
-                        venue: new_venue.clone(),
-                        description: new_description.clone(),
-                        recurring: new_recurring.clone(),
-                        date: next_date_str,
-                        time: new_time.clone(),
-                        duration_minutes: new_duration_minutes,
-                        created_by: original_class.created_by.clone(),
-                    };
-
-                    match create_class(&pool, req).await {
-                        Ok(_) => created += 1,
-                        Err(e) => {
-                            leptos::logging::log!(
-                                "Warning: failed to create rewritten instance {}: {}",
-                                i,
-                                e
-                            );
-                        }
-                    }
-                }
-            }
-        }
-    }
+    let update = update_series(&pool, class_id, scope, &request, recurrence.as_ref()).await?;
 
-    let msg = if let Some(r) = &new_recurring {
-        let total_instances = created + 1; // include the edited class
+    let message = if recurrence.is_none() {
+        format!(
+            "Series updated to 'No repeat'. Removed {} future instance(s).",
+            update.removed
+        )
+    } else if update.created > 0 || update.removed > 0 {
         format!(
-            "Series updated to '{}' recurrence. Now {} instance(s) in total.",
-            r, total_instances
+            "Series updated to '{}'. Created {} class(es) and removed {}.",
+            update.class.recurring.clone().unwrap_or_default(),
+            update.created,
+            update.removed
         )
     } else {
-        "Series updated to 'No repeat'. Removed future instances.".to_string()
+        format!("Updated {} classes in the series", update.updated)
     };
 
     Ok(ClassResponse {
         success: true,
-        message: msg,
-        class: Some(updated_class),
+        message,
+        class: Some(update.class),
+        clashes,
+    })
+}
+
+/// The series a class belongs to, if any
+#[server(GetClassSeries, "/api")]
+pub async fn get_class_series_fn(class_id: i64) -> Result<ClassSeriesResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_class_staff(&pool, class_id).await?;
+
+    let series = match get_class_by_id(&pool, class_id).await?.series_id {
+        Some(series_id) => get_series(&pool, series_id).await?,
+        None => None,
+    };
+
+    Ok(ClassSeriesResponse {
+        success: true,
+        message: "Series retrieved".to_string(),
+        series,
     })
 }
 
@@ -736,12 +677,12 @@ pub async fn rewrite_recurring_series_fn(
 pub async fn update_class_status_fn(
     class_id: i64,
     status: String,
-) -> Result<ClassResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<ClassResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let now = chrono::Utc::now().to_rfc3339();
+    require_class_staff(&pool, class_id).await?;
+
+    let now = clock().now().to_rfc3339();
 
     sqlx::query(
         r#"
@@ -755,7 +696,7 @@ pub async fn update_class_status_fn(
     .bind(class_id)
     .execute(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to update status: {}", e)))?;
+    .map_err(db_error("Failed to update status"))?;
 
     let class = sqlx::query_as::<_, crate::database::classes::DbClass>(
         "SELECT * FROM classes WHERE classID = ?",
@@ -763,12 +704,13 @@ pub async fn update_class_status_fn(
     .bind(class_id)
     .fetch_one(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to fetch updated class: {}", e)))?;
+    .map_err(db_error("Failed to fetch updated class"))?;
 
     Ok(ClassResponse {
         success: true,
         message: format!("Class status updated to {}", status),
         class: Some(class.into()),
+        clashes: Vec::new(),
     })
 }
 
@@ -779,71 +721,79 @@ pub async fn start_class_session_fn(
     longitude: Option<f64>,
     accuracy: Option<f64>,
     radius: Option<f64>,
-) -> Result<ClassSessionResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    let (lat, lng) = match (latitude, longitude) {
-        (Some(lat), Some(lng)) => (lat, lng),
-        _ => return Ok(ClassSessionResponse {
-            success: false,
-            message:
-                "Unable to determine lecturer location. Please allow location access and try again."
-                    .to_string(),
-            session: None,
-            class_status: None,
-        }),
+    qr_rotation_seconds: Option<i64>, // How often the check-in QR code changes
+) -> Result<ClassSessionResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let clock = clock();
+
+    let started_by = require_class_staff(&pool, class_id).await?.email_address;
+
+    let venue = get_class_venue(&pool, class_id).await?;
+    let polygon = venue.as_ref().and_then(|venue| venue.polygon.clone());
+
+    // Fence around the lecturer when they have a fix, otherwise around the venue
+    let lecturer_fix = latitude.zip(longitude);
+    let venue_centre = venue
+        .as_ref()
+        .and_then(|venue| venue.latitude.zip(venue.longitude));
+    let Some((lat, lng)) = lecturer_fix.or(venue_centre).or_else(|| {
+        polygon.as_ref().and_then(|polygon| polygon.first().map(|[lat, lng]| (*lat, *lng)))
+    }) else {
+        return Err(ClockItError::Validation(
+            "Unable to determine lecturer location. Please allow location access and try again, or save a geofence for this venue."
+                .to_string(),
+        ));
     };
 
-    let accuracy = accuracy.filter(|value| value.is_finite() && *value >= 0.0);
+    let accuracy = accuracy
+        .filter(|value| value.is_finite() && *value >= 0.0)
+        .filter(|_| lecturer_fix.is_some());
     let radius = radius
         .filter(|value| value.is_finite() && *value > 0.0)
+        .or_else(|| venue.as_ref().and_then(|venue| venue.radius_meters))
         .unwrap_or(30.0);
+    let qr_rotation_seconds = qr_rotation_seconds
+        .map(qr_tokens::clamp_rotation_seconds)
+        .unwrap_or_else(qr_tokens::default_rotation_seconds);
 
-    match create_session(
+    let session = create_session(
         &pool,
         class_id,
-        None,
+        Some(started_by),
         Some(lat),
         Some(lng),
         accuracy,
         Some(radius),
+        polygon.as_deref(),
+        qr_rotation_seconds,
+        &*clock,
     )
-    .await
-    {
-        Ok(session) => {
-            let now = Utc::now().to_rfc3339();
-            sqlx::query(
-                "UPDATE classes SET status = 'in_progress', updated_at = ? WHERE classID = ?",
-            )
-            .bind(&now)
-            .bind(class_id)
-            .execute(&pool)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to update class status: {}", e)))?;
+    .await?;
 
-            Ok(ClassSessionResponse {
-                success: true,
-                message: "Session started".to_string(),
-                session: Some(session),
-                class_status: Some("in_progress".to_string()),
-            })
-        }
-        Err(e) => Ok(ClassSessionResponse {
-            success: false,
-            message: e,
-            session: None,
-            class_status: None,
-        }),
-    }
+    let now = clock.now().to_rfc3339();
+    sqlx::query("UPDATE classes SET status = 'in_progress', updated_at = ? WHERE classID = ?")
+        .bind(&now)
+        .bind(class_id)
+        .execute(&pool)
+        .await
+        .map_err(db_error("Failed to update class status"))?;
+
+    Ok(ClassSessionResponse {
+        success: true,
+        message: "Session started".to_string(),
+        session: Some(session),
+        class_status: Some("in_progress".to_string()),
+    })
 }
 
 #[server(EndClassSession, "/api")]
-pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let clock = clock();
+
+    require_session_staff(&pool, session_id).await?;
 
     // Manual end session should always work, regardless of timing logic
     leptos::logging::log!("Manual end session requested for session_id: {}", session_id);
@@ -856,7 +806,7 @@ pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionRespons
             
             if session.ended_at.is_some() {
                 leptos::logging::log!("Session {} is already ended, but updating class status anyway", session_id);
-                let now = Utc::now().to_rfc3339();
+                let now = clock.now().to_rfc3339();
                 let _ = sqlx::query("UPDATE classes SET status = 'completed', updated_at = ? WHERE classID = ?")
                     .bind(&now)
                     .bind(session.class_id)
@@ -873,21 +823,16 @@ pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionRespons
         }
         Ok(None) => {
             leptos::logging::log!("Session {} not found!", session_id);
-            return Ok(ClassSessionResponse {
-                success: false,
-                message: "Session not found".to_string(),
-                session: None,
-                class_status: None,
-            });
+            return Err(ClockItError::NotFound("Session not found".to_string()));
         }
         Err(e) => {
             leptos::logging::log!("Error fetching session {}: {}", session_id, e);
         }
     }
 
-    match end_session(&pool, session_id).await {
+    match end_session(&pool, session_id, &*clock).await {
         Ok(session) => {
-            let now = Utc::now().to_rfc3339();
+            let now = clock.now().to_rfc3339();
             
             // Always update class status to completed, even if session was already ended
             sqlx::query(
@@ -897,7 +842,7 @@ pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionRespons
             .bind(session.class_id)
             .execute(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to update class status: {}", e)))?;
+            .map_err(db_error("Failed to update class status"))?;
             
             leptos::logging::log!("Session {} manually ended for class {}", session_id, session.class_id);
 
@@ -917,7 +862,7 @@ pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionRespons
             match get_session_by_id(&pool, session_id).await {
                 Ok(Some(session)) => {
                     // Session exists but couldn't be ended - might already be ended
-                    let now = Utc::now().to_rfc3339();
+                    let now = clock.now().to_rfc3339();
                     let _ = sqlx::query("UPDATE classes SET status = 'completed', updated_at = ? WHERE classID = ?")
                         .bind(&now)
                         .bind(session.class_id)
@@ -931,28 +876,55 @@ pub async fn end_class_session_fn(session_id: i64) -> Result<ClassSessionRespons
                         class_status: Some("completed".to_string()),
                     })
                 }
-                _ => Ok(ClassSessionResponse {
-                    success: false,
-                    message: format!("Failed to end session: {}", e),
-                    session: None,
-                    class_status: None,
-                }),
+                _ => Err(e),
             }
         },
     }
 }
 
+/// Current check-in token for a session's QR code; the page refetches it when it expires
+#[server(GetSessionQrToken, "/api")]
+pub async fn get_session_qr_token_fn(session_id: i64) -> Result<QrTokenResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_session_staff(&pool, session_id).await?;
+
+    let session = get_session_by_id(&pool, session_id).await?;
+
+    let Some(session) = session.filter(|s| s.ended_at.is_none()) else {
+        return Err(ClockItError::Conflict("Session is not active".to_string()));
+    };
+
+    let Some((secret, rotation_seconds)) = get_session_qr_secret(&pool, session_id).await?
+    else {
+        return Err(ClockItError::Internal("Session has no signing key".to_string()));
+    };
+
+    let (payload, expires_in_seconds) = qr_tokens::issue(
+        &secret,
+        session.session_id,
+        session.class_id,
+        rotation_seconds,
+        clock().now().timestamp(),
+    );
+
+    Ok(QrTokenResponse {
+        success: true,
+        message: "Token issued".to_string(),
+        payload: Some(payload),
+        expires_in_seconds,
+    })
+}
+
 #[server(GetActiveClassSession, "/api")]
 pub async fn get_active_class_session_fn(
     class_id: i64,
-) -> Result<ClassSessionResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<ClassSessionResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let session = ensure_session_state(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
+    require_class_staff(&pool, class_id).await?;
+
+    let session = ensure_session_state(&pool, class_id, &*clock()).await?;
     let class_status = get_class_by_id(&pool, class_id)
         .await
         .map(|c| c.status)
@@ -975,230 +947,212 @@ pub async fn get_active_class_session_fn(
 }
 
 #[server(CheckAllActiveSessions, "/api")]
-pub async fn check_all_active_sessions_fn() -> Result<String, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-    
-    // Get all classes that might need status updates (in_progress OR upcoming classes that might have passed)
-    let classes: Vec<(i64, String)> = sqlx::query_as("SELECT classID, status FROM classes WHERE status IN ('in_progress', 'upcoming')")
-        .fetch_all(&pool)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to fetch classes: {}", e)))?;
-    
-    let total_classes = classes.len();
-    let mut updated_count = 0;
-    for (class_id, old_status) in classes {
-        if let Err(e) = ensure_session_state(&pool, class_id).await {
-            leptos::logging::log!("Error checking session for class {}: {}", class_id, e);
-        } else {
-            // Check if the class status changed
-            let new_status: Option<String> = sqlx::query_scalar("SELECT status FROM classes WHERE classID = ?")
-                .bind(class_id)
-                .fetch_optional(&pool)
-                .await
-                .map_err(|e| ServerFnError::new(format!("Failed to check class status: {}", e)))?;
-            
-            if let Some(new_status) = new_status {
-                if new_status != old_status {
-                    updated_count += 1;
-                    leptos::logging::log!("Class {} status changed from {} to {}", class_id, old_status, new_status);
-                }
-            }
-        }
-    }
-    
-    Ok(format!("Checked {} classes, updated {} statuses", total_classes, updated_count))
-}
+pub async fn check_all_active_sessions_fn() -> Result<String, ClockItError> {
+    let pool = db_pool()?;
 
-// Helper function to save a single instance
-pub async fn save_single_instance(
-    class_id: i32,
-    class_data: web_sys::FormData,
-) -> Result<(), ServerFnError> {
-    let url = format!("/api/classes/{}", class_id);
+    require_role(&pool, STAFF).await?;
 
-    let response = Request::put(&url)
-        .body(class_data)?
-        .send()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Request failed: {}", e)))?;
+    let sweep = sweep_session_states(&pool, &*clock()).await?;
 
-    if response.ok() {
-        Ok(())
-    } else {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(ServerFnError::new(format!(
-            "Failed to update class: {}",
-            error_text
-        )))
-    }
-}
-
-// Helper function to save all future instances in a series
-pub async fn save_recurring_series(
-    series_id: String,
-    class_date: String,
-    class_data: web_sys::FormData,
-) -> Result<(), ServerFnError> {
-    let url = format!("/api/classes/recurring/{}/from/{}", series_id, class_date);
-
-    let response = Request::put(&url)
-        .body(class_data)?
-        .send()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Request failed: {}", e)))?;
-
-    if response.ok() {
-        Ok(())
-    } else {
-        let error_text = response
-            .text()
-            .await
-            .unwrap_or_else(|_| "Unknown error".to_string());
-        Err(ServerFnError::new(format!(
-            "Failed to update recurring classes: {}",
-            error_text
-        )))
-    }
+    Ok(format!(
+        "Checked {} classes, updated {} statuses",
+        sweep.checked, sweep.updated
+    ))
 }
 
 #[server(RecordSessionAttendance, "/api")]
 pub async fn record_session_attendance_fn(
     payload: String,
-    student_email: String,
     latitude: Option<f64>,
     longitude: Option<f64>,
     accuracy: Option<f64>,
-) -> Result<RecordAttendanceResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<RecordAttendanceResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let parts: Vec<&str> = payload.split(':').collect();
-    if parts.len() != 4 || parts[0] != "session" || parts[2] != "class" {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Invalid QR code".to_string(),
-        });
-    }
+    let clock = clock();
 
-    let session_id: i64 = parts[1]
-        .parse()
-        .map_err(|_| ServerFnError::new("Invalid session id"))?;
-    let class_id: i64 = parts[3]
-        .parse()
-        .map_err(|_| ServerFnError::new("Invalid class id"))?;
+    let student = require_role(&pool, &[Role::Student]).await?;
 
-    let _ = ensure_session_state(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
+    let Some(token) = qr_tokens::parse(&payload) else {
+        return Err(ClockItError::Validation("Invalid QR code".to_string()));
+    };
 
-    let session = match get_session_by_id(&pool, session_id).await {
-        Ok(Some(session)) => session,
-        Ok(None) => {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: "Session not found".to_string(),
-            })
+    let session_id = token.session_id;
+    let class_id = token.class_id;
+
+    let Some((secret, rotation_seconds)) = get_session_qr_secret(&pool, session_id).await?
+    else {
+        return Err(ClockItError::NotFound("Session not found".to_string()));
+    };
+
+    match qr_tokens::verify(&secret, &token, rotation_seconds, clock.now().timestamp()) {
+        TokenCheck::Valid => {}
+        TokenCheck::Expired => {
+            return Err(ClockItError::Validation(
+                "This QR code has expired. Please scan the code currently on screen.".to_string(),
+            ))
         }
-        Err(e) => {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: e,
-            })
+        TokenCheck::Forged => {
+            return Err(ClockItError::Validation("Invalid QR code".to_string()));
         }
+    }
+
+    let _ = ensure_session_state(&pool, class_id, &*clock).await?;
+
+    let Some(session) = get_session_by_id(&pool, session_id).await? else {
+        return Err(ClockItError::NotFound("Session not found".to_string()));
     };
 
     if session.ended_at.is_some() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Session has ended".to_string(),
-        });
+        return Err(ClockItError::Conflict("Session has ended".to_string()));
     }
 
     if session.class_id != class_id {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Session mismatch".to_string(),
-        });
+        return Err(ClockItError::Validation("Session mismatch".to_string()));
     }
 
     let student_latitude = latitude.filter(|value| value.is_finite());
     let student_longitude = longitude.filter(|value| value.is_finite());
     let student_accuracy = accuracy.filter(|value| value.is_finite() && *value >= 0.0);
 
-    if let (Some(lect_lat), Some(lect_lng), Some(radius)) = (
-        session.start_latitude,
-        session.start_longitude,
-        session.location_radius,
-    ) {
+    let fenced = session.location_polygon.is_some()
+        || (session.start_latitude.is_some()
+            && session.start_longitude.is_some()
+            && session.location_radius.is_some());
+
+    if fenced {
         let (Some(student_lat), Some(student_lng)) = (student_latitude, student_longitude) else {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: "Location permission is required to record attendance for this session."
+            return Err(ClockItError::Validation(
+                "Location permission is required to record attendance for this session."
                     .to_string(),
-            });
+            ));
         };
-
-        let lecturer_accuracy = session.start_accuracy.unwrap_or(0.0).max(0.0);
         let student_accuracy_value = student_accuracy.unwrap_or(0.0);
-        let distance = haversine_distance(lect_lat, lect_lng, student_lat, student_lng);
-        let allowed_distance = radius + lecturer_accuracy + student_accuracy_value;
 
-        if distance > allowed_distance {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: format!(
+        if let Some(polygon) = &session.location_polygon {
+            let distance = distance_outside_polygon(student_lat, student_lng, polygon);
+            if distance > student_accuracy_value {
+                return Err(ClockItError::Validation(format!(
+                    "You are outside the venue ({:.0}m away)",
+                    distance
+                )));
+            }
+        } else if let (Some(lect_lat), Some(lect_lng), Some(radius)) = (
+            session.start_latitude,
+            session.start_longitude,
+            session.location_radius,
+        ) {
+            let lecturer_accuracy = session.start_accuracy.unwrap_or(0.0).max(0.0);
+            let distance = haversine_distance(lect_lat, lect_lng, student_lat, student_lng);
+            let allowed_distance = radius + lecturer_accuracy + student_accuracy_value;
+
+            if distance > allowed_distance {
+                return Err(ClockItError::Validation(format!(
                     "You are too far from the lecturer ({:.0}m away, must be within {:.0}m)",
                     distance, allowed_distance
-                ),
-            });
+                )));
+            }
         }
     }
 
-    if student_email.trim().is_empty() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Missing student email".to_string(),
-        });
+    let student_id = student.user_id;
+
+    let class = get_class_by_id(&pool, class_id).await?;
+
+    let window = get_check_in_window(&pool, class_id).await?;
+    let tz = module_timezone(&pool, &class.module_code).await?;
+    let minutes_after_start = minutes_since_class_start(&class, tz, clock.now()).unwrap_or(0);
+    let timing = window.classify(minutes_after_start);
+
+    if timing == CheckInTiming::Closed {
+        return Err(ClockItError::Conflict(format!(
+            "Check-in for this class closed {} minutes after it started",
+            window.late_minutes
+        )));
     }
 
-    let student_id: Option<i64> =
-        sqlx::query_scalar("SELECT userID FROM users WHERE emailAddress = ? AND role = 'student'")
-            .bind(&student_email)
-            .fetch_optional(&pool)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup student: {}", e)))?;
+    // Students the class isn't for, whether outside the module or in another tutorial
+    // group, go through the walk-in flow like any other visitor
+    let expected = is_expected_at_class(&pool, class_id, &student.email_address).await?;
 
-    let student_id = match student_id {
-        Some(id) => id,
-        None => {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: "Student not found".to_string(),
-            })
+    if !expected {
+        let reason =
+            if is_student_enrolled(&pool, &class.module_code, &student.email_address).await? {
+                "This class is for other tutorial groups"
+            } else {
+                "You are not enrolled in this module"
+            };
+        let allow_walk_ins = get_module(&pool, &class.module_code)
+            .await?
+            .map(|module| module.allow_walk_ins)
+            .unwrap_or(false);
+
+        if !allow_walk_ins {
+            return Err(ClockItError::Forbidden(reason.to_string()));
         }
-    };
 
-    let now = Utc::now().to_rfc3339();
+        let status = upsert_walk_in_request(
+            &pool,
+            class_id,
+            session_id,
+            student_id,
+            student_latitude,
+            student_longitude,
+            student_accuracy,
+        )
+        .await?;
 
-    let existing: Option<i64> = sqlx::query_scalar(
-        "SELECT attendanceID FROM attendance WHERE classID = ? AND studentID = ?",
+        return Ok(match status.as_str() {
+            "accepted" => RecordAttendanceResponse {
+                success: true,
+                message: "Your walk-in request was already accepted".to_string(),
+            },
+            "rejected" => {
+                return Err(ClockItError::Forbidden(
+                    "Your walk-in request for this class was declined".to_string(),
+                ))
+            }
+            _ => RecordAttendanceResponse {
+                success: true,
+                message: format!(
+                    "{}. Your check-in has been sent to the lecturer for approval.",
+                    reason
+                ),
+            },
+        });
+    }
+
+    let now = clock.now().to_rfc3339();
+    let status = if timing == CheckInTiming::Late {
+        "late"
+    } else {
+        "present"
+    };
+
+    let existing: Option<(i64, String)> = sqlx::query_as(
+        "SELECT attendanceID, status FROM attendance WHERE classID = ? AND studentID = ?",
     )
     .bind(class_id)
     .bind(student_id)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to check attendance: {}", e)))?;
+    .map_err(db_error("Failed to check attendance"))?;
 
-    if let Some(attendance_id) = existing {
+    if let Some((_, existing_status)) = &existing {
+        // A second scan never downgrades an earlier on-time check-in
+        if existing_status == "present" || existing_status == "late" {
+            return Ok(RecordAttendanceResponse {
+                success: true,
+                message: "Attendance already recorded".to_string(),
+            });
+        }
+    }
+
+    if let Some((attendance_id, _)) = existing {
         sqlx::query(
-            "UPDATE attendance SET status = 'present', recorded_at = ?, notes = NULL, check_latitude = ?, check_longitude = ?, location_accuracy = ? WHERE attendanceID = ?"
+            "UPDATE attendance SET status = ?, recorded_at = ?, notes = NULL, check_latitude = ?, check_longitude = ?, location_accuracy = ? WHERE attendanceID = ?"
         )
+        .bind(status)
         .bind(&now)
         .bind(student_latitude)
         .bind(student_longitude)
@@ -1206,83 +1160,73 @@ pub async fn record_session_attendance_fn(
         .bind(attendance_id)
         .execute(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to update attendance: {}", e)))?;
+        .map_err(db_error("Failed to update attendance"))?;
     } else {
         sqlx::query(
-            "INSERT INTO attendance (studentID, classID, status, recorded_at, notes, check_latitude, check_longitude, location_accuracy) VALUES (?, ?, 'present', ?, NULL, ?, ?, ?)"
+            "INSERT INTO attendance (studentID, classID, status, recorded_at, notes, check_latitude, check_longitude, location_accuracy) VALUES (?, ?, ?, ?, NULL, ?, ?, ?)"
         )
         .bind(student_id)
         .bind(class_id)
+        .bind(status)
         .bind(&now)
         .bind(student_latitude)
         .bind(student_longitude)
         .bind(student_accuracy)
         .execute(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to insert attendance: {}", e)))?;
+        .map_err(db_error("Failed to insert attendance"))?;
     }
 
     Ok(RecordAttendanceResponse {
         success: true,
-        message: "Attendance recorded".to_string(),
+        message: if timing == CheckInTiming::Late {
+            "Attendance recorded (late)".to_string()
+        } else {
+            "Attendance recorded".to_string()
+        },
     })
 }
 
+/// Minutes between the scheduled start of `class` and now, negative before it starts
 #[cfg(feature = "ssr")]
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let earth_radius_m = 6_371_000.0_f64;
-    let d_lat = (lat2 - lat1).to_radians();
-    let d_lon = (lon2 - lon1).to_radians();
-    let lat1_rad = lat1.to_radians();
-    let lat2_rad = lat2.to_radians();
-
-    let a =
-        (d_lat / 2.0).sin().powi(2) + lat1_rad.cos() * lat2_rad.cos() * (d_lon / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-
-    earth_radius_m * c
+fn minutes_since_class_start(class: &Class, tz: Tz, now: DateTime<Utc>) -> Option<i64> {
+    let window = ClassWindow::new(&class.date, &class.time, class.duration_minutes, tz)?;
+    Some(window.minutes_since_start(now))
 }
 
 #[server(RecordManualAttendance, "/api")]
 pub async fn record_manual_attendance_fn(
     class_id: i64,
     student_email: String,
-) -> Result<RecordAttendanceResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<RecordAttendanceResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let clock = clock();
+
+    require_class_staff(&pool, class_id).await?;
 
     // Verify class exists and get active session
-    let _ = ensure_session_state(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(e))?;
+    let _ = ensure_session_state(&pool, class_id, &*clock).await?;
 
     // Verify session is active
-    let session = get_active_session(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to check session: {}", e)))?;
+    let session = get_active_session(&pool, class_id).await?;
 
     if session.is_none() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "No active session for this class".to_string(),
-        });
+        return Err(ClockItError::Conflict(
+            "No active session for this class".to_string(),
+        ));
     }
 
     let session = session.unwrap();
     if session.ended_at.is_some() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Session has ended".to_string(),
-        });
+        return Err(ClockItError::Conflict("Session has ended".to_string()));
     }
 
     // Verify student exists and is enrolled
     if student_email.trim().is_empty() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Missing student email".to_string(),
-        });
+        return Err(ClockItError::Validation(
+            "Missing student email".to_string(),
+        ));
     }
 
     let student_id: Option<i64> =
@@ -1290,41 +1234,28 @@ pub async fn record_manual_attendance_fn(
             .bind(&student_email)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup student: {}", e)))?;
+            .map_err(db_error("Failed to lookup student"))?;
 
     let student_id = match student_id {
         Some(id) => id,
         None => {
-            return Ok(RecordAttendanceResponse {
-                success: false,
-                message: "Student not found".to_string(),
-            })
+            return Err(ClockItError::NotFound("Student not found".to_string()));
         }
     };
 
     // Get class info to check module enrollment
-    let class = get_class_by_id(&pool, class_id)
-        .await
-        .map_err(|e| ServerFnError::new(format!("Failed to get class: {}", e)))?;
+    let class = get_class_by_id(&pool, class_id).await?;
 
     // Verify student is enrolled in the module
-    let is_enrolled: Option<i64> = sqlx::query_scalar(
-        "SELECT 1 FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ?",
-    )
-    .bind(&class.module_code)
-    .bind(&student_email)
-    .fetch_optional(&pool)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to check enrollment: {}", e)))?;
+    let is_enrolled = is_student_enrolled(&pool, &class.module_code, &student_email).await?;
 
-    if is_enrolled.is_none() {
-        return Ok(RecordAttendanceResponse {
-            success: false,
-            message: "Student is not enrolled in this module".to_string(),
-        });
+    if !is_enrolled {
+        return Err(ClockItError::Validation(
+            "Student is not enrolled in this module".to_string(),
+        ));
     }
 
-    let now = Utc::now().to_rfc3339();
+    let now = clock.now().to_rfc3339();
 
     // Check if attendance record already exists
     let existing: Option<i64> = sqlx::query_scalar(
@@ -1334,7 +1265,7 @@ pub async fn record_manual_attendance_fn(
     .bind(student_id)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to check attendance: {}", e)))?;
+    .map_err(db_error("Failed to check attendance"))?;
 
     if let Some(attendance_id) = existing {
         sqlx::query(
@@ -1344,7 +1275,7 @@ pub async fn record_manual_attendance_fn(
         .bind(attendance_id)
         .execute(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to update attendance: {}", e)))?;
+        .map_err(db_error("Failed to update attendance"))?;
     } else {
         sqlx::query(
             "INSERT INTO attendance (studentID, classID, status, recorded_at, notes) VALUES (?, ?, 'present', ?, 'Manual check-in by lecturer')"
@@ -1354,7 +1285,7 @@ pub async fn record_manual_attendance_fn(
         .bind(&now)
         .execute(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to insert attendance: {}", e)))?;
+        .map_err(db_error("Failed to insert attendance"))?;
     }
 
     Ok(RecordAttendanceResponse {
@@ -1362,3 +1293,55 @@ pub async fn record_manual_attendance_fn(
         message: "Attendance recorded manually".to_string(),
     })
 }
+
+/// Pending walk-in check-ins for a class, oldest first
+#[server(GetWalkInRequests, "/api")]
+pub async fn get_walk_in_requests_fn(
+    class_id: i64,
+) -> Result<WalkInRequestsResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_class_staff(&pool, class_id).await?;
+
+    let requests = get_pending_walk_ins(&pool, class_id).await?;
+
+    Ok(WalkInRequestsResponse {
+        success: true,
+        message: "Walk-in requests fetched successfully".to_string(),
+        requests,
+    })
+}
+
+/// Accept a walk-in (recording the student present) or reject it
+#[server(DecideWalkInRequest, "/api")]
+pub async fn decide_walk_in_request_fn(
+    request_id: i64,
+    accept: bool,
+) -> Result<RecordAttendanceResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let Some(request) = get_walk_in_request(&pool, request_id).await? else {
+        require_role(&pool, STAFF).await?;
+        return Err(forbidden());
+    };
+
+    let staff = require_class_staff(&pool, request.class_id).await?;
+
+    let decided = decide_walk_in_request(&pool, request_id, accept, &staff.email_address).await?;
+
+    if !decided {
+        return Err(ClockItError::Conflict(
+            "This request has already been handled".to_string(),
+        ));
+    }
+
+    let name = format!("{} {}", request.student_name, request.student_surname);
+    Ok(RecordAttendanceResponse {
+        success: true,
+        message: if accept {
+            format!("{} marked present", name)
+        } else {
+            format!("Walk-in request from {} rejected", name)
+        },
+    })
+}
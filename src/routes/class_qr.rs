@@ -6,15 +6,20 @@ use leptos_router::hooks::{use_navigate, use_query_map};
 use qrcode::{render::svg, QrCode};
 use urlencoding::encode;
 
+use crate::clock::clock;
+use crate::error::ClockItError;
 use crate::routes::{
-    class_functions::{end_class_session_fn, get_active_class_session_fn, get_class_fn, record_manual_attendance_fn},
+    class_functions::{
+        decide_walk_in_request_fn, end_class_session_fn, get_active_class_session_fn, get_class_fn,
+        get_session_qr_token_fn, get_walk_in_requests_fn, record_manual_attendance_fn,
+        ClassSessionResponse,
+    },
     helpers::build_return_path,
-    student_functions::get_module_students,
+    student_functions::get_class_students,
 };
 
-fn format_date_label(date_str: &str) -> (String, String) {
+fn format_date_label(date_str: &str, today: NaiveDate) -> (String, String) {
     if let Ok(date) = NaiveDate::parse_from_str(date_str, "%Y-%m-%d") {
-        let today = Local::now().naive_local().date();
         let label = if date == today {
             "Today".to_string()
         } else if date == today + Duration::days(1) {
@@ -60,6 +65,68 @@ fn build_qr_svg(data: &str, size: u32) -> Option<String> {
     })
 }
 
+fn qr_image_url(payload: Option<&str>, size: u32) -> String {
+    match payload.and_then(|data| build_qr_svg(data, size)) {
+        Some(svg) => format!("data:image/svg+xml;utf8,{}", encode(&svg)),
+        None => format!(
+            "data:image/svg+xml;utf8,%3Csvg xmlns='http://www.w3.org/2000/svg' width='{0}' height='{0}'%3E%3Crect width='100%25' height='100%25' fill='%23f1f5f9'/%3E%3C/svg%3E",
+            size
+        ),
+    }
+}
+
+/// Keep `qr_payload` filled with the active session's current check-in token.
+/// Tokens rotate server-side, so each fetch schedules the next one for when it expires.
+fn use_rotating_qr_payload(
+    session_resource: Resource<Result<ClassSessionResponse, ClockItError>>,
+) -> RwSignal<Option<String>> {
+    let qr_payload = RwSignal::new(None::<String>);
+    let qr_refresh = RwSignal::new(0u32);
+
+    Effect::new(move |_| {
+        let generation = qr_refresh.get();
+        let session_id = session_resource
+            .get()
+            .and_then(|resp| resp.ok())
+            .and_then(|resp| resp.session)
+            .map(|s| s.session_id);
+
+        let Some(session_id) = session_id else {
+            qr_payload.set(None);
+            return;
+        };
+
+        spawn_local(async move {
+            let retry_in = match get_session_qr_token_fn(session_id).await {
+                Ok(token) if token.success => {
+                    qr_payload.try_set(token.payload);
+                    token.expires_in_seconds.max(1)
+                }
+                Ok(token) => {
+                    leptos::logging::log!("Failed to load QR token: {}", token.message);
+                    qr_payload.try_set(None);
+                    5
+                }
+                Err(e) => {
+                    leptos::logging::log!("Failed to load QR token: {}", e);
+                    5
+                }
+            };
+            set_timeout(
+                move || {
+                    // Only the newest fetch may schedule the next one
+                    if qr_refresh.try_get_untracked() == Some(generation) {
+                        qr_refresh.try_set(generation + 1);
+                    }
+                },
+                std::time::Duration::from_secs(retry_in as u64),
+            );
+        });
+    });
+
+    qr_payload
+}
+
 #[component]
 pub fn ClassQrPage() -> impl IntoView {
     let navigate = use_navigate();
@@ -97,6 +164,37 @@ pub fn ClassQrPage() -> impl IntoView {
         move || class_id.get(),
         |id| async move { get_active_class_session_fn(id).await },
     );
+    let qr_payload = use_rotating_qr_payload(session_resource);
+
+    // Walk-in requests are re-fetched whenever the QR code rotates or a request is decided
+    let walk_in_refresh = RwSignal::new(0u32);
+    let walk_ins_resource = Resource::new(
+        move || (class_id.get(), qr_payload.get(), walk_in_refresh.get()),
+        |(id, payload, _)| async move {
+            if id == 0 || payload.is_none() {
+                return Vec::new();
+            }
+            match get_walk_in_requests_fn(id).await {
+                Ok(response) if response.success => response.requests,
+                _ => Vec::new(),
+            }
+        },
+    );
+    let decide_walk_in_action = Action::new(move |(request_id, accept): &(i64, bool)| {
+        let request_id = *request_id;
+        let accept = *accept;
+        async move { decide_walk_in_request_fn(request_id, accept).await }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = decide_walk_in_action.value().get() {
+            match result {
+                Ok(response) => manual_feedback.set(Some((response.success, response.message))),
+                Err(e) => manual_feedback.set(Some((false, e.to_string()))),
+            }
+            walk_in_refresh.update(|n| *n = n.wrapping_add(1));
+        }
+    });
 
     let end_session_action = Action::new(move |session_id: &i64| {
         let id = *session_id;
@@ -139,12 +237,12 @@ pub fn ClassQrPage() -> impl IntoView {
         }
     });
 
-    // Load students enrolled in the module
+    // Load the students expected at the class
     let students_resource = Resource::new(
         move || class_resource.get().and_then(|c| c),
         |class_opt| async move {
             match class_opt {
-                Some(class) => match get_module_students(class.module_code.clone()).await {
+                Some(class) => match get_class_students(class.class_id).await {
                     Ok(response) if response.success => Some(response.students),
                     _ => None,
                 },
@@ -199,7 +297,7 @@ pub fn ClassQrPage() -> impl IntoView {
                                 Some(class) => {
                                     let return_path = build_return_path(origin_value.clone(), &class.module_code);
                                     last_return_path.set(return_path.clone());
-                                    let (day_label, pretty_date) = format_date_label(&class.date);
+                                    let (day_label, pretty_date) = format_date_label(&class.date, clock().local_today());
                                     let (time_display, duration_display) = format_time_label(&class.time, class.duration_minutes.max(15));
                                     let time_display_for_meta = time_display.clone();
                                     let venue_display = class.venue.clone().unwrap_or_else(|| "TBA".to_string());
@@ -211,35 +309,13 @@ pub fn ClassQrPage() -> impl IntoView {
                                             None
                                         }
                                     });
-                                    let session_payload = active_session
-                                        .as_ref()
-                                        .map(|s| {
-                                            let payload = format!("session:{}:class:{}", s.session_id, class.class_id);
-                                            leptos::logging::log!("=== QR CODE DEBUG ===");
-                                            leptos::logging::log!("Session ID: {}", s.session_id);
-                                            leptos::logging::log!("Class ID: {}", class.class_id);
-                                            leptos::logging::log!("Full Payload: {}", payload);
-                                            leptos::logging::log!("====================");
-                                            payload
-                                        })
-                                        .unwrap_or_else(|| {
-                                            leptos::logging::log!("❌ NO ACTIVE SESSION - QR will be invalid");
-                                            format!("class:{}:inactive", class.class_id)
-                                        });
-                                    let qr_svg = if active_session.is_some() {
-                                        build_qr_svg(&session_payload, 220).unwrap_or_default()
-                                    } else {
-                                        String::new()
-                                    };
-                                    let download_url = if qr_svg.is_empty() {
-                                        String::new()
-                                    } else {
-                                        format!("data:image/svg+xml;utf8,{}", encode(&qr_svg))
-                                    };
-                                    let image_url = if download_url.is_empty() {
-                                        "data:image/svg+xml;utf8,%3Csvg xmlns='http://www.w3.org/2000/svg' width='220' height='220'%3E%3Crect width='100%25' height='100%25' fill='%23f1f5f9'/%3E%3C/svg%3E".to_string()
-                                    } else {
-                                        download_url.clone()
+                                    if active_session.is_none() {
+                                        leptos::logging::log!("❌ NO ACTIVE SESSION - QR will be invalid");
+                                    }
+                                    let session_is_live = active_session.is_some();
+                                    let image_url = move || {
+                                        let payload = qr_payload.get().filter(|_| session_is_live);
+                                        qr_image_url(payload.as_deref(), 220)
                                     };
                                     let session_label = active_session
                                         .as_ref()
@@ -299,7 +375,7 @@ pub fn ClassQrPage() -> impl IntoView {
                                                 <p class="session-id">{session_label.clone()}</p>
                                                 <A href=enlarge_href attr:class="qr-image-link" attr:aria-label="View QR code full screen">
                                                     <div class="qr-image">
-                                                        <img src=image_url.clone() alt="QR code for session" width="220" height="220"/>
+                                                        <img src=image_url alt="QR code for session" width="220" height="220"/>
                                                     </div>
                                                 </A>
                                                 <p class="session-status">
@@ -310,6 +386,39 @@ pub fn ClassQrPage() -> impl IntoView {
                                                 
                                             </div>
 
+                                            <Show when=move || session_is_live && walk_ins_resource.get().is_some_and(|requests| !requests.is_empty())>
+                                                <div class="walk-in-requests">
+                                                    <h3 class="heading">"Walk-in Requests"</h3>
+                                                    <p class="muted">"These students scanned in but aren't enrolled in this module."</p>
+                                                    <div class="student-list">
+                                                        {move || walk_ins_resource.get().unwrap_or_default().into_iter().map(|request| {
+                                                            let request_id = request.request_id;
+                                                            let full_name = format!("{} {}", request.student_name, request.student_surname);
+                                                            view! {
+                                                                <div class="student-item walk-in-item">
+                                                                    <div class="student-info">
+                                                                        <div class="student-name">{full_name}</div>
+                                                                        <div class="student-email muted">{request.student_email.clone()}</div>
+                                                                    </div>
+                                                                    <div class="walk-in-actions">
+                                                                        <button
+                                                                            class="btn btn-primary"
+                                                                            disabled=move || decide_walk_in_action.pending().get()
+                                                                            on:click=move |_| { decide_walk_in_action.dispatch((request_id, true)); }
+                                                                        >"Accept"</button>
+                                                                        <button
+                                                                            class="btn btn-outline"
+                                                                            disabled=move || decide_walk_in_action.pending().get()
+                                                                            on:click=move |_| { decide_walk_in_action.dispatch((request_id, false)); }
+                                                                        >"Reject"</button>
+                                                                    </div>
+                                                                </div>
+                                                            }
+                                                        }).collect_view()}
+                                                    </div>
+                                                </div>
+                                            </Show>
+
                                             <div class="qr-actions">
                                                 <A href=return_path.clone() attr:class="btn btn-outline">"Close"</A>
                                                 {if session_is_active {
@@ -522,6 +631,7 @@ pub fn ClassQrFullscreenPage() -> impl IntoView {
         move || class_id.get(),
         |id| async move { get_active_class_session_fn(id).await },
     );
+    let qr_payload = use_rotating_qr_payload(session_resource);
 
     let end_session_action = Action::new(move |session_id: &i64| {
         let id = *session_id;
@@ -577,30 +687,13 @@ pub fn ClassQrFullscreenPage() -> impl IntoView {
                                         None
                                     }
                                 });
-                                let session_payload = active_session
-                                    .as_ref()
-                                    .map(|s| {
-                                        let payload = format!("session:{}:class:{}", s.session_id, class.class_id);
-                                        leptos::logging::log!("=== QR CODE DEBUG ===");
-                                        leptos::logging::log!("Session ID: {}", s.session_id);
-                                        leptos::logging::log!("Class ID: {}", class.class_id);
-                                        leptos::logging::log!("Full Payload: {}", payload);
-                                        leptos::logging::log!("====================");
-                                        payload
-                                    })
-                                    .unwrap_or_else(|| {
-                                        leptos::logging::log!("❌ NO ACTIVE SESSION - QR will be invalid");
-                                        format!("class:{}:inactive", class.class_id)
-                                    });
-                                let qr_svg = if active_session.is_some() {
-                                    build_qr_svg(&session_payload, 360).unwrap_or_default()
-                                } else {
-                                    String::new()
-                                };
-                                let image_url = if qr_svg.is_empty() {
-                                    "data:image/svg+xml;utf8,%3Csvg xmlns='http://www.w3.org/2000/svg' width='360' height='360'%3E%3Crect width='100%25' height='100%25' fill='%23f1f5f9'/%3E%3C/svg%3E".to_string()
-                                } else {
-                                    format!("data:image/svg+xml;utf8,{}", encode(&qr_svg))
+                                if active_session.is_none() {
+                                    leptos::logging::log!("❌ NO ACTIVE SESSION - QR will be invalid");
+                                }
+                                let session_is_live = active_session.is_some();
+                                let image_url = move || {
+                                    let payload = qr_payload.get().filter(|_| session_is_live);
+                                    qr_image_url(payload.as_deref(), 360)
                                 };
                                 let origin_param = origin_value.clone().unwrap_or_else(|| "classes".to_string());
                                 let qr_page_path = format!("/classes/qr?id={}&origin={}", class.class_id, origin_param);
@@ -609,7 +702,7 @@ pub fn ClassQrFullscreenPage() -> impl IntoView {
                                 view! {
                                     <div class="qr-full-card">
                                         <div class="qr-full-wrapper">
-                                            <img src=image_url.clone() alt="QR code for session" width="360" height="360"/>
+                                            <img src=image_url alt="QR code for session" width="360" height="360"/>
                                         </div>
                                         <div class="qr-full-actions">
                                             <A href=qr_page_path attr:class="btn btn-outline">"Close"</A>
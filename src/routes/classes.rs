@@ -1,4 +1,6 @@
 use crate::database::classes::Class;
+#[cfg(feature = "ssr")]
+use crate::error::ClockItError;
 use crate::routes::class_functions::{
     delete_class_fn, get_active_class_session_fn, get_module_classes_fn, start_class_session_fn,
 };
@@ -6,7 +8,6 @@ use crate::routes::module_functions::get_module_fn;
 use crate::routes::student_functions::get_module_students;
 use crate::user_context::get_current_user;
 use leptos::prelude::*;
-use leptos::server_fn::ServerFnError;
 use leptos::web_sys::window;
 use leptos_router::components::A;
 use leptos_router::hooks::{use_navigate, use_query_map};
@@ -130,6 +131,7 @@ pub fn ClassesPage() -> impl IntoView {
                     </div>
                 </div>
                 <div class="header-actions">
+                    <A href=move || format!("/classes/import?module={}", module_code.get()) attr:class="btn btn-outline">"Import Timetable"</A>
                     <A href=move || format!("/classes/new?module={}", module_code.get()) attr:class="btn btn-primary">"+ Add Class"</A>
                 </div>
             </div>
@@ -336,7 +338,6 @@ fn ClassRow(class: Class) -> impl IntoView {
 
     let start_session_href = format!("/classes/qr?id={}&origin=classes", class_id);
     let view_session_href = start_session_href.clone();
-    const LOCATION_RADIUS_METERS: f64 = 30.0;
     let location_status = RwSignal::new(None::<String>);
     let location_error = RwSignal::new(None::<String>);
 
@@ -361,7 +362,8 @@ fn ClassRow(class: Class) -> impl IntoView {
                             Some(loc.latitude),
                             Some(loc.longitude),
                             loc.accuracy,
-                            Some(LOCATION_RADIUS_METERS),
+                            None,
+                            None,
                         )
                         .await;
 
@@ -372,9 +374,10 @@ fn ClassRow(class: Class) -> impl IntoView {
                         response
                     }
                     Err(err) => {
-                        location_status.set(None);
-                        location_error.set(Some(err.clone()));
-                        Err(ServerFnError::new(err))
+                        // Without a fix the session can still be fenced by the venue's saved geofence
+                        leptos::logging::log!("Location unavailable ({}), using venue geofence", err);
+                        location_status.set(Some("Starting session at the saved venue...".to_string()));
+                        start_class_session_fn(id, None, None, None, None, None).await
                     }
                 }
             }
@@ -392,8 +395,8 @@ fn ClassRow(class: Class) -> impl IntoView {
                 location_status.set(None);
                 let msg = "Location capture requires a browser context.".to_string();
                 location_error.set(Some(msg.clone()));
-                Err::<crate::routes::class_functions::ClassSessionResponse, ServerFnError>(
-                    ServerFnError::new(msg),
+                Err::<crate::routes::class_functions::ClassSessionResponse, ClockItError>(
+                    ClockItError::Internal(msg),
                 )
             }
         }
@@ -1,6 +1,14 @@
+use crate::components::{
+    ClashWarnings, GroupPicker, RecurrenceFields, RecurrenceForm, VenueOptions,
+};
+use crate::database::class_series::SeriesEditScope;
+use crate::database::clashes::ClassClash;
+use crate::database::classes::{CheckInWindow, UpdateClassRequest};
 use crate::routes::class_functions::{
-    delete_class_fn, get_class_fn, rewrite_recurring_series_fn, update_class_fn,
+    delete_class_fn, get_class_fn, get_class_series_fn, save_recurring_series_fn,
+    save_single_instance_fn, update_class_fn,
 };
+use crate::routes::group_functions::get_class_groups_fn;
 use crate::routes::helpers::build_return_path;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
@@ -26,17 +34,26 @@ pub fn EditClass() -> impl IntoView {
     let title = RwSignal::new(String::new());
     let venue = RwSignal::new(String::new());
     let desc = RwSignal::new(String::new());
-    let recurring = RwSignal::new("No repeat".to_string());
-    let recurrence_count = RwSignal::new("8".to_string());
-    let original_recurring: RwSignal<Option<String>> = RwSignal::new(None);
+    let recurrence = RecurrenceForm::new();
+    // Whether the class belongs to a series, and which of its classes an edit reaches:
+    // "this", "following" or "all"
+    let in_series = RwSignal::new(false);
+    let edit_scope = RwSignal::new("this".to_string());
     let date = RwSignal::new(String::new());
     let hour = RwSignal::new("10".to_string());
     let minute = RwSignal::new("00".to_string());
     let duration = RwSignal::new("90".to_string());
+    let on_time_minutes = RwSignal::new(String::new());
+    let late_minutes = RwSignal::new(String::new());
+    // The class's tutorial groups, left alone on save until they have loaded
+    let group_ids = RwSignal::new(Vec::<i64>::new());
+    let groups_loaded = RwSignal::new(false);
     let message = RwSignal::new(String::new());
     let success = RwSignal::new(false);
+    // What the last save clashed with, and whether the lecturer chose to save over it
+    let clashes = RwSignal::new(Vec::<ClassClash>::new());
+    let allow_clashes = RwSignal::new(false);
     let class_title_display = RwSignal::new(String::new());
-    let original_title = RwSignal::new(String::new());
     let module_code = RwSignal::new(String::new());
 
     let module_code_for_return = module_code.clone();
@@ -61,18 +78,53 @@ pub fn EditClass() -> impl IntoView {
         },
     );
 
+    let series_resource = Resource::new(
+        move || class_id.get(),
+        |id| async move {
+            if id == 0 {
+                return None;
+            }
+            get_class_series_fn(id)
+                .await
+                .ok()
+                .and_then(|response| response.series)
+        },
+    );
+
+    let groups_resource = Resource::new(
+        move || class_id.get(),
+        |id| async move {
+            if id == 0 {
+                return None;
+            }
+            get_class_groups_fn(id).await.ok()
+        },
+    );
+
+    Effect::new(move |_| {
+        if let Some(Some(ids)) = groups_resource.get() {
+            group_ids.set(ids);
+            groups_loaded.set(true);
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(series) = series_resource.get() {
+            in_series.set(series.is_some());
+            if let Some(series) = series {
+                recurrence.load(&series.recurrence);
+            }
+        }
+    });
+
     // Populate form when class loads
     Effect::new(move |_| {
         if let Some(Some(class)) = class_resource.get() {
             title.set(class.title.clone());
             class_title_display.set(class.title.clone());
-            original_title.set(class.title.clone());
             module_code.set(class.module_code.clone());
             venue.set(class.venue.unwrap_or_default());
             desc.set(class.description.unwrap_or_default());
-            let rec = class.recurring.clone();
-            recurring.set(rec.clone().unwrap_or_else(|| "No repeat".to_string()));
-            original_recurring.set(rec);
             date.set(class.date.clone());
 
             // Parse time
@@ -83,6 +135,17 @@ pub fn EditClass() -> impl IntoView {
             }
 
             duration.set(class.duration_minutes.max(15).to_string());
+
+            match class.check_in_window {
+                Some(window) => {
+                    on_time_minutes.set(window.on_time_minutes.to_string());
+                    late_minutes.set(window.late_minutes.to_string());
+                }
+                None => {
+                    on_time_minutes.set(String::new());
+                    late_minutes.set(String::new());
+                }
+            }
         }
     });
 
@@ -146,65 +209,84 @@ pub fn EditClass() -> impl IntoView {
         } else {
             Some(desc.get())
         };
-        let recurring_val = if recurring.get() == "No repeat" {
-            None
-        } else {
-            Some(recurring.get())
-        };
         let current_class_id = class_id.get();
-        let current_title = title.get();
-        let current_date = date.get();
         let nav = navigate.clone();
-        let mod_code = module_code.get();
-        let original_rec = original_recurring.get();
-        let orig_title = original_title.get();
-        let count_val = if recurring.get() != "No repeat" {
-            recurrence_count.get().parse::<i32>().ok()
-        } else {
-            None
-        };
         let return_to = return_path.get();
         let duration_minutes = duration.get().parse::<i32>().unwrap_or(90).max(15);
+        let window = match CheckInWindow::parse_override(&on_time_minutes.get(), &late_minutes.get()) {
+            Ok(window) => window,
+            Err(e) => {
+                message.set(e);
+                return;
+            }
+        };
+        let request = UpdateClassRequest {
+            title: title.get(),
+            description: desc_val,
+            date: date.get(),
+            time: time_str,
+            duration_minutes,
+            venue: venue_val,
+            recurring: None,
+            check_in_window: window,
+            group_ids: groups_loaded.get().then(|| group_ids.get()),
+        };
+
+        // Repeat settings only apply when the edit reaches more than this class
+        let scope = match (in_series.get(), edit_scope.get().as_str()) {
+            (true, "this") => None,
+            (true, "following") => Some(SeriesEditScope::ThisAndFollowing),
+            _ => Some(SeriesEditScope::All),
+        };
+        let recurrence_val = match scope {
+            Some(_) => match recurrence.recurrence(&request.date) {
+                Ok(recurrence_val) => recurrence_val,
+                Err(e) => {
+                    message.set(e);
+                    return;
+                }
+            },
+            None => None,
+        };
+        let is_series = in_series.get();
+        let allow = allow_clashes.get();
 
         spawn_local(async move {
-            // If recurrence pattern changed, rewrite the series; otherwise update just this class
-            let changed_recurrence = recurring_val != original_rec;
-            let should_rewwrite =
-                changed_recurrence || (recurring_val.is_some() && count_val.is_some());
-            let resp = if should_rewwrite {
-                rewrite_recurring_series_fn(
-                    current_class_id,
-                    mod_code.clone(),
-                    orig_title,
-                    original_rec,
-                    current_title,
-                    desc_val.clone(),
-                    venue_val.clone(),
-                    current_date.clone(),
-                    time_str.clone(),
-                    duration_minutes,
-                    recurring_val.clone(),
-                    count_val,
-                )
-                .await
-            } else {
-                update_class_fn(
-                    current_class_id,
-                    current_title,
-                    desc_val,
-                    current_date,
-                    time_str,
-                    duration_minutes,
-                    venue_val,
-                    recurring_val,
-                )
-                .await
+            let resp = match scope {
+                None => save_single_instance_fn(current_class_id, request, allow).await,
+                // A one-off class that stays one-off is a plain update
+                Some(_) if !is_series && recurrence_val.is_none() => {
+                    update_class_fn(
+                        current_class_id,
+                        request.title,
+                        request.description,
+                        request.date,
+                        request.time,
+                        request.duration_minutes,
+                        request.venue,
+                        None,
+                        request.check_in_window,
+                        request.group_ids,
+                        allow,
+                    )
+                    .await
+                }
+                Some(scope) => {
+                    save_recurring_series_fn(current_class_id, scope, request, recurrence_val, allow)
+                        .await
+                }
             };
 
             match resp {
                 Ok(response) => {
                     message.set(response.message);
                     success.set(response.success);
+                    if response.success {
+                        clashes.set(Vec::new());
+                    } else {
+                        allow_clashes.set(false);
+                        clashes.set(response.clashes);
+                    }
                     if response.success {
                         set_timeout(
                             move || {
@@ -244,56 +326,32 @@ pub fn EditClass() -> impl IntoView {
                                         <input class="input" placeholder="Hash Tables & Collision" bind:value=title />
 
                                         <label class="label" style="margin-top:16px;">"Venue"</label>
-                                        <input class="input" placeholder="Room C301" bind:value=venue />
+                                        <input class="input" placeholder="Room C301" list="venue-options" bind:value=venue />
+                                        <VenueOptions id="venue-options"/>
 
                                         <label class="label" style="margin-top:16px;">"Description"</label>
                                         <textarea class="textarea" placeholder="Enter a class description" bind:value=desc></textarea>
 
-                                    <label class="label" style="margin-top:16px;">"Recurring"</label>
-                                    <select class="input" bind:value=recurring>
-                                        <option selected>"No repeat"</option>
-                                        <option>"Daily"</option>
-                                        <option>"Weekly"</option>
-                                        <option>"Monthly"</option>
-                                    </select>
-
-                                    // Show recurrence count input if recurring is enabled
-                                    <Show when=move || recurring.get() != "No repeat">
-                                        <div style="margin-top:10px;">
-                                            <label class="label">"Number of Occurrences"</label>
-                                            <div style="display:flex; align-items:center; gap:12px;">
-                                                <input
-                                                    type="number"
-                                                    class="input"
-                                                    min="2"
-                                                    max="52"
-                                                    bind:value=recurrence_count
-                                                    style="max-width:120px;"
-                                                />
-                                                <span class="muted" style="font-size:13px;">
-                                                    {move || match recurring.get().as_str() {
-                                                        "Daily" => "days",
-                                                        "Weekly" => "weeks",
-                                                        "Monthly" => "months",
-                                                        _ => "instances"
-                                                    }}
-                                                </span>
-                                            </div>
+                                        <GroupPicker module_code=module_code selected=group_ids/>
+
+                                    <Show when=move || in_series.get()>
+                                        <label class="label" style="margin-top:16px;">"Apply changes to"</label>
+                                        <select class="input" bind:value=edit_scope>
+                                            <option value="this" selected>"This class only"</option>
+                                            <option value="following">"This and following classes"</option>
+                                            <option value="all">"All upcoming classes in the series"</option>
+                                        </select>
+                                    </Show>
+
+                                    <Show
+                                        when=move || !in_series.get() || edit_scope.get() != "this"
+                                        fallback=|| view! {
                                             <p class="muted" style="font-size:12px; margin-top:6px;">
-                                                {move || {
-                                                    let count = recurrence_count.get().parse::<i32>().unwrap_or(8);
-                                                    let pattern = recurring.get();
-                                                    format!("This will create {} {} class instances", count,
-                                                        match pattern.as_str() {
-                                                            "Daily" => "daily",
-                                                            "Weekly" => "weekly",
-                                                            "Monthly" => "monthly",
-                                                            _ => ""
-                                                        }
-                                                    )
-                                                }}
+                                                "This class stays in its series. Choose the following or all classes to change how it repeats."
                                             </p>
-                                        </div>
+                                        }
+                                    >
+                                        <RecurrenceFields form=recurrence start_date=date/>
                                     </Show>
                                 </div>
 
@@ -324,6 +382,15 @@ pub fn EditClass() -> impl IntoView {
                                             <option value="105">"1 hour 45 min"</option>
                                             <option value="120">"2 hours"</option>
                                         </select>
+
+                                        <label class="label" style="margin-top:16px;">"Check-in window"</label>
+                                        <div style="display:flex; gap:8px;">
+                                            <input class="input" type="number" min="0" placeholder="On time (min)" bind:value=on_time_minutes/>
+                                            <input class="input" type="number" min="0" placeholder="Late until (min)" bind:value=late_minutes/>
+                                        </div>
+                                        <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                                            "Leave blank to use the module's window."
+                                        </p>
                                     </aside>
                                 </div>
 
@@ -333,6 +400,8 @@ pub fn EditClass() -> impl IntoView {
                                     </p>
                                 </Show>
 
+                                <ClashWarnings clashes=clashes allow_clashes=allow_clashes/>
+
                                 <div class="actions-row">
                                     <button class="btn btn-accent" on:click=on_submit>"✓ Save Class"</button>
                                     <button
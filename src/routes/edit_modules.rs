@@ -1,3 +1,5 @@
+use crate::components::{ModuleJoinPanel, TutorialGroupsPanel};
+use crate::database::classes::CheckInWindow;
 use crate::routes::module_functions::{get_module_fn, update_module_fn};
 use crate::routes::student_functions::*;
 use crate::user_context::get_current_user;
@@ -29,6 +31,9 @@ pub fn EditModule() -> impl IntoView {
 
     let title = RwSignal::new(String::new());
     let desc = RwSignal::new(String::new());
+    let allow_walk_ins = RwSignal::new(false);
+    let on_time_minutes = RwSignal::new(String::new());
+    let late_minutes = RwSignal::new(String::new());
     let message = RwSignal::new(String::new());
     let success = RwSignal::new(false);
 
@@ -101,6 +106,9 @@ pub fn EditModule() -> impl IntoView {
         if let Some(Some(module)) = module_resource.get() {
             title.set(module.module_title.clone());
             desc.set(module.description.unwrap_or_default());
+            allow_walk_ins.set(module.allow_walk_ins);
+            on_time_minutes.set(module.check_in_window.on_time_minutes.to_string());
+            late_minutes.set(module.check_in_window.late_minutes.to_string());
         }
     });
 
@@ -119,11 +127,19 @@ pub fn EditModule() -> impl IntoView {
     });
 
     let update_action = Action::new(
-        move |(code, title_val, desc_val): &(String, String, Option<String>)| {
+        move |(code, title_val, desc_val, walk_ins, window): &(
+            String,
+            String,
+            Option<String>,
+            bool,
+            CheckInWindow,
+        )| {
             let code = code.clone();
             let title_val = title_val.clone();
             let desc_val = desc_val.clone();
-            async move { update_module_fn(code, title_val, desc_val).await }
+            let walk_ins = *walk_ins;
+            let window = *window;
+            async move { update_module_fn(code, title_val, desc_val, walk_ins, window).await }
         },
     );
 
@@ -176,7 +192,24 @@ pub fn EditModule() -> impl IntoView {
             Some(desc.get())
         };
 
-        update_action.dispatch((code, title.get(), desc_val));
+        let (Ok(on_time), Ok(late)) = (
+            on_time_minutes.get().trim().parse::<i32>(),
+            late_minutes.get().trim().parse::<i32>(),
+        ) else {
+            message.set("Please enter the check-in window in whole minutes".to_string());
+            return;
+        };
+
+        update_action.dispatch((
+            code,
+            title.get(),
+            desc_val,
+            allow_walk_ins.get(),
+            CheckInWindow {
+                on_time_minutes: on_time,
+                late_minutes: late,
+            },
+        ));
     };
 
     // Handle delete module
@@ -391,6 +424,18 @@ pub fn EditModule() -> impl IntoView {
         }
     });
 
+    // Backdate or clear when a student's enrollment starts
+    let on_change_enrolled_on = move |email: String, date: String| {
+        let code = module_code.get();
+        let date = Some(date).filter(|d| !d.is_empty());
+        leptos::task::spawn_local(async move {
+            match set_enrollment_start_fn(code, email, date).await {
+                Ok(response) => student_message.set(response.message),
+                Err(e) => student_message.set(format!("Error: {}", e)),
+            }
+        });
+    };
+
     // Tutor management handlers
     let handle_add_tutor = move |_: leptos::ev::MouseEvent| {
         new_tutor_email.set(String::new());
@@ -526,6 +571,28 @@ pub fn EditModule() -> impl IntoView {
                                     bind:value=desc
                                 ></textarea>
 
+                                <label class="label" style="margin-top:10px; display:flex; align-items:center; gap:8px;">
+                                    <input type="checkbox" bind:checked=allow_walk_ins/>
+                                    "Allow walk-ins"
+                                </label>
+                                <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                                    "Students who aren't enrolled can scan in; their check-ins wait for your approval."
+                                </p>
+
+                                <div style="display:flex; gap:12px; margin-top:10px;">
+                                    <div style="flex:1;">
+                                        <label class="label">"On time for (min)"</label>
+                                        <input class="input" type="number" min="0" bind:value=on_time_minutes/>
+                                    </div>
+                                    <div style="flex:1;">
+                                        <label class="label">"Late until (min)"</label>
+                                        <input class="input" type="number" min="0" bind:value=late_minutes/>
+                                    </div>
+                                </div>
+                                <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                                    "Minutes after a class starts. Scans after the late cut-off are refused. Classes can override this."
+                                </p>
+
                                 <Show when=move || !message.get().is_empty()>
                                     <p class=move || if success.get() { "success center" } else { "error center" } style="margin-top:12px;">
                                         {message}
@@ -619,6 +686,14 @@ pub fn EditModule() -> impl IntoView {
 
                                 <div class="divider"></div>
 
+                                <TutorialGroupsPanel module_code=module_code/>
+
+                                <div class="divider"></div>
+
+                                <ModuleJoinPanel module_code=module_code refresh=bulk_enroll_action.version()/>
+
+                                <div class="divider"></div>
+
                                 <div class="heading" style="display:flex; align-items:center; justify-content:space-between;">
                                     <span>"Student Management"</span>
                                     <div style="display:flex; gap:8px;">
@@ -657,17 +732,29 @@ pub fn EditModule() -> impl IntoView {
                                                 <tr>
                                                     <th>"Name"</th>
                                                     <th>"Email"</th>
+                                                    <th title="Classes before this date don't count against the student. Blank counts from the first class.">"Enrolled from"</th>
                                                     <th>"Action"</th>
                                                 </tr>
                                             </thead>
                                             <tbody>
                                                 {move || students.get().into_iter().map(|student| {
                                                     let email = student.email_address.clone();
+                                                    let email_for_date = email.clone();
                                                     let full_name = format!("{} {}", student.name, student.surname);
                                                     view! {
                                                         <tr>
                                                             <td>{full_name.clone()}</td>
                                                             <td>{email.clone()}</td>
+                                                            <td>
+                                                                <input
+                                                                    class="input"
+                                                                    type="date"
+                                                                    prop:value=student.enrolled_on.clone().unwrap_or_default()
+                                                                    on:change=move |ev| {
+                                                                        on_change_enrolled_on(email_for_date.clone(), event_target_value(&ev));
+                                                                    }
+                                                                />
+                                                            </td>
                                                             <td>
                                                                 <button
                                                                     class="btn btn-outline btn-small"
@@ -766,7 +853,7 @@ pub fn EditModule() -> impl IntoView {
                             <p class="modal-text">
                                 "Are you sure you want to remove "
                                 <strong>{move || student_name_to_remove.get()}</strong>
-                                " from this module?"
+                                " from this module? Their attendance up to today is kept."
                             </p>
 
                             <div class="modal-actions">
@@ -0,0 +1,224 @@
+use crate::database::excuses::{ExcuseAttachment, ExcuseRequest};
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::database::{
+    classes::get_class_by_id,
+    excuses::{
+        decide_excuse_request, get_excuse_request, get_excuse_status, get_pending_excuses_for_staff,
+        get_stored_attachment, upsert_excuse_request, StoredAttachment,
+    },
+    db_pool,
+    modules::is_student_enrolled,
+};
+#[cfg(feature = "ssr")]
+use crate::authorization::{forbidden, require_class_staff, require_role, Role, STAFF};
+#[cfg(feature = "ssr")]
+use crate::error::db_error;
+#[cfg(feature = "ssr")]
+use crate::session::require_user;
+#[cfg(feature = "ssr")]
+use crate::uploads;
+
+pub const MAX_REASON_LENGTH: usize = 2000;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExcuseResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExcuseRequestsResponse {
+    pub success: bool,
+    pub message: String,
+    pub requests: Vec<ExcuseRequest>,
+}
+
+/// Ask for an absence from a class to be excused, optionally with a supporting document
+#[server(SubmitExcuse, "/api")]
+pub async fn submit_excuse_fn(
+    class_id: i64,
+    reason: String,
+    attachment: Option<ExcuseAttachment>,
+) -> Result<ExcuseResponse, ClockItError> {
+    use base64::Engine;
+
+    let reason = reason.trim().to_string();
+    if reason.is_empty() {
+        return Err(ClockItError::Validation(
+            "Please give a reason for your absence".to_string(),
+        ));
+    }
+    if reason.chars().count() > MAX_REASON_LENGTH {
+        return Err(ClockItError::Validation(format!(
+            "Please keep your reason under {} characters",
+            MAX_REASON_LENGTH
+        )));
+    }
+
+    let pool = db_pool()?;
+
+    let student = require_role(&pool, &[Role::Student]).await?;
+
+    let class = get_class_by_id(&pool, class_id).await?;
+
+    let enrolled = is_student_enrolled(&pool, &class.module_code, &student.email_address).await?;
+    if !enrolled {
+        return Err(forbidden());
+    }
+
+    let attendance_status: Option<String> =
+        sqlx::query_scalar("SELECT status FROM attendance WHERE classID = ? AND studentID = ?")
+            .bind(class_id)
+            .bind(student.user_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(db_error("Failed to check attendance"))?;
+
+    if matches!(attendance_status.as_deref(), Some("present") | Some("late")) {
+        return Err(ClockItError::Conflict(
+            "You were checked in to this class".to_string(),
+        ));
+    }
+
+    let existing = get_excuse_status(&pool, class_id, student.user_id).await?;
+    if existing.as_deref() == Some("approved") {
+        return Err(ClockItError::Conflict(
+            "Your absence from this class is already excused".to_string(),
+        ));
+    }
+
+    let stored = match attachment {
+        Some(attachment) => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(attachment.data_base64.trim())
+                .map_err(|_| {
+                    ClockItError::Validation("The attachment could not be read".to_string())
+                })?;
+
+            let path = uploads::save_upload("excuses", &attachment.content_type, &bytes)
+                .await
+                .map_err(ClockItError::Validation)?;
+
+            Some(StoredAttachment {
+                path,
+                file_name: attachment.file_name.trim().to_string(),
+                content_type: attachment.content_type.trim().to_ascii_lowercase(),
+            })
+        }
+        None => None,
+    };
+
+    match upsert_excuse_request(&pool, class_id, student.user_id, &reason, stored.as_ref()).await {
+        Ok(replaced) => {
+            if let Some(replaced) = replaced {
+                if let Err(e) = uploads::remove_upload(&replaced.path).await {
+                    eprintln!("⚠️ {}", e);
+                }
+            }
+
+            println!(
+                "📝 Excuse submitted by {} for class {}",
+                student.email_address, class_id
+            );
+
+            Ok(ExcuseResponse {
+                success: true,
+                message: "Your excuse has been sent to your lecturer".to_string(),
+            })
+        }
+        Err(e) => {
+            if let Some(stored) = stored {
+                let _ = uploads::remove_upload(&stored.path).await;
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Pending excuses across every module the signed-in lecturer or tutor teaches
+#[server(GetPendingExcuses, "/api")]
+pub async fn get_pending_excuses_fn() -> Result<ExcuseRequestsResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let staff = require_role(&pool, STAFF).await?;
+
+    let requests = get_pending_excuses_for_staff(&pool, &staff.email_address).await?;
+
+    Ok(ExcuseRequestsResponse {
+        success: true,
+        message: "Excuse requests fetched successfully".to_string(),
+        requests,
+    })
+}
+
+/// Download the document attached to an excuse. Open to the class's staff and
+/// the student who submitted it.
+#[server(GetExcuseAttachment, "/api")]
+pub async fn get_excuse_attachment_fn(request_id: i64) -> Result<ExcuseAttachment, ClockItError> {
+    use base64::Engine;
+
+    let pool = db_pool()?;
+
+    let user = require_user(&pool).await?;
+
+    let Some(request) = get_excuse_request(&pool, request_id).await? else {
+        return Err(forbidden());
+    };
+
+    if user.user_id != request.student_id {
+        require_class_staff(&pool, request.class_id).await?;
+    }
+
+    let Some(stored) = get_stored_attachment(&pool, request_id).await? else {
+        return Err(ClockItError::NotFound(
+            "This excuse has no attachment".to_string(),
+        ));
+    };
+
+    let bytes = uploads::read_upload(&stored.path)
+        .await
+        .map_err(ClockItError::Internal)?;
+
+    Ok(ExcuseAttachment {
+        file_name: stored.file_name,
+        content_type: stored.content_type,
+        data_base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+    })
+}
+
+/// Approve an excuse (marking the student excused for the class) or reject it
+#[server(DecideExcuse, "/api")]
+pub async fn decide_excuse_fn(
+    request_id: i64,
+    approve: bool,
+) -> Result<ExcuseResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let Some(request) = get_excuse_request(&pool, request_id).await? else {
+        require_role(&pool, STAFF).await?;
+        return Err(forbidden());
+    };
+
+    let staff = require_class_staff(&pool, request.class_id).await?;
+
+    let decided = decide_excuse_request(&pool, request_id, approve, &staff.email_address).await?;
+
+    if !decided {
+        return Err(ClockItError::Conflict(
+            "This excuse has already been handled".to_string(),
+        ));
+    }
+
+    let name = format!("{} {}", request.student_name, request.student_surname);
+    Ok(ExcuseResponse {
+        success: true,
+        message: if approve {
+            format!("{} excused from {}", name, request.class_title)
+        } else {
+            format!("Excuse from {} rejected", name)
+        },
+    })
+}
@@ -1,6 +1,7 @@
 use leptos::prelude::*;
 use leptos_router::components::A;
 use crate::routes::auth_functions::{send_password_reset_otp, verify_otp, reset_password};
+use crate::types::OtpPurpose;
 
 #[component]
 pub fn ForgotPassword() -> impl IntoView {
@@ -25,7 +26,7 @@ pub fn ForgotPassword() -> impl IntoView {
     let verify_otp_action = Action::new(|(email, otp): &(String, String)| {
         let email = email.clone();
         let otp = otp.clone();
-        async move { verify_otp(email, otp).await }
+        async move { verify_otp(email, otp, OtpPurpose::PasswordReset).await }
     });
     
     let reset_password_action = Action::new(|(email, password, confirm): &(String, String, String)| {
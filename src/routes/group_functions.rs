@@ -0,0 +1,144 @@
+use crate::database::groups::{GroupRequest, TutorialGroup};
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::database::{
+    db_pool,
+    groups::{
+        create_group, delete_group, get_class_group_ids, get_group, get_group_members,
+        get_module_groups, set_group_members, update_group,
+    },
+};
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_class_staff, require_module_owner, require_module_staff};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct GroupResponse {
+    pub success: bool,
+    pub message: String,
+    pub group: Option<TutorialGroup>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct GroupsResponse {
+    pub success: bool,
+    pub message: String,
+    pub groups: Vec<TutorialGroup>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct GroupMembersResponse {
+    pub success: bool,
+    pub message: String,
+    pub members: Vec<String>,
+}
+
+/// A module's tutorial groups, for its lecturers and tutors
+#[server(GetModuleGroups, "/api")]
+pub async fn get_module_groups_fn(module_code: String) -> Result<GroupsResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
+
+    let groups = get_module_groups(&pool, &module_code).await?;
+
+    Ok(GroupsResponse {
+        success: true,
+        message: "Groups retrieved".to_string(),
+        groups,
+    })
+}
+
+/// Create a group in a module, or update it when `group_id` is given
+#[server(SaveGroup, "/api")]
+pub async fn save_group_fn(
+    module_code: String,
+    group_id: Option<i64>,
+    request: GroupRequest,
+) -> Result<GroupResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    request.validate().map_err(ClockItError::Validation)?;
+
+    let group = match group_id {
+        Some(group_id) => {
+            if get_group(&pool, group_id).await?.module_code != module_code {
+                return Err(ClockItError::NotFound("Group not found".to_string()));
+            }
+            update_group(&pool, group_id, &request).await
+        }
+        None => create_group(&pool, &module_code, &request).await,
+    }?;
+
+    Ok(GroupResponse {
+        success: true,
+        message: format!("Saved {}", group.name),
+        group: Some(group),
+    })
+}
+
+#[server(DeleteGroup, "/api")]
+pub async fn delete_group_fn(group_id: i64) -> Result<GroupResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let group = get_group(&pool, group_id).await?;
+    require_module_owner(&pool, &group.module_code).await?;
+
+    delete_group(&pool, group_id).await?;
+
+    Ok(GroupResponse {
+        success: true,
+        message: format!("Deleted {}", group.name),
+        group: None,
+    })
+}
+
+#[server(GetGroupMembers, "/api")]
+pub async fn get_group_members_fn(group_id: i64) -> Result<GroupMembersResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let group = get_group(&pool, group_id).await?;
+    require_module_staff(&pool, &group.module_code).await?;
+
+    let members = get_group_members(&pool, group_id).await?;
+
+    Ok(GroupMembersResponse {
+        success: true,
+        message: "Members retrieved".to_string(),
+        members,
+    })
+}
+
+/// Replace a group's members. Every email has to be enrolled in the module.
+#[server(SetGroupMembers, "/api")]
+pub async fn set_group_members_fn(
+    group_id: i64,
+    emails: Vec<String>,
+) -> Result<GroupMembersResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let group = get_group(&pool, group_id).await?;
+    require_module_owner(&pool, &group.module_code).await?;
+
+    let count = set_group_members(&pool, group_id, &emails).await?;
+    let members = get_group_members(&pool, group_id).await?;
+
+    Ok(GroupMembersResponse {
+        success: true,
+        message: format!("{} now has {} member(s)", group.name, count),
+        members,
+    })
+}
+
+/// The groups a class is aimed at; empty when it is for the whole module
+#[server(GetClassGroups, "/api")]
+pub async fn get_class_groups_fn(class_id: i64) -> Result<Vec<i64>, ClockItError> {
+    let pool = db_pool()?;
+
+    require_class_staff(&pool, class_id).await?;
+
+    get_class_group_ids(&pool, class_id).await
+}
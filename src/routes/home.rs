@@ -1,4 +1,4 @@
-use crate::components::{Calendar, ClassList, Header, StatTile};
+use crate::components::{Calendar, ClassList, ExcuseReviewPanel, Header, StatTile};
 use crate::database::modules::ModuleWithStats;
 use crate::routes::class_functions::get_lecturer_classes_fn;
 use crate::routes::module_functions::{get_lecturer_modules_fn, get_tutor_modules_fn};
@@ -36,8 +36,8 @@ pub fn HomePage() -> impl IntoView {
             match user {
                 Some(user) => {
                     let response = match user.role.as_str() {
-                        "tutor" => get_tutor_modules_fn(user.email_address.clone()).await,
-                        _ => get_lecturer_modules_fn(user.email_address.clone()).await,
+                        "tutor" => get_tutor_modules_fn().await,
+                        _ => get_lecturer_modules_fn().await,
                     };
                     match response {
                         Ok(response) if response.success => Some(response.modules),
@@ -54,8 +54,8 @@ pub fn HomePage() -> impl IntoView {
         move || (current_user.get(), refresh_trigger.get()),
         |(user, _)| async move {
             match user {
-                Some(user) => {
-                    match get_lecturer_classes_fn(user.email_address.clone()).await {
+                Some(_) => {
+                    match get_lecturer_classes_fn().await {
                         Ok(response) if response.success => Some(response.classes),
                         _ => None,
                     }
@@ -215,6 +215,7 @@ pub fn HomePage() -> impl IntoView {
                     <div class="classes-scroll-container">
                     <ClassList classes=filtered_classes/>
                     </div>
+                    <ExcuseReviewPanel/>
                 </aside>
             </div>
         </section>
@@ -0,0 +1,133 @@
+use crate::error::ClockItError;
+use crate::utils::timetable_import::TimetableImport;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::authorization::require_module_staff;
+#[cfg(feature = "ssr")]
+use crate::database::{
+    classes::{create_class, get_module_classes, CreateClassRequest},
+    db_pool, DbPool,
+};
+#[cfg(feature = "ssr")]
+use crate::utils::{ics::parse_events, timetable_import::plan_import};
+#[cfg(feature = "ssr")]
+use std::collections::HashSet;
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct TimetableImportResponse {
+    pub success: bool,
+    pub message: String,
+    pub import: TimetableImport,
+}
+
+/// The classes an .ics timetable holds for `module_code`, with the ones the module
+/// already has marked as duplicates
+#[cfg(feature = "ssr")]
+async fn read_timetable(
+    pool: &DbPool,
+    module_code: &str,
+    ics: &str,
+) -> Result<TimetableImport, ClockItError> {
+    let events = parse_events(ics).map_err(ClockItError::Validation)?;
+    if events.is_empty() {
+        return Err(ClockItError::Validation(
+            "The file doesn't contain any events".to_string(),
+        ));
+    }
+
+    let existing: HashSet<(String, String)> = get_module_classes(pool, module_code)
+        .await?
+        .into_iter()
+        .map(|class| (class.date, class.time))
+        .collect();
+
+    let mut import = plan_import(&events);
+    import.mark_duplicates(&existing);
+    Ok(import)
+}
+
+/// What importing a timetable into `module_code` would create, without saving anything
+#[server(PreviewTimetableImport, "/api")]
+pub async fn preview_timetable_import_fn(
+    module_code: String,
+    ics: String,
+) -> Result<TimetableImportResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
+
+    let import = read_timetable(&pool, &module_code, &ics).await?;
+    let new = import.new_classes().count();
+    let message = match (new, import.classes.len() - new) {
+        (0, 0) => "No classes found in the file".to_string(),
+        (0, _) => "Every class in the file is already in this module".to_string(),
+        (new, 0) => format!("{} classes to import", new),
+        (new, existing) => format!(
+            "{} classes to import; {} are already in this module and will be skipped",
+            new, existing
+        ),
+    };
+
+    Ok(TimetableImportResponse {
+        success: true,
+        message,
+        import,
+    })
+}
+
+/// Create a class in `module_code` for each class in the timetable it doesn't
+/// already have. Importing the same file again adds nothing.
+#[server(ImportTimetable, "/api")]
+pub async fn import_timetable_fn(
+    module_code: String,
+    ics: String,
+) -> Result<TimetableImportResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    // Classes are attributed to the signed-in user
+    let created_by = require_module_staff(&pool, &module_code)
+        .await?
+        .email_address;
+
+    let import = read_timetable(&pool, &module_code, &ics).await?;
+
+    let mut created = 0;
+    for class in import.new_classes() {
+        create_class(
+            &pool,
+            CreateClassRequest {
+                module_code: module_code.clone(),
+                title: class.title.clone(),
+                venue: class.venue.clone(),
+                description: None,
+                recurring: None,
+                date: class.date.clone(),
+                time: class.time.clone(),
+                duration_minutes: class.duration_minutes,
+                created_by: Some(created_by.clone()),
+                check_in_window: None,
+                series_id: None,
+                group_ids: Vec::new(),
+            },
+        )
+        .await?;
+        created += 1;
+    }
+
+    let skipped = import.classes.len() - created;
+    let message = match (created, skipped) {
+        (0, _) => "Nothing to import: every class is already in this module".to_string(),
+        (created, 0) => format!("Imported {} classes", created),
+        (created, skipped) => format!(
+            "Imported {} classes; {} were already in this module",
+            created, skipped
+        ),
+    };
+
+    Ok(TimetableImportResponse {
+        success: true,
+        message,
+        import,
+    })
+}
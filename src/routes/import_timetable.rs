@@ -0,0 +1,184 @@
+use crate::error::ClockItError;
+use crate::routes::import_functions::{import_timetable_fn, TimetableImportResponse};
+use crate::utils::timetable_import::TimetableImport;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use leptos_router::components::A;
+use leptos_router::hooks::use_query_map;
+
+/// Import a module's classes from the timetable .ics file the university system exports
+#[component]
+pub fn ImportTimetable() -> impl IntoView {
+    let query = use_query_map();
+    let module_code = Signal::derive(move || query.with(|q| q.get("module").unwrap_or_default()));
+
+    // The file's text, kept so the import reads exactly what was previewed
+    let ics = RwSignal::new(None::<String>);
+    let preview = RwSignal::new(None::<TimetableImport>);
+    let imported = RwSignal::new(false);
+    let message = RwSignal::new(String::new());
+    let success = RwSignal::new(false);
+    let busy = RwSignal::new(false);
+
+    let handle_response = move |result: Result<TimetableImportResponse, ClockItError>| {
+        busy.set(false);
+        match result {
+            Ok(response) => {
+                message.set(response.message);
+                success.set(response.success);
+                preview.set(Some(response.import));
+            }
+            Err(e) => {
+                message.set(e.to_string());
+                success.set(false);
+                preview.set(None);
+            }
+        }
+    };
+
+    let on_file = move |ev: leptos::ev::Event| {
+        #[cfg(feature = "hydrate")]
+        {
+            use crate::routes::import_functions::preview_timetable_import_fn;
+            use wasm_bindgen::JsCast;
+
+            let Some(input) = ev
+                .target()
+                .and_then(|target| target.dyn_into::<web_sys::HtmlInputElement>().ok())
+            else {
+                return;
+            };
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let file = gloo::file::File::from(file);
+            busy.set(true);
+            imported.set(false);
+            spawn_local(async move {
+                match gloo::file::futures::read_as_text(&file).await {
+                    Ok(text) => {
+                        ics.set(Some(text.clone()));
+                        handle_response(
+                            preview_timetable_import_fn(module_code.get_untracked(), text).await,
+                        );
+                    }
+                    Err(e) => {
+                        busy.set(false);
+                        message.set(format!("Could not read file: {}", e));
+                        success.set(false);
+                    }
+                }
+            });
+        }
+
+        #[cfg(not(feature = "hydrate"))]
+        let _ = ev;
+    };
+
+    let on_import = move |_| {
+        let Some(text) = ics.get() else {
+            return;
+        };
+        busy.set(true);
+        spawn_local(async move {
+            let result = import_timetable_fn(module_code.get_untracked(), text).await;
+            if result.is_ok() {
+                imported.set(true);
+                ics.set(None);
+            }
+            handle_response(result);
+        });
+    };
+
+    let new_count = move || {
+        preview
+            .get()
+            .map(|import| import.new_classes().count())
+            .unwrap_or(0)
+    };
+
+    view! {
+        <section class="import-timetable-page">
+            <div class="page-header" style="display:flex;align-items:center;gap:8px;">
+                <A href=move || format!("/classes?module={}", module_code.get()) attr:class="link">"←"</A>
+                <h1 class="page-title">{move || format!("Import Timetable • {}", module_code.get())}</h1>
+            </div>
+            <p class="muted" style="margin-bottom:12px;">
+                "Choose the .ics file exported from the university timetable. Repeating classes are expanded into one class per date. Classes the module already has at the same date and time are skipped, so importing the same file again is safe."
+            </p>
+
+            <div class="card" style="margin-bottom:16px;">
+                <label class="label">"Timetable file (.ics)"</label>
+                <input
+                    class="input"
+                    type="file"
+                    accept=".ics,text/calendar"
+                    disabled=move || busy.get() || module_code.get().is_empty()
+                    on:change=on_file
+                />
+            </div>
+
+            <Show when=move || !message.get().is_empty()>
+                <p class=move || if success.get() { "success center" } else { "error center" } style="margin-bottom:12px;">
+                    {message}
+                </p>
+            </Show>
+
+            {move || preview.get().map(|import| {
+                let has_skipped = !import.skipped.is_empty();
+                let skipped = import.skipped;
+                view! {
+                    <div class="card">
+                        <Show when=move || !imported.get()>
+                            <div style="display:flex; justify-content:space-between; align-items:center; margin-bottom:12px;">
+                                <h3 class="heading">"Preview"</h3>
+                                <button
+                                    class="btn btn-accent"
+                                    on:click=on_import
+                                    disabled=move || busy.get() || new_count() == 0
+                                >
+                                    {move || match new_count() {
+                                        1 => "Import 1 class".to_string(),
+                                        n => format!("Import {} classes", n),
+                                    }}
+                                </button>
+                            </div>
+                        </Show>
+                        <table class="table">
+                            <thead>
+                                <tr>
+                                    <th>"Date"</th>
+                                    <th>"Time"</th>
+                                    <th>"Class"</th>
+                                    <th>"Venue"</th>
+                                    <th>"Duration"</th>
+                                    <th></th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                {import.classes.into_iter().map(|class| view! {
+                                    <tr class:muted=class.duplicate>
+                                        <td>{class.date}</td>
+                                        <td>{class.time}</td>
+                                        <td>{class.title}</td>
+                                        <td>{class.venue.unwrap_or_else(|| "—".to_string())}</td>
+                                        <td>{format!("{} min", class.duration_minutes)}</td>
+                                        <td>{if class.duplicate { "Already in module" } else { "" }}</td>
+                                    </tr>
+                                }).collect_view()}
+                            </tbody>
+                        </table>
+                        <Show when=move || has_skipped>
+                            <h4 class="heading" style="margin-top:12px;">"Not imported"</h4>
+                            <ul class="muted">
+                                {skipped.clone().into_iter().map(|reason| view! { <li>{reason}</li> }).collect_view()}
+                            </ul>
+                        </Show>
+                    </div>
+                }
+            })}
+        </section>
+    }
+}
@@ -0,0 +1,147 @@
+use crate::database::invitations::ModuleInvitation;
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_module_owner, require_module_staff, require_role, Role};
+#[cfg(feature = "ssr")]
+use crate::clock::clock;
+#[cfg(feature = "ssr")]
+use crate::database::{
+    db_pool,
+    invitations::{
+        cancel_invitation, get_module_invitations, join_code_for_module, join_module,
+        module_for_join_code, reset_join_code,
+    },
+    modules::get_module,
+    universities::module_today,
+};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JoinCodeResponse {
+    pub success: bool,
+    pub message: String,
+    pub code: String,
+    /// Path of the student home page with the code filled in
+    pub path: String,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct InvitationsResponse {
+    pub success: bool,
+    pub message: String,
+    pub invitations: Vec<ModuleInvitation>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct JoinModuleResponse {
+    pub success: bool,
+    pub message: String,
+    pub module_code: Option<String>,
+}
+
+#[cfg(feature = "ssr")]
+fn join_code_response(code: String, message: &str) -> JoinCodeResponse {
+    JoinCodeResponse {
+        success: true,
+        message: message.to_string(),
+        path: format!("/student/home?join={}", code),
+        code,
+    }
+}
+
+/// The module's join code, for its lecturers and tutors to hand out
+#[server(GetJoinCode, "/api")]
+pub async fn get_join_code_fn(module_code: String) -> Result<JoinCodeResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
+
+    let code = join_code_for_module(&pool, &module_code).await?;
+    Ok(join_code_response(code, "Join code retrieved"))
+}
+
+/// Replace the module's join code so the old one can't be used any more
+#[server(ResetJoinCode, "/api")]
+pub async fn reset_join_code_fn(module_code: String) -> Result<JoinCodeResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    let code = reset_join_code(&pool, &module_code).await?;
+    Ok(join_code_response(
+        code,
+        "Join code replaced. The old code and link no longer work.",
+    ))
+}
+
+/// Enroll the signed-in student in the module a join code belongs to, from today
+#[server(JoinModule, "/api")]
+pub async fn join_module_fn(code: String) -> Result<JoinModuleResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let user = require_role(&pool, &[Role::Student]).await?;
+
+    let module_code = module_for_join_code(&pool, &code)
+        .await?
+        .ok_or_else(|| ClockItError::NotFound("No module has this join code".to_string()))?;
+    let module = get_module(&pool, &module_code)
+        .await?
+        .ok_or_else(|| ClockItError::NotFound("Module not found".to_string()))?;
+
+    let email = user.email_address.trim().to_lowercase();
+    let enrolled_on = module_today(&pool, &module_code, &*clock()).await?;
+    if !join_module(&pool, &module_code, &email, enrolled_on).await? {
+        return Err(ClockItError::Conflict(format!(
+            "You are already enrolled in {}",
+            module_code
+        )));
+    }
+
+    Ok(JoinModuleResponse {
+        success: true,
+        message: format!("Joined {} - {}", module_code, module.module_title),
+        module_code: Some(module_code),
+    })
+}
+
+/// Emails invited to the module that haven't registered yet
+#[server(GetModuleInvitations, "/api")]
+pub async fn get_module_invitations_fn(
+    module_code: String,
+) -> Result<InvitationsResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
+
+    let invitations = get_module_invitations(&pool, &module_code).await?;
+
+    Ok(InvitationsResponse {
+        success: true,
+        message: "Invitations retrieved".to_string(),
+        invitations,
+    })
+}
+
+/// Withdraw an invitation, so the email isn't enrolled when it registers
+#[server(CancelInvitation, "/api")]
+pub async fn cancel_invitation_fn(
+    module_code: String,
+    email: String,
+) -> Result<InvitationsResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    if !cancel_invitation(&pool, &module_code, &email).await? {
+        return Err(ClockItError::NotFound("Invitation not found".to_string()));
+    }
+
+    let invitations = get_module_invitations(&pool, &module_code).await?;
+
+    Ok(InvitationsResponse {
+        success: true,
+        message: format!("Invitation for {} cancelled", email.trim()),
+        invitations,
+    })
+}
@@ -1,14 +1,21 @@
 
+pub mod academic_calendar;
 pub mod auth_functions;
+pub mod calendar_functions;
 pub mod class_functions;
 pub mod class_qr;
 pub mod classes;
 pub mod edit_class;
 pub mod edit_modules;
 pub mod error;
+pub mod excuse_functions;
 pub mod forgot_password;
+pub mod group_functions;
 pub mod helpers;
 pub mod home;
+pub mod import_functions;
+pub mod import_timetable;
+pub mod invitation_functions;
 pub mod login;
 pub mod module_functions;
 pub mod new_class;
@@ -20,8 +27,12 @@ pub mod statistics;
 pub mod stats_functions;
 pub mod student_functions;
 pub mod timetable;
+pub mod university_functions;
+pub mod venue_functions;
+pub mod venues;
 
 
+pub use academic_calendar::AcademicCalendarPage;
 pub use class_qr::{ClassQrFullscreenPage, ClassQrPage};
 pub use classes::ClassesPage;
 pub use edit_class::EditClass;
@@ -29,6 +40,7 @@ pub use edit_modules::EditModule;
 pub use error::Error;
 pub use forgot_password::ForgotPassword;
 pub use home::HomePage;
+pub use import_timetable::ImportTimetable;
 pub use login::Login;
 pub use new_class::NewClass;
 pub use new_module::NewModule;
@@ -36,3 +48,4 @@ pub use profile::Profile;
 pub use register::Register;
 pub use statistics::Statistics;
 pub use timetable::Timetable;
+pub use venues::Venues;
@@ -1,13 +1,17 @@
+use crate::database::classes::CheckInWindow;
 use crate::database::modules::{CreateModuleRequest, Module, ModuleWithStats, UpdateModuleRequest};
+use crate::error::ClockItError;
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
 use crate::database::{
-    init_db_pool,
+    db_pool,
     modules::{
         create_module, delete_module, get_lecturer_modules_with_stats, get_tutor_modules_with_stats, get_module, update_module,
     },
 };
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_module_owner, require_module_staff, require_role, Role};
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct ModuleResponse {
@@ -29,29 +33,25 @@ pub async fn create_module_fn(
     module_code: String,
     module_title: String,
     description: Option<String>,
-    lecturer_email: String,
-) -> Result<ModuleResponse, ServerFnError> {
+) -> Result<ModuleResponse, ClockItError> {
     // Validate input
     if module_code.trim().is_empty() {
-        return Ok(ModuleResponse {
-            success: false,
-            message: "Module code is required".to_string(),
-            module: None,
-        });
+        return Err(ClockItError::Validation(
+            "Module code is required".to_string(),
+        ));
     }
 
     if module_title.trim().is_empty() {
-        return Ok(ModuleResponse {
-            success: false,
-            message: "Module title is required".to_string(),
-            module: None,
-        });
+        return Err(ClockItError::Validation(
+            "Module title is required".to_string(),
+        ));
     }
 
     // Initialize database connection
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
+
+    // The signed-in lecturer becomes the module's owner
+    let lecturer_email = require_role(&pool, &[Role::Lecturer]).await?.email_address;
 
     let request = CreateModuleRequest {
         module_code: module_code.trim().to_string(),
@@ -59,90 +59,63 @@ pub async fn create_module_fn(
         description: description.filter(|s| !s.trim().is_empty()),
     };
 
-    match create_module(&pool, &lecturer_email, request).await {
-        Ok(module) => Ok(ModuleResponse {
-            success: true,
-            message: "Module created successfully!".to_string(),
-            module: Some(module),
-        }),
-        Err(e) => Ok(ModuleResponse {
-            success: false,
-            message: e,
-            module: None,
-        }),
-    }
+    let module = create_module(&pool, &lecturer_email, request).await?;
+
+    Ok(ModuleResponse {
+        success: true,
+        message: "Module created successfully!".to_string(),
+        module: Some(module),
+    })
 }
 
 /// Get all modules for the current lecturer
 #[server(GetLecturerModules, "/api")]
-pub async fn get_lecturer_modules_fn(
-    lecturer_email: String,
-) -> Result<ModulesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    match get_lecturer_modules_with_stats(&pool, &lecturer_email).await {
-        Ok(modules) => Ok(ModulesListResponse {
-            success: true,
-            message: "Modules fetched successfully".to_string(),
-            modules,
-        }),
-        Err(e) => Ok(ModulesListResponse {
-            success: false,
-            message: e,
-            modules: vec![],
-        }),
-    }
+pub async fn get_lecturer_modules_fn() -> Result<ModulesListResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let lecturer_email = require_role(&pool, &[Role::Lecturer]).await?.email_address;
+
+    let modules = get_lecturer_modules_with_stats(&pool, &lecturer_email).await?;
+
+    Ok(ModulesListResponse {
+        success: true,
+        message: "Modules fetched successfully".to_string(),
+        modules,
+    })
 }
 
 /// Get all modules for the current tutor
 #[server(GetTutorModules, "/api")]
-pub async fn get_tutor_modules_fn(
-    tutor_email: String,
-) -> Result<ModulesListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    match get_tutor_modules_with_stats(&pool, &tutor_email).await {
-        Ok(modules) => Ok(ModulesListResponse {
-            success: true,
-            message: "Modules fetched successfully".to_string(),
-            modules,
-        }),
-        Err(e) => Ok(ModulesListResponse {
-            success: false,
-            message: e,
-            modules: vec![],
-        }),
-    }
+pub async fn get_tutor_modules_fn() -> Result<ModulesListResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let tutor_email = require_role(&pool, &[Role::Tutor]).await?.email_address;
+
+    let modules = get_tutor_modules_with_stats(&pool, &tutor_email).await?;
+
+    Ok(ModulesListResponse {
+        success: true,
+        message: "Modules fetched successfully".to_string(),
+        modules,
+    })
 }
 
 /// Get a single module by code
 #[server(GetModule, "/api")]
-pub async fn get_module_fn(module_code: String) -> Result<ModuleResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    match get_module(&pool, &module_code).await {
-        Ok(Some(module)) => Ok(ModuleResponse {
-            success: true,
-            message: "Module found".to_string(),
-            module: Some(module),
-        }),
-        Ok(None) => Ok(ModuleResponse {
-            success: false,
-            message: "Module not found".to_string(),
-            module: None,
-        }),
-        Err(e) => Ok(ModuleResponse {
-            success: false,
-            message: e,
-            module: None,
-        }),
-    }
+pub async fn get_module_fn(module_code: String) -> Result<ModuleResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
+
+    let module = get_module(&pool, &module_code)
+        .await?
+        .ok_or_else(|| ClockItError::NotFound("Module not found".to_string()))?;
+
+    Ok(ModuleResponse {
+        success: true,
+        message: "Module found".to_string(),
+        module: Some(module),
+    })
 }
 
 /// Update a module
@@ -151,56 +124,52 @@ pub async fn update_module_fn(
     module_code: String,
     module_title: String,
     description: Option<String>,
-) -> Result<ModuleResponse, ServerFnError> {
+    allow_walk_ins: bool,
+    check_in_window: CheckInWindow,
+) -> Result<ModuleResponse, ClockItError> {
     if module_title.trim().is_empty() {
-        return Ok(ModuleResponse {
-            success: false,
-            message: "Module title is required".to_string(),
-            module: None,
-        });
+        return Err(ClockItError::Validation(
+            "Module title is required".to_string(),
+        ));
     }
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    check_in_window
+        .validate()
+        .map_err(ClockItError::Validation)?;
+
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
 
     let request = UpdateModuleRequest {
         module_code,
         module_title: module_title.trim().to_string(),
         description: description.filter(|s| !s.trim().is_empty()),
+        allow_walk_ins,
+        check_in_window,
     };
 
-    match update_module(&pool, request).await {
-        Ok(module) => Ok(ModuleResponse {
-            success: true,
-            message: "Module updated successfully!".to_string(),
-            module: Some(module),
-        }),
-        Err(e) => Ok(ModuleResponse {
-            success: false,
-            message: e,
-            module: None,
-        }),
-    }
+    let module = update_module(&pool, request).await?;
+
+    Ok(ModuleResponse {
+        success: true,
+        message: "Module updated successfully!".to_string(),
+        module: Some(module),
+    })
 }
 
 /// Delete a module
 #[server(DeleteModule, "/api")]
-pub async fn delete_module_fn(module_code: String) -> Result<ModuleResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    match delete_module(&pool, &module_code).await {
-        Ok(_) => Ok(ModuleResponse {
-            success: true,
-            message: "Module deleted successfully!".to_string(),
-            module: None,
-        }),
-        Err(e) => Ok(ModuleResponse {
-            success: false,
-            message: e,
-            module: None,
-        }),
-    }
+pub async fn delete_module_fn(module_code: String) -> Result<ModuleResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    delete_module(&pool, &module_code).await?;
+
+    Ok(ModuleResponse {
+        success: true,
+        message: "Module deleted successfully!".to_string(),
+        module: None,
+    })
 }
@@ -1,5 +1,10 @@
+use crate::components::{
+    ClashWarnings, GroupPicker, RecurrenceFields, RecurrenceForm, VenueOptions,
+};
+use crate::database::class_series::Recurrence;
+use crate::database::clashes::ClassClash;
+use crate::database::classes::CheckInWindow;
 use crate::routes::class_functions::create_class_fn;
-use crate::user_context::get_current_user;
 use leptos::prelude::*;
 use leptos_router::components::A;
 use leptos_router::hooks::{use_navigate, use_query_map};
@@ -8,13 +13,11 @@ use leptos_router::hooks::{use_navigate, use_query_map};
 pub fn NewClass() -> impl IntoView {
     let navigate = use_navigate();
     let query = use_query_map();
-    let current_user = get_current_user();
 
     let title = RwSignal::new(String::new());
     let venue = RwSignal::new(String::new());
     let desc = RwSignal::new(String::new());
-    let recurring = RwSignal::new("No repeat".to_string());
-    let recurrence_count = RwSignal::new("8".to_string()); // Store as String for input binding
+    let recurrence = RecurrenceForm::new();
     let date = RwSignal::new(String::new());
     // Set default time to 09:00 (common class start time)
     let hour = RwSignal::new("09".to_string());
@@ -26,8 +29,16 @@ pub fn NewClass() -> impl IntoView {
         format!("{:02}", num)
     };
     let duration = RwSignal::new("90".to_string());
+    // Blank means the module's check-in window applies
+    let on_time_minutes = RwSignal::new(String::new());
+    let late_minutes = RwSignal::new(String::new());
+    // No groups means the whole module
+    let group_ids = RwSignal::new(Vec::<i64>::new());
     let message = RwSignal::new(String::new());
     let success = RwSignal::new(false);
+    // What the last save clashed with, and whether the lecturer chose to save over it
+    let clashes = RwSignal::new(Vec::<ClassClash>::new());
+    let allow_clashes = RwSignal::new(false);
 
     // Get module code from URL query params
     let module_code = move || query.with(|q| q.get("module").unwrap_or_default());
@@ -38,46 +49,50 @@ pub fn NewClass() -> impl IntoView {
             title_val,
             venue_val,
             desc_val,
-            recurring_val,
+            recurrence_val,
             date_val,
             time_val,
             duration_val,
-            count,
-            created_by,
+            window,
+            groups,
+            allow,
         ): &(
             String,
             String,
             Option<String>,
             Option<String>,
-            Option<String>,
+            Option<Recurrence>,
             String,
             String,
             i32,
-            Option<i32>,
-            String,
+            Option<CheckInWindow>,
+            Vec<i64>,
+            bool,
         )| {
             let module = module.clone();
             let title_val = title_val.clone();
             let venue_val = venue_val.clone();
             let desc_val = desc_val.clone();
-            let recurring_val = recurring_val.clone();
+            let recurrence_val = recurrence_val.clone();
             let date_val = date_val.clone();
             let time_val = time_val.clone();
             let duration_val = *duration_val;
-            let count = *count;
-            let created_by = created_by.clone();
+            let window = *window;
+            let groups = groups.clone();
+            let allow = *allow;
             async move {
                 create_class_fn(
                     module,
                     title_val,
                     venue_val,
                     desc_val,
-                    recurring_val,
+                    recurrence_val,
                     date_val,
                     time_val,
                     duration_val,
-                    count,
-                    created_by,
+                    window,
+                    groups,
+                    allow,
                 )
                 .await
             }
@@ -119,35 +134,36 @@ pub fn NewClass() -> impl IntoView {
         } else {
             Some(desc.get())
         };
-        let recurring_val = if recurring.get() == "No repeat" {
-            None
-        } else {
-            Some(recurring.get())
-        };
-
-        // Only pass recurrence_count if recurring is enabled
-        let count_val = if recurring.get() != "No repeat" {
-            // Parse string to i32, default to 8 if invalid
-            recurrence_count.get().parse::<i32>().ok()
-        } else {
-            None
+        let recurrence_val = match recurrence.recurrence(&date.get()) {
+            Ok(recurrence_val) => recurrence_val,
+            Err(e) => {
+                message.set(e);
+                return;
+            }
         };
 
         let duration_val = duration.get().parse::<i32>().unwrap_or(90).max(15);
-        
-        let user_email = current_user.get().map(|u| u.email_address).unwrap_or_default();
+
+        let window = match CheckInWindow::parse_override(&on_time_minutes.get(), &late_minutes.get()) {
+            Ok(window) => window,
+            Err(e) => {
+                message.set(e);
+                return;
+            }
+        };
 
         create_action.dispatch((
             current_module,
             title.get(),
             venue_val,
             desc_val,
-            recurring_val,
+            recurrence_val,
             date.get(),
             time_str,
             duration_val,
-            count_val,
-            user_email,
+            window,
+            group_ids.get(),
+            allow_clashes.get(),
         ));
     };
 
@@ -159,6 +175,12 @@ pub fn NewClass() -> impl IntoView {
                     Ok(response) => {
                         message.set(response.message.clone());
                         success.set(response.success);
+                        if response.success {
+                            clashes.set(Vec::new());
+                        } else {
+                            allow_clashes.set(false);
+                            clashes.set(response.clashes);
+                        }
 
                         if response.success {
                             let nav = navigate.clone();
@@ -202,57 +224,15 @@ pub fn NewClass() -> impl IntoView {
                         <input class="input" placeholder="Linked List Fundamentals – Lecture 4" bind:value=title />
 
                         <label class="label" style="margin-top:10px;">"Venue"</label>
-                        <input class="input" placeholder="Room A101" bind:value=venue />
+                        <input class="input" placeholder="Room A101" list="venue-options" bind:value=venue />
+                        <VenueOptions id="venue-options"/>
 
                         <label class="label" style="margin-top:10px;">"Description"</label>
                         <textarea class="textarea" placeholder="Enter a class description" bind:value=desc></textarea>
 
-                        <label class="label" style="margin-top:10px;">"Recurring"</label>
-                        <select class="input" bind:value=recurring>
-                            <option selected>"No repeat"</option>
-                            <option>"Daily"</option>
-                            <option>"Weekly"</option>
-                            <option>"Monthly"</option>
-                        </select>
+                        <RecurrenceFields form=recurrence start_date=date/>
 
-                        // Show recurrence count input if recurring is enabled
-                        <Show when=move || recurring.get() != "No repeat">
-                            <div style="margin-top:10px;">
-                                <label class="label">"Number of Occurrences"</label>
-                                <div style="display:flex; align-items:center; gap:12px;">
-                                    <input
-                                        type="number"
-                                        class="input"
-                                        min="2"
-                                        max="52"
-                                        bind:value=recurrence_count
-                                        style="max-width:120px;"
-                                    />
-                                    <span class="muted" style="font-size:13px;">
-                                        {move || match recurring.get().as_str() {
-                                            "Daily" => "days",
-                                            "Weekly" => "weeks",
-                                            "Monthly" => "months",
-                                            _ => "instances"
-                                        }}
-                                    </span>
-                                </div>
-                                <p class="muted" style="font-size:12px; margin-top:6px;">
-                                    {move || {
-                                        let count = recurrence_count.get().parse::<i32>().unwrap_or(8);
-                                        let pattern = recurring.get();
-                                        format!("This will create {} {} class instances", count,
-                                            match pattern.as_str() {
-                                                "Daily" => "daily",
-                                                "Weekly" => "weekly",
-                                                "Monthly" => "monthly",
-                                                _ => ""
-                                            }
-                                        )
-                                    }}
-                                </p>
-                            </div>
-                        </Show>
+                        <GroupPicker module_code=Signal::derive(module_code) selected=group_ids/>
                     </div>
 
                     <aside class="form-side">
@@ -299,6 +279,15 @@ pub fn NewClass() -> impl IntoView {
                             <option value="105">"1 hour 45 min"</option>
                             <option value="120">"2 hours"</option>
                         </select>
+
+                        <label class="label" style="margin-top:16px;">"Check-in window"</label>
+                        <div style="display:flex; gap:8px;">
+                            <input class="input" type="number" min="0" placeholder="On time (min)" bind:value=on_time_minutes/>
+                            <input class="input" type="number" min="0" placeholder="Late until (min)" bind:value=late_minutes/>
+                        </div>
+                        <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                            "Leave blank to use the module's window."
+                        </p>
                     </aside>
                 </div>
 
@@ -308,6 +297,8 @@ pub fn NewClass() -> impl IntoView {
                     </p>
                 </Show>
 
+                <ClashWarnings clashes=clashes allow_clashes=allow_clashes/>
+
                 <div class="actions-row">
                     <button
                         class="btn btn-accent"
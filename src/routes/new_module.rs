@@ -2,7 +2,6 @@ use crate::routes::module_functions::create_module_fn;
 use crate::routes::student_functions::*;
 use crate::user_context::get_current_user;
 use leptos::prelude::*;
-use leptos::web_sys;
 use leptos_router::components::A;
 use leptos_router::hooks::use_navigate;
 
@@ -50,12 +49,11 @@ pub fn NewModule() -> impl IntoView {
     let created_module_code = RwSignal::new(String::new());
 
     let create_action = Action::new(
-        move |(code, title_val, desc_val, email): &(String, String, Option<String>, String)| {
+        move |(code, title_val, desc_val): &(String, String, Option<String>)| {
             let code = code.clone();
             let title_val = title_val.clone();
             let desc_val = desc_val.clone();
-            let email = email.clone();
-            async move { create_module_fn(code, title_val, desc_val, email).await }
+            async move { create_module_fn(code, title_val, desc_val).await }
         },
     );
 
@@ -104,19 +102,11 @@ pub fn NewModule() -> impl IntoView {
             return;
         }
 
-        let email = match current_user.get() {
-            Some(user) => {
-                web_sys::console::log_1(
-                    &format!("Creating module for email: {}", user.email_address).into(),
-                );
-                user.email_address
-            }
-            None => {
-                message.set("You must be logged in to create a module".to_string());
-                success.set(false);
-                return;
-            }
-        };
+        if current_user.get().is_none() {
+            message.set("You must be logged in to create a module".to_string());
+            success.set(false);
+            return;
+        }
 
         let desc_val = if desc.get().trim().is_empty() {
             None
@@ -125,7 +115,7 @@ pub fn NewModule() -> impl IntoView {
         };
 
         created_module_code.set(code.clone());
-        create_action.dispatch((code, title.get(), desc_val, email));
+        create_action.dispatch((code, title.get(), desc_val));
     };
 
     // Handle module creation response
@@ -1,3 +1,4 @@
+use crate::components::CalendarFeedPanel;
 use crate::routes::auth_functions::ResetPassword;
 use crate::routes::profile_functions::{update_profile, UpdateProfileRequest};
 use crate::user_context::{get_current_user, set_current_user};
@@ -48,16 +49,12 @@ pub fn Profile() -> impl IntoView {
         message.set(String::new());
         success.set(false);
 
-        let user_id = match current_user.get() {
-            Some(user) => user.user_id,
-            None => {
-                message.set("You must be logged in".to_string());
-                return;
-            }
-        };
+        if current_user.get().is_none() {
+            message.set("You must be logged in".to_string());
+            return;
+        }
 
         let request = UpdateProfileRequest {
-            user_id,
             name: name.get(),
             surname: surname.get(),
             email_address: email.get(),
@@ -317,6 +314,21 @@ pub fn Profile() -> impl IntoView {
                 </Show>
             </section>
 
+            <section class="profile-section" aria-labelledby="calendar-feed">
+                <div class="profile-section-header">
+                    <span class="profile-section-icon" aria-hidden="true">
+                        <svg width="18" height="18" viewBox="0 0 24 24" fill="none" stroke="currentColor" stroke-width="1.6" stroke-linecap="round" stroke-linejoin="round">
+                            <rect x="3" y="4" width="18" height="18" rx="2" ry="2"/>
+                            <line x1="16" y1="2" x2="16" y2="6"/>
+                            <line x1="8" y1="2" x2="8" y2="6"/>
+                            <line x1="3" y1="10" x2="21" y2="10"/>
+                        </svg>
+                    </span>
+                    <h2 id="calendar-feed" class="profile-section-title">"Calendar Subscription"</h2>
+                </div>
+                <CalendarFeedPanel/>
+            </section>
+
             <Show when=move || !message.get().is_empty()>
                 <p class=move || if success.get() { "success center" } else { "error center" } style="margin-top:16px;">
                     {message}
@@ -1,14 +1,22 @@
+use crate::error::ClockItError;
 use crate::types::UserProfile;
 use leptos::prelude::*;
 
 #[cfg(feature = "ssr")]
-use crate::database::init_db_pool;
+use crate::calendar_feed::feed_path;
+#[cfg(feature = "ssr")]
+use crate::database::universities::ensure_university;
+#[cfg(feature = "ssr")]
+use crate::database::{db_pool, feed_token_for_user, reset_feed_token};
+#[cfg(feature = "ssr")]
+use crate::error::db_error;
+#[cfg(feature = "ssr")]
+use crate::session::require_user;
 #[cfg(feature = "ssr")]
 use chrono::Utc;
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct UpdateProfileRequest {
-    pub user_id: i64,
     pub name: String,
     pub surname: String,
     pub email_address: String,
@@ -22,42 +30,53 @@ pub struct ProfileResponse {
     pub user: Option<UserProfile>,
 }
 
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct CalendarFeedResponse {
+    pub success: bool,
+    pub message: String,
+    /// The feed's path on this site, `/calendar/{token}.ics`
+    pub path: String,
+}
+
 /// Update user profile
 #[server(UpdateProfile, "/api")]
 pub async fn update_profile(
     request: UpdateProfileRequest,
-) -> Result<ProfileResponse, ServerFnError> {
+) -> Result<ProfileResponse, ClockItError> {
     // Validate input
     if request.name.trim().is_empty() {
-        return Ok(ProfileResponse {
-            success: false,
-            message: "Name is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Name is required".to_string()));
     }
 
     if request.surname.trim().is_empty() {
-        return Ok(ProfileResponse {
-            success: false,
-            message: "Surname is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Surname is required".to_string()));
     }
 
     if request.email_address.trim().is_empty() {
-        return Ok(ProfileResponse {
-            success: false,
-            message: "Email is required".to_string(),
-            user: None,
-        });
+        return Err(ClockItError::Validation("Email is required".to_string()));
     }
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let pool = db_pool()?;
+
+    // Users can only edit their own profile
+    let current = require_user(&pool).await?;
+    let user_id = current.user_id;
+    let email = request.email_address.trim();
 
     let now = Utc::now().to_rfc3339();
 
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(db_error("Failed to start transaction"))?;
+
+    // Enrolments and teaching assignments are keyed by email, so they follow the
+    // address; the foreign keys are checked once everything has moved
+    sqlx::query("PRAGMA defer_foreign_keys = ON")
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error("Failed to update profile"))?;
+
     // Update user
     sqlx::query(
         r#"
@@ -68,22 +87,47 @@ pub async fn update_profile(
     )
     .bind(&request.name.trim())
     .bind(&request.surname.trim())
-    .bind(&request.email_address.trim())
+    .bind(email)
     .bind(&request.university.trim())
     .bind(&now)
-    .bind(request.user_id)
-    .execute(&pool)
+    .bind(user_id)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to update profile: {}", e)))?;
+    .map_err(db_error("Failed to update profile"))?;
+
+    // A university nobody has named before starts in the default timezone
+    ensure_university(&mut tx, &request.university).await?;
+
+    if email != current.email_address {
+        for statement in [
+            "UPDATE module_students SET studentEmailAddress = ? WHERE studentEmailAddress = ?",
+            "UPDATE module_enrollment_history SET studentEmailAddress = ? WHERE studentEmailAddress = ?",
+            "UPDATE lecturer_module SET lecturerEmailAddress = ? WHERE lecturerEmailAddress = ?",
+            "UPDATE module_tutor SET tutorEmailAddress = ? WHERE tutorEmailAddress = ?",
+            "UPDATE group_students SET studentEmailAddress = ? WHERE studentEmailAddress = ?",
+            "UPDATE module_groups SET tutorEmailAddress = ? WHERE tutorEmailAddress = ?",
+        ] {
+            sqlx::query(statement)
+                .bind(email)
+                .bind(&current.email_address)
+                .execute(&mut *tx)
+                .await
+                .map_err(db_error("Failed to update profile"))?;
+        }
+    }
+
+    tx.commit()
+        .await
+        .map_err(db_error("Failed to update profile"))?;
 
     // Fetch updated user
     let user = sqlx::query_as::<_, (i64, String, String, String, String, String)>(
         "SELECT userID, name, surname, emailAddress, role, university FROM users WHERE userID = ?",
     )
-    .bind(request.user_id)
+    .bind(user_id)
     .fetch_one(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to fetch updated user: {}", e)))?;
+    .map_err(db_error("Failed to fetch updated user"))?;
 
     Ok(ProfileResponse {
         success: true,
@@ -98,3 +142,34 @@ pub async fn update_profile(
         }),
     })
 }
+
+/// The signed-in user's personal .ics feed link, created the first time it is asked for
+#[server(GetCalendarFeed, "/api")]
+pub async fn get_calendar_feed_fn() -> Result<CalendarFeedResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let user = require_user(&pool).await?;
+    let token = feed_token_for_user(&pool, user.user_id).await?;
+
+    Ok(CalendarFeedResponse {
+        success: true,
+        message: "Calendar feed ready".to_string(),
+        path: feed_path(&token),
+    })
+}
+
+/// Replace the user's feed link, for when it has been shared by mistake
+#[server(ResetCalendarFeed, "/api")]
+pub async fn reset_calendar_feed_fn() -> Result<CalendarFeedResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let user = require_user(&pool).await?;
+    let token = reset_feed_token(&pool, user.user_id).await?;
+
+    Ok(CalendarFeedResponse {
+        success: true,
+        message: "New calendar link created. Calendars using the old link will stop updating."
+            .to_string(),
+        path: feed_path(&token),
+    })
+}
@@ -4,7 +4,7 @@ use leptos_router::hooks::use_navigate;
 
 // Import the server functions and types
 use crate::routes::auth_functions::{register_user, send_otp, verify_otp};
-use crate::types::RegisterData;
+use crate::types::{OtpPurpose, RegisterData};
 
 // Email validation function
 fn is_valid_email(email: &str) -> bool {
@@ -47,7 +47,7 @@ pub fn Register() -> impl IntoView {
     let verify_otp_action = Action::new(|(email, otp): &(String, String)| {
         let email = email.clone();
         let otp = otp.clone();
-        async move { verify_otp(email, otp).await }
+        async move { verify_otp(email, otp, OtpPurpose::Signup).await }
     });
 
     let on_submit = move |_| {
@@ -1,3 +1,4 @@
+use crate::error::ClockItError;
 use crate::routes::stats_functions::*;
 use crate::user_context::get_current_user;
 use chrono::Utc;
@@ -24,8 +25,8 @@ pub fn Statistics() -> impl IntoView {
         move || current_user.get().map(|u| u.email_address),
         |email| async move {
             match email {
-                Some(email) => get_module_options(email).await,
-                None => Err(ServerFnError::new("Not logged in".to_string())),
+                Some(_) => get_module_options().await,
+                None => Err(ClockItError::Unauthorized("Not logged in".to_string())),
             }
         },
     );
@@ -40,8 +41,8 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module)| async move {
             match email {
-                Some(email) => get_overall_stats(email, module, None).await,
-                None => Err(ServerFnError::new("Not logged in".to_string())),
+                Some(_) => get_overall_stats(module, None).await,
+                None => Err(ClockItError::Unauthorized("Not logged in".to_string())),
             }
         },
     );
@@ -58,8 +59,8 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module, tf, month)| async move {
             match email {
-                Some(email) => get_weekly_trends(email, module, Some(tf), Some(month)).await,
-                None => Err(ServerFnError::new("Not logged in".to_string())),
+                Some(_) => get_weekly_trends(module, Some(tf), Some(month)).await,
+                None => Err(ClockItError::Unauthorized("Not logged in".to_string())),
             }
         },
     );
@@ -74,10 +75,10 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module)| async move {
             match email {
-                Some(email) => {
-                    get_weekly_trends(email, module, Some("Monthly".to_string()), None).await
+                Some(_) => {
+                    get_weekly_trends(module, Some("Monthly".to_string()), None).await
                 }
-                None => Err(ServerFnError::new("Not logged in".to_string())),
+                None => Err(ClockItError::Unauthorized("Not logged in".to_string())),
             }
         },
     );
@@ -92,8 +93,8 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module)| async move {
             match email {
-                Some(email) => get_most_missed_modules(email, module).await,
-                None => Err(ServerFnError::new("Not logged in".to_string())),
+                Some(_) => get_most_missed_modules(module).await,
+                None => Err(ClockItError::Unauthorized("Not logged in".to_string())),
             }
         },
     );
@@ -108,8 +109,8 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module)| async move {
             match (email, module) {
-                (Some(email), Some(code)) => get_module_student_attendance(email, code, None).await,
-                _ => Err(ServerFnError::new("No context".to_string())),
+                (Some(_), Some(code)) => get_module_student_attendance(code, None).await,
+                _ => Err(ClockItError::Validation("No context".to_string())),
             }
         },
     );
@@ -126,10 +127,10 @@ pub fn Statistics() -> impl IntoView {
         },
         |(email, module, student_id)| async move {
             match (email, module, student_id) {
-                (Some(email), Some(code), Some(sid)) => {
-                    get_student_module_attendance_detail(email, code, sid).await
+                (Some(_), Some(code), Some(sid)) => {
+                    get_student_module_attendance_detail(code, sid).await
                 }
-                _ => Err(ServerFnError::new("No student selected".to_string())),
+                _ => Err(ClockItError::Validation("No student selected".to_string())),
             }
         },
     );
@@ -186,9 +187,8 @@ pub fn Statistics() -> impl IntoView {
                         use wasm_bindgen::JsCast;
                         
                         spawn_local(async move {
-                            if let Some(email) = current_user.get().map(|u| u.email_address) {
+                            if current_user.get().is_some() {
                                 match export_attendance_data(
-                                    email,
                                     selected_module.get(),
                                     Some(timeframe.get()),
                                     if timeframe.get() == "Weekly" { Some(selected_month.get()) } else { None }
@@ -358,6 +358,11 @@ pub fn Statistics() -> impl IntoView {
                                 <div class="kpi-value">{s.absent_today}</div>
                                 <div class="kpi-meta">{move || if s.total_students>0 { format!("{:.1}% of total", (s.absent_today as f64)*100.0/(s.total_students as f64)) } else { String::new() }}</div>
                             </div>
+                            <div class="card">
+                                <div class="kpi-title">"Late Check-ins" <span class="kpi-ico">"⏱"</span></div>
+                                <div class="kpi-value">{s.late_count}</div>
+                                <div class="kpi-meta">"Scanned after the on-time window"</div>
+                            </div>
                         </div>
                     }.into_any(),
                     Err(_) => view! { <div class="card">"Error loading overview"</div> }.into_any(),
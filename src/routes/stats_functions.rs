@@ -1,8 +1,19 @@
+use crate::error::ClockItError;
 use leptos::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
-use crate::database::init_db_pool;
+use crate::clock::clock;
+#[cfg(feature = "ssr")]
+use crate::database::db_pool;
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_class_staff, require_module_staff, require_role, STAFF};
+#[cfg(feature = "ssr")]
+use crate::database::universities::{class_timezone, module_timezone, user_timezone};
+#[cfg(feature = "ssr")]
+use crate::utils::class_time::local_today;
+#[cfg(feature = "ssr")]
+use chrono::NaiveDate;
 
 // Statistics data structures
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -12,6 +23,7 @@ pub struct OverallStats {
     pub total_classes: i64,
     pub absent_today: i64,
     pub avg_class_size: f64,
+    pub late_count: i64,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -46,28 +58,90 @@ pub struct StudentAttendance {
     pub surname: String,
     pub email_address: String,
     pub present: i64,
+    pub late: i64,
     pub total: i64,
     pub attendance_rate: f64,
 }
 
+// Figures across several classes read them through the `teaching_classes` view, which
+// leaves out classes held on a holiday or in a recess
+
+// Statistics are staff-only, and any module or class filter must be one the caller teaches
+#[cfg(feature = "ssr")]
+async fn require_stats_access(
+    pool: &sqlx::SqlitePool,
+    module_code: Option<&str>,
+    class_id: Option<i64>,
+) -> Result<String, ClockItError> {
+    if let Some(cid) = class_id {
+        require_class_staff(pool, cid).await?;
+    }
+    let user = match module_code {
+        Some(code) => require_module_staff(pool, code).await?,
+        None => require_role(pool, STAFF).await?,
+    };
+    Ok(user.email_address)
+}
+
+/// Today's date at the university the statistics are about: the class's or module's
+/// when one is chosen, otherwise the signed-in lecturer's
+#[cfg(feature = "ssr")]
+async fn stats_today(
+    pool: &sqlx::SqlitePool,
+    module_code: Option<&str>,
+    class_id: Option<i64>,
+    lecturer_email: &str,
+) -> Result<NaiveDate, ClockItError> {
+    let tz = match (class_id, module_code) {
+        (Some(cid), _) => class_timezone(pool, cid).await?,
+        (None, Some(code)) => module_timezone(pool, code).await?,
+        (None, None) => user_timezone(pool, lecturer_email).await?,
+    };
+    Ok(local_today(tz, clock().now()))
+}
+
 // Server function to get overall statistics with optional filters
 #[server(GetOverallStats, "/api")]
 pub async fn get_overall_stats(
-    lecturer_email: String,
     module_code: Option<String>,
     class_id: Option<i64>,
-) -> Result<OverallStats, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<OverallStats, ClockItError> {
+    let pool = db_pool()?;
+
+    let lecturer_email = require_stats_access(&pool, module_code.as_deref(), class_id).await?;
+
+    let today = stats_today(&pool, module_code.as_deref(), class_id, &lecturer_email)
+        .await?
+        .format("%Y-%m-%d")
+        .to_string();
 
+    Ok(overall_stats(
+        &pool,
+        module_code.as_deref(),
+        class_id,
+        &lecturer_email,
+        &today,
+    )
+    .await)
+}
+
+/// The overall figures for a class, a module, or everything `lecturer_email` teaches.
+/// Late check-ins count as attended, and are also counted on their own.
+#[cfg(feature = "ssr")]
+async fn overall_stats(
+    pool: &sqlx::SqlitePool,
+    module_code: Option<&str>,
+    class_id: Option<i64>,
+    lecturer_email: &str,
+    today: &str,
+) -> OverallStats {
     // Get overall attendance rate
     let attendance_rate: f64 = if let Some(cid) = class_id {
         sqlx::query_scalar(
             r#"
             SELECT 
                 COALESCE(
-                    CAST(SUM(CASE WHEN status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
+                    CAST(SUM(CASE WHEN status IN ('present', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
                     NULLIF(CAST(COUNT(*) AS REAL), 0),
                     0.0
                 )
@@ -76,25 +150,25 @@ pub async fn get_overall_stats(
             "#,
         )
         .bind(cid)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0)
-    } else if let Some(mc) = &module_code {
+    } else if let Some(mc) = module_code {
         sqlx::query_scalar(
             r#"
             SELECT 
                 COALESCE(
-                    CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
+                    CAST(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
                     NULLIF(CAST(COUNT(*) AS REAL), 0),
                     0.0
                 )
             FROM attendance a
-            JOIN classes c ON a.classID = c.classID
+            JOIN teaching_classes c ON a.classID = c.classID
             WHERE c.moduleCode = ?
             "#,
         )
         .bind(mc)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0)
     } else {
@@ -102,44 +176,43 @@ pub async fn get_overall_stats(
             r#"
             SELECT 
                 COALESCE(
-                    CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
+                    CAST(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
                     NULLIF(CAST(COUNT(*) AS REAL), 0),
                     0.0
                 )
             FROM attendance a
-            JOIN classes c ON a.classID = c.classID
+            JOIN teaching_classes c ON a.classID = c.classID
             JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
             WHERE lm.lecturerEmailAddress = ?
             "#,
         )
-        .bind(&lecturer_email)
-        .fetch_one(&pool)
+        .bind(lecturer_email)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0)
     };
 
     // Get total students, scoped to filter (class/module/lecturer)
     let total_students: i64 = if let Some(cid) = class_id {
-        // Count distinct enrolled students for the class's module
+        // Count the students expected at the class (its groups, or the whole module)
         sqlx::query_scalar(
             r#"
-            SELECT COUNT(DISTINCT ms.studentEmailAddress)
-            FROM classes c
-            JOIN module_students ms ON c.moduleCode = ms.moduleCode
-            WHERE c.classID = ?
+            SELECT COUNT(DISTINCT studentEmailAddress)
+            FROM class_students
+            WHERE classID = ?
             "#,
         )
         .bind(cid)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
-    } else if let Some(mc) = &module_code {
+    } else if let Some(mc) = module_code {
         // Count distinct students enrolled in this module
         sqlx::query_scalar(
-            r#"SELECT COUNT(DISTINCT studentEmailAddress) FROM module_students WHERE moduleCode = ?"#
+            r#"SELECT COUNT(DISTINCT studentEmailAddress) FROM module_students WHERE moduleCode = ? AND unenrolled_on IS NULL"#
         )
         .bind(mc)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     } else {
@@ -150,12 +223,13 @@ pub async fn get_overall_stats(
             FROM module_students ms
             LEFT JOIN lecturer_module lm ON ms.moduleCode = lm.moduleCode
             LEFT JOIN module_tutor mt ON ms.moduleCode = mt.moduleCode
-            WHERE lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?
+            WHERE (lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?)
+              AND ms.unenrolled_on IS NULL
             "#,
         )
-        .bind(&lecturer_email)
-        .bind(&lecturer_email)
-        .fetch_one(&pool)
+        .bind(lecturer_email)
+        .bind(lecturer_email)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     };
@@ -164,30 +238,30 @@ pub async fn get_overall_stats(
     let total_classes: i64 = if let Some(cid) = class_id {
         sqlx::query_scalar("SELECT COUNT(*) FROM classes WHERE classID = ?")
             .bind(cid)
-            .fetch_one(&pool)
+            .fetch_one(pool)
             .await
             .unwrap_or(0)
-    } else if let Some(mc) = &module_code {
+    } else if let Some(mc) = module_code {
         sqlx::query_scalar(
-            r#"SELECT COUNT(*) FROM classes WHERE moduleCode = ?"#,
+            r#"SELECT COUNT(*) FROM teaching_classes WHERE moduleCode = ?"#,
         )
         .bind(mc)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     } else {
         sqlx::query_scalar(
             r#"
             SELECT COUNT(*) 
-            FROM classes c
+            FROM teaching_classes c
             LEFT JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
             LEFT JOIN module_tutor mt ON c.moduleCode = mt.moduleCode
             WHERE lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?
             "#,
         )
-        .bind(&lecturer_email)
-        .bind(&lecturer_email)
-        .fetch_one(&pool)
+        .bind(lecturer_email)
+        .bind(lecturer_email)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     };
@@ -199,28 +273,30 @@ pub async fn get_overall_stats(
             SELECT COUNT(*)
             FROM attendance a
             JOIN classes c ON a.classID = c.classID
-            WHERE c.date = date('now')
-            AND a.status IN ('absent', 'late')
+            WHERE c.date = ?
+            AND a.status = 'absent'
             AND a.classID = ?
             "#,
         )
+        .bind(today)
         .bind(cid)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
-    } else if let Some(mc) = &module_code {
+    } else if let Some(mc) = module_code {
         sqlx::query_scalar(
             r#"
             SELECT COUNT(*)
             FROM attendance a
-            JOIN classes c ON a.classID = c.classID
-            WHERE c.date = date('now')
-            AND a.status IN ('absent', 'late')
+            JOIN teaching_classes c ON a.classID = c.classID
+            WHERE c.date = ?
+            AND a.status = 'absent'
             AND c.moduleCode = ?
             "#,
         )
+        .bind(today)
         .bind(mc)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     } else {
@@ -228,17 +304,18 @@ pub async fn get_overall_stats(
             r#"
             SELECT COUNT(*)
             FROM attendance a
-            JOIN classes c ON a.classID = c.classID
+            JOIN teaching_classes c ON a.classID = c.classID
             LEFT JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
             LEFT JOIN module_tutor mt ON c.moduleCode = mt.moduleCode
-            WHERE c.date = date('now')
-            AND a.status IN ('absent', 'late')
+            WHERE c.date = ?
+            AND a.status = 'absent'
             AND (lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?)
             "#,
         )
-        .bind(&lecturer_email)
-        .bind(&lecturer_email)
-        .fetch_one(&pool)
+        .bind(today)
+        .bind(lecturer_email)
+        .bind(lecturer_email)
+        .fetch_one(pool)
         .await
         .unwrap_or(0)
     };
@@ -247,23 +324,23 @@ pub async fn get_overall_stats(
     let avg_class_size: f64 = if let Some(cid) = class_id {
         sqlx::query_scalar(
             r#"
-            SELECT COALESCE(COUNT(DISTINCT CASE WHEN status = 'present' THEN studentID END), 0)
+            SELECT COALESCE(COUNT(DISTINCT CASE WHEN status IN ('present', 'late') THEN studentID END), 0)
             FROM attendance
             WHERE classID = ?
             "#,
         )
         .bind(cid)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .map(|count: i64| count as f64)
         .unwrap_or(0.0)
-    } else if let Some(mc) = &module_code {
+    } else if let Some(mc) = module_code {
         sqlx::query_scalar(
             r#"
             SELECT COALESCE(AVG(student_count), 0.0)
             FROM (
-                SELECT COUNT(DISTINCT CASE WHEN a.status = 'present' THEN a.studentID END) as student_count
-                FROM classes c
+                SELECT COUNT(DISTINCT CASE WHEN a.status IN ('present', 'late') THEN a.studentID END) as student_count
+                FROM teaching_classes c
                 LEFT JOIN attendance a ON c.classID = a.classID
                 WHERE c.moduleCode = ?
                 GROUP BY c.classID
@@ -271,7 +348,7 @@ pub async fn get_overall_stats(
             "#
         )
         .bind(mc)
-        .fetch_one(&pool)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0)
     } else {
@@ -279,8 +356,8 @@ pub async fn get_overall_stats(
             r#"
             SELECT COALESCE(AVG(student_count), 0.0)
             FROM (
-                SELECT COUNT(DISTINCT CASE WHEN a.status = 'present' THEN a.studentID END) as student_count
-                FROM classes c
+                SELECT COUNT(DISTINCT CASE WHEN a.status IN ('present', 'late') THEN a.studentID END) as student_count
+                FROM teaching_classes c
                 LEFT JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
                 LEFT JOIN module_tutor mt ON c.moduleCode = mt.moduleCode
                 LEFT JOIN attendance a ON c.classID = a.classID
@@ -289,58 +366,108 @@ pub async fn get_overall_stats(
             )
             "#
         )
-        .bind(&lecturer_email)
-        .bind(&lecturer_email)
-        .fetch_one(&pool)
+        .bind(lecturer_email)
+        .bind(lecturer_email)
+        .fetch_one(pool)
         .await
         .unwrap_or(0.0)
     };
 
-    Ok(OverallStats {
+    // Get late check-ins (filtered by lecturer)
+    let late_count: i64 = if let Some(cid) = class_id {
+        sqlx::query_scalar("SELECT COUNT(*) FROM attendance WHERE status = 'late' AND classID = ?")
+            .bind(cid)
+            .fetch_one(pool)
+            .await
+            .unwrap_or(0)
+    } else if let Some(mc) = module_code {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM attendance a
+            JOIN teaching_classes c ON a.classID = c.classID
+            WHERE a.status = 'late'
+            AND c.moduleCode = ?
+            "#,
+        )
+        .bind(mc)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(0)
+    } else {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM attendance a
+            JOIN teaching_classes c ON a.classID = c.classID
+            WHERE a.status = 'late'
+            AND c.moduleCode IN (
+                SELECT moduleCode FROM lecturer_module WHERE lecturerEmailAddress = ?
+                UNION
+                SELECT moduleCode FROM module_tutor WHERE tutorEmailAddress = ?
+            )
+            "#,
+        )
+        .bind(lecturer_email)
+        .bind(lecturer_email)
+        .fetch_one(pool)
+        .await
+        .unwrap_or(0)
+    };
+
+    OverallStats {
         attendance_rate,
         total_students,
         total_classes,
         absent_today,
         avg_class_size,
-    })
+        late_count,
+    }
 }
 
 // Server function to get weekly attendance trends
 #[server(GetWeeklyTrends, "/api")]
 pub async fn get_weekly_trends(
-    lecturer_email: String,
     module_code: Option<String>,
     timeframe: Option<String>, // "Weekly" | "Monthly"
     month: Option<String>,     // when Weekly: filter like "YYYY-MM"
-) -> Result<Vec<WeeklyTrend>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<Vec<WeeklyTrend>, ClockItError> {
+    let pool = db_pool()?;
+
+    let lecturer_email = require_stats_access(&pool, module_code.as_deref(), None).await?;
 
     let is_monthly = timeframe.as_deref() == Some("Monthly");
+    let today = stats_today(&pool, module_code.as_deref(), None, &lecturer_email).await?;
+    let year = today.format("%Y").to_string();
 
     let query: Vec<(String, f64, i64)> = if is_monthly {
         // Monthly trend for current year, up to current month
         if let Some(mc) = &module_code {
             sqlx::query_as(
                 r#"
-                SELECT strftime('%Y-%m', c.date) as label,
+                SELECT strftime('%Y-%m', pc.date) as label,
                     COALESCE(
-                        CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 /
-                        NULLIF(CAST(COUNT(DISTINCT c.classID) * (SELECT COUNT(*) FROM module_students WHERE moduleCode = ?) AS REAL), 0),
+                        CAST(SUM(pc.present) AS REAL) * 100.0 /
+                        NULLIF(CAST(SUM(pc.expected) AS REAL), 0),
                         0.0
                     ) as rate,
-                    COUNT(DISTINCT c.classID) as class_cnt
-                FROM classes c
-                LEFT JOIN attendance a ON c.classID = a.classID
-                WHERE c.moduleCode = ?
-                  AND strftime('%Y', c.date) = strftime('%Y','now')
-                GROUP BY strftime('%Y-%m', c.date)
+                    COUNT(*) as class_cnt
+                FROM (
+                    SELECT c.date,
+                        (SELECT COUNT(*) FROM attendance a
+                         WHERE a.classID = c.classID AND a.status IN ('present', 'late')) AS present,
+                        (SELECT COUNT(*) FROM class_students cs
+                         WHERE cs.classID = c.classID) AS expected
+                    FROM teaching_classes c
+                    WHERE c.moduleCode = ?
+                      AND strftime('%Y', c.date) = ?
+                ) pc
+                GROUP BY strftime('%Y-%m', pc.date)
                 ORDER BY label ASC
                 "#
             )
             .bind(mc)
-            .bind(mc)
+            .bind(&year)
             .fetch_all(&pool)
             .await
             .unwrap_or_default()
@@ -349,51 +476,57 @@ pub async fn get_weekly_trends(
                 r#"
                 SELECT strftime('%Y-%m', c.date) as label,
                     COALESCE(
-                        CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 /
+                        CAST(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 /
                         NULLIF(CAST(COUNT(a.attendanceID) AS REAL), 0),
                         0.0
                     ) as rate,
                     COUNT(DISTINCT c.classID) as class_cnt
-                FROM classes c
+                FROM teaching_classes c
                 LEFT JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
                 LEFT JOIN module_tutor mt ON c.moduleCode = mt.moduleCode
                 LEFT JOIN attendance a ON c.classID = a.classID
                 WHERE (lm.lecturerEmailAddress = ? OR mt.tutorEmailAddress = ?)
-                  AND strftime('%Y', c.date) = strftime('%Y','now')
+                  AND strftime('%Y', c.date) = ?
                 GROUP BY strftime('%Y-%m', c.date)
                 ORDER BY label ASC
                 "#
             )
             .bind(&lecturer_email)
             .bind(&lecturer_email)
+            .bind(&year)
             .fetch_all(&pool)
             .await
             .unwrap_or_default()
         }
     } else {
         // Weekly trend within a selected month (YYYY-MM). Always return W1..W4.
-        let month = month.unwrap_or_else(|| chrono::Utc::now().format("%Y-%m").to_string());
+        let month = month.unwrap_or_else(|| today.format("%Y-%m").to_string());
         if let Some(mc) = &module_code {
             // aggregate existing weeks, then fill to W1..W5 in Rust
             let rows: Vec<(i64, f64, i64)> = sqlx::query_as(
                 r#"
-                SELECT (((CAST(strftime('%d', c.date) AS INTEGER) - 1) / 7) + 1) AS w,
+                SELECT (((CAST(strftime('%d', pc.date) AS INTEGER) - 1) / 7) + 1) AS w,
                        COALESCE(
-                           CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 /
-                           NULLIF(CAST(COUNT(DISTINCT c.classID) * (SELECT COUNT(*) FROM module_students WHERE moduleCode = ?) AS REAL), 0),
+                           CAST(SUM(pc.present) AS REAL) * 100.0 /
+                           NULLIF(CAST(SUM(pc.expected) AS REAL), 0),
                            0.0
                        ) AS rate,
-                       COUNT(DISTINCT c.classID) AS class_cnt
-                FROM classes c
-                LEFT JOIN attendance a ON c.classID = a.classID
-                WHERE c.moduleCode = ?
-                  AND strftime('%Y-%m', c.date) = ?
+                       COUNT(*) AS class_cnt
+                FROM (
+                    SELECT c.date,
+                        (SELECT COUNT(*) FROM attendance a
+                         WHERE a.classID = c.classID AND a.status IN ('present', 'late')) AS present,
+                        (SELECT COUNT(*) FROM class_students cs
+                         WHERE cs.classID = c.classID) AS expected
+                    FROM teaching_classes c
+                    WHERE c.moduleCode = ?
+                      AND strftime('%Y-%m', c.date) = ?
+                ) pc
                 GROUP BY w
                 ORDER BY w ASC
                 "#
             )
             .bind(mc)
-            .bind(mc)
             .bind(&month)
             .fetch_all(&pool)
             .await
@@ -415,12 +548,12 @@ pub async fn get_weekly_trends(
                 r#"
                 SELECT (((CAST(strftime('%d', c.date) AS INTEGER) - 1) / 7) + 1) AS w,
                        COALESCE(
-                           CAST(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END) AS REAL) * 100.0 /
+                           CAST(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 /
                            NULLIF(CAST(COUNT(a.attendanceID) AS REAL), 0),
                            0.0
                        ) AS rate,
                        COUNT(DISTINCT c.classID) AS class_cnt
-                FROM classes c
+                FROM teaching_classes c
                 LEFT JOIN lecturer_module lm ON c.moduleCode = lm.moduleCode
                 LEFT JOIN module_tutor mt ON c.moduleCode = mt.moduleCode
                 LEFT JOIN attendance a ON c.classID = a.classID
@@ -460,11 +593,9 @@ pub async fn get_weekly_trends(
         .collect();
     // If monthly, fill from Jan..current month even if missing
     if is_monthly {
-        use chrono::{Datelike, Utc as CUtc};
-        let now = CUtc::now();
-        let year = now.year();
+        use chrono::Datelike;
         let mut filled: Vec<WeeklyTrend> = Vec::new();
-        for m in 1..=now.month() {
+        for m in 1..=today.month() {
             let label = format!("{}-{:02}", year, m);
             if let Some(t) = trends.iter().find(|t| t.week == label) {
                 filled.push(t.clone());
@@ -485,12 +616,11 @@ pub async fn get_weekly_trends(
 // Server function to get most missed modules
 #[server(GetMostMissedModules, "/api")]
 pub async fn get_most_missed_modules(
-    lecturer_email: String,
     module_code: Option<String>,
-) -> Result<Vec<ModuleAbsence>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<Vec<ModuleAbsence>, ClockItError> {
+    let pool = db_pool()?;
+
+    let lecturer_email = require_stats_access(&pool, module_code.as_deref(), None).await?;
 
     let rows: Vec<(String, f64)> = if let Some(mc) = &module_code {
         sqlx::query_as(
@@ -498,12 +628,12 @@ pub async fn get_most_missed_modules(
             SELECT 
                 m.moduleTitle as title,
                 COALESCE(
-                    CAST(SUM(CASE WHEN a.status IN ('absent', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
+                    CAST(SUM(CASE WHEN a.status = 'absent' THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
                     NULLIF(CAST(COUNT(*) AS REAL), 0),
                     0.0
                 ) as absence_rate
             FROM modules m
-            JOIN classes c ON m.moduleCode = c.moduleCode
+            JOIN teaching_classes c ON m.moduleCode = c.moduleCode
             LEFT JOIN lecturer_module lm ON m.moduleCode = lm.moduleCode
             LEFT JOIN module_tutor mt ON m.moduleCode = mt.moduleCode
             LEFT JOIN attendance a ON c.classID = a.classID
@@ -526,12 +656,12 @@ pub async fn get_most_missed_modules(
             SELECT 
                 m.moduleTitle as title,
                 COALESCE(
-                    CAST(SUM(CASE WHEN a.status IN ('absent', 'late') THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
+                    CAST(SUM(CASE WHEN a.status = 'absent' THEN 1 ELSE 0 END) AS REAL) * 100.0 / 
                     NULLIF(CAST(COUNT(*) AS REAL), 0),
                     0.0
                 ) as absence_rate
             FROM modules m
-            JOIN classes c ON m.moduleCode = c.moduleCode
+            JOIN teaching_classes c ON m.moduleCode = c.moduleCode
             LEFT JOIN lecturer_module lm ON m.moduleCode = lm.moduleCode
             LEFT JOIN module_tutor mt ON m.moduleCode = mt.moduleCode
             LEFT JOIN attendance a ON c.classID = a.classID
@@ -560,12 +690,10 @@ pub async fn get_most_missed_modules(
 
 // Server function to get module options for dropdown (supports both lecturers and tutors)
 #[server(GetModuleOptions, "/api")]
-pub async fn get_module_options(
-    user_email: String,
-) -> Result<Vec<ModuleOption>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_module_options() -> Result<Vec<ModuleOption>, ClockItError> {
+    let pool = db_pool()?;
+
+    let user_email = require_role(&pool, STAFF).await?.email_address;
 
     let rows: Vec<(String, String)> = sqlx::query_as(
         r#"
@@ -596,10 +724,10 @@ pub async fn get_module_options(
 #[server(GetClassOptions, "/api")]
 pub async fn get_class_options(
     module_code: Option<String>,
-) -> Result<Vec<ClassOption>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<Vec<ClassOption>, ClockItError> {
+    let pool = db_pool()?;
+
+    require_stats_access(&pool, module_code.as_deref(), None).await?;
 
     let query = if let Some(mc) = &module_code {
         sqlx::query_as(
@@ -628,34 +756,33 @@ pub async fn get_class_options(
         .collect())
 }
 
+// (userID, name, surname, email, present, late, total, rate)
+#[cfg(feature = "ssr")]
+type StudentAttendanceRow = (i64, String, String, String, i64, i64, i64, f64);
+
 // Per-student attendance for a module (optionally for a specific class)
 #[server(GetModuleStudentAttendance, "/api")]
 pub async fn get_module_student_attendance(
-    lecturer_email: String,
     module_code: String,
     class_id: Option<i64>,
-) -> Result<Vec<StudentAttendance>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<Vec<StudentAttendance>, ClockItError> {
+    let pool = db_pool()?;
 
     // Only allow for modules taught by this lecturer or where they are a tutor
-    let teaches: bool = sqlx::query_scalar(
-        "SELECT EXISTS(SELECT 1 FROM lecturer_module WHERE moduleCode = ? AND lecturerEmailAddress = ?) OR EXISTS(SELECT 1 FROM module_tutor WHERE moduleCode = ? AND tutorEmailAddress = ?)"
-    )
-    .bind(&module_code)
-    .bind(&lecturer_email)
-    .bind(&module_code)
-    .bind(&lecturer_email)
-    .fetch_one(&pool)
-    .await
-    .unwrap_or(false);
-    if !teaches {
-        return Ok(vec![]);
-    }
+    require_stats_access(&pool, Some(&module_code), class_id).await?;
 
-    // Only students enrolled in module_students for this module
-    let rows: Vec<(i64, String, String, String, i64, i64, f64)> = if let Some(cid) = class_id {
+    Ok(module_student_attendance(&pool, &module_code, class_id).await)
+}
+
+/// Everyone who has been enrolled in the module, including students who have since
+/// left, counting only the classes each of them was expected at while enrolled
+#[cfg(feature = "ssr")]
+async fn module_student_attendance(
+    pool: &sqlx::SqlitePool,
+    module_code: &str,
+    class_id: Option<i64>,
+) -> Vec<StudentAttendance> {
+    let rows: Vec<StudentAttendanceRow> = if let Some(cid) = class_id {
         sqlx::query_as(
             r#"
             SELECT u.userID,
@@ -663,25 +790,30 @@ pub async fn get_module_student_attendance(
                    u.surname,
                    u.emailAddress,
                    COALESCE(COUNT(DISTINCT CASE WHEN a.status = 'present' THEN c.classID END), 0) AS present_cnt,
+                   COALESCE(COUNT(DISTINCT CASE WHEN a.status = 'late' THEN c.classID END), 0) AS late_cnt,
                    COALESCE(COUNT(DISTINCT c.classID), 0) AS total_cnt,
                    COALESCE(
                      CASE WHEN COUNT(DISTINCT c.classID) = 0 THEN 0.0
-                          ELSE (CAST(COUNT(DISTINCT CASE WHEN a.status = 'present' THEN c.classID END) AS REAL) * 100.0)
+                          ELSE (CAST(COUNT(DISTINCT CASE WHEN a.status IN ('present', 'late') THEN c.classID END) AS REAL) * 100.0)
                                / CAST(COUNT(DISTINCT c.classID) AS REAL)
                      END, 0.0
                    ) AS rate
             FROM module_students ms
             JOIN users u ON u.emailAddress = ms.studentEmailAddress
-            LEFT JOIN classes c ON c.classID = ? AND c.moduleCode = ms.moduleCode AND c.status IN ('completed', 'in_progress')
+            LEFT JOIN classes c ON c.classID = ?1 AND c.moduleCode = ms.moduleCode AND c.status IN ('completed', 'in_progress')
             LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = u.userID
-            WHERE ms.moduleCode = ?
+            WHERE ms.moduleCode = ?2
+              AND EXISTS (
+                  SELECT 1 FROM class_students cs
+                  WHERE cs.classID = ?1 AND cs.studentEmailAddress = ms.studentEmailAddress
+              )
             GROUP BY u.userID, u.name, u.surname, u.emailAddress
             ORDER BY u.surname, u.name
             "#,
         )
         .bind(cid)
-        .bind(&module_code)
-        .fetch_all(&pool)
+        .bind(module_code)
+        .fetch_all(pool)
         .await
         .unwrap_or_default()
     } else {
@@ -692,42 +824,47 @@ pub async fn get_module_student_attendance(
                    u.surname,
                    u.emailAddress,
                    COALESCE(COUNT(DISTINCT CASE WHEN a.status = 'present' THEN c.classID END), 0) AS present_cnt,
+                   COALESCE(COUNT(DISTINCT CASE WHEN a.status = 'late' THEN c.classID END), 0) AS late_cnt,
                    COALESCE(COUNT(DISTINCT c.classID), 0) AS total_cnt,
                    COALESCE(
                      CASE WHEN COUNT(DISTINCT c.classID) = 0 THEN 0.0
-                          ELSE (CAST(COUNT(DISTINCT CASE WHEN a.status = 'present' THEN c.classID END) AS REAL) * 100.0)
+                          ELSE (CAST(COUNT(DISTINCT CASE WHEN a.status IN ('present', 'late') THEN c.classID END) AS REAL) * 100.0)
                                / CAST(COUNT(DISTINCT c.classID) AS REAL)
                      END, 0.0
                    ) AS rate
             FROM module_students ms
             JOIN users u ON u.emailAddress = ms.studentEmailAddress
-            LEFT JOIN classes c ON c.moduleCode = ms.moduleCode AND c.status IN ('completed', 'in_progress')
+            LEFT JOIN teaching_classes c ON c.moduleCode = ms.moduleCode AND c.status IN ('completed', 'in_progress')
+                AND EXISTS (
+                    SELECT 1 FROM class_students cs
+                    WHERE cs.classID = c.classID AND cs.studentEmailAddress = ms.studentEmailAddress
+                )
             LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = u.userID
             WHERE ms.moduleCode = ?
             GROUP BY u.userID, u.name, u.surname, u.emailAddress
             ORDER BY u.surname, u.name
             "#,
         )
-        .bind(&module_code)
-        .fetch_all(&pool)
+        .bind(module_code)
+        .fetch_all(pool)
         .await
         .unwrap_or_default()
     };
 
-    Ok(rows
-        .into_iter()
+    rows.into_iter()
         .map(
-            |(id, name, surname, email, present, total, rate)| StudentAttendance {
+            |(id, name, surname, email, present, late, total, rate)| StudentAttendance {
                 user_id: id,
                 name,
                 surname,
                 email_address: email,
                 present,
+                late,
                 total,
                 attendance_rate: rate,
             },
         )
-        .collect())
+        .collect()
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -741,36 +878,31 @@ pub struct StudentClassAttendance {
 
 #[server(GetStudentModuleAttendanceDetail, "/api")]
 pub async fn get_student_module_attendance_detail(
-    lecturer_email: String,
     module_code: String,
     student_id: i64,
-) -> Result<Vec<StudentClassAttendance>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<Vec<StudentClassAttendance>, ClockItError> {
+    let pool = db_pool()?;
 
     // Confirm lecturer teaches module or is a tutor for the module
-    let teaches: bool = sqlx::query_scalar(
-        "SELECT EXISTS(SELECT 1 FROM lecturer_module WHERE moduleCode = ? AND lecturerEmailAddress = ?) OR EXISTS(SELECT 1 FROM module_tutor WHERE moduleCode = ? AND tutorEmailAddress = ?)"
-    )
-    .bind(&module_code)
-    .bind(&lecturer_email)
-    .bind(&module_code)
-    .bind(&lecturer_email)
-    .fetch_one(&pool)
-    .await
-    .unwrap_or(false);
-    if !teaches {
-        return Ok(vec![]);
-    }
+    require_module_staff(&pool, &module_code).await?;
 
+    // The classes the student was expected at, and any others they turned up to
     let rows: Vec<(i64, String, String, String, Option<String>)> = sqlx::query_as(
         r#"
         SELECT c.classID, c.title, c.date, c.time,
                a.status
-        FROM classes c
-        LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
-        WHERE c.moduleCode = ?
+        FROM teaching_classes c
+        LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?1
+        WHERE c.moduleCode = ?2
+          AND (
+              a.attendanceID IS NOT NULL
+              OR EXISTS (
+                  SELECT 1
+                  FROM class_students cs
+                  INNER JOIN users u ON u.emailAddress = cs.studentEmailAddress
+                  WHERE cs.classID = c.classID AND u.userID = ?1
+              )
+          )
         ORDER BY c.date ASC, c.time ASC
         "#,
     )
@@ -794,19 +926,20 @@ pub async fn get_student_module_attendance_detail(
         .collect())
 }
 
-// Enrollment count for a module (number of students in module_students)
+// Enrollment count for a module (number of students currently enrolled)
 #[server(GetModuleEnrollmentCount, "/api")]
-pub async fn get_module_enrollment_count(module_code: String) -> Result<i64, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_module_enrollment_count(module_code: String) -> Result<i64, ClockItError> {
+    let pool = db_pool()?;
 
-    let count: i64 =
-        sqlx::query_scalar("SELECT COUNT(*) FROM module_students WHERE moduleCode = ?")
-            .bind(&module_code)
-            .fetch_one(&pool)
-            .await
-            .unwrap_or(0);
+    require_module_staff(&pool, &module_code).await?;
+
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM module_students WHERE moduleCode = ? AND unenrolled_on IS NULL",
+    )
+    .bind(&module_code)
+    .fetch_one(&pool)
+    .await
+    .unwrap_or(0);
 
     Ok(count)
 }
@@ -814,41 +947,41 @@ pub async fn get_module_enrollment_count(module_code: String) -> Result<i64, Ser
 // Export attendance data to CSV format
 #[server(ExportAttendanceData, "/api")]
 pub async fn export_attendance_data(
-    lecturer_email: String,
     module_code: Option<String>,
     timeframe: Option<String>,
     month: Option<String>,
-) -> Result<String, ServerFnError> {
-    let _pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<String, ClockItError> {
+    let pool = db_pool()?;
+
+    require_stats_access(&pool, module_code.as_deref(), None).await?;
 
     let mut csv_content = String::new();
 
     if let Some(mc) = &module_code {
         // Export student attendance data for specific module
-        csv_content.push_str("Student Name,Email,Present Classes,Total Classes,Attendance Rate (%)\n");
+        csv_content.push_str("Student Name,Email,Present Classes,Late Classes,Total Classes,Attendance Rate (%)\n");
         
-        let students = get_module_student_attendance(lecturer_email.clone(), mc.clone(), None).await?;
+        let students = get_module_student_attendance(mc.clone(), None).await?;
         for student in students {
             csv_content.push_str(&format!(
-                "\"{} {}\",{},{},{},{:.1}\n",
+                "\"{} {}\",{},{},{},{},{:.1}\n",
                 student.name,
                 student.surname,
                 student.email_address,
                 student.present,
+                student.late,
                 student.total,
                 student.attendance_rate
             ));
         }
     } else {
         // Export overall statistics and trends
-        csv_content.push_str("Type,Period,Attendance Rate (%),Class Count\n");
+        csv_content.push_str("Type,Period,Attendance Rate (%),Class Count,Late Check-ins\n");
         
-        let trends = get_weekly_trends(lecturer_email.clone(), module_code.clone(), timeframe, month).await?;
+        let trends = get_weekly_trends(module_code.clone(), timeframe, month).await?;
         for trend in trends {
             csv_content.push_str(&format!(
-                "Trend,{},{:.1},{}\n",
+                "Trend,{},{:.1},{},\n",
                 trend.week,
                 trend.attendance_rate,
                 trend.class_count
@@ -856,13 +989,133 @@ pub async fn export_attendance_data(
         }
         
         // Add overall stats
-        let stats = get_overall_stats(lecturer_email, module_code, None).await?;
+        let stats = get_overall_stats(module_code, None).await?;
         csv_content.push_str(&format!(
-            "Overall,All Time,{:.1},{}\n",
+            "Overall,All Time,{:.1},{},{}\n",
             stats.attendance_rate,
-            stats.total_classes
+            stats.total_classes,
+            stats.late_count
         ));
     }
 
     Ok(csv_content)
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+    use crate::database::modules::{end_enrollment, enroll_in_module};
+    use crate::database::test_support::{add_class, add_module, add_user, enroll, run, test_pool};
+    use sqlx::SqlitePool;
+
+    const MODULE: &str = "STA101";
+    const LECTURER: &str = "stats-lecturer@test.example";
+    const STUDENT: &str = "stats-student@test.example";
+
+    fn day(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    async fn record(pool: &SqlitePool, class_id: i64, email: &str, status: &str) {
+        sqlx::query(
+            r#"
+            INSERT INTO attendance (studentID, classID, status, recorded_at)
+            SELECT userID, ?, ?, '2030-01-01T00:00:00Z' FROM users WHERE emailAddress = ?
+            "#,
+        )
+        .bind(class_id)
+        .bind(status)
+        .bind(email)
+        .execute(pool)
+        .await
+        .expect("attendance");
+    }
+
+    async fn complete_classes(pool: &SqlitePool) {
+        sqlx::query("UPDATE classes SET status = 'completed' WHERE moduleCode = ?")
+            .bind(MODULE)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    async fn enroll_from(pool: &SqlitePool, date: &str) {
+        let enrolled = enroll_in_module(
+            &mut pool.acquire().await.unwrap(),
+            MODULE,
+            STUDENT,
+            day(date),
+        )
+        .await
+        .unwrap();
+        assert!(enrolled);
+    }
+
+    #[test]
+    fn earlier_enrollments_still_count_after_enrolling_again() {
+        run(async {
+            let pool = test_pool().await;
+            add_user(&pool, LECTURER, "lecturer").await;
+            add_user(&pool, STUDENT, "student").await;
+            add_module(&pool, MODULE, LECTURER).await;
+            let mut classes = Vec::new();
+            for date in ["2030-02-04", "2030-02-11", "2030-02-18", "2030-02-25"] {
+                classes.push(add_class(&pool, MODULE, "A101", date, "09:00", 60, LECTURER).await);
+            }
+            complete_classes(&pool).await;
+
+            // Enrolled for the first class, away for the second, back for the last two
+            enroll_from(&pool, "2030-02-01").await;
+            record(&pool, classes[0], STUDENT, "present").await;
+            assert!(end_enrollment(&pool, MODULE, STUDENT, day("2030-02-10"))
+                .await
+                .unwrap());
+            enroll_from(&pool, "2030-02-15").await;
+            record(&pool, classes[3], STUDENT, "present").await;
+
+            let stats = module_student_attendance(&pool, MODULE, None).await;
+            assert_eq!(stats.len(), 1);
+            assert_eq!((stats[0].present, stats[0].total), (2, 3));
+
+            // Only the classes held while enrolled list the student
+            for (class_id, listed) in classes.iter().zip([1, 0, 1, 1]) {
+                let stats = module_student_attendance(&pool, MODULE, Some(*class_id)).await;
+                assert_eq!(stats.len(), listed, "class {}", class_id);
+            }
+        });
+    }
+
+    #[test]
+    fn a_late_check_in_counts_as_attended_but_not_absent() {
+        const ABSENT: &str = "stats-absent@test.example";
+
+        run(async {
+            let pool = test_pool().await;
+            add_user(&pool, LECTURER, "lecturer").await;
+            add_module(&pool, MODULE, LECTURER).await;
+            for student in [STUDENT, ABSENT] {
+                add_user(&pool, student, "student").await;
+                enroll(&pool, MODULE, student).await;
+            }
+            let class_id =
+                add_class(&pool, MODULE, "A101", "2030-02-04", "09:00", 60, LECTURER).await;
+            complete_classes(&pool).await;
+            record(&pool, class_id, STUDENT, "late").await;
+            record(&pool, class_id, ABSENT, "absent").await;
+
+            let stats = overall_stats(&pool, Some(MODULE), None, LECTURER, "2030-02-04").await;
+            assert_eq!(stats.attendance_rate, 50.0);
+            assert_eq!(stats.absent_today, 1);
+            assert_eq!(stats.late_count, 1);
+            assert_eq!(stats.avg_class_size, 1.0);
+
+            let students = module_student_attendance(&pool, MODULE, None).await;
+            let late = students
+                .iter()
+                .find(|student| student.email_address == STUDENT)
+                .unwrap();
+            assert_eq!((late.present, late.late, late.total), (0, 1, 1));
+            assert_eq!(late.attendance_rate, 100.0);
+        });
+    }
+}
@@ -1,10 +1,25 @@
+use crate::error::ClockItError;
 use leptos::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssr")]
-use crate::database::init_db_pool;
+use crate::clock::clock;
 #[cfg(feature = "ssr")]
-use chrono::{Datelike, Duration as ChronoDuration, Local, NaiveDate, Utc};
+use crate::database::db_pool;
+#[cfg(feature = "ssr")]
+use crate::error::db_error;
+#[cfg(feature = "ssr")]
+use crate::authorization::{
+    require_class_staff, require_module_owner, require_module_staff, require_role, Role,
+};
+#[cfg(feature = "ssr")]
+use chrono::{Datelike, Duration as ChronoDuration, NaiveDate};
+#[cfg(feature = "ssr")]
+use crate::database::modules::{end_enrollment, enroll_in_module, set_enrollment_start};
+#[cfg(feature = "ssr")]
+use crate::database::universities::{module_today, user_timezone};
+#[cfg(feature = "ssr")]
+use crate::utils::class_time::local_today;
 
 // Student enrollment data structures
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -19,6 +34,9 @@ pub struct StudentInfo {
     pub name: String,
     pub surname: String,
     pub email_address: String,
+    /// First day of the enrollment, `None` when it counts from the module's first class
+    #[serde(default)]
+    pub enrolled_on: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -40,9 +58,11 @@ pub struct StudentStatsSummary {
     pub overall_attendance_rate: f64,
     pub weekly_attendance_rate: f64,
     pub total_present: i64,
+    pub total_late: i64,
     pub total_recorded: i64,
     pub upcoming_classes: i64,
     pub week_present: i64,
+    pub week_late: i64,
     pub week_recorded: i64,
 }
 
@@ -69,6 +89,7 @@ pub struct StudentRecentActivity {
     pub date: String,
     pub time: String,
     pub status: String,
+    pub excuse_status: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -115,10 +136,10 @@ struct DbStudentScheduleRow {
 #[server(EnrollStudent, "/api")]
 pub async fn enroll_student(
     request: EnrollStudentRequest,
-) -> Result<EnrollmentResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<EnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &request.module_code).await?;
 
     // Check if student exists
     let student = sqlx::query_as::<_, (i64, String, String, String)>(
@@ -127,16 +148,14 @@ pub async fn enroll_student(
     .bind(&request.student_email)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
 
     let student = match student {
         Some(s) => s,
         None => {
-            return Ok(EnrollmentResponse {
-                success: false,
-                message: "Student not found with this email address".to_string(),
-                student: None,
-            })
+            return Err(ClockItError::NotFound(
+                "Student not found with this email address".to_string(),
+            ));
         }
     };
 
@@ -146,46 +165,27 @@ pub async fn enroll_student(
             .bind(&request.module_code)
             .fetch_one(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            .map_err(db_error("Database error"))?;
 
     if !module_exists {
-        return Ok(EnrollmentResponse {
-            success: false,
-            message: "Module not found".to_string(),
-            student: None,
-        });
+        return Err(ClockItError::NotFound("Module not found".to_string()));
     }
 
-    // Check if already enrolled
-    let already_enrolled = sqlx::query_scalar::<_, bool>(
-        "SELECT EXISTS(SELECT 1 FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ?)"
-    )
-    .bind(&request.module_code)
-    .bind(&request.student_email)
-    .fetch_one(&pool)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    // Enroll student from today, so classes already held don't count against them
+    let enrolled_on = module_today(&pool, &request.module_code, &*clock()).await?;
+    let mut conn = pool
+        .acquire()
+        .await
+        .map_err(db_error("Database error"))?;
+    let enrolled =
+        enroll_in_module(&mut conn, &request.module_code, &student.3, enrolled_on).await?;
 
-    if already_enrolled {
-        return Ok(EnrollmentResponse {
-            success: false,
-            message: "Student is already enrolled in this module".to_string(),
-            student: None,
-        });
+    if !enrolled {
+        return Err(ClockItError::Conflict(
+            "Student is already enrolled in this module".to_string(),
+        ));
     }
 
-    // Enroll student
-    let now = Utc::now().to_rfc3339();
-    sqlx::query(
-        "INSERT INTO module_students (moduleCode, studentEmailAddress, created_at) VALUES (?, ?, ?)"
-    )
-    .bind(&request.module_code)
-    .bind(&request.student_email)
-    .bind(&now)
-    .execute(&pool)
-    .await
-    .map_err(|e| ServerFnError::new(format!("Failed to enroll student: {}", e)))?;
-
     Ok(EnrollmentResponse {
         success: true,
         message: "Student enrolled successfully!".to_string(),
@@ -194,6 +194,7 @@ pub async fn enroll_student(
             name: student.1,
             surname: student.2,
             email_address: student.3,
+            enrolled_on: Some(enrolled_on.format("%Y-%m-%d").to_string()),
         }),
     })
 }
@@ -202,24 +203,61 @@ pub async fn enroll_student(
 #[server(GetModuleStudents, "/api")]
 pub async fn get_module_students(
     module_code: String,
-) -> Result<StudentsListResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<StudentsListResponse, ClockItError> {
+    let pool = db_pool()?;
 
-    let students = sqlx::query_as::<_, (i64, String, String, String)>(
+    require_module_staff(&pool, &module_code).await?;
+
+    let students = sqlx::query_as::<_, (i64, String, String, String, Option<String>)>(
         r#"
-        SELECT u.userID, u.name, u.surname, u.emailAddress
+        SELECT u.userID, u.name, u.surname, u.emailAddress, ms.enrolled_on
         FROM users u
         INNER JOIN module_students ms ON u.emailAddress = ms.studentEmailAddress
-        WHERE ms.moduleCode = ?
+        WHERE ms.moduleCode = ? AND ms.unenrolled_on IS NULL
         ORDER BY u.surname, u.name
         "#,
     )
     .bind(&module_code)
     .fetch_all(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
+
+    Ok(StudentsListResponse {
+        success: true,
+        message: "Students fetched successfully".to_string(),
+        students: students
+            .into_iter()
+            .map(|(id, name, surname, email, enrolled_on)| StudentInfo {
+                user_id: id,
+                name,
+                surname,
+                email_address: email,
+                enrolled_on,
+            })
+            .collect(),
+    })
+}
+
+// Get the students expected at a class: its tutorial groups, or the whole module
+#[server(GetClassStudents, "/api")]
+pub async fn get_class_students(class_id: i64) -> Result<StudentsListResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_class_staff(&pool, class_id).await?;
+
+    let students = sqlx::query_as::<_, (i64, String, String, String)>(
+        r#"
+        SELECT u.userID, u.name, u.surname, u.emailAddress
+        FROM users u
+        INNER JOIN class_students cs ON u.emailAddress = cs.studentEmailAddress
+        WHERE cs.classID = ?
+        ORDER BY u.surname, u.name
+        "#,
+    )
+    .bind(class_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(db_error("Database error"))?;
 
     Ok(StudentsListResponse {
         success: true,
@@ -231,35 +269,60 @@ pub async fn get_module_students(
                 name,
                 surname,
                 email_address: email,
+                enrolled_on: None,
             })
             .collect(),
     })
 }
 
+/// Move the first day of a student's enrollment, for a late joiner added after the
+/// fact or to count them from the module's first class again (`None`)
+#[server(SetEnrollmentStart, "/api")]
+pub async fn set_enrollment_start_fn(
+    module_code: String,
+    student_email: String,
+    enrolled_on: Option<String>,
+) -> Result<EnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    let enrolled_on = match enrolled_on.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        Some(date) => Some(NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| {
+            ClockItError::Validation("Please choose a valid enrollment date".to_string())
+        })?),
+        None => None,
+    };
+
+    set_enrollment_start(&pool, &module_code, &student_email, enrolled_on).await?;
+
+    Ok(EnrollmentResponse {
+        success: true,
+        message: match enrolled_on {
+            Some(date) => format!("{} is enrolled from {}", student_email, date.format("%-d %B %Y")),
+            None => format!("{} is enrolled from the first class", student_email),
+        },
+        student: None,
+    })
+}
+
 // Remove a student from a module
 #[server(UnenrollStudent, "/api")]
 pub async fn unenroll_student(
     module_code: String,
     student_email: String,
-) -> Result<EnrollmentResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    let result =
-        sqlx::query("DELETE FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ?")
-            .bind(&module_code)
-            .bind(&student_email)
-            .execute(&pool)
-            .await
-            .map_err(|e| ServerFnError::new(format!("Failed to unenroll student: {}", e)))?;
-
-    if result.rows_affected() == 0 {
-        return Ok(EnrollmentResponse {
-            success: false,
-            message: "Student was not enrolled in this module".to_string(),
-            student: None,
-        });
+) -> Result<EnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    // The enrollment ends today rather than disappearing, so the classes they were
+    // enrolled for still count in the module's history
+    let today = module_today(&pool, &module_code, &*clock()).await?;
+    if !end_enrollment(&pool, &module_code, &student_email, today).await? {
+        return Err(ClockItError::NotFound(
+            "Student was not enrolled in this module".to_string(),
+        ));
     }
 
     Ok(EnrollmentResponse {
@@ -274,122 +337,75 @@ pub async fn unenroll_student(
 pub async fn bulk_enroll_students(
     module_code: String,
     student_emails: Vec<String>,
-) -> Result<EnrollmentResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
-
-    let now = Utc::now().to_rfc3339();
-    let mut enrolled_count = 0;
-    let mut errors = Vec::new();
-
-    for email in student_emails {
-        let email = email.trim().to_lowercase();
-        if email.is_empty() {
-            continue;
-        }
-
-        // Check if student exists
-        let student_exists = sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(SELECT 1 FROM users WHERE emailAddress = ? AND role = 'student')",
-        )
-        .bind(&email)
-        .fetch_one(&pool)
-        .await
-        .unwrap_or(false);
-
-        if !student_exists {
-            errors.push(format!("{} (not found)", email));
-            continue;
-        }
-
-        // Check if already enrolled
-        let already_enrolled = sqlx::query_scalar::<_, bool>(
-            "SELECT EXISTS(SELECT 1 FROM module_students WHERE moduleCode = ? AND studentEmailAddress = ?)"
-        )
-        .bind(&module_code)
-        .bind(&email)
-        .fetch_one(&pool)
-        .await
-        .unwrap_or(false);
-
-        if already_enrolled {
-            continue; // Skip already enrolled students silently
-        }
-
-        // Enroll student
-        match sqlx::query(
-            "INSERT INTO module_students (moduleCode, studentEmailAddress, created_at) VALUES (?, ?, ?)"
-        )
-        .bind(&module_code)
-        .bind(&email)
-        .bind(&now)
-        .execute(&pool)
-        .await {
-            Ok(_) => enrolled_count += 1,
-            Err(_) => errors.push(format!("{} (enrollment failed)", email)),
-        }
+) -> Result<EnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &module_code).await?;
+
+    // The enrolment runs in one transaction, so an error here means nobody was enrolled
+    let enrolled_on = module_today(&pool, &module_code, &*clock()).await?;
+    let outcome = crate::database::modules::bulk_enroll_students(
+        &pool,
+        &module_code,
+        &student_emails,
+        enrolled_on,
+    )
+    .await?;
+    let enrolled_count = outcome.enrolled;
+
+    let mut message = format!("Enrolled {} student(s)", enrolled_count);
+    if !outcome.invited.is_empty() {
+        message.push_str(&format!(
+            ". Invited {} without an account, who will be enrolled when they register",
+            outcome.invited.len()
+        ));
+    }
+    if !outcome.not_found.is_empty() {
+        message.push_str(&format!(
+            ". Errors: {}",
+            outcome
+                .not_found
+                .iter()
+                .map(|email| format!("{} (not a student)", email))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
     }
-
-    let message = if errors.is_empty() {
-        format!("Successfully enrolled {} student(s)", enrolled_count)
-    } else {
-        format!(
-            "Enrolled {} student(s). Errors: {}",
-            enrolled_count,
-            errors.join(", ")
-        )
-    };
 
     Ok(EnrollmentResponse {
-        success: enrolled_count > 0,
+        success: enrolled_count > 0 || !outcome.invited.is_empty(),
         message,
         student: None,
     })
 }
 
+/// Today's date at the student's university
+#[cfg(feature = "ssr")]
+async fn student_today(pool: &sqlx::SqlitePool, email: &str) -> Result<NaiveDate, ClockItError> {
+    Ok(local_today(user_timezone(pool, email).await?, clock().now()))
+}
+
 // Get upcoming classes for a student (default: today)
 #[server(GetStudentSchedule, "/api")]
 pub async fn get_student_schedule(
-    student_email: String,
     date: Option<String>,
-) -> Result<StudentScheduleResponse, ServerFnError> {
-    let trimmed_email = student_email.trim().to_lowercase();
-    if trimmed_email.is_empty() {
-        return Ok(StudentScheduleResponse {
-            success: false,
-            message: "Student email is required".to_string(),
-            classes: Vec::new(),
-        });
-    }
-
-    let selected_date = date
-        .map(|d| d.trim().to_string())
-        .filter(|d| !d.is_empty())
-        .unwrap_or_else(|| {
-            #[cfg(feature = "ssr")]
-            {
-                Local::now().naive_local().format("%Y-%m-%d").to_string()
-            }
-
-            #[cfg(not(feature = "ssr"))]
-            {
-                // Fallback for non-SSR builds (should not happen for server functions)
-                String::new()
-            }
-        });
-
-    if selected_date.is_empty() {
-        return Ok(StudentScheduleResponse {
-            success: false,
-            message: "Could not determine the requested date".to_string(),
-            classes: Vec::new(),
-        });
-    }
-
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<StudentScheduleResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let trimmed_email = require_role(&pool, &[Role::Student])
+        .await?
+        .email_address
+        .trim()
+        .to_lowercase();
+
+    // Without a date, today at the student's university
+    let selected_date = match date.map(|d| d.trim().to_string()).filter(|d| !d.is_empty()) {
+        Some(date) => date,
+        None => student_today(&pool, &trimmed_email)
+            .await?
+            .format("%Y-%m-%d")
+            .to_string(),
+    };
 
 let rows = sqlx::query_as::<_, DbStudentScheduleRow>(
     r#"
@@ -404,9 +420,9 @@ let rows = sqlx::query_as::<_, DbStudentScheduleRow>(
         c.status AS status,
         c.venue_updated_at AS venue_updated_at
     FROM classes c
-    INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+    INNER JOIN class_students cs ON cs.classID = c.classID
     INNER JOIN modules m ON m.moduleCode = c.moduleCode
-    WHERE ms.studentEmailAddress = ?
+    WHERE cs.studentEmailAddress = ?
       AND c.date >= ?
     ORDER BY c.date ASC, c.time ASC
     LIMIT 10
@@ -416,7 +432,7 @@ let rows = sqlx::query_as::<_, DbStudentScheduleRow>(
     .bind(&selected_date)
     .fetch_all(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to fetch student schedule: {}", e)))?;
+    .map_err(db_error("Failed to fetch student schedule"))?;
 
     let classes = rows
         .into_iter()
@@ -445,57 +461,49 @@ let rows = sqlx::query_as::<_, DbStudentScheduleRow>(
 }
 
 #[server(GetStudentStatsSummary, "/api")]
-pub async fn get_student_stats_summary(
-    student_email: String,
-) -> Result<StudentStatsSummary, ServerFnError> {
-    let normalized_email = student_email.trim().to_lowercase();
-    if normalized_email.is_empty() {
-        return Ok(StudentStatsSummary {
-            overall_attendance_rate: 0.0,
-            weekly_attendance_rate: 0.0,
-            total_present: 0,
-            total_recorded: 0,
-            upcoming_classes: 0,
-            week_present: 0,
-            week_recorded: 0,
-        });
-    }
+pub async fn get_student_stats_summary() -> Result<StudentStatsSummary, ClockItError> {
+    let pool = db_pool()?;
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let normalized_email = require_role(&pool, &[Role::Student])
+        .await?
+        .email_address
+        .trim()
+        .to_lowercase();
 
     let student_id: Option<i64> =
         sqlx::query_scalar("SELECT userID FROM users WHERE LOWER(emailAddress) = ?")
             .bind(&normalized_email)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup user: {}", e)))?;
+            .map_err(db_error("Failed to lookup user"))?;
 
     let Some(student_id) = student_id else {
         return Ok(StudentStatsSummary {
             overall_attendance_rate: 0.0,
             weekly_attendance_rate: 0.0,
             total_present: 0,
+            total_late: 0,
             total_recorded: 0,
             upcoming_classes: 0,
             week_present: 0,
+            week_late: 0,
             week_recorded: 0,
         });
     };
 
-    let today: NaiveDate = Local::now().naive_local().date();
+    let today: NaiveDate = student_today(&pool, &normalized_email).await?;
     let today_str = today.format("%Y-%m-%d").to_string();
 
-    let (total_present, total_recorded): (i64, i64) = sqlx::query_as(
+    let (total_present, total_late, total_recorded): (i64, i64, i64) = sqlx::query_as(
         r#"
         SELECT
             COALESCE(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END), 0) AS present_cnt,
+            COALESCE(SUM(CASE WHEN a.status = 'late' THEN 1 ELSE 0 END), 0) AS late_cnt,
             COALESCE(COUNT(a.attendanceID), 0) AS recorded_cnt
-        FROM classes c
-        INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+        FROM teaching_classes c
+        INNER JOIN class_students cs ON cs.classID = c.classID
         LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
-        WHERE ms.studentEmailAddress = ?
+        WHERE cs.studentEmailAddress = ?
           AND c.date <= ?
         "#,
     )
@@ -504,7 +512,7 @@ pub async fn get_student_stats_summary(
     .bind(&today_str)
     .fetch_one(&pool)
     .await
-    .unwrap_or((0, 0));
+    .unwrap_or((0, 0, 0));
 
     let start_of_week = {
         let weekday_offset = today.weekday().num_days_from_monday() as i64;
@@ -512,15 +520,16 @@ pub async fn get_student_stats_summary(
     };
     let end_of_week = start_of_week + ChronoDuration::days(6);
 
-    let (week_present, week_recorded): (i64, i64) = sqlx::query_as(
+    let (week_present, week_late, week_recorded): (i64, i64, i64) = sqlx::query_as(
         r#"
         SELECT
             COALESCE(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END), 0) AS present_cnt,
+            COALESCE(SUM(CASE WHEN a.status = 'late' THEN 1 ELSE 0 END), 0) AS late_cnt,
             COALESCE(COUNT(a.attendanceID), 0) AS recorded_cnt
-        FROM classes c
-        INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+        FROM teaching_classes c
+        INNER JOIN class_students cs ON cs.classID = c.classID
         LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
-        WHERE ms.studentEmailAddress = ?
+        WHERE cs.studentEmailAddress = ?
           AND c.date BETWEEN ? AND ?
         "#,
     )
@@ -530,14 +539,14 @@ pub async fn get_student_stats_summary(
     .bind(end_of_week.format("%Y-%m-%d").to_string())
     .fetch_one(&pool)
     .await
-    .unwrap_or((0, 0));
+    .unwrap_or((0, 0, 0));
 
     let upcoming_classes: i64 = sqlx::query_scalar(
         r#"
         SELECT COUNT(*)
         FROM classes c
-        INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
-        WHERE ms.studentEmailAddress = ?
+        INNER JOIN class_students cs ON cs.classID = c.classID
+        WHERE cs.studentEmailAddress = ?
           AND c.date > ?
         "#,
     )
@@ -548,12 +557,12 @@ pub async fn get_student_stats_summary(
     .unwrap_or(0);
 
     let overall_rate = if total_recorded > 0 {
-        (total_present as f64) * 100.0 / (total_recorded as f64)
+        ((total_present + total_late) as f64) * 100.0 / (total_recorded as f64)
     } else {
         0.0
     };
     let weekly_rate = if week_recorded > 0 {
-        (week_present as f64) * 100.0 / (week_recorded as f64)
+        ((week_present + week_late) as f64) * 100.0 / (week_recorded as f64)
     } else {
         0.0
     };
@@ -562,38 +571,38 @@ pub async fn get_student_stats_summary(
         overall_attendance_rate: overall_rate,
         weekly_attendance_rate: weekly_rate,
         total_present,
+        total_late,
         total_recorded,
         upcoming_classes,
         week_present,
+        week_late,
         week_recorded,
     })
 }
 
 #[server(GetStudentWeeklyAttendance, "/api")]
 pub async fn get_student_weekly_attendance(
-    student_email: String,
-) -> Result<Vec<StudentWeeklyAttendancePoint>, ServerFnError> {
-    let normalized_email = student_email.trim().to_lowercase();
-    if normalized_email.is_empty() {
-        return Ok(vec![]);
-    }
+) -> Result<Vec<StudentWeeklyAttendancePoint>, ClockItError> {
+    let pool = db_pool()?;
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let normalized_email = require_role(&pool, &[Role::Student])
+        .await?
+        .email_address
+        .trim()
+        .to_lowercase();
 
     let student_id: Option<i64> =
         sqlx::query_scalar("SELECT userID FROM users WHERE LOWER(emailAddress) = ?")
             .bind(&normalized_email)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup user: {}", e)))?;
+            .map_err(db_error("Failed to lookup user"))?;
 
     let Some(student_id) = student_id else {
         return Ok(vec![]);
     };
 
-    let today: NaiveDate = Local::now().naive_local().date();
+    let today: NaiveDate = student_today(&pool, &normalized_email).await?;
     let start_of_week = {
         let weekday_offset = today.weekday().num_days_from_monday() as i64;
         today - ChronoDuration::days(weekday_offset)
@@ -604,12 +613,12 @@ pub async fn get_student_weekly_attendance(
         r#"
         SELECT
             c.date,
-            COALESCE(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END), 0) AS present_cnt,
+            COALESCE(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END), 0) AS present_cnt,
             COALESCE(COUNT(a.attendanceID), 0) AS recorded_cnt
-        FROM classes c
-        INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+        FROM teaching_classes c
+        INNER JOIN class_students cs ON cs.classID = c.classID
         LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
-        WHERE ms.studentEmailAddress = ?
+        WHERE cs.studentEmailAddress = ?
           AND c.date BETWEEN ? AND ?
         GROUP BY c.date
         ORDER BY c.date ASC
@@ -649,30 +658,27 @@ pub async fn get_student_weekly_attendance(
 }
 
 #[server(GetStudentModuleBreakdown, "/api")]
-pub async fn get_student_module_breakdown(
-    student_email: String,
-) -> Result<Vec<StudentModuleBreakdown>, ServerFnError> {
-    let normalized_email = student_email.trim().to_lowercase();
-    if normalized_email.is_empty() {
-        return Ok(vec![]);
-    }
+pub async fn get_student_module_breakdown() -> Result<Vec<StudentModuleBreakdown>, ClockItError> {
+    let pool = db_pool()?;
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let normalized_email = require_role(&pool, &[Role::Student])
+        .await?
+        .email_address
+        .trim()
+        .to_lowercase();
 
     let student_id: Option<i64> =
         sqlx::query_scalar("SELECT userID FROM users WHERE LOWER(emailAddress) = ?")
             .bind(&normalized_email)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup user: {}", e)))?;
+            .map_err(db_error("Failed to lookup user"))?;
 
     let Some(student_id) = student_id else {
         return Ok(vec![]);
     };
 
-    let today: NaiveDate = Local::now().naive_local().date();
+    let today: NaiveDate = student_today(&pool, &normalized_email).await?;
     let today_str = today.format("%Y-%m-%d").to_string();
 
     let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(
@@ -680,11 +686,15 @@ pub async fn get_student_module_breakdown(
         SELECT
             m.moduleCode,
             m.moduleTitle,
-            COALESCE(SUM(CASE WHEN a.status = 'present' THEN 1 ELSE 0 END), 0) AS present_cnt,
+            COALESCE(SUM(CASE WHEN a.status IN ('present', 'late') THEN 1 ELSE 0 END), 0) AS present_cnt,
             COALESCE(COUNT(a.attendanceID), 0) AS recorded_cnt
         FROM modules m
         INNER JOIN module_students ms ON ms.moduleCode = m.moduleCode
-        LEFT JOIN classes c ON c.moduleCode = m.moduleCode AND c.date <= ?
+        LEFT JOIN teaching_classes c ON c.moduleCode = m.moduleCode AND c.date <= ?
+            AND EXISTS (
+                SELECT 1 FROM class_students cs
+                WHERE cs.classID = c.classID AND cs.studentEmailAddress = ms.studentEmailAddress
+            )
         LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
         WHERE ms.studentEmailAddress = ?
         GROUP BY m.moduleCode, m.moduleTitle
@@ -709,31 +719,32 @@ pub async fn get_student_module_breakdown(
         .collect())
 }
 
+// (classID, title, moduleCode, date, time, attendance status, excuse status)
+#[cfg(feature = "ssr")]
+type RecentActivityRow = (i64, String, String, String, String, Option<String>, Option<String>);
+
 #[server(GetStudentRecentActivity, "/api")]
-pub async fn get_student_recent_activity(
-    student_email: String,
-) -> Result<Vec<StudentRecentActivity>, ServerFnError> {
-    let normalized_email = student_email.trim().to_lowercase();
-    if normalized_email.is_empty() {
-        return Ok(vec![]);
-    }
+pub async fn get_student_recent_activity() -> Result<Vec<StudentRecentActivity>, ClockItError> {
+    let pool = db_pool()?;
 
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+    let normalized_email = require_role(&pool, &[Role::Student])
+        .await?
+        .email_address
+        .trim()
+        .to_lowercase();
 
     let student_id: Option<i64> =
         sqlx::query_scalar("SELECT userID FROM users WHERE LOWER(emailAddress) = ?")
             .bind(&normalized_email)
             .fetch_optional(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Failed to lookup user: {}", e)))?;
+            .map_err(db_error("Failed to lookup user"))?;
 
     let Some(student_id) = student_id else {
         return Ok(vec![]);
     };
 
-    let rows: Vec<(i64, String, String, String, String, Option<String>)> = sqlx::query_as(
+    let rows: Vec<RecentActivityRow> = sqlx::query_as(
         r#"
         SELECT
             c.classID,
@@ -741,16 +752,19 @@ pub async fn get_student_recent_activity(
             c.moduleCode,
             c.date,
             c.time,
-            a.status
+            a.status,
+            e.status
         FROM classes c
-        INNER JOIN module_students ms ON ms.moduleCode = c.moduleCode
+        INNER JOIN class_students cs ON cs.classID = c.classID
         LEFT JOIN attendance a ON a.classID = c.classID AND a.studentID = ?
-        WHERE ms.studentEmailAddress = ?
+        LEFT JOIN excuse_requests e ON e.classID = c.classID AND e.studentID = ?
+        WHERE cs.studentEmailAddress = ?
         ORDER BY c.date DESC, c.time DESC
         LIMIT 10
         "#,
     )
     .bind(student_id)
+    .bind(student_id)
     .bind(&normalized_email)
     .fetch_all(&pool)
     .await
@@ -759,13 +773,16 @@ pub async fn get_student_recent_activity(
     Ok(rows
         .into_iter()
         .map(
-            |(class_id, title, module_code, date, time, status)| StudentRecentActivity {
-                class_id,
-                title,
-                module_code,
-                date,
-                time,
-                status: status.unwrap_or_else(|| "upcoming".to_string()),
+            |(class_id, title, module_code, date, time, status, excuse_status)| {
+                StudentRecentActivity {
+                    class_id,
+                    title,
+                    module_code,
+                    date,
+                    time,
+                    status: status.unwrap_or_else(|| "upcoming".to_string()),
+                    excuse_status,
+                }
             },
         )
         .collect())
@@ -798,10 +815,10 @@ pub struct TutorInfo {
 #[server(EnrollTutor, "/api")]
 pub async fn enroll_tutor(
     request: EnrollTutorRequest,
-) -> Result<TutorEnrollmentResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<TutorEnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &request.module_code).await?;
 
     // Check if tutor exists
     let tutor = sqlx::query_as::<_, (i64, String, String, String)>(
@@ -810,16 +827,14 @@ pub async fn enroll_tutor(
     .bind(&request.tutor_email)
     .fetch_optional(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
 
     let tutor = match tutor {
         Some(t) => t,
         None => {
-            return Ok(TutorEnrollmentResponse {
-                success: false,
-                message: "Tutor not found with this email address".to_string(),
-                tutor: None,
-            })
+            return Err(ClockItError::NotFound(
+                "Tutor not found with this email address".to_string(),
+            ));
         }
     };
 
@@ -829,14 +844,10 @@ pub async fn enroll_tutor(
             .bind(&request.module_code)
             .fetch_one(&pool)
             .await
-            .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+            .map_err(db_error("Database error"))?;
 
     if !module_exists {
-        return Ok(TutorEnrollmentResponse {
-            success: false,
-            message: "Module not found".to_string(),
-            tutor: None,
-        });
+        return Err(ClockItError::NotFound("Module not found".to_string()));
     }
 
     // Check if tutor is already enrolled
@@ -847,14 +858,12 @@ pub async fn enroll_tutor(
     .bind(&request.tutor_email)
     .fetch_one(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
 
     if already_enrolled {
-        return Ok(TutorEnrollmentResponse {
-            success: false,
-            message: "Tutor is already enrolled in this module".to_string(),
-            tutor: None,
-        });
+        return Err(ClockItError::Conflict(
+            "Tutor is already enrolled in this module".to_string(),
+        ));
     }
 
     // Enroll the tutor
@@ -867,7 +876,7 @@ pub async fn enroll_tutor(
     .bind(&now)
     .execute(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Failed to enroll tutor: {}", e)))?;
+    .map_err(db_error("Failed to enroll tutor"))?;
 
     Ok(TutorEnrollmentResponse {
         success: true,
@@ -883,10 +892,10 @@ pub async fn enroll_tutor(
 
 // Get tutors for a module
 #[server(GetModuleTutors, "/api")]
-pub async fn get_module_tutors(module_code: String) -> Result<Vec<TutorInfo>, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+pub async fn get_module_tutors(module_code: String) -> Result<Vec<TutorInfo>, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_staff(&pool, &module_code).await?;
 
     let tutors = sqlx::query_as::<_, (i64, String, String, String)>(
         r#"
@@ -900,7 +909,7 @@ pub async fn get_module_tutors(module_code: String) -> Result<Vec<TutorInfo>, Se
     .bind(&module_code)
     .fetch_all(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
 
     Ok(tutors
         .into_iter()
@@ -917,10 +926,10 @@ pub async fn get_module_tutors(module_code: String) -> Result<Vec<TutorInfo>, Se
 #[server(UnenrollTutor, "/api")]
 pub async fn unenroll_tutor(
     request: EnrollTutorRequest,
-) -> Result<TutorEnrollmentResponse, ServerFnError> {
-    let pool = init_db_pool()
-        .await
-        .map_err(|e| ServerFnError::new(format!("Database connection failed: {}", e)))?;
+) -> Result<TutorEnrollmentResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_module_owner(&pool, &request.module_code).await?;
 
     // Check if tutor is enrolled
     let enrolled = sqlx::query_scalar::<_, bool>(
@@ -930,14 +939,12 @@ pub async fn unenroll_tutor(
     .bind(&request.tutor_email)
     .fetch_one(&pool)
     .await
-    .map_err(|e| ServerFnError::new(format!("Database error: {}", e)))?;
+    .map_err(db_error("Database error"))?;
 
     if !enrolled {
-        return Ok(TutorEnrollmentResponse {
-            success: false,
-            message: "Tutor is not enrolled in this module".to_string(),
-            tutor: None,
-        });
+        return Err(ClockItError::NotFound(
+            "Tutor is not enrolled in this module".to_string(),
+        ));
     }
 
     // Remove the tutor
@@ -946,7 +953,17 @@ pub async fn unenroll_tutor(
         .bind(&request.tutor_email)
         .execute(&pool)
         .await
-        .map_err(|e| ServerFnError::new(format!("Failed to unenroll tutor: {}", e)))?;
+        .map_err(db_error("Failed to unenroll tutor"))?;
+
+    // Their tutorial groups are left without a tutor
+    sqlx::query(
+        "UPDATE module_groups SET tutorEmailAddress = NULL, updated_at = CURRENT_TIMESTAMP WHERE moduleCode = ? AND tutorEmailAddress = ?",
+    )
+    .bind(&request.module_code)
+    .bind(&request.tutor_email)
+    .execute(&pool)
+    .await
+    .map_err(db_error("Failed to unenroll tutor"))?;
 
     Ok(TutorEnrollmentResponse {
         success: true,
@@ -1,4 +1,6 @@
 use crate::database::classes::Class;
+#[cfg(feature = "ssr")]
+use crate::error::ClockItError;
 use crate::routes::class_functions::{
     check_all_active_sessions_fn, get_active_class_session_fn, get_lecturer_classes_fn, start_class_session_fn,
 };
@@ -7,7 +9,6 @@ use crate::user_context::get_current_user;
 use crate::utils::module_visuals::{module_visual, ModuleVisual};
 use chrono::{Local, NaiveTime};
 use leptos::prelude::*;
-use leptos::server_fn::ServerFnError;
 use leptos_router::components::A;
 use leptos_router::hooks::use_navigate;
 use std::collections::HashSet;
@@ -34,9 +35,9 @@ pub fn Timetable() -> impl IntoView {
         move || (current_user.get(), session_refresh_trigger.get()),
         |(user, _)| async move {
             match user {
-                Some(user) => {
+                Some(_) => {
                     // Both lecturers and tutors see all classes in their modules
-                    let response = get_lecturer_classes_fn(user.email_address.clone()).await;
+                    let response = get_lecturer_classes_fn().await;
                     match response {
                         Ok(response) if response.success => Some(response.classes),
                         _ => None,
@@ -274,7 +275,6 @@ fn TimetableRow(class: Class) -> impl IntoView {
     let venue = class.venue.clone().unwrap_or_else(|| "TBA".to_string());
     let module_code_display = class.module_code.clone();
 
-    const LOCATION_RADIUS_METERS: f64 = 30.0;
     let location_status = RwSignal::new(None::<String>);
     let location_error = RwSignal::new(None::<String>);
 
@@ -299,7 +299,8 @@ fn TimetableRow(class: Class) -> impl IntoView {
                             Some(loc.latitude),
                             Some(loc.longitude),
                             loc.accuracy,
-                            Some(LOCATION_RADIUS_METERS),
+                            None,
+                            None,
                         )
                         .await;
 
@@ -310,9 +311,10 @@ fn TimetableRow(class: Class) -> impl IntoView {
                         response
                     }
                     Err(err) => {
-                        location_status.set(None);
-                        location_error.set(Some(err.clone()));
-                        Err(ServerFnError::new(err))
+                        // Without a fix the session can still be fenced by the venue's saved geofence
+                        leptos::logging::log!("Location unavailable ({}), using venue geofence", err);
+                        location_status.set(Some("Starting session at the saved venue...".to_string()));
+                        start_class_session_fn(id, None, None, None, None, None).await
                     }
                 }
             }
@@ -330,8 +332,8 @@ fn TimetableRow(class: Class) -> impl IntoView {
                 location_status.set(None);
                 let msg = "Location capture requires a browser context.".to_string();
                 location_error.set(Some(msg.clone()));
-                Err::<crate::routes::class_functions::ClassSessionResponse, ServerFnError>(
-                    ServerFnError::new(msg),
+                Err::<crate::routes::class_functions::ClassSessionResponse, ClockItError>(
+                    ClockItError::Internal(msg),
                 )
             }
         }
@@ -0,0 +1,65 @@
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_role, Role, STAFF};
+#[cfg(feature = "ssr")]
+use crate::database::{
+    db_pool,
+    universities::{set_university_timezone, university_timezone},
+};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct UniversityTimezoneResponse {
+    pub success: bool,
+    pub message: String,
+    pub university: String,
+    /// IANA name, such as `Africa/Johannesburg`
+    pub timezone: String,
+}
+
+/// The timezone the signed-in lecturer's or tutor's university schedules classes in
+#[server(GetUniversityTimezone, "/api")]
+pub async fn get_university_timezone_fn() -> Result<UniversityTimezoneResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let user = require_role(&pool, STAFF).await?;
+
+    let timezone = university_timezone(&pool, &user.university).await?;
+
+    Ok(UniversityTimezoneResponse {
+        success: true,
+        message: "Timezone retrieved".to_string(),
+        university: user.university.trim().to_string(),
+        timezone: timezone.name().to_string(),
+    })
+}
+
+/// Change the timezone of the signed-in lecturer's university. Class dates and times
+/// stay as they are, so every class of the university moves to the new zone.
+#[server(SetUniversityTimezone, "/api")]
+pub async fn set_university_timezone_fn(
+    timezone: String,
+) -> Result<UniversityTimezoneResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    let user = require_role(&pool, &[Role::Lecturer]).await?;
+    if user.university.trim().is_empty() {
+        return Err(ClockItError::Validation(
+            "Add your university to your profile first".to_string(),
+        ));
+    }
+
+    let timezone = set_university_timezone(&pool, &user.university, &timezone).await?;
+
+    Ok(UniversityTimezoneResponse {
+        success: true,
+        message: format!(
+            "{} now schedules classes in {}",
+            user.university.trim(),
+            timezone.name()
+        ),
+        university: user.university.trim().to_string(),
+        timezone: timezone.name().to_string(),
+    })
+}
@@ -0,0 +1,80 @@
+use crate::database::venues::{Venue, VenueRequest};
+use crate::error::ClockItError;
+use leptos::prelude::*;
+
+#[cfg(feature = "ssr")]
+use crate::database::{
+    db_pool,
+    venues::{create_venue, delete_venue, get_all_venues, update_venue},
+};
+#[cfg(feature = "ssr")]
+use crate::authorization::{require_role, Role, STAFF};
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct VenueResponse {
+    pub success: bool,
+    pub message: String,
+    pub venue: Option<Venue>,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct VenuesResponse {
+    pub success: bool,
+    pub message: String,
+    pub venues: Vec<Venue>,
+}
+
+/// Every registered venue, for lecturers and tutors picking where a class is held
+#[server(GetVenues, "/api")]
+pub async fn get_venues_fn() -> Result<VenuesResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, STAFF).await?;
+
+    let venues = get_all_venues(&pool).await?;
+
+    Ok(VenuesResponse {
+        success: true,
+        message: "Venues retrieved".to_string(),
+        venues,
+    })
+}
+
+/// Create a venue, or update it when `venue_id` is given
+#[server(SaveVenue, "/api")]
+pub async fn save_venue_fn(
+    venue_id: Option<i64>,
+    request: VenueRequest,
+) -> Result<VenueResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    request.validate().map_err(ClockItError::Validation)?;
+
+    let venue = match venue_id {
+        Some(venue_id) => update_venue(&pool, venue_id, &request).await,
+        None => create_venue(&pool, &request).await,
+    }?;
+
+    Ok(VenueResponse {
+        success: true,
+        message: format!("Saved {}", venue.name),
+        venue: Some(venue),
+    })
+}
+
+#[server(DeleteVenue, "/api")]
+pub async fn delete_venue_fn(venue_id: i64) -> Result<VenueResponse, ClockItError> {
+    let pool = db_pool()?;
+
+    require_role(&pool, &[Role::Lecturer]).await?;
+
+    delete_venue(&pool, venue_id).await?;
+
+    Ok(VenueResponse {
+        success: true,
+        message: "Venue deleted".to_string(),
+        venue: None,
+    })
+}
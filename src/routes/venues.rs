@@ -0,0 +1,312 @@
+use crate::database::venues::{Venue, VenueRequest};
+use crate::error::ClockItError;
+use crate::routes::venue_functions::{delete_venue_fn, get_venues_fn, save_venue_fn, VenueResponse};
+use crate::user_context::get_current_user;
+use crate::utils::geofence::{format_polygon, parse_polygon};
+use leptos::prelude::*;
+use leptos_router::components::A;
+
+/// Register lecture venues and the geofence sessions held there are checked against
+#[component]
+pub fn Venues() -> impl IntoView {
+    let current_user = get_current_user();
+    let can_edit = Signal::derive(move || {
+        current_user
+            .get()
+            .map(|user| user.role == "lecturer")
+            .unwrap_or(false)
+    });
+
+    let refresh = RwSignal::new(0u32);
+    let editing_id = RwSignal::new(None::<i64>);
+    let name = RwSignal::new(String::new());
+    let building = RwSignal::new(String::new());
+    let campus = RwSignal::new(String::new());
+    let latitude = RwSignal::new(String::new());
+    let longitude = RwSignal::new(String::new());
+    let radius = RwSignal::new(String::new());
+    let polygon = RwSignal::new(String::new());
+    let capacity = RwSignal::new(String::new());
+    let message = RwSignal::new(String::new());
+    let success = RwSignal::new(false);
+    let locating = RwSignal::new(false);
+
+    let venues_resource = Resource::new(
+        move || refresh.get(),
+        |_| async move {
+            match get_venues_fn().await {
+                Ok(response) if response.success => response.venues,
+                _ => Vec::new(),
+            }
+        },
+    );
+
+    let clear_form = move || {
+        editing_id.set(None);
+        for field in [
+            name, building, campus, latitude, longitude, radius, polygon, capacity,
+        ] {
+            field.set(String::new());
+        }
+    };
+
+    let edit_venue = move |venue: Venue| {
+        let number = |value: Option<f64>| value.map(|v| v.to_string()).unwrap_or_default();
+        editing_id.set(Some(venue.venue_id));
+        name.set(venue.name);
+        building.set(venue.building.unwrap_or_default());
+        campus.set(venue.campus.unwrap_or_default());
+        latitude.set(number(venue.latitude));
+        longitude.set(number(venue.longitude));
+        radius.set(number(venue.radius_meters));
+        polygon.set(venue.polygon.as_deref().map(format_polygon).unwrap_or_default());
+        capacity.set(venue.capacity.map(|c| c.to_string()).unwrap_or_default());
+        message.set(String::new());
+    };
+
+    let save_action = Action::new(move |(venue_id, request): &(Option<i64>, VenueRequest)| {
+        let venue_id = *venue_id;
+        let request = request.clone();
+        async move { save_venue_fn(venue_id, request).await }
+    });
+
+    let delete_action = Action::new(move |venue_id: &i64| {
+        let venue_id = *venue_id;
+        async move { delete_venue_fn(venue_id).await }
+    });
+
+    let handle_response = move |result: Result<VenueResponse, ClockItError>| match result {
+        Ok(response) => {
+            message.set(response.message);
+            success.set(response.success);
+            if response.success {
+                clear_form();
+                refresh.update(|n| *n = n.wrapping_add(1));
+            }
+        }
+        Err(e) => {
+            message.set(e.to_string());
+            success.set(false);
+        }
+    };
+
+    Effect::new(move |_| {
+        if let Some(result) = save_action.value().get() {
+            handle_response(result);
+        }
+    });
+
+    Effect::new(move |_| {
+        if let Some(result) = delete_action.value().get() {
+            handle_response(result);
+        }
+    });
+
+    let use_my_location = move |_| {
+        locating.set(true);
+        leptos::task::spawn_local(async move {
+            match crate::utils::geolocation::get_current_location().await {
+                Ok(loc) => {
+                    latitude.set(format!("{:.6}", loc.latitude));
+                    longitude.set(format!("{:.6}", loc.longitude));
+                }
+                Err(e) => {
+                    message.set(e);
+                    success.set(false);
+                }
+            }
+            locating.set(false);
+        });
+    };
+
+    let on_submit = move |_| {
+        message.set(String::new());
+        success.set(false);
+
+        let optional_text = |value: String| {
+            let value = value.trim().to_string();
+            (!value.is_empty()).then_some(value)
+        };
+        let optional_number = |value: String, label: &str| -> Result<Option<f64>, String> {
+            optional_text(value)
+                .map(|v| v.parse::<f64>().map_err(|_| format!("{} must be a number", label)))
+                .transpose()
+        };
+
+        let request = (|| -> Result<VenueRequest, String> {
+            Ok(VenueRequest {
+                name: name.get().trim().to_string(),
+                building: optional_text(building.get()),
+                campus: optional_text(campus.get()),
+                latitude: optional_number(latitude.get(), "Latitude")?,
+                longitude: optional_number(longitude.get(), "Longitude")?,
+                radius_meters: optional_number(radius.get(), "Radius")?,
+                polygon: optional_text(polygon.get())
+                    .map(|text| parse_polygon(&text))
+                    .transpose()?,
+                capacity: optional_text(capacity.get())
+                    .map(|v| v.parse::<i64>().map_err(|_| "Capacity must be a whole number".to_string()))
+                    .transpose()?,
+            })
+        })()
+        .and_then(|request| request.validate().map(|_| request));
+
+        match request {
+            Ok(request) => {
+                save_action.dispatch((editing_id.get(), request));
+            }
+            Err(e) => message.set(e),
+        }
+    };
+
+    view! {
+        <section class="venues-page">
+            <div class="page-header" style="display:flex;align-items:center;gap:8px;">
+                <A href="/home" attr:class="link">"←"</A>
+                <h1 class="page-title">"Venues"</h1>
+            </div>
+
+            <Show when=move || can_edit.get()>
+                <div class="form-card">
+                    <h3 class="heading">
+                        {move || if editing_id.get().is_some() { "Edit Venue" } else { "Add Venue" }}
+                    </h3>
+
+                    <label class="label" style="margin-top:6px;">"Name "<span style="color:#ef4444;">"*"</span></label>
+                    <input class="input" placeholder="e.g., Engineering Lecture Hall 1" bind:value=name/>
+
+                    <div style="display:flex; gap:12px; margin-top:10px;">
+                        <div style="flex:1;">
+                            <label class="label">"Building"</label>
+                            <input class="input" bind:value=building/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Campus"</label>
+                            <input class="input" bind:value=campus/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Capacity"</label>
+                            <input class="input" type="number" min="1" bind:value=capacity/>
+                        </div>
+                    </div>
+
+                    <div style="display:flex; gap:12px; margin-top:10px; align-items:flex-end;">
+                        <div style="flex:1;">
+                            <label class="label">"Centre latitude"</label>
+                            <input class="input" bind:value=latitude/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Centre longitude"</label>
+                            <input class="input" bind:value=longitude/>
+                        </div>
+                        <div style="flex:1;">
+                            <label class="label">"Radius (m)"</label>
+                            <input class="input" type="number" min="1" placeholder="30" bind:value=radius/>
+                        </div>
+                        <button class="btn btn-outline" on:click=use_my_location disabled=move || locating.get()>
+                            {move || if locating.get() { "Locating..." } else { "Use my location" }}
+                        </button>
+                    </div>
+
+                    <label class="label" style="margin-top:10px;">"Outline (optional)"</label>
+                    <textarea
+                        class="textarea"
+                        placeholder="One \"latitude, longitude\" corner per line"
+                        bind:value=polygon
+                    ></textarea>
+                    <p class="muted" style="margin-top:4px; font-size:0.85rem;">
+                        "Students must be inside the outline when one is saved, otherwise within the radius of the centre. Sessions use this geofence when your device can't get a location fix."
+                    </p>
+
+                    <Show when=move || !message.get().is_empty()>
+                        <p class=move || if success.get() { "success center" } else { "error center" } style="margin-top:12px;">
+                            {message}
+                        </p>
+                    </Show>
+
+                    <div class="actions-row">
+                        <button
+                            class="btn btn-accent"
+                            on:click=on_submit
+                            disabled=move || save_action.pending().get()
+                        >
+                            {move || if save_action.pending().get() { "Saving..." } else { "Save Venue" }}
+                        </button>
+                        <Show when=move || editing_id.get().is_some()>
+                            <button class="btn btn-outline" on:click=move |_| clear_form()>"Cancel"</button>
+                        </Show>
+                    </div>
+                </div>
+            </Show>
+
+            <div class="card" style="padding:0; margin-top:16px;">
+                <Suspense fallback=move || view! { <div class="loading">"Loading venues..."</div> }>
+                    {move || venues_resource.get().map(|venues| {
+                        if venues.is_empty() {
+                            return view! {
+                                <p style="padding:20px; text-align:center; color:#6b7280;">
+                                    "No venues saved yet."
+                                </p>
+                            }.into_any();
+                        }
+
+                        view! {
+                            <table class="table">
+                                <thead>
+                                    <tr>
+                                        <th>"Name"</th>
+                                        <th>"Location"</th>
+                                        <th>"Geofence"</th>
+                                        <th>"Capacity"</th>
+                                        <Show when=move || can_edit.get()>
+                                            <th>"Action"</th>
+                                        </Show>
+                                    </tr>
+                                </thead>
+                                <tbody>
+                                    {venues.into_iter().map(|venue| {
+                                        let location = [venue.building.clone(), venue.campus.clone()]
+                                            .into_iter()
+                                            .flatten()
+                                            .collect::<Vec<_>>()
+                                            .join(", ");
+                                        let geofence = match (&venue.polygon, venue.latitude) {
+                                            (Some(polygon), _) => format!("Outline ({} points)", polygon.len()),
+                                            (None, Some(_)) => format!("{:.0}m radius", venue.radius_meters.unwrap_or(30.0)),
+                                            (None, None) => "Not set".to_string(),
+                                        };
+                                        let capacity = venue.capacity.map(|c| c.to_string()).unwrap_or_default();
+                                        let venue_id = venue.venue_id;
+                                        let venue = StoredValue::new(venue.clone());
+                                        view! {
+                                            <tr>
+                                                <td>{venue.with_value(|v| v.name.clone())}</td>
+                                                <td>{location}</td>
+                                                <td>{geofence}</td>
+                                                <td>{capacity}</td>
+                                                <Show when=move || can_edit.get()>
+                                                    <td style="display:flex; gap:6px;">
+                                                        <button
+                                                            class="btn btn-outline btn-small"
+                                                            on:click=move |_| edit_venue(venue.get_value())
+                                                        >"Edit"</button>
+                                                        <button
+                                                            class="btn btn-outline btn-small"
+                                                            style="color:#ef4444; border-color:#fecaca;"
+                                                            disabled=move || delete_action.pending().get()
+                                                            on:click=move |_| { delete_action.dispatch(venue_id); }
+                                                        >"🗑 Delete"</button>
+                                                    </td>
+                                                </Show>
+                                            </tr>
+                                        }
+                                    }).collect_view()}
+                                </tbody>
+                            </table>
+                        }.into_any()
+                    })}
+                </Suspense>
+            </div>
+        </section>
+    }
+}
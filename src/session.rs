@@ -0,0 +1,86 @@
+//! Server-side login sessions.
+//!
+//! `login_user` stores the signed-in user's ID in an `axum_session` session that is
+//! persisted in the SQLite database. Server functions resolve the caller through
+//! [`require_user`] instead of trusting identity fields sent by the browser.
+
+use crate::database::{get_user_by_id, DbPool};
+use crate::error::ClockItError;
+use crate::types::UserProfile;
+use axum_session::{SameSite, Session, SessionConfig, SessionLayer, SessionMode, SessionStore};
+use axum_session_sqlx::SessionSqlitePool;
+
+pub type AppSession = Session<SessionSqlitePool>;
+
+const USER_ID_KEY: &str = "user_id";
+
+/// Build the session layer, creating the `sessions` table if needed
+pub async fn session_layer(
+    pool: &DbPool,
+    secure_cookies: bool,
+) -> Result<SessionLayer<SessionSqlitePool>, String> {
+    // OptIn: anonymous visitors get no stored session until they sign in
+    let config = SessionConfig::default()
+        .with_table_name("sessions")
+        .with_session_name("clock_it_session")
+        .with_mode(SessionMode::OptIn)
+        .with_http_only(true)
+        .with_secure(secure_cookies)
+        .with_cookie_same_site(SameSite::Lax)
+        .with_lifetime(chrono::Duration::days(7));
+
+    let store = SessionStore::<SessionSqlitePool>::new(Some(pool.clone().into()), config)
+        .await
+        .map_err(|e| format!("Failed to initialise session store: {}", e))?;
+
+    Ok(SessionLayer::new(store))
+}
+
+async fn current_session() -> Result<AppSession, ClockItError> {
+    leptos_axum::extract::<AppSession>()
+        .await
+        .map_err(|e| ClockItError::Internal(format!("Session unavailable: {}", e)))
+}
+
+/// Attach `user` to the caller's session, issuing a fresh session ID
+pub async fn sign_in(user: &UserProfile) -> Result<(), ClockItError> {
+    let session = current_session().await?;
+    session.renew();
+    session.set_store(true);
+    session.set(USER_ID_KEY, user.user_id);
+    Ok(())
+}
+
+/// Invalidate the caller's session, both in the database and in the browser
+pub async fn sign_out() -> Result<(), ClockItError> {
+    let session = current_session().await?;
+    session.destroy();
+    Ok(())
+}
+
+/// The signed-in user, if the request carries a valid session
+pub async fn current_user(pool: &DbPool) -> Result<Option<UserProfile>, ClockItError> {
+    let session = current_session().await?;
+    let Some(user_id) = session.get::<i64>(USER_ID_KEY) else {
+        return Ok(None);
+    };
+
+    let user = get_user_by_id(pool, user_id).await?;
+
+    // The account was deleted after sign-in; drop the stale session
+    if user.is_none() {
+        session.destroy();
+    }
+
+    Ok(user)
+}
+
+/// The error for a request that needs a signed-in user but has none (401)
+pub fn sign_in_required() -> ClockItError {
+    ClockItError::Unauthorized("Please sign in to continue".to_string())
+}
+
+/// The signed-in user, or an `Unauthorized` (401) error when there is none
+pub async fn require_user(pool: &DbPool) -> Result<UserProfile, ClockItError> {
+    current_user(pool).await?.ok_or_else(sign_in_required)
+}
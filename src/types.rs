@@ -33,3 +33,19 @@ pub struct BasicResponse {
     pub success: bool,
     pub message: String,
 }
+
+/// What a one-time email code was issued for; a code only verifies for its own purpose
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OtpPurpose {
+    Signup,
+    PasswordReset,
+}
+
+impl OtpPurpose {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OtpPurpose::Signup => "signup",
+            OtpPurpose::PasswordReset => "password_reset",
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Files uploaded by users, kept on disk under `CLOCK_IT_UPLOAD_DIR` (default
+//! `uploads`). The database only stores the path relative to that directory,
+//! so the directory can be moved without rewriting rows.
+
+use std::path::{Component, Path, PathBuf};
+
+const DEFAULT_UPLOAD_DIR: &str = "uploads";
+
+/// Largest attachment accepted, before base64 encoding
+pub const MAX_ATTACHMENT_BYTES: usize = 2 * 1024 * 1024;
+
+/// Attachment types accepted, with the extension they are stored under
+const ALLOWED_TYPES: [(&str, &str); 4] = [
+    ("application/pdf", "pdf"),
+    ("image/png", "png"),
+    ("image/jpeg", "jpg"),
+    ("image/webp", "webp"),
+];
+
+pub fn upload_dir() -> PathBuf {
+    PathBuf::from(
+        std::env::var("CLOCK_IT_UPLOAD_DIR").unwrap_or_else(|_| DEFAULT_UPLOAD_DIR.to_string()),
+    )
+}
+
+/// The extension an upload of `content_type` is stored with, or None if the type
+/// isn't accepted
+pub fn extension_for(content_type: &str) -> Option<&'static str> {
+    let content_type = content_type.trim().to_ascii_lowercase();
+    ALLOWED_TYPES
+        .iter()
+        .find(|(mime, _)| *mime == content_type)
+        .map(|(_, ext)| *ext)
+}
+
+/// Write `bytes` to a new file in `category` and return its stored (relative) path
+pub async fn save_upload(category: &str, content_type: &str, bytes: &[u8]) -> Result<String, String> {
+    let ext = extension_for(content_type)
+        .ok_or_else(|| "Attachments must be a PDF, PNG, JPEG or WebP file".to_string())?;
+    if bytes.len() > MAX_ATTACHMENT_BYTES {
+        return Err(format!(
+            "Attachments can be at most {} MB",
+            MAX_ATTACHMENT_BYTES / (1024 * 1024)
+        ));
+    }
+
+    let relative = format!("{}/{}.{}", category, uuid::Uuid::new_v4(), ext);
+    let full = upload_dir().join(&relative);
+    if let Some(parent) = full.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create upload directory: {}", e))?;
+    }
+
+    tokio::fs::write(&full, bytes)
+        .await
+        .map_err(|e| format!("Failed to save attachment: {}", e))?;
+
+    Ok(relative)
+}
+
+pub async fn read_upload(stored_path: &str) -> Result<Vec<u8>, String> {
+    let full = resolve(stored_path)?;
+    tokio::fs::read(&full)
+        .await
+        .map_err(|e| format!("Failed to read attachment: {}", e))
+}
+
+/// Remove a stored upload. A file that is already gone is not an error.
+pub async fn remove_upload(stored_path: &str) -> Result<(), String> {
+    let full = resolve(stored_path)?;
+    match tokio::fs::remove_file(&full).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(format!("Failed to remove attachment: {}", e)),
+    }
+}
+
+// Stored paths are always ones we generated, but never follow one out of the upload directory
+fn resolve(stored_path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(stored_path);
+    if path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return Err("Invalid attachment path".to_string());
+    }
+    Ok(upload_dir().join(path))
+}
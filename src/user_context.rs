@@ -9,24 +9,24 @@ pub fn init_user_context() {
     let signal = RwSignal::new(None);
     CURRENT_USER.set(signal).unwrap_or(());
 
-    // Load user from storage after a small delay to ensure DOM is ready
+    // Restore the signed-in user from the server-side session cookie
     #[cfg(target_arch = "wasm32")]
     {
+        use crate::routes::auth_functions::get_session_user;
         use leptos::task::spawn_local;
         spawn_local(async {
-            // Small delay to ensure everything is initialized
-            gloo_timers::future::TimeoutFuture::new(50).await;
-
-            if let Some(stored_user) = load_user_from_storage() {
-                web_sys::console::log_1(
-                    &format!(
-                        "Loading user from storage: {} {}",
-                        stored_user.name, stored_user.surname
-                    )
-                    .into(),
-                );
-                if let Some(signal) = CURRENT_USER.get() {
-                    signal.set(Some(stored_user));
+            match get_session_user().await {
+                Ok(Some(user)) => {
+                    web_sys::console::log_1(
+                        &format!("Restored session for: {} {}", user.name, user.surname).into(),
+                    );
+                    if let Some(signal) = CURRENT_USER.get() {
+                        signal.set(Some(user));
+                    }
+                }
+                Ok(None) => web_sys::console::log_1(&"No active session".into()),
+                Err(e) => {
+                    web_sys::console::log_1(&format!("Failed to restore session: {}", e).into())
                 }
             }
         });
@@ -57,14 +57,7 @@ pub fn set_current_user(user: UserProfile) {
     web_sys::console::log_1(&format!("Setting user: {} {}", user.name, user.surname).into());
 
     if let Some(signal) = CURRENT_USER.get() {
-        signal.set(Some(user.clone()));
-
-        // Save to localStorage (client-side only)
-        #[cfg(target_arch = "wasm32")]
-        {
-            save_user_to_storage(&user);
-            web_sys::console::log_1(&"User saved to localStorage".into());
-        }
+        signal.set(Some(user));
     }
 }
 
@@ -77,13 +70,18 @@ pub fn clear_current_user() {
 
     if let Some(signal) = CURRENT_USER.get() {
         signal.set(None);
+    }
 
-        // Remove from localStorage (client-side only)
-        #[cfg(target_arch = "wasm32")]
-        {
-            remove_user_from_storage();
-            web_sys::console::log_1(&"User removed from localStorage".into());
-        }
+    // Invalidate the server-side session as well
+    #[cfg(target_arch = "wasm32")]
+    {
+        use crate::routes::auth_functions::logout_user;
+        use leptos::task::spawn_local;
+        spawn_local(async {
+            if let Err(e) = logout_user().await {
+                web_sys::console::log_1(&format!("Failed to end session: {}", e).into());
+            }
+        });
     }
 }
 
@@ -97,54 +95,3 @@ pub fn get_user_name() -> String {
         "User".to_string()
     }
 }
-
-// localStorage helper functions (client-side only)
-#[cfg(target_arch = "wasm32")]
-fn save_user_to_storage(user: &UserProfile) {
-    use web_sys::window;
-
-    if let Some(window) = window() {
-        if let Ok(Some(storage)) = window.local_storage() {
-            if let Ok(serialized) = serde_json::to_string(user) {
-                match storage.set_item("clock_it_user", &serialized) {
-                    Ok(_) => web_sys::console::log_1(&"Successfully saved to localStorage".into()),
-                    Err(e) => web_sys::console::log_1(
-                        &format!("Error saving to localStorage: {:?}", e).into(),
-                    ),
-                }
-            }
-        }
-    }
-}
-
-#[cfg(target_arch = "wasm32")]
-fn load_user_from_storage() -> Option<UserProfile> {
-    use web_sys::window;
-
-    if let Some(window) = window() {
-        if let Ok(Some(storage)) = window.local_storage() {
-            if let Ok(Some(stored)) = storage.get_item("clock_it_user") {
-                web_sys::console::log_1(&format!("Found stored data: {}", stored).into());
-                if let Ok(user) = serde_json::from_str::<UserProfile>(&stored) {
-                    return Some(user);
-                } else {
-                    web_sys::console::log_1(&"Error parsing stored user data".into());
-                }
-            } else {
-                web_sys::console::log_1(&"No stored user data found".into());
-            }
-        }
-    }
-    None
-}
-
-#[cfg(target_arch = "wasm32")]
-fn remove_user_from_storage() {
-    use web_sys::window;
-
-    if let Some(window) = window() {
-        if let Ok(Some(storage)) = window.local_storage() {
-            let _ = storage.remove_item("clock_it_user");
-        }
-    }
-}
@@ -0,0 +1,166 @@
+//! Classes are stored as the wall-clock `date` and `time` at the module's university,
+//! which only become instants in a timezone. Everything that compares a class with
+//! the current time goes through here, so the answer doesn't depend on the zone the
+//! server happens to run in. Callers pass `now` in, which keeps the arithmetic pure.
+
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// The zone used for a university without one, and for modules without a university
+pub const DEFAULT_TIMEZONE: Tz = chrono_tz::Africa::Johannesburg;
+
+/// An IANA timezone name, such as `Africa/Johannesburg`
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.trim()
+        .parse::<Tz>()
+        .map_err(|_| format!("\"{}\" isn't a known timezone", name.trim()))
+}
+
+/// The instant a wall-clock time in `tz` refers to. A time that happens twice when
+/// the clocks go back is the first of the two; a time skipped when they go forward
+/// is read with the offset from before the change, landing just after the gap.
+pub fn zoned(local: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(instant) => instant.with_timezone(&Utc),
+        LocalResult::Ambiguous(earliest, _) => earliest.with_timezone(&Utc),
+        LocalResult::None => {
+            let before = tz.offset_from_utc_datetime(&(local - Duration::days(1)));
+            let offset = chrono::Offset::fix(&before);
+            Utc.from_utc_datetime(&(local - Duration::seconds(offset.local_minus_utc() as i64)))
+        }
+    }
+}
+
+/// The wall-clock time in `tz` at `now`
+pub fn local_now(tz: Tz, now: DateTime<Utc>) -> NaiveDateTime {
+    now.with_timezone(&tz).naive_local()
+}
+
+/// The date in `tz` at `now`
+pub fn local_today(tz: Tz, now: DateTime<Utc>) -> NaiveDate {
+    local_now(tz, now).date()
+}
+
+/// When a class starts and ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl ClassWindow {
+    /// From a class's stored `date` (`YYYY-MM-DD`) and `time` (`HH:MM`) at a university
+    /// in `tz`. A class lasts `duration_minutes` of real time, even across a clock change.
+    pub fn new(date: &str, time: &str, duration_minutes: i32, tz: Tz) -> Option<Self> {
+        let date = NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d").ok()?;
+        let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").ok()?;
+        let start = zoned(date.and_time(time), tz);
+        Some(Self {
+            start,
+            end: start + Duration::minutes(duration_minutes.max(0) as i64),
+        })
+    }
+
+    /// Negative before the class starts
+    pub fn minutes_since_start(&self, now: DateTime<Utc>) -> i64 {
+        (now - self.start).num_minutes()
+    }
+
+    pub fn has_ended(&self, now: DateTime<Utc>) -> bool {
+        now >= self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn class_in_johannesburg_is_two_hours_ahead_of_utc() {
+        let window = ClassWindow::new("2025-03-10", "09:00", 90, DEFAULT_TIMEZONE).unwrap();
+
+        assert_eq!(window.start, utc("2025-03-10T07:00:00Z"));
+        assert_eq!(window.end, utc("2025-03-10T08:30:00Z"));
+    }
+
+    #[test]
+    fn a_utc_clock_doesnt_end_a_class_early() {
+        let window = ClassWindow::new("2025-03-10", "09:00", 90, DEFAULT_TIMEZONE).unwrap();
+
+        // 09:30 on the server's UTC clock is 11:30 in Stellenbosch: the class has ended
+        let now = utc("2025-03-10T09:30:00Z");
+        assert!(window.has_ended(now));
+
+        // 08:00 UTC is 10:00 local, an hour into the class
+        let now = utc("2025-03-10T08:00:00Z");
+        assert!(!window.has_ended(now));
+        assert_eq!(window.minutes_since_start(now), 60);
+    }
+
+    #[test]
+    fn minutes_since_start_is_negative_before_the_class() {
+        let window = ClassWindow::new("2025-03-10", "09:00", 60, DEFAULT_TIMEZONE).unwrap();
+
+        assert_eq!(window.minutes_since_start(utc("2025-03-10T06:45:00Z")), -15);
+    }
+
+    #[test]
+    fn today_is_the_universitys_date() {
+        // 23:30 UTC on the 9th is already the 10th in Johannesburg
+        let now = utc("2025-03-09T23:30:00Z");
+
+        assert_eq!(
+            local_today(DEFAULT_TIMEZONE, now),
+            NaiveDate::from_ymd_opt(2025, 3, 10).unwrap()
+        );
+        assert_eq!(
+            local_today(chrono_tz::America::New_York, now),
+            NaiveDate::from_ymd_opt(2025, 3, 9).unwrap()
+        );
+    }
+
+    #[test]
+    fn class_across_the_spring_forward_gap_keeps_its_length() {
+        let london = chrono_tz::Europe::London;
+        // Clocks go from 01:00 to 02:00 GMT on 30 March 2025
+        let window = ClassWindow::new("2025-03-30", "00:30", 60, london).unwrap();
+
+        assert_eq!(window.start, utc("2025-03-30T00:30:00Z"));
+        assert_eq!(window.end, utc("2025-03-30T01:30:00Z"));
+    }
+
+    #[test]
+    fn class_in_the_spring_forward_gap_starts_after_it() {
+        let london = chrono_tz::Europe::London;
+        // 01:30 doesn't exist on 30 March 2025; read as GMT it is 02:30 BST
+        let window = ClassWindow::new("2025-03-30", "01:30", 60, london).unwrap();
+
+        assert_eq!(window.start, utc("2025-03-30T01:30:00Z"));
+    }
+
+    #[test]
+    fn class_in_the_repeated_autumn_hour_is_the_first_one() {
+        let london = chrono_tz::Europe::London;
+        // 01:00 to 02:00 happens twice on 26 October 2025, first in BST
+        let window = ClassWindow::new("2025-10-26", "01:30", 60, london).unwrap();
+
+        assert_eq!(window.start, utc("2025-10-26T00:30:00Z"));
+        assert_eq!(window.end, utc("2025-10-26T01:30:00Z"));
+    }
+
+    #[test]
+    fn invalid_dates_and_times_have_no_window() {
+        assert!(ClassWindow::new("2025-02-30", "09:00", 60, DEFAULT_TIMEZONE).is_none());
+        assert!(ClassWindow::new("2025-03-10", "9am", 60, DEFAULT_TIMEZONE).is_none());
+    }
+
+    #[test]
+    fn parses_iana_names() {
+        assert_eq!(parse_timezone(" Africa/Johannesburg "), Ok(DEFAULT_TIMEZONE));
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+    }
+}
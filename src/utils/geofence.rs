@@ -0,0 +1,112 @@
+//! Distance and containment checks for check-in geofences. Polygons are lists of
+//! `[latitude, longitude]` vertices; they are small enough (a lecture hall) that
+//! treating the ground as flat around them is accurate to well under a metre.
+
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
+
+/// Great-circle distance in metres
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+    let lat1_rad = lat1.to_radians();
+    let lat2_rad = lat2.to_radians();
+
+    let a =
+        (d_lat / 2.0).sin().powi(2) + lat1_rad.cos() * lat2_rad.cos() * (d_lon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+
+    EARTH_RADIUS_M * c
+}
+
+/// Ray-casting containment test
+pub fn point_in_polygon(lat: f64, lng: f64, polygon: &[[f64; 2]]) -> bool {
+    let mut inside = false;
+    let mut j = polygon.len().wrapping_sub(1);
+    for i in 0..polygon.len() {
+        let [lat_i, lng_i] = polygon[i];
+        let [lat_j, lng_j] = polygon[j];
+        if (lat_i > lat) != (lat_j > lat)
+            && lng < (lng_j - lng_i) * (lat - lat_i) / (lat_j - lat_i) + lng_i
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Metres from the point to the nearest edge of the polygon, or 0 inside it
+pub fn distance_outside_polygon(lat: f64, lng: f64, polygon: &[[f64; 2]]) -> f64 {
+    if polygon.len() < 3 || point_in_polygon(lat, lng, polygon) {
+        return 0.0;
+    }
+
+    // Project onto a local plane in metres centred on the point
+    let metres_per_deg_lat = EARTH_RADIUS_M.to_radians();
+    let metres_per_deg_lng = metres_per_deg_lat * lat.to_radians().cos();
+    let project = |[p_lat, p_lng]: [f64; 2]| {
+        (
+            (p_lng - lng) * metres_per_deg_lng,
+            (p_lat - lat) * metres_per_deg_lat,
+        )
+    };
+
+    let mut nearest = f64::INFINITY;
+    for i in 0..polygon.len() {
+        let (ax, ay) = project(polygon[i]);
+        let (bx, by) = project(polygon[(i + 1) % polygon.len()]);
+        let (dx, dy) = (bx - ax, by - ay);
+        let length_sq = dx * dx + dy * dy;
+        let t = if length_sq == 0.0 {
+            0.0
+        } else {
+            (-(ax * dx + ay * dy) / length_sq).clamp(0.0, 1.0)
+        };
+        let (cx, cy) = (ax + t * dx, ay + t * dy);
+        nearest = nearest.min((cx * cx + cy * cy).sqrt());
+    }
+    nearest
+}
+
+/// Parse a polygon typed as one `latitude, longitude` pair per line
+pub fn parse_polygon(text: &str) -> Result<Vec<[f64; 2]>, String> {
+    let mut points = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let point = line
+            .split_once(',')
+            .and_then(|(lat, lng)| Some([lat.trim().parse().ok()?, lng.trim().parse().ok()?]))
+            .ok_or_else(|| format!("Line {}: expected \"latitude, longitude\"", index + 1))?;
+        validate_coordinates(point[0], point[1])
+            .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        points.push(point);
+    }
+
+    if points.len() < 3 {
+        return Err("A venue outline needs at least three points".to_string());
+    }
+    Ok(points)
+}
+
+/// The inverse of [`parse_polygon`]
+pub fn format_polygon(polygon: &[[f64; 2]]) -> String {
+    polygon
+        .iter()
+        .map(|[lat, lng]| format!("{}, {}", lat, lng))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn validate_coordinates(lat: f64, lng: f64) -> Result<(), String> {
+    if !lat.is_finite() || !(-90.0..=90.0).contains(&lat) {
+        return Err("Latitude must be between -90 and 90".to_string());
+    }
+    if !lng.is_finite() || !(-180.0..=180.0).contains(&lng) {
+        return Err("Longitude must be between -180 and 180".to_string());
+    }
+    Ok(())
+}
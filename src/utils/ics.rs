@@ -0,0 +1,319 @@
+//! Reading and writing iCalendar (RFC 5545) files. Only VEVENTs are read, and
+//! only the properties the calendar imports use. Times are taken as written: a
+//! TZID or a trailing `Z` is not converted. Written events are in UTC, so calendar
+//! apps show them at the right time wherever they are.
+
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+
+/// Longest content line allowed before it has to be folded, in bytes
+const MAX_LINE_OCTETS: usize = 75;
+
+/// One VEVENT from an iCalendar file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IcsEvent {
+    pub uid: Option<String>,
+    pub summary: String,
+    pub location: Option<String>,
+    pub start: NaiveDateTime,
+    /// DTEND as written; exclusive, so an all-day event on one day ends the next
+    pub end: Option<NaiveDateTime>,
+    /// DTSTART was a date without a time
+    pub all_day: bool,
+    /// The RRULE value as written, for a repeating event
+    pub rrule: Option<String>,
+    /// Dates from every EXDATE, which the RRULE skips
+    pub exdates: Vec<NaiveDate>,
+    /// Set on an edited copy of one occurrence of a repeating event with the same UID
+    pub recurrence_id: Option<NaiveDateTime>,
+    /// STATUS:CANCELLED
+    pub cancelled: bool,
+}
+
+impl IcsEvent {
+    /// The first and last day the event covers
+    pub fn days(&self) -> (NaiveDate, NaiveDate) {
+        let first = self.start.date();
+        let last = match self.end {
+            // Ending at midnight means the day before was the last
+            Some(end) if end.time() == NaiveTime::MIN => {
+                (end.date() - Duration::days(1)).max(first)
+            }
+            Some(end) => end.date().max(first),
+            None => first,
+        };
+        (first, last)
+    }
+}
+
+/// Every VEVENT in `text`. Events without a DTSTART are skipped.
+pub fn parse_events(text: &str) -> Result<Vec<IcsEvent>, String> {
+    let lines = unfold(text);
+    if !lines
+        .iter()
+        .any(|line| line.eq_ignore_ascii_case("BEGIN:VCALENDAR"))
+    {
+        return Err("This isn't an iCalendar (.ics) file".to_string());
+    }
+
+    let mut events = Vec::new();
+    let mut current: Option<Vec<(String, String, String)>> = None;
+    for line in &lines {
+        let Some((name, params, value)) = split_property(line) else {
+            continue;
+        };
+        match (name.as_str(), value.to_ascii_uppercase().as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => {
+                if let Some(properties) = current.take() {
+                    events.extend(build_event(&properties)?);
+                }
+            }
+            _ => {
+                if let Some(properties) = current.as_mut() {
+                    properties.push((name, params, value));
+                }
+            }
+        }
+    }
+    Ok(events)
+}
+
+// Lines that start with a space or tab continue the line before
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// `NAME;PARAM=x:VALUE` into the upper-cased name, its parameters and the value
+fn split_property(line: &str) -> Option<(String, String, String)> {
+    let (head, value) = line.split_once(':')?;
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    Some((
+        name.trim().to_ascii_uppercase(),
+        params.to_ascii_uppercase(),
+        value.to_string(),
+    ))
+}
+
+fn build_event(properties: &[(String, String, String)]) -> Result<Option<IcsEvent>, String> {
+    let find = |wanted: &str| {
+        properties
+            .iter()
+            .find(|(name, _, _)| name == wanted)
+            .map(|(_, params, value)| (params.as_str(), value.as_str()))
+    };
+
+    let Some((_, start)) = find("DTSTART") else {
+        return Ok(None);
+    };
+    let (start, all_day) = parse_date_time(start)?;
+    let end = match (find("DTEND"), find("DURATION")) {
+        (Some((_, end)), _) => Some(parse_date_time(end)?.0),
+        (None, Some((_, duration))) => Some(start + parse_duration(duration)?),
+        (None, None) => None,
+    };
+
+    let mut exdates = Vec::new();
+    for (_, _, value) in properties.iter().filter(|(name, _, _)| name == "EXDATE") {
+        for date in value.split(',').filter(|date| !date.trim().is_empty()) {
+            exdates.push(parse_date_time(date)?.0.date());
+        }
+    }
+
+    Ok(Some(IcsEvent {
+        uid: find("UID").map(|(_, value)| unescape(value)),
+        summary: find("SUMMARY")
+            .map(|(_, value)| unescape(value))
+            .unwrap_or_default(),
+        location: find("LOCATION")
+            .map(|(_, value)| unescape(value))
+            .filter(|location| !location.trim().is_empty()),
+        start,
+        end,
+        all_day,
+        rrule: find("RRULE").map(|(_, value)| value.trim().to_string()),
+        exdates,
+        recurrence_id: find("RECURRENCE-ID")
+            .map(|(_, value)| parse_date_time(value).map(|(at, _)| at))
+            .transpose()?,
+        cancelled: find("STATUS").is_some_and(|(_, value)| value.trim().eq_ignore_ascii_case("CANCELLED")),
+    }))
+}
+
+// `20250418` (a date) or `20250418T090000`, optionally with `Z`
+fn parse_date_time(value: &str) -> Result<(NaiveDateTime, bool), String> {
+    let value = value.trim().trim_end_matches('Z');
+    let invalid = || format!("\"{}\" isn't an iCalendar date", value);
+    let date = value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(invalid)?;
+
+    match value.get(8..) {
+        Some(time) if !time.is_empty() => {
+            let time = time
+                .strip_prefix('T')
+                .and_then(|time| NaiveTime::parse_from_str(time, "%H%M%S").ok())
+                .ok_or_else(invalid)?;
+            Ok((date.and_time(time), false))
+        }
+        _ => Ok((date.and_time(NaiveTime::MIN), true)),
+    }
+}
+
+// `PT1H30M`, `P1D` or `P1W`: weeks, days, hours, minutes and seconds
+fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let invalid = || format!("\"{}\" isn't an iCalendar duration", value);
+    let (negative, rest) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let rest = rest.strip_prefix('P').ok_or_else(invalid)?;
+
+    let mut total = Duration::zero();
+    let mut number = String::new();
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => continue,
+            unit => {
+                let amount = number.parse::<i64>().map_err(|_| invalid())?;
+                number.clear();
+                total += match unit {
+                    'W' => Duration::weeks(amount),
+                    'D' => Duration::days(amount),
+                    'H' => Duration::hours(amount),
+                    'M' => Duration::minutes(amount),
+                    'S' => Duration::seconds(amount),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(if negative { -total } else { total })
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+/// One VEVENT to write into a published calendar
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FeedEvent {
+    /// Must stay the same across edits so calendar apps update the event in place
+    pub uid: String,
+    pub summary: String,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    /// In UTC
+    pub start: NaiveDateTime,
+    /// In UTC
+    pub end: NaiveDateTime,
+    pub cancelled: bool,
+    /// When the event last changed, in UTC
+    pub last_modified: NaiveDateTime,
+}
+
+/// A complete VCALENDAR named `name` holding `events`
+pub fn write_calendar(name: &str, events: &[FeedEvent]) -> String {
+    let mut out = String::new();
+    let mut line = |text: String| {
+        fold_into(&mut out, &text);
+    };
+
+    line("BEGIN:VCALENDAR".to_string());
+    line("VERSION:2.0".to_string());
+    line("PRODID:-//Clock-It//Class Timetable//EN".to_string());
+    line("CALSCALE:GREGORIAN".to_string());
+    line("METHOD:PUBLISH".to_string());
+    line(format!("X-WR-CALNAME:{}", escape(name)));
+    // Hints for how often subscribed calendars should fetch the feed again
+    line("REFRESH-INTERVAL;VALUE=DURATION:PT1H".to_string());
+    line("X-PUBLISHED-TTL:PT1H".to_string());
+
+    for event in events {
+        line("BEGIN:VEVENT".to_string());
+        line(format!("UID:{}", escape(&event.uid)));
+        line(format!("DTSTAMP:{}", utc_stamp(event.last_modified)));
+        line(format!("LAST-MODIFIED:{}", utc_stamp(event.last_modified)));
+        line(format!("DTSTART:{}", utc_stamp(event.start)));
+        line(format!("DTEND:{}", utc_stamp(event.end)));
+        line(format!("SUMMARY:{}", escape(&event.summary)));
+        if let Some(location) = &event.location {
+            line(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(description) = &event.description {
+            line(format!("DESCRIPTION:{}", escape(description)));
+        }
+        line(format!(
+            "STATUS:{}",
+            if event.cancelled { "CANCELLED" } else { "CONFIRMED" }
+        ));
+        line("TRANSP:OPAQUE".to_string());
+        line("END:VEVENT".to_string());
+    }
+
+    line("END:VCALENDAR".to_string());
+    out
+}
+
+fn utc_stamp(at: NaiveDateTime) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+// Split `text` into CRLF lines of at most MAX_LINE_OCTETS bytes, continuation
+// lines starting with a space, never splitting a UTF-8 character
+fn fold_into(out: &mut String, text: &str) {
+    let mut limit = MAX_LINE_OCTETS;
+    let mut used = 0;
+    for c in text.chars() {
+        if used + c.len_utf8() > limit {
+            out.push_str("\r\n ");
+            // The leading space counts towards the next line's length
+            limit = MAX_LINE_OCTETS - 1;
+            used = 0;
+        }
+        out.push(c);
+        used += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
@@ -1,2 +1,7 @@
+pub mod class_time;
 pub mod geolocation;
+pub mod geofence;
+pub mod ics;
 pub mod module_visuals;
+pub mod recurrence;
+pub mod timetable_import;
@@ -0,0 +1,662 @@
+//! RFC 5545 recurrence rules for class series. Only what a timetable needs is
+//! supported: DAILY, WEEKLY and MONTHLY rules with INTERVAL and BYDAY, ended by
+//! either COUNT or UNTIL. Occurrences are whole dates; the time of day is the
+//! class's own. Rules without an end are refused so a series is always finite.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Most classes a single series may generate
+pub const MAX_OCCURRENCES: usize = 200;
+
+/// Stop looking for matching dates after this many periods, for rules such as
+/// "the 31st of every second month" that rarely match
+const MAX_PERIODS: u32 = 2_000;
+
+/// Largest INTERVAL accepted, which keeps every generated date well inside the
+/// range chrono can represent
+const MAX_INTERVAL: u32 = 1_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// An entry in BYDAY: a weekday, and for monthly rules optionally which one in the
+/// month (`2TU` is the second Tuesday, `-1FR` the last Friday)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WeekdayNum {
+    pub ordinal: Option<i32>,
+    pub weekday: Weekday,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecurrenceEnd {
+    Count(u32),
+    /// Inclusive
+    Until(NaiveDate),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<WeekdayNum>,
+    pub end: RecurrenceEnd,
+}
+
+impl RecurrenceRule {
+    /// Parse an RRULE value such as `FREQ=WEEKLY;BYDAY=MO,WE;COUNT=24`, with or
+    /// without the leading `RRULE:`
+    pub fn parse(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let rule = rule
+            .get(..6)
+            .filter(|prefix| prefix.eq_ignore_ascii_case("RRULE:"))
+            .map_or(rule, |_| &rule[6..]);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in rule.split(';').filter(|part| !part.trim().is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Malformed repeat rule part \"{}\"", part))?;
+            let value = value.trim();
+            match name.trim().to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(format!("Unsupported repeat frequency \"{}\"", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse::<u32>()
+                        .ok()
+                        .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                        .ok_or_else(|| {
+                            format!("INTERVAL must be a whole number from 1 to {}", MAX_INTERVAL)
+                        })?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse::<u32>()
+                            .ok()
+                            .filter(|count| (1..=MAX_OCCURRENCES as u32).contains(count))
+                            .ok_or_else(|| {
+                                format!("COUNT must be between 1 and {}", MAX_OCCURRENCES)
+                            })?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_ical_date(value)?),
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_weekday_num)
+                        .collect::<Result<Vec<_>, _>>()?
+                }
+                // Weeks start on Monday, which is already the default
+                "WKST" if value.eq_ignore_ascii_case("MO") => {}
+                other => return Err(format!("Unsupported repeat rule part \"{}\"", other)),
+            }
+        }
+
+        let frequency = frequency.ok_or("A repeat rule needs a FREQ")?;
+        let end = match (count, until) {
+            (Some(count), None) => RecurrenceEnd::Count(count),
+            (None, Some(until)) => RecurrenceEnd::Until(until),
+            (Some(_), Some(_)) => return Err("Use either COUNT or UNTIL, not both".to_string()),
+            (None, None) => {
+                return Err("A repeating class needs an end date or a number of classes".to_string())
+            }
+        };
+        if frequency != Frequency::Monthly && by_day.iter().any(|day| day.ordinal.is_some()) {
+            return Err("Numbered weekdays such as 2TU only apply to monthly rules".to_string());
+        }
+
+        Ok(Self {
+            frequency,
+            interval,
+            by_day,
+            end,
+        })
+    }
+
+    /// Dates of the series starting on `start`, which always counts as the first,
+    /// leaving out `exdates`. As in RFC 5545, excluded dates still count towards COUNT.
+    pub fn occurrences(&self, start: NaiveDate, exdates: &[NaiveDate]) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        let mut generated = 0;
+        let mut emit = |date: NaiveDate| -> bool {
+            match self.end {
+                RecurrenceEnd::Until(until) if date > until => return false,
+                RecurrenceEnd::Count(count) if generated >= count as usize => return false,
+                _ => {}
+            }
+            generated += 1;
+            if !exdates.contains(&date) {
+                dates.push(date);
+            }
+            generated < MAX_OCCURRENCES
+        };
+
+        if !emit(start) {
+            return dates;
+        }
+        for period in 0..MAX_PERIODS {
+            // Past the last date chrono can represent there is nothing more to generate
+            let Some(period_dates) = self.period_dates(start, period) else {
+                break;
+            };
+            for date in period_dates {
+                if date > start && !emit(date) {
+                    return dates;
+                }
+            }
+        }
+        dates
+    }
+
+    /// Candidate dates in the `period`th day, week or month after `start`, in order,
+    /// or `None` once that period is beyond the dates chrono can represent
+    fn period_dates(&self, start: NaiveDate, period: u32) -> Option<Vec<NaiveDate>> {
+        let step = period.checked_mul(self.interval)? as i64;
+        Some(match self.frequency {
+            Frequency::Daily => {
+                let date = start.checked_add_signed(Duration::days(step))?;
+                if self.by_day.is_empty() || self.by_day.iter().any(|d| d.weekday == date.weekday())
+                {
+                    vec![date]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let from_monday =
+                    |weekday: Weekday| Duration::days(weekday.num_days_from_monday() as i64);
+                let monday = start
+                    .checked_sub_signed(from_monday(start.weekday()))?
+                    .checked_add_signed(Duration::weeks(step))?;
+                let mut dates: Vec<NaiveDate> = if self.by_day.is_empty() {
+                    vec![monday.checked_add_signed(from_monday(start.weekday()))?]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|d| monday.checked_add_signed(from_monday(d.weekday)))
+                        .collect::<Option<_>>()?
+                };
+                dates.sort();
+                dates.dedup();
+                dates
+            }
+            Frequency::Monthly => {
+                let months = start.year() as i64 * 12 + start.month0() as i64 + step;
+                let (year, month) = (i32::try_from(months / 12).ok()?, (months % 12) as u32 + 1);
+                NaiveDate::from_ymd_opt(year, month, 1)?;
+                let mut dates: Vec<NaiveDate> = if self.by_day.is_empty() {
+                    // Months without the start's day of the month are skipped
+                    NaiveDate::from_ymd_opt(year, month, start.day())
+                        .into_iter()
+                        .collect()
+                } else {
+                    self.by_day
+                        .iter()
+                        .flat_map(|day| weekdays_in_month(year, month, *day))
+                        .collect()
+                };
+                dates.sort();
+                dates.dedup();
+                dates
+            }
+        })
+    }
+
+    /// Short description for people, such as "Weekly on Mon, Wed, 12 times"
+    pub fn describe(&self) -> String {
+        let unit = match self.frequency {
+            Frequency::Daily => ("Daily", "days"),
+            Frequency::Weekly => ("Weekly", "weeks"),
+            Frequency::Monthly => ("Monthly", "months"),
+        };
+        let mut text = if self.interval == 1 {
+            unit.0.to_string()
+        } else {
+            format!("Every {} {}", self.interval, unit.1)
+        };
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|day| match day.ordinal {
+                    Some(-1) => format!("last {}", day.weekday),
+                    Some(n) if n < 0 => format!("{}-last {}", ordinal(-n), day.weekday),
+                    Some(n) => format!("{} {}", ordinal(n), day.weekday),
+                    None => day.weekday.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            text.push_str(&format!(" on {}", days));
+        }
+        match self.end {
+            RecurrenceEnd::Count(count) => text.push_str(&format!(", {} times", count)),
+            RecurrenceEnd::Until(until) => {
+                text.push_str(&format!(", until {}", until.format("%Y-%m-%d")))
+            }
+        }
+        text
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days = self
+                .by_day
+                .iter()
+                .map(|day| {
+                    let code = weekday_code(day.weekday);
+                    match day.ordinal {
+                        Some(n) => format!("{}{}", n, code),
+                        None => code.to_string(),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            write!(f, ";BYDAY={}", days)?;
+        }
+        match self.end {
+            RecurrenceEnd::Count(count) => write!(f, ";COUNT={}", count),
+            RecurrenceEnd::Until(until) => write!(f, ";UNTIL={}", until.format("%Y%m%d")),
+        }
+    }
+}
+
+/// Parse EXDATE-style dates, one `YYYY-MM-DD` (or `YYYYMMDD`) per line or comma
+pub fn parse_exdates(text: &str) -> Result<Vec<NaiveDate>, String> {
+    let mut dates = text
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|date| !date.is_empty())
+        .map(|date| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .or_else(|_| parse_ical_date(date))
+                .map_err(|_| format!("\"{}\" isn't a date like 2025-03-21", date))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    dates.sort();
+    dates.dedup();
+    Ok(dates)
+}
+
+/// What a series does with an occurrence that falls on a blackout date
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlackoutPolicy {
+    /// Drop the class
+    #[default]
+    Skip,
+    /// Move the class to the next free weekday before the pattern's next class,
+    /// or drop it if there is none
+    Shift,
+}
+
+impl BlackoutPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlackoutPolicy::Skip => "skip",
+            BlackoutPolicy::Shift => "shift",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "shift" => BlackoutPolicy::Shift,
+            _ => BlackoutPolicy::Skip,
+        }
+    }
+}
+
+/// Apply `policy` to the occurrences in `dates` (ascending) that `is_blackout` rejects
+pub fn avoid_blackouts(
+    dates: &[NaiveDate],
+    is_blackout: impl Fn(NaiveDate) -> bool,
+    policy: BlackoutPolicy,
+) -> Vec<NaiveDate> {
+    let mut kept = Vec::with_capacity(dates.len());
+    for (index, &date) in dates.iter().enumerate() {
+        if !is_blackout(date) {
+            kept.push(date);
+            continue;
+        }
+        if policy == BlackoutPolicy::Skip {
+            continue;
+        }
+
+        let next = dates.get(index + 1).copied();
+        let shifted = (1..)
+            .map(|days| date + Duration::days(days))
+            .take_while(|candidate| next.is_none_or(|next| *candidate < next))
+            .take(MAX_PERIODS as usize)
+            .find(|candidate| {
+                candidate.weekday().num_days_from_monday() < 5 && !is_blackout(*candidate)
+            });
+        kept.extend(shifted);
+    }
+    kept
+}
+
+/// The iCalendar two-letter code for a weekday, as used in BYDAY
+pub fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+/// `YYYYMMDD`, optionally followed by a time such as `T235959Z`, which is dropped
+fn parse_ical_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("\"{}\" isn't a date like 20250321", value))
+}
+
+fn parse_weekday_num(value: &str) -> Result<WeekdayNum, String> {
+    let value = value.trim().to_ascii_uppercase();
+    let not_a_weekday = || format!("\"{}\" isn't a weekday such as MO or 2TU", value);
+    // Only ASCII is upper-cased, so the last two bytes may be part of another character
+    let split = value.len().saturating_sub(2);
+    let (ordinal, code) = value
+        .get(..split)
+        .zip(value.get(split..))
+        .ok_or_else(not_a_weekday)?;
+    let weekday = match code {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return Err(not_a_weekday()),
+    };
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        Some(
+            ordinal
+                .parse::<i32>()
+                .ok()
+                .filter(|n| *n != 0 && (-5..=5).contains(n))
+                .ok_or_else(not_a_weekday)?,
+        )
+    };
+    Ok(WeekdayNum { ordinal, weekday })
+}
+
+/// Every matching weekday in the month, or only the numbered one (negative counts
+/// back from the end of the month)
+fn weekdays_in_month(year: i32, month: u32, day: WeekdayNum) -> Vec<NaiveDate> {
+    let all: Vec<NaiveDate> = (1..=31)
+        .filter_map(|d| NaiveDate::from_ymd_opt(year, month, d))
+        .filter(|date| date.weekday() == day.weekday)
+        .collect();
+    match day.ordinal {
+        None => all,
+        Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+        Some(n) => all
+            .len()
+            .checked_sub(n.unsigned_abs() as usize)
+            .and_then(|index| all.get(index).copied())
+            .into_iter()
+            .collect(),
+    }
+}
+
+fn ordinal(n: i32) -> String {
+    let suffix = match (n % 100, n % 10) {
+        (11..=13, _) => "th",
+        (_, 1) => "st",
+        (_, 2) => "nd",
+        (_, 3) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(texts: &[&str]) -> Vec<NaiveDate> {
+        texts.iter().map(|text| date(text)).collect()
+    }
+
+    /// Occurrences of `rule` from `start`, skipping `exdates`
+    fn occurrences(rule: &str, start: &str, exdates: &[&str]) -> Vec<NaiveDate> {
+        RecurrenceRule::parse(rule)
+            .unwrap()
+            .occurrences(date(start), &dates(exdates))
+    }
+
+    #[test]
+    fn count_ends_the_series_after_that_many_classes() {
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;COUNT=3", "2030-02-04", &[]),
+            dates(&["2030-02-04", "2030-02-11", "2030-02-18"])
+        );
+    }
+
+    #[test]
+    fn until_includes_its_own_date() {
+        assert_eq!(
+            occurrences("RRULE:FREQ=DAILY;UNTIL=20300206T235959Z", "2030-02-04", &[]),
+            dates(&["2030-02-04", "2030-02-05", "2030-02-06"])
+        );
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;UNTIL=20300210", "2030-02-04", &[]),
+            dates(&["2030-02-04"])
+        );
+    }
+
+    #[test]
+    fn interval_skips_periods() {
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;INTERVAL=2;COUNT=3", "2030-02-04", &[]),
+            dates(&["2030-02-04", "2030-02-18", "2030-03-04"])
+        );
+        assert_eq!(
+            occurrences("FREQ=DAILY;INTERVAL=3;UNTIL=20300210", "2030-02-04", &[]),
+            dates(&["2030-02-04", "2030-02-07", "2030-02-10"])
+        );
+    }
+
+    #[test]
+    fn weekly_byday_repeats_on_each_day() {
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;BYDAY=WE,MO;COUNT=5", "2030-02-04", &[]),
+            dates(&[
+                "2030-02-04",
+                "2030-02-06",
+                "2030-02-11",
+                "2030-02-13",
+                "2030-02-18"
+            ])
+        );
+    }
+
+    #[test]
+    fn monthly_byday_ordinals_count_from_either_end_of_the_month() {
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;BYDAY=2TU;COUNT=3", "2030-02-12", &[]),
+            dates(&["2030-02-12", "2030-03-12", "2030-04-09"])
+        );
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;BYDAY=-1FR;COUNT=3", "2030-02-22", &[]),
+            dates(&["2030-02-22", "2030-03-29", "2030-04-26"])
+        );
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;BYDAY=-2MO;COUNT=3", "2030-02-18", &[]),
+            dates(&["2030-02-18", "2030-03-18", "2030-04-22"])
+        );
+    }
+
+    #[test]
+    fn monthly_without_byday_skips_months_missing_the_day() {
+        assert_eq!(
+            occurrences("FREQ=MONTHLY;COUNT=3", "2030-01-31", &[]),
+            dates(&["2030-01-31", "2030-03-31", "2030-05-31"])
+        );
+    }
+
+    #[test]
+    fn exdates_are_left_out_but_still_count() {
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;COUNT=3", "2030-02-04", &["2030-02-11"]),
+            dates(&["2030-02-04", "2030-02-18"])
+        );
+        assert_eq!(
+            occurrences("FREQ=WEEKLY;UNTIL=20300218", "2030-02-04", &["2030-02-04"]),
+            dates(&["2030-02-11", "2030-02-18"])
+        );
+    }
+
+    #[test]
+    fn exdates_parse_in_either_date_form() {
+        assert_eq!(
+            parse_exdates("2030-02-11, 20300204\n\n2030-02-11").unwrap(),
+            dates(&["2030-02-04", "2030-02-11"])
+        );
+        assert!(parse_exdates("11 Feb").is_err());
+    }
+
+    #[test]
+    fn rules_without_a_single_end_are_refused() {
+        for rule in [
+            "FREQ=WEEKLY",
+            "FREQ=WEEKLY;COUNT=3;UNTIL=20300301",
+            "FREQ=WEEKLY;COUNT=0",
+            "FREQ=WEEKLY;COUNT=201",
+            "FREQ=YEARLY;COUNT=3",
+            "FREQ=WEEKLY;INTERVAL=0;COUNT=3",
+            "FREQ=DAILY;INTERVAL=100000000;COUNT=2",
+            "FREQ=WEEKLY;BYDAY=2TU;COUNT=3",
+            "FREQ=MONTHLY;BYDAY=6TU;COUNT=3",
+            "COUNT=3",
+        ] {
+            assert!(RecurrenceRule::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn byday_entries_that_are_not_weekdays_are_refused() {
+        for rule in [
+            "FREQ=WEEKLY;BYDAY=éX;COUNT=3",
+            "FREQ=WEEKLY;BYDAY=Xé;COUNT=3",
+            "FREQ=WEEKLY;BYDAY=M;COUNT=3",
+            "FREQ=MONTHLY;BYDAY=éTU;COUNT=3",
+        ] {
+            assert!(RecurrenceRule::parse(rule).is_err(), "{}", rule);
+        }
+    }
+
+    #[test]
+    fn generation_stops_at_the_last_representable_date() {
+        let rule = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: u32::MAX,
+            by_day: Vec::new(),
+            end: RecurrenceEnd::Count(3),
+        };
+        assert_eq!(
+            rule.occurrences(date("2030-02-04"), &[]),
+            dates(&["2030-02-04"])
+        );
+
+        let near_the_end = NaiveDate::MAX - Duration::days(3);
+        for frequency in [Frequency::Daily, Frequency::Weekly, Frequency::Monthly] {
+            let rule = RecurrenceRule {
+                frequency,
+                interval: MAX_INTERVAL,
+                by_day: Vec::new(),
+                end: RecurrenceEnd::Count(3),
+            };
+            assert_eq!(rule.occurrences(near_the_end, &[]), vec![near_the_end]);
+        }
+    }
+
+    #[test]
+    fn rules_round_trip_through_their_rrule() {
+        for rule in [
+            "FREQ=DAILY;COUNT=10",
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20300630",
+            "FREQ=MONTHLY;BYDAY=2TU,-1FR;COUNT=6",
+        ] {
+            assert_eq!(RecurrenceRule::parse(rule).unwrap().to_string(), rule);
+        }
+    }
+
+    #[test]
+    fn descriptions_name_ordinals_from_the_end() {
+        let describe = |rule: &str| RecurrenceRule::parse(rule).unwrap().describe();
+
+        assert_eq!(describe("FREQ=WEEKLY;COUNT=12"), "Weekly, 12 times");
+        assert_eq!(
+            describe("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;UNTIL=20300630"),
+            "Every 2 weeks on Mon, Wed, until 2030-06-30"
+        );
+        assert_eq!(
+            describe("FREQ=MONTHLY;BYDAY=1MO,2TU,3WE,-1TH,-2FR;COUNT=6"),
+            "Monthly on 1st Mon, 2nd Tue, 3rd Wed, last Thu, 2nd-last Fri, 6 times"
+        );
+    }
+
+    #[test]
+    fn blackouts_are_skipped_or_shifted_to_the_next_free_weekday() {
+        let weekly = dates(&["2030-02-04", "2030-02-11", "2030-02-18"]);
+        let holiday = |day: NaiveDate| day == date("2030-02-11") || day == date("2030-02-12");
+
+        assert_eq!(
+            avoid_blackouts(&weekly, holiday, BlackoutPolicy::Skip),
+            dates(&["2030-02-04", "2030-02-18"])
+        );
+        assert_eq!(
+            avoid_blackouts(&weekly, holiday, BlackoutPolicy::Shift),
+            dates(&["2030-02-04", "2030-02-13", "2030-02-18"])
+        );
+
+        // No free weekday before the next class: the class is dropped
+        let daily = dates(&["2030-02-11", "2030-02-12", "2030-02-13"]);
+        assert_eq!(
+            avoid_blackouts(&daily, holiday, BlackoutPolicy::Shift),
+            dates(&["2030-02-13"])
+        );
+    }
+}
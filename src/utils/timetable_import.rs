@@ -0,0 +1,139 @@
+//! Turning a timetable exported from the university system (an .ics file) into
+//! classes. Repeating events are expanded with their RRULE and EXDATEs, and an
+//! edited occurrence (a VEVENT with a RECURRENCE-ID) replaces the one it edits.
+//! All-day and cancelled events are left out, since they aren't classes.
+
+use crate::utils::ics::IcsEvent;
+use crate::utils::recurrence::RecurrenceRule;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Length given to an event without an end time
+const DEFAULT_DURATION_MINUTES: i32 = 60;
+
+/// One class an import would create
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportedClass {
+    pub title: String,
+    pub venue: Option<String>,
+    /// `YYYY-MM-DD`
+    pub date: String,
+    /// `HH:MM`
+    pub time: String,
+    pub duration_minutes: i32,
+    /// The module already has a class at this date and time, so importing skips it
+    pub duplicate: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimetableImport {
+    /// In date and time order
+    pub classes: Vec<ImportedClass>,
+    /// Events that couldn't be turned into classes, and why
+    pub skipped: Vec<String>,
+}
+
+impl TimetableImport {
+    pub fn new_classes(&self) -> impl Iterator<Item = &ImportedClass> {
+        self.classes.iter().filter(|class| !class.duplicate)
+    }
+
+    /// Mark the classes whose `(date, time)` is in `existing`, and any that repeat
+    /// an earlier class in the file
+    pub fn mark_duplicates(&mut self, existing: &HashSet<(String, String)>) {
+        let mut seen = existing.clone();
+        for class in &mut self.classes {
+            class.duplicate = !seen.insert((class.date.clone(), class.time.clone()));
+        }
+    }
+}
+
+/// The classes `events` describe, before checking them against existing classes
+pub fn plan_import(events: &[IcsEvent]) -> TimetableImport {
+    let mut import = TimetableImport::default();
+
+    // Occurrences replaced by an edited copy, by UID
+    let overridden: HashSet<(&str, NaiveDate)> = events
+        .iter()
+        .filter_map(|event| Some((event.uid.as_deref()?, event.recurrence_id?.date())))
+        .collect();
+
+    for event in events {
+        let label = || {
+            let title = if event.summary.is_empty() {
+                "Untitled event"
+            } else {
+                &event.summary
+            };
+            format!("{} ({})", title, event.start.format("%Y-%m-%d"))
+        };
+
+        if event.cancelled {
+            import.skipped.push(format!("{}: cancelled", label()));
+            continue;
+        }
+        if event.all_day {
+            import.skipped.push(format!("{}: all-day events aren't classes", label()));
+            continue;
+        }
+
+        let dates = match (&event.rrule, event.recurrence_id) {
+            (Some(rrule), None) => {
+                let rule = match RecurrenceRule::parse(&without_week_start(rrule)) {
+                    Ok(rule) => rule,
+                    Err(e) => {
+                        import.skipped.push(format!("{}: {}", label(), e));
+                        continue;
+                    }
+                };
+                let mut exdates = event.exdates.clone();
+                if let Some(uid) = event.uid.as_deref() {
+                    exdates.extend(
+                        overridden
+                            .iter()
+                            .filter(|(other, _)| *other == uid)
+                            .map(|(_, date)| *date),
+                    );
+                }
+                rule.occurrences(event.start.date(), &exdates)
+            }
+            _ => vec![event.start.date()],
+        };
+
+        let duration_minutes = event
+            .end
+            .map(|end| (end - event.start).num_minutes())
+            .filter(|minutes| *minutes > 0)
+            .map_or(DEFAULT_DURATION_MINUTES, |minutes| minutes as i32);
+        let title = if event.summary.is_empty() {
+            "Imported class".to_string()
+        } else {
+            event.summary.clone()
+        };
+
+        import.classes.extend(dates.into_iter().map(|date| ImportedClass {
+            title: title.clone(),
+            venue: event.location.clone(),
+            date: date.format("%Y-%m-%d").to_string(),
+            time: event.start.format("%H:%M").to_string(),
+            duration_minutes,
+            duplicate: false,
+        }));
+    }
+
+    import
+        .classes
+        .sort_by(|a, b| (&a.date, &a.time).cmp(&(&b.date, &b.time)));
+    import
+}
+
+// Exports often name the day weeks start on. Only Monday is supported, and it only
+// matters for rules that repeat every few weeks on several days.
+fn without_week_start(rrule: &str) -> String {
+    rrule
+        .split(';')
+        .filter(|part| !part.trim().to_ascii_uppercase().starts_with("WKST="))
+        .collect::<Vec<_>>()
+        .join(";")
+}